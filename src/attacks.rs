@@ -0,0 +1,334 @@
+// the squares each kind of piece attacks from a square. the leaper tables are built at compile
+// time and the slider tables the first time they're needed, then shared by everything, so none of
+// this needs a board
+
+use std::sync::OnceLock;
+
+use crate::{
+    bitboard::{Bitboard, EMPTY_BB},
+    board::Side,
+    magics::{BISHOP_ATTACK_TABLE_SIZE, BISHOP_MAGICS, ROOK_ATTACK_TABLE_SIZE, ROOK_MAGICS},
+    square::Square,
+};
+
+const NOT_A_FILE: u64 = 18374403900871474942u64;
+const NOT_H_FILE: u64 = 9187201950435737471u64;
+const NOT_AB_FILE: u64 = 18229723555195321596u64;
+const NOT_GH_FILE: u64 = 4557430888798830399u64;
+
+const fn init_white_pawn_attacks() -> [Bitboard; 64] {
+    let mut square_idx: usize = 0;
+
+    let mut pawn_attacks: [Bitboard; 64] = [EMPTY_BB; 64];
+
+    while square_idx < 64 {
+        let square_bb = 1u64 << square_idx;
+
+        let north_east = (square_bb << 9) & NOT_A_FILE;
+        let north_west = (square_bb << 7) & NOT_H_FILE;
+
+        pawn_attacks[square_idx] = Bitboard(north_west | north_east);
+
+        square_idx += 1;
+    }
+
+    pawn_attacks
+}
+
+const fn init_black_pawn_attacks() -> [Bitboard; 64] {
+    let mut square_idx: usize = 0;
+
+    let mut pawn_attacks: [Bitboard; 64] = [EMPTY_BB; 64];
+
+    while square_idx < 64 {
+        let square_bb = 1u64 << square_idx;
+
+        let south_east = (square_bb >> 7) & NOT_A_FILE;
+        let south_west = (square_bb >> 9) & NOT_H_FILE;
+
+        pawn_attacks[square_idx] = Bitboard(south_west | south_east);
+
+        square_idx += 1;
+    }
+
+    pawn_attacks
+}
+
+const fn init_knight_attacks() -> [Bitboard; 64] {
+    let mut square_idx: usize = 0;
+
+    let mut knight_attacks: [Bitboard; 64] = [EMPTY_BB; 64];
+
+    while square_idx < 64 {
+        let square_bb = 1u64 << square_idx;
+
+        // NORTH: << 8
+        // SOUTH: >> 8
+        // EAST: << 1
+        // WEST >> 1
+
+        let north_north_east = (square_bb << 17) & NOT_A_FILE;
+        let north_north_west = (square_bb << 15) & NOT_H_FILE;
+        let north_east_east = (square_bb << 10) & NOT_AB_FILE;
+        let north_west_west = (square_bb << 6) & NOT_GH_FILE;
+
+        let south_south_east = (square_bb >> 15) & NOT_A_FILE;
+        let south_south_west = (square_bb >> 17) & NOT_H_FILE;
+        let south_east_east = (square_bb >> 6) & NOT_AB_FILE;
+        let south_west_west = (square_bb >> 10) & NOT_GH_FILE;
+
+        knight_attacks[square_idx] = Bitboard(
+            north_north_east
+                | north_north_west
+                | north_east_east
+                | north_west_west
+                | south_south_east
+                | south_south_west
+                | south_east_east
+                | south_west_west,
+        );
+
+        square_idx += 1;
+    }
+
+    knight_attacks
+}
+
+const fn north_one(bits: u64) -> u64 {
+    bits << 8
+}
+const fn south_one(bits: u64) -> u64 {
+    bits >> 8
+}
+
+const fn east_one(bits: u64) -> u64 {
+    (bits << 1) & NOT_A_FILE
+}
+
+const fn west_one(bits: u64) -> u64 {
+    (bits >> 1) & NOT_H_FILE
+}
+
+const fn init_king_attacks() -> [Bitboard; 64] {
+    let mut square_idx: usize = 0;
+
+    let mut king_attacks: [Bitboard; 64] = [EMPTY_BB; 64];
+
+    while square_idx < 64 {
+        // "parallel prefix" method described here: https://www.chessprogramming.org/King_Pattern#by_Calculation
+        let mut king_bb = 1u64 << square_idx;
+        let mut attacks = east_one(king_bb) | west_one(king_bb);
+        king_bb |= attacks;
+        attacks |= north_one(king_bb) | south_one(king_bb);
+
+        king_attacks[square_idx] = Bitboard(attacks);
+
+        square_idx += 1;
+    }
+
+    king_attacks
+}
+
+const WHITE_PAWN_ATTACKS: [Bitboard; 64] = init_white_pawn_attacks();
+const BLACK_PAWN_ATTACKS: [Bitboard; 64] = init_black_pawn_attacks();
+
+const KNIGHT_ATTACKS: [Bitboard; 64] = init_knight_attacks();
+
+const KING_ATTACKS: [Bitboard; 64] = init_king_attacks();
+
+pub const ROOK_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+pub const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+pub fn generate_sliding_blocker_mask(square: Square, directions: [(i32, i32); 4]) -> Bitboard {
+    let mut blockers = EMPTY_BB;
+
+    let start_rank = (square.index() / 8) as i32;
+    let start_file = (square.index() % 8) as i32;
+
+    for (rank_offset, file_offset) in directions {
+        let mut rank = start_rank;
+        let mut file = start_file;
+
+        loop {
+            let next_square = Square::new(
+                (rank as usize).try_into().unwrap(),
+                (file as usize).try_into().unwrap(),
+            )
+            .bitboard();
+
+            rank += rank_offset;
+            file += file_offset;
+
+            if (0..=7).contains(&rank) && (0..=7).contains(&file) {
+                blockers |= next_square;
+            } else {
+                break;
+            }
+        }
+    }
+
+    blockers.clear_bit(square);
+    blockers
+}
+
+pub fn generate_sliding_attack_mask(
+    square: Square,
+    blockers: Bitboard,
+    directions: [(i32, i32); 4],
+) -> Bitboard {
+    let mut attacks = EMPTY_BB;
+
+    let start_rank = (square.index() / 8) as i32;
+    let start_file = (square.index() % 8) as i32;
+
+    for (rank_offset, file_offset) in directions {
+        let mut rank = start_rank;
+        let mut file = start_file;
+
+        loop {
+            let next_square = Square::new(
+                (rank as usize).try_into().unwrap(),
+                (file as usize).try_into().unwrap(),
+            )
+            .bitboard();
+
+            rank += rank_offset;
+            file += file_offset;
+
+            attacks |= next_square;
+
+            if !(0..=7).contains(&rank) || !(0..=7).contains(&file) {
+                break;
+            }
+
+            if (blockers & next_square) != EMPTY_BB {
+                break;
+            }
+        }
+    }
+
+    attacks.clear_bit(square);
+    attacks
+}
+
+fn init_rook_attacks() -> Vec<Bitboard> {
+    let mut rook_attacks = vec![EMPTY_BB; ROOK_ATTACK_TABLE_SIZE];
+
+    for (square, magic) in ROOK_MAGICS.iter().enumerate() {
+        let mask = Bitboard(magic.blocker_mask);
+        let mut blockers = EMPTY_BB;
+
+        loop {
+            let moves = generate_sliding_attack_mask(square.into(), blockers, ROOK_DIRECTIONS);
+            rook_attacks[magic.get_magic_index(blockers)] = moves;
+
+            blockers = (blockers - mask) & mask;
+            if blockers == EMPTY_BB {
+                break;
+            }
+        }
+    }
+
+    rook_attacks
+}
+
+fn init_bishop_attacks() -> Vec<Bitboard> {
+    let mut bishop_attacks = vec![EMPTY_BB; BISHOP_ATTACK_TABLE_SIZE];
+
+    for (square, magic) in BISHOP_MAGICS.iter().enumerate() {
+        let mask = Bitboard(magic.blocker_mask);
+        let mut blockers = EMPTY_BB;
+
+        loop {
+            let moves = generate_sliding_attack_mask(square.into(), blockers, BISHOP_DIRECTIONS);
+            bishop_attacks[magic.get_magic_index(blockers)] = moves;
+
+            blockers = (blockers - mask) & mask;
+            if blockers == EMPTY_BB {
+                break;
+            }
+        }
+    }
+
+    bishop_attacks
+}
+
+struct SliderTables {
+    rook: Vec<Bitboard>,
+    bishop: Vec<Bitboard>,
+}
+
+static SLIDER_TABLES: OnceLock<SliderTables> = OnceLock::new();
+
+fn slider_tables() -> &'static SliderTables {
+    SLIDER_TABLES.get_or_init(|| SliderTables {
+        rook: init_rook_attacks(),
+        bishop: init_bishop_attacks(),
+    })
+}
+
+/// the squares a pawn of `side` on `square` attacks
+pub fn pawn(side: Side, square: Square) -> Bitboard {
+    match side {
+        Side::White => WHITE_PAWN_ATTACKS[square.index()],
+        Side::Black => BLACK_PAWN_ATTACKS[square.index()],
+    }
+}
+
+/// every square attacked by the given pawns, worked out for all of them at once rather than one
+/// table lookup per pawn
+pub fn pawns(side: Side, pawns: Bitboard) -> Bitboard {
+    let pawns = pawns.0;
+
+    let attacks = match side {
+        Side::White => ((pawns << 9) & NOT_A_FILE) | ((pawns << 7) & NOT_H_FILE),
+        Side::Black => ((pawns >> 7) & NOT_A_FILE) | ((pawns >> 9) & NOT_H_FILE),
+    };
+
+    Bitboard(attacks)
+}
+
+pub fn knight(square: Square) -> Bitboard {
+    KNIGHT_ATTACKS[square.index()]
+}
+
+pub fn king(square: Square) -> Bitboard {
+    KING_ATTACKS[square.index()]
+}
+
+/// the squares a bishop on `square` attacks, up to and including the first piece in `occupancy`
+/// in each direction
+pub fn bishop(square: Square, occupancy: Bitboard) -> Bitboard {
+    let magic = BISHOP_MAGICS[square.index()];
+    slider_tables().bishop[magic.get_magic_index(occupancy)]
+}
+
+/// the squares a rook on `square` attacks, up to and including the first piece in `occupancy` in
+/// each direction
+pub fn rook(square: Square, occupancy: Bitboard) -> Bitboard {
+    let magic = ROOK_MAGICS[square.index()];
+    slider_tables().rook[magic.get_magic_index(occupancy)]
+}
+
+pub fn queen(square: Square, occupancy: Bitboard) -> Bitboard {
+    bishop(square, occupancy) | rook(square, occupancy)
+}
+
+/// the squares strictly between `a` and `b`, or nothing if they aren't on the same rank, file or
+/// diagonal
+pub fn between(a: Square, b: Square) -> Bitboard {
+    // each slider's rays stop at the other square, so they only overlap on the line joining them
+    if rook(a, EMPTY_BB) & b.bitboard() != EMPTY_BB {
+        rook(a, b.bitboard()) & rook(b, a.bitboard())
+    } else if bishop(a, EMPTY_BB) & b.bitboard() != EMPTY_BB {
+        bishop(a, b.bitboard()) & bishop(b, a.bitboard())
+    } else {
+        EMPTY_BB
+    }
+}
+
+/// the slider tables, which are allocated once for the whole program
+pub fn slider_tables_size_bytes() -> usize {
+    let tables = slider_tables();
+    (tables.rook.capacity() + tables.bishop.capacity()) * std::mem::size_of::<Bitboard>()
+}
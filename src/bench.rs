@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+use anyhow::bail;
+
+use crate::{
+    profile::{self, Phase},
+    reporter::SilentReporter,
+    search::{OrderingStats, Search},
+    search_limits::SearchLimits,
+};
+
+pub const BENCH_DEPTH: u8 = 7;
+
+// a mix of opening, middlegame and endgame positions, chosen to exercise castling, en passant,
+// promotions and the usual tactical themes
+pub const BENCH_FENS: [&str; 20] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "4rrk1/2p1b1p1/p1p3q1/4p3/2P2n1p/1P1NR2P/PB3PP1/3R1QK1 b - - 2 24",
+    "r3qbrk/6p1/2b2pPp/p3pP1Q/PpPpP2P/3P1B2/2PB3K/R5R1 w - - 16 42",
+    "6k1/1R3p2/6p1/2Bp3p/3P2q1/P7/1P2rQ1K/5R2 b - - 4 44",
+    "8/8/1p2k1p1/3p3p/1p1P1P1P/1P2PK2/8/8 w - - 3 54",
+    "7r/2p3k1/1p1p1qp1/1P1Bp3/p1P2r1P/P7/4R3/Q4RK1 w - - 0 36",
+    "r1bq1rk1/pp2b1pp/n1pp1n2/3P1p2/2P1p3/2N1P2N/PP2BPPP/R1BQ1RK1 b - - 2 10",
+    "3r3k/2r4p/1p1b3q/p4P2/P2Pp3/1B2P3/3BQ1RP/6K1 w - - 3 87",
+    "2r4r/1p4k1/1Pnp4/3Qb1pq/8/4BpPp/5P2/2RR1BK1 w - - 0 42",
+    "4q1bk/6b1/7p/p1p4p/PNPpP2P/KN4P1/3Q4/4R3 b - - 0 37",
+    "2q3r1/1r2pk2/pp3pp1/2pP3p/P1Pb1BbP/1P4Q1/R3NPP1/4R1K1 w - - 2 34",
+    "1r2r2k/1b4q1/pp5p/2pPp1p1/P3Pn2/1P1B1Q1P/2R3P1/4BR1K b - - 1 37",
+    "8/6pk/2b1Rp2/3r4/1R1B2PP/P5K1/8/2r5 b - - 16 42",
+    "8/p2B4/PkP5/4p1pK/4Pb1p/5P2/8/8 w - - 29 68",
+    "1r5k/2pq2p1/3p3p/p1pP4/4QP2/PP1R3P/6PK/8 w - - 1 51",
+];
+
+#[derive(Debug, Default)]
+pub struct BenchResult {
+    pub nodes: u64,
+    // the nodes of each of `BENCH_FENS`, in the same order
+    pub position_nodes: Vec<u64>,
+    // the part of `nodes` spent in the capture search past the horizon
+    pub quiescence_nodes: u64,
+    pub elapsed: Duration,
+    pub ordering: OrderingStats,
+    // only available when built with the `profile` feature
+    pub profile: Option<[(Phase, Duration); Phase::ALL.len()]>,
+}
+
+/// searches every one of `BENCH_FENS` to `depth`, starting each from a freshly reset search so
+/// none of them depends on the ones before. the total node count is the signature of the search:
+/// it only changes when the search itself does
+pub fn run_bench(search: &mut Search, depth: u8) -> anyhow::Result<BenchResult> {
+    if depth == 0 {
+        bail!("Bench depth must be at least 1");
+    }
+
+    let mut result = BenchResult::default();
+
+    profile::reset();
+    let start_time = Instant::now();
+
+    for fen in BENCH_FENS {
+        search.reset();
+        search.board.parse_fen(fen)?;
+        search.search_position(&SearchLimits::depth(depth), &mut SilentReporter)?;
+
+        result.nodes += search.search_info.nodes_searched;
+        result
+            .position_nodes
+            .push(search.search_info.nodes_searched);
+        result.quiescence_nodes += search.search_info.quiescence_nodes;
+        result.ordering += search.search_info.ordering;
+    }
+
+    result.elapsed = start_time.elapsed();
+    result.profile = profile::summary();
+
+    Ok(result)
+}
@@ -1,5 +1,7 @@
-use crate::square::{File, Rank, Square};
-use colored::Colorize;
+use crate::{
+    square::{File, Rank, Square, SquareColor},
+    style,
+};
 use std::{
     fmt::{Debug, Display},
     ops::{
@@ -12,6 +14,30 @@ pub struct Bitboard(pub u64);
 
 pub const EMPTY_BB: Bitboard = Bitboard(0u64);
 
+const fn dark_square_mask() -> u64 {
+    let mut mask = 0;
+
+    let mut rank = 0;
+    let mut file = 0;
+
+    while rank < 8 {
+        while file < 8 {
+            if (file + rank) % 2 == 0 {
+                mask |= 1u64 << (rank * 8 + file);
+            }
+            file += 1;
+        }
+
+        file = 0;
+        rank += 1;
+    }
+
+    mask
+}
+
+pub const DARK_SQUARES: Bitboard = Bitboard(dark_square_mask());
+pub const LIGHT_SQUARES: Bitboard = Bitboard(!dark_square_mask());
+
 impl Bitboard {
     pub fn set_bit(&mut self, square: Square) {
         *self |= square.bitboard();
@@ -42,6 +68,14 @@ impl Bitboard {
     pub fn count_ones(&self) -> u32 {
         self.0.count_ones()
     }
+
+    /// the squares of this bitboard that are of the given color
+    pub fn on_color(self, color: SquareColor) -> Bitboard {
+        match color {
+            SquareColor::Light => self & LIGHT_SQUARES,
+            SquareColor::Dark => self & DARK_SQUARES,
+        }
+    }
 }
 
 impl Display for Bitboard {
@@ -57,7 +91,7 @@ impl Debug for Bitboard {
         for &rank in Rank::EVERY.iter().rev() {
             for file in File::EVERY {
                 if file == File::A {
-                    write!(f, "  {}", (rank + 1).to_string().cyan())?;
+                    write!(f, "  {}", style::accent(rank + 1))?;
                 }
 
                 let square = Square::new(rank, file);
@@ -74,7 +108,7 @@ impl Debug for Bitboard {
         write!(f, "   ")?;
 
         for file in File::EVERY {
-            write!(f, "  {}", file.to_string().cyan())?;
+            write!(f, "  {}", style::accent(file))?;
         }
 
         writeln!(f)?;
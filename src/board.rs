@@ -4,12 +4,15 @@ use std::{
 };
 
 use anyhow::{bail, Context};
-use colored::Colorize;
 
 use crate::{
+    attacks,
     bitboard::{Bitboard, EMPTY_BB},
+    evaluate::Accumulator,
     move_generator::{MoveGenerator, MoveList},
-    square::{File, Piece, PieceColor, PieceKind, Rank, Square},
+    profile::{self, Phase},
+    square::{File, Piece, PieceColor, PieceKind, Rank, Square, SquareColor},
+    style,
     zobrist_hash::{ZobristHasher, ZobristKey},
 };
 
@@ -24,20 +27,15 @@ pub enum Side {
 }
 
 impl Side {
-    pub fn index(&self) -> usize {
-        *self as usize
+    /// for indexing anything stored per side, e.g. search tables
+    pub const fn index(self) -> usize {
+        self as usize
     }
 }
 
-impl TryFrom<PieceColor> for Side {
-    type Error = anyhow::Error;
-
-    fn try_from(value: PieceColor) -> Result<Self, Self::Error> {
-        match value {
-            PieceColor::White => Ok(Side::White),
-            PieceColor::Black => Ok(Side::Black),
-            PieceColor::None => bail!("piece has no color"),
-        }
+impl From<Side> for usize {
+    fn from(value: Side) -> Self {
+        value.index()
     }
 }
 
@@ -52,6 +50,7 @@ impl Not for Side {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum CastlingKind {
     WhiteKing = 0b0001,
@@ -60,8 +59,137 @@ pub enum CastlingKind {
     BlackQueen = 0b1000,
 }
 
+impl CastlingKind {
+    pub const EVERY: [Self; 4] = [
+        Self::WhiteKing,
+        Self::WhiteQueen,
+        Self::BlackKing,
+        Self::BlackQueen,
+    ];
+
+    pub const fn index(self) -> usize {
+        (self as u8).trailing_zeros() as usize
+    }
+
+    pub const fn side(self) -> Side {
+        match self {
+            Self::WhiteKing | Self::WhiteQueen => Side::White,
+            Self::BlackKing | Self::BlackQueen => Side::Black,
+        }
+    }
+
+    pub const fn is_king_side(self) -> bool {
+        matches!(self, Self::WhiteKing | Self::BlackKing)
+    }
+
+    fn new(side: Side, king_side: bool) -> Self {
+        match (side, king_side) {
+            (Side::White, true) => Self::WhiteKing,
+            (Side::White, false) => Self::WhiteQueen,
+            (Side::Black, true) => Self::BlackKing,
+            (Side::Black, false) => Self::BlackQueen,
+        }
+    }
+}
+
 pub type CastlingRights = u8;
 
+const ALL_CASTLING_RIGHTS: CastlingRights = 0b1111;
+
+/// where the king and rook start and end up for one castling right. in Chess960 they can start
+/// anywhere on the back rank, but they always end up where they would in standard chess
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CastlingPath {
+    pub king: Square,
+    pub rook: Square,
+    pub king_destination: Square,
+    pub rook_destination: Square,
+    // every square the king or rook passes over or lands on, other than the ones they start on.
+    // they must all be empty
+    pub between: Bitboard,
+    // the king's square and every one it passes over, which mustn't be attacked. the destination
+    // is checked along with every other move when the move is made
+    pub king_path: Bitboard,
+}
+
+impl CastlingPath {
+    pub fn new(king: Square, rook: Square) -> Self {
+        let (king_destination, rook_destination) = castling_destinations(king, rook);
+
+        let span = |from: Square, to: Square| {
+            let (low, high) = (from.index().min(to.index()), from.index().max(to.index()));
+            (low..=high).fold(EMPTY_BB, |squares, index| {
+                squares | Square::from(index).bitboard()
+            })
+        };
+
+        let king_and_rook = king.bitboard() | rook.bitboard();
+        let between =
+            (span(king, king_destination) | span(rook, rook_destination)) & !king_and_rook;
+        let king_path = span(king, king_destination) & !king_destination.bitboard();
+
+        Self {
+            king,
+            rook,
+            king_destination,
+            rook_destination,
+            between,
+            king_path: king_path | king.bitboard(),
+        }
+    }
+}
+
+fn standard_castling_paths() -> [CastlingPath; 4] {
+    [
+        CastlingPath::new(Square::E1, Square::H1),
+        CastlingPath::new(Square::E1, Square::A1),
+        CastlingPath::new(Square::E8, Square::H8),
+        CastlingPath::new(Square::E8, Square::A8),
+    ]
+}
+
+/// where the king and the rook on `rook` end up after castling. the king goes to the g-file when
+/// the rook is on its right and the c-file when it's on its left, with the rook next to it on the
+/// inside
+pub fn castling_destinations(king: Square, rook: Square) -> (Square, Square) {
+    let first_square = king.index() & !7;
+
+    if rook.index() > king.index() {
+        (
+            Square::from(first_square + 6),
+            Square::from(first_square + 5),
+        )
+    } else {
+        (
+            Square::from(first_square + 2),
+            Square::from(first_square + 3),
+        )
+    }
+}
+
+// room for the moves of a long game plus the deepest search line on top of it, so making and
+// unmaking moves during a search never has to reallocate
+const HISTORY_CAPACITY: usize = 1024;
+
+// the positions the game and the search have been through, oldest first. a copy keeps the full
+// capacity, since `Vec::clone` only allocates what's in use and a board that's been copied for a
+// `position` command or a helper thread would have to grow it again in the middle of a search
+struct History(Vec<HistoryItem>);
+
+impl History {
+    fn new() -> Self {
+        Self(Vec::with_capacity(HISTORY_CAPACITY))
+    }
+}
+
+impl Clone for History {
+    fn clone(&self) -> Self {
+        let mut items = Vec::with_capacity(self.0.len().max(HISTORY_CAPACITY));
+        items.extend_from_slice(&self.0);
+        Self(items)
+    }
+}
+
 impl TryFrom<char> for CastlingKind {
     type Error = anyhow::Error;
 
@@ -76,6 +204,7 @@ impl TryFrom<char> for CastlingKind {
     }
 }
 
+#[derive(Clone)]
 pub struct HistoryItem {
     pub castling_rights: CastlingRights,
     pub en_passant_square: Square,
@@ -83,8 +212,12 @@ pub struct HistoryItem {
     pub moved_piece: Piece,
     pub captured_piece: Piece,
     pub hash: u64,
+    pub has_castled: [bool; 2],
 }
 
+// cloning a board is cheap enough for giving each search thread its own copy. the attack tables
+// are shared by every board, see `attacks`
+#[derive(Clone)]
 pub struct Board {
     white_pawns: Bitboard,
     white_knights: Bitboard,
@@ -102,23 +235,48 @@ pub struct Board {
 
     pieces: BoardPieces,
 
+    // indexed by side. checking for check happens several times per node, so the kings are kept
+    // track of as they move rather than found in their bitboards every time
+    king_squares: [Square; 2],
+
     white_occupancies: Bitboard,
     black_occupancies: Bitboard,
 
+    accumulator: Accumulator,
+
     side: Side,
 
     halfmove_clock: usize,
 
     castling_rights: CastlingRights,
+    // indexed by `CastlingKind::index`. only meaningful for rights the position has
+    castling_paths: [CastlingPath; 4],
+    // the rights left after a move from or to each square, e.g. moving the king loses both of its
+    // side's rights. ANDed with the current rights after every move
+    castling_permissions: [CastlingRights; 64],
+
+    // indexed by side. FEN doesn't say whether a king castled, so positions loaded from one
+    // assume it hasn't
+    has_castled: [bool; 2],
 
     en_passant_square: Square,
 
-    history: Vec<HistoryItem>,
+    history: History,
 
     pub move_generator: MoveGenerator,
 
     hasher: ZobristHasher,
     hash: u64,
+
+    // only set once a FEN has been loaded successfully. an empty board has no kings, so most of
+    // the engine can't work with it
+    initialized: bool,
+
+    // how a promotion sent without its piece is read, see `set_assume_queen_promotion`
+    assume_queen_promotion: bool,
+
+    // how castling is written, see `set_chess960`
+    chess960: bool,
 }
 
 impl Index<Square> for BoardPieces {
@@ -154,22 +312,33 @@ impl Default for Board {
 
             pieces: [Piece::default(); 64],
 
+            king_squares: [Square::None; 2],
+
             white_occupancies: EMPTY_BB,
             black_occupancies: EMPTY_BB,
 
+            accumulator: Accumulator::default(),
+
             side: Side::White,
             castling_rights: 0,
+            castling_paths: standard_castling_paths(),
+            castling_permissions: [ALL_CASTLING_RIGHTS; 64],
+            has_castled: [false; 2],
 
             halfmove_clock: 0,
 
             en_passant_square: Square::None,
 
-            history: Vec::new(),
+            history: History::new(),
 
-            move_generator: MoveGenerator::default(),
+            move_generator: MoveGenerator,
 
             hasher: ZobristHasher::default(),
             hash: 0,
+            initialized: false,
+
+            assume_queen_promotion: true,
+            chess960: false,
         }
     }
 }
@@ -192,17 +361,29 @@ impl Board {
 
         self.pieces = [Piece::default(); 64];
 
+        self.king_squares = [Square::None; 2];
+
         self.white_occupancies = EMPTY_BB;
         self.black_occupancies = EMPTY_BB;
 
+        self.accumulator = Accumulator::default();
+
         self.side = Side::White;
         self.castling_rights = 0;
+        self.castling_paths = standard_castling_paths();
+        self.castling_permissions = [ALL_CASTLING_RIGHTS; 64];
+        self.has_castled = [false; 2];
+        self.initialized = false;
 
         self.halfmove_clock = 0;
 
         self.en_passant_square = Square::None;
 
-        self.history = Vec::new();
+        self.hash = 0;
+
+        // keep the allocation around, there's no point giving it back just to grow it again
+        self.history.0.clear();
+        self.history.0.reserve(HISTORY_CAPACITY);
     }
 
     pub fn get_piece_bb(&self, piece: Piece) -> anyhow::Result<Bitboard> {
@@ -223,59 +404,69 @@ impl Board {
         }
     }
 
-    pub fn get_piece_bb_mut(&mut self, piece: Piece) -> anyhow::Result<&mut Bitboard> {
+    pub fn get_piece_bb_mut(&mut self, piece: Piece) -> &mut Bitboard {
         match (piece.color, piece.kind) {
-            (PieceColor::White, PieceKind::Pawn) => Ok(&mut self.white_pawns),
-            (PieceColor::White, PieceKind::Knight) => Ok(&mut self.white_knights),
-            (PieceColor::White, PieceKind::Bishop) => Ok(&mut self.white_bishops),
-            (PieceColor::White, PieceKind::Rook) => Ok(&mut self.white_rooks),
-            (PieceColor::White, PieceKind::Queen) => Ok(&mut self.white_queens),
-            (PieceColor::White, PieceKind::King) => Ok(&mut self.white_king),
-            (PieceColor::Black, PieceKind::Pawn) => Ok(&mut self.black_pawns),
-            (PieceColor::Black, PieceKind::Knight) => Ok(&mut self.black_knights),
-            (PieceColor::Black, PieceKind::Bishop) => Ok(&mut self.black_bishops),
-            (PieceColor::Black, PieceKind::Rook) => Ok(&mut self.black_rooks),
-            (PieceColor::Black, PieceKind::Queen) => Ok(&mut self.black_queens),
-            (PieceColor::Black, PieceKind::King) => Ok(&mut self.black_king),
-            _ => bail!("cannot get bitboard for invalid piece"),
+            (PieceColor::White, PieceKind::Pawn) => &mut self.white_pawns,
+            (PieceColor::White, PieceKind::Knight) => &mut self.white_knights,
+            (PieceColor::White, PieceKind::Bishop) => &mut self.white_bishops,
+            (PieceColor::White, PieceKind::Rook) => &mut self.white_rooks,
+            (PieceColor::White, PieceKind::Queen) => &mut self.white_queens,
+            (PieceColor::White, PieceKind::King) => &mut self.white_king,
+            (PieceColor::Black, PieceKind::Pawn) => &mut self.black_pawns,
+            (PieceColor::Black, PieceKind::Knight) => &mut self.black_knights,
+            (PieceColor::Black, PieceKind::Bishop) => &mut self.black_bishops,
+            (PieceColor::Black, PieceKind::Rook) => &mut self.black_rooks,
+            (PieceColor::Black, PieceKind::Queen) => &mut self.black_queens,
+            (PieceColor::Black, PieceKind::King) => &mut self.black_king,
+            _ => panic!("cannot get bitboard for invalid piece"),
         }
     }
 
-    pub fn add_piece(&mut self, piece: Piece, square: Square) -> anyhow::Result<()> {
-        self.get_piece_bb_mut(piece)?.set_bit(square);
-        self.occupancy_mut(piece.color.try_into()?).set_bit(square);
-        self.pieces[square] = piece;
+    // make and unmake only move pieces that are on the board, so neither this nor `remove_piece`
+    // has anything to check
+    pub fn add_piece(&mut self, piece: Piece, square: Square) {
+        let side = piece.color.known_side();
 
-        Ok(())
+        self.get_piece_bb_mut(piece).set_bit(square);
+        self.occupancy_mut(side).set_bit(square);
+
+        if piece.kind == PieceKind::King {
+            self.king_squares[side.index()] = square;
+        }
+
+        self.pieces[square] = piece;
+        self.accumulator.add_piece(piece, square);
     }
 
-    pub fn add_piece_and_hash(&mut self, piece: Piece, square: Square) -> anyhow::Result<()> {
-        self.add_piece(piece, square)?;
+    pub fn add_piece_and_hash(&mut self, piece: Piece, square: Square) {
+        self.add_piece(piece, square);
         self.update_hash(ZobristKey::Piece(piece, square));
-
-        Ok(())
     }
 
-    pub fn remove_piece(&mut self, square: Square) -> anyhow::Result<Piece> {
+    pub fn remove_piece(&mut self, square: Square) -> Piece {
         let piece = self.pieces[square];
+        let side = piece.color.known_side();
 
-        match piece.kind {
-            PieceKind::NoPiece => bail!("cannot remove empty piece"),
-            _ => {
-                self.get_piece_bb_mut(piece)?.clear_bit(square);
-                self.occupancy_mut(piece.color.try_into()?)
-                    .clear_bit(square);
-                self.pieces[square] = Piece::default();
-                Ok(piece)
-            }
+        self.get_piece_bb_mut(piece).clear_bit(square);
+        self.occupancy_mut(side).clear_bit(square);
+
+        // unmaking a move puts the king back on its starting square before taking it off the
+        // one it moved to, so by then the cache already points somewhere else
+        if piece.kind == PieceKind::King && self.king_squares[side.index()] == square {
+            self.king_squares[side.index()] = Square::None;
         }
+
+        self.pieces[square] = Piece::default();
+        self.accumulator.remove_piece(piece, square);
+
+        piece
     }
 
-    pub fn remove_piece_and_hash(&mut self, square: Square) -> anyhow::Result<Piece> {
-        let piece = self.remove_piece(square)?;
+    pub fn remove_piece_and_hash(&mut self, square: Square) -> Piece {
+        let piece = self.remove_piece(square);
         self.update_hash(ZobristKey::Piece(piece, square));
 
-        Ok(piece)
+        piece
     }
 
     pub fn get_piece(&self, square: Square) -> Piece {
@@ -318,7 +509,7 @@ impl Board {
                     'p' | 'n' | 'b' | 'r' | 'q' | 'k' | 'P' | 'N' | 'B' | 'R' | 'Q' | 'K' => {
                         let piece: Piece = ch.try_into()?;
                         let square = Square::new(rank, file_index.try_into()?);
-                        self.add_piece(piece, square)?;
+                        self.add_piece(piece, square);
                         file_index += 1;
                     }
 
@@ -351,8 +542,7 @@ impl Board {
                 continue;
             }
 
-            let castling_kind: CastlingKind = ch.try_into()?;
-            self.castling_rights |= castling_kind as u8;
+            self.add_castling_right(ch)?;
         }
 
         let en_passant = fields.get(3).unwrap().chars();
@@ -371,11 +561,208 @@ impl Board {
 
         self.halfmove_clock = halfmove_clock.parse()?;
 
-        self.hash = self.hasher.hash_position(self);
+        self.validate_position()?;
+
+        self.hash = self.hash_from_scratch();
+        self.initialized = true;
 
         Ok(())
     }
 
+    // `KQkq` castle with the outermost rook on that side of the king, which is always the one in
+    // standard chess. Shredder-FEN (and X-FEN, when there's more than one rook on a side) gives the
+    // rook's file instead, e.g. `HAha`
+    fn add_castling_right(&mut self, ch: char) -> anyhow::Result<()> {
+        let side = if ch.is_ascii_uppercase() {
+            Side::White
+        } else {
+            Side::Black
+        };
+
+        let king = self.king_square(side);
+        let back_rank = match side {
+            Side::White => 0,
+            Side::Black => 56,
+        };
+
+        if king == Square::None || king.index() & !7 != back_rank {
+            bail!("Castling right `{}` needs the king on its back rank", ch);
+        }
+
+        let rook = Piece::new(side.into(), PieceKind::Rook);
+        let is_rook = |file: usize| self.get_piece(Square::from(back_rank + file)) == rook;
+        let king_file = king.index() & 7;
+
+        let rook_file = match ch.to_ascii_lowercase() {
+            'k' => (king_file + 1..8).rev().find(|&file| is_rook(file)),
+            'q' => (0..king_file).find(|&file| is_rook(file)),
+            'a'..='h' => {
+                let file = ch.to_ascii_lowercase() as usize - 'a' as usize;
+                (file != king_file && is_rook(file)).then_some(file)
+            }
+            _ => bail!("Invalid castling rights character: {}", ch),
+        };
+
+        let Some(rook_file) = rook_file else {
+            bail!("Castling right `{}` has no rook to castle with", ch);
+        };
+
+        let rook = Square::from(back_rank + rook_file);
+        let kind = CastlingKind::new(side, rook_file > king_file);
+
+        self.castling_rights |= kind as u8;
+        self.castling_paths[kind.index()] = CastlingPath::new(king, rook);
+
+        let side_rights =
+            CastlingKind::new(side, true) as u8 | CastlingKind::new(side, false) as u8;
+        self.castling_permissions[king.index()] &= !side_rights;
+        self.castling_permissions[rook.index()] &= !(kind as u8);
+
+        Ok(())
+    }
+
+    // positions from editors can be impossible, and searching them would go wrong in much more
+    // confusing ways
+    fn validate_position(&self) -> anyhow::Result<()> {
+        for (side, name) in [(Side::White, "white"), (Side::Black, "black")] {
+            let kings = self.get_piece_bb(Piece::new(side.into(), PieceKind::King))?;
+
+            if kings.0.count_ones() != 1 {
+                bail!("FEN must have exactly one {} king", name);
+            }
+        }
+
+        if self.is_in_check(!self.side) {
+            bail!("FEN is illegal: the side not to move is in check");
+        }
+
+        Ok(())
+    }
+
+    /// some GUIs leave the piece off a promotion, e.g. `e7e8`. almost every promotion is to a
+    /// queen, so by default that's the one it means. otherwise the move matches nothing. loading a
+    /// new position keeps the setting
+    pub fn set_assume_queen_promotion(&mut self, enabled: bool) {
+        self.assume_queen_promotion = enabled;
+    }
+
+    pub fn assumes_queen_promotion(&self) -> bool {
+        self.assume_queen_promotion
+    }
+
+    /// castling is written as the king capturing its own rook in Chess960, and as the king's
+    /// two-square move otherwise. the board plays the same either way, only the moves it generates
+    /// are written differently. loading a new position keeps the setting
+    pub fn set_chess960(&mut self, enabled: bool) {
+        self.chess960 = enabled;
+    }
+
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// fails with a readable error instead of letting code further down panic on an empty board
+    pub fn ensure_initialized(&self) -> anyhow::Result<()> {
+        if !self.initialized {
+            bail!("no position loaded");
+        }
+
+        Ok(())
+    }
+
+    pub fn fen(&self) -> String {
+        let mut fen = String::new();
+
+        for &rank in Rank::EVERY.iter().rev() {
+            let mut empty_count = 0;
+
+            for file in File::EVERY {
+                let piece = self.get_piece(Square::new(rank, file));
+
+                if piece.kind == PieceKind::NoPiece {
+                    empty_count += 1;
+                    continue;
+                }
+
+                if empty_count > 0 {
+                    fen.push_str(&empty_count.to_string());
+                    empty_count = 0;
+                }
+
+                fen.push(piece.fen_char());
+            }
+
+            if empty_count > 0 {
+                fen.push_str(&empty_count.to_string());
+            }
+
+            if rank != Rank::First {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(match self.side {
+            Side::White => " w ",
+            Side::Black => " b ",
+        });
+
+        let mut castling_string: String = CastlingKind::EVERY
+            .into_iter()
+            .filter(|&kind| self.can_castle(kind))
+            .map(|kind| self.castling_char(kind))
+            .collect();
+
+        if castling_string.is_empty() {
+            castling_string.push('-');
+        }
+
+        fen.push_str(&castling_string);
+
+        let en_passant = match self.en_passant_square {
+            Square::None => String::from("-"),
+            square => format!("{:?}", square).to_lowercase(),
+        };
+
+        // the fullmove number isn't tracked, it doesn't affect the search at all
+        fen.push_str(&format!(" {} {} 1", en_passant, self.halfmove_clock));
+
+        fen
+    }
+
+    // X-FEN: the usual letters unless another rook stands further out on the same side, which only
+    // happens in Chess960
+    fn castling_char(&self, kind: CastlingKind) -> char {
+        let path = self.castling_paths[kind.index()];
+        let rook = Piece::new(kind.side().into(), PieceKind::Rook);
+
+        let rook_file = path.rook.index() & 7;
+        let further_out = if kind.is_king_side() {
+            rook_file + 1..8
+        } else {
+            0..rook_file
+        };
+
+        let first_square = path.rook.index() & !7;
+        let is_outermost = further_out
+            .into_iter()
+            .all(|file| self.get_piece(Square::from(first_square + file)) != rook);
+
+        let ch = match (is_outermost, kind.is_king_side()) {
+            (true, true) => 'k',
+            (true, false) => 'q',
+            (false, _) => (b'a' + rook_file as u8) as char,
+        };
+
+        match kind.side() {
+            Side::White => ch.to_ascii_uppercase(),
+            Side::Black => ch,
+        }
+    }
+
     pub fn can_castle(&self, castling_kind: CastlingKind) -> bool {
         self.castling_rights & (castling_kind as u8) != 0
     }
@@ -392,6 +779,33 @@ impl Board {
         self.castling_rights = rights;
     }
 
+    /// the squares involved in castling with `kind`, as the position was loaded
+    pub fn castling_path(&self, kind: CastlingKind) -> &CastlingPath {
+        &self.castling_paths[kind.index()]
+    }
+
+    /// the castling rights that survive a move from or to `square`
+    pub fn castling_permissions(&self, square: Square) -> CastlingRights {
+        self.castling_permissions[square.index()]
+    }
+
+    pub fn has_castled(&self, side: Side) -> bool {
+        self.has_castled[side.index()]
+    }
+
+    pub fn mark_castled_and_hash(&mut self, side: Side) {
+        self.has_castled[side.index()] = true;
+        self.update_hash(ZobristKey::Castled(side));
+    }
+
+    pub fn set_castled_flags(&mut self, has_castled: [bool; 2]) {
+        self.has_castled = has_castled;
+    }
+
+    pub fn castled_flags(&self) -> [bool; 2] {
+        self.has_castled
+    }
+
     pub fn empty_squares(&self) -> Bitboard {
         !(self.occupancy(Side::White) | self.occupancy(Side::Black))
     }
@@ -438,12 +852,22 @@ impl Board {
         self.en_passant_square
     }
 
-    pub fn hash_en_passant_square(&mut self) {
-        self.update_hash(ZobristKey::EnPassantFile(self.en_passant_square));
+    pub fn set_en_passant_square(&mut self, square: Square) {
+        self.en_passant_square = square;
     }
 
-    pub fn set_en_passant_square(&mut self, square: Square) {
+    // the square's key is always taken out of the hash before it changes, and the new one put in
+    // after, so the hash can't be left holding a square the board no longer has
+    pub fn set_en_passant_square_and_hash(&mut self, square: Square) {
+        self.clear_en_passant_square_and_hash();
+
         self.en_passant_square = square;
+        self.update_hash(ZobristKey::EnPassantFile(square));
+    }
+
+    pub fn clear_en_passant_square_and_hash(&mut self) {
+        self.update_hash(ZobristKey::EnPassantFile(self.en_passant_square));
+        self.en_passant_square = Square::None;
     }
 
     pub fn halfmove_clock(&self) -> usize {
@@ -454,35 +878,84 @@ impl Board {
         self.halfmove_clock = halfmove_clock;
     }
 
-    fn get_king_square(&self, side: Side) -> Square {
-        let king_bitboard = self
-            .get_piece_bb(Piece::new(side.into(), PieceKind::King))
+    /// where `side`'s king is. every loaded position has exactly one king per side, but in the
+    /// middle of making a move, or on a board with no position loaded, this is `Square::None`
+    pub fn king_square(&self, side: Side) -> Square {
+        self.king_squares[side.index()]
+    }
+
+    /// every square attacked by `side`'s pawns
+    pub fn pawn_attacks_bb(&self, side: Side) -> Bitboard {
+        let pawns = self
+            .get_piece_bb(Piece::new(side.into(), PieceKind::Pawn))
             .unwrap();
 
-        king_bitboard.0.trailing_zeros().into()
+        attacks::pawns(side, pawns)
+    }
+
+    pub fn is_attacked_by_pawn(&self, square: Square, side: Side) -> bool {
+        let pawns = self
+            .get_piece_bb(Piece::new(side.into(), PieceKind::Pawn))
+            .unwrap();
+
+        // a pawn of `side` attacks `square` if a pawn of the other side standing on `square` would
+        // attack it back
+        attacks::pawn(!side, square) & pawns != EMPTY_BB
+    }
+
+    pub fn is_attacked_by_knight(&self, square: Square, side: Side) -> bool {
+        let knights = self
+            .get_piece_bb(Piece::new(side.into(), PieceKind::Knight))
+            .unwrap();
+
+        attacks::knight(square) & knights != EMPTY_BB
+    }
+
+    /// the pieces giving check to the side to move
+    pub fn checkers(&self) -> Bitboard {
+        let king_square = self.king_square(self.side);
+
+        if king_square == Square::None {
+            return EMPTY_BB;
+        }
+
+        let occupied = self.occupancy(Side::White) | self.occupancy(Side::Black);
+        self.attackers_to(king_square, occupied) & self.occupancy(!self.side)
     }
 
     pub fn is_in_check(&self, side: Side) -> bool {
         self.move_generator
-            .is_square_attacked(self, self.get_king_square(side), !side)
+            .is_square_attacked(self, self.king_square(side), !side)
     }
 
     pub fn push_history(&mut self, history_item: HistoryItem) {
-        self.history.push(history_item);
+        self.history.0.push(history_item);
+    }
+
+    pub fn history_size_bytes(&self) -> usize {
+        self.history.0.capacity() * std::mem::size_of::<HistoryItem>()
     }
 
     pub fn pop_history(&mut self) -> HistoryItem {
-        self.history.pop().unwrap()
+        self.history.0.pop().unwrap()
     }
 
     pub fn generate_all_moves(&self, move_list: &mut MoveList) -> anyhow::Result<()> {
+        self.ensure_initialized()?;
+        let _timer = profile::time(Phase::MoveGeneration);
         self.move_generator.generate_all_moves(self, move_list)
     }
 
     pub fn generate_all_captures(&self, move_list: &mut MoveList) -> anyhow::Result<()> {
+        self.ensure_initialized()?;
+        let _timer = profile::time(Phase::MoveGeneration);
         self.move_generator.generate_all_captures(self, move_list)
     }
 
+    pub fn accumulator(&self) -> &Accumulator {
+        &self.accumulator
+    }
+
     pub fn pieces(&self) -> &[Piece] {
         &self.pieces
     }
@@ -495,6 +968,12 @@ impl Board {
         self.hash
     }
 
+    /// the hash worked out from the position alone, which the incrementally updated one should
+    /// always match
+    pub fn hash_from_scratch(&self) -> u64 {
+        self.hasher.hash_position(self)
+    }
+
     pub fn set_hash(&mut self, hash: u64) {
         self.hash = hash
     }
@@ -504,11 +983,21 @@ impl Board {
     }
 
     fn is_repeated_position(&self) -> bool {
+        // getting back to a position takes at least two reversible moves from each side. this is
+        // also what keeps the check cheap in the capture search, where the clock is usually 0
+        if self.halfmove_clock < 4 {
+            return false;
+        }
+
+        // a freshly loaded position has no history to repeat
+        let Some(mut i) = self.history.0.len().checked_sub(1) else {
+            return false;
+        };
+
         // search backwards as it's more likely that a repeated position occurred recently
-        let mut i = self.history.len() - 1;
 
         while i > 0 {
-            let history_item = &self.history[i];
+            let history_item = &self.history.0[i];
             if history_item.hash == self.hash {
                 return true;
             }
@@ -525,6 +1014,23 @@ impl Board {
         false
     }
 
+    /// how many times the current position has already appeared since the last irreversible move
+    pub fn repetition_count(&self) -> usize {
+        let mut count = 0;
+
+        for history_item in self.history.0.iter().rev() {
+            if history_item.hash == self.hash {
+                count += 1;
+            }
+
+            if history_item.halfmove_clock == 0 {
+                break;
+            }
+        }
+
+        count
+    }
+
     pub fn has_insufficient_material(&self) -> bool {
         // pawns
         let white_pawns = self.piece_count(Piece::new(PieceColor::White, PieceKind::Pawn));
@@ -562,17 +1068,12 @@ impl Board {
             && white_bishops == 1
             && black_bishops == 1
         {
-            let white_bishop_square = self
-                .get_piece_bb(Piece::new(PieceColor::White, PieceKind::Bishop))
-                .unwrap()
-                .get_lsb_square();
-
-            let black_bishop_square = self
-                .get_piece_bb(Piece::new(PieceColor::White, PieceKind::Bishop))
-                .unwrap()
-                .get_lsb_square();
+            let bishops = self.white_bishops | self.black_bishops;
 
-            if white_bishop_square.is_same_color(black_bishop_square) {
+            // neither bishop can ever attack a square the other one defends
+            if bishops.on_color(SquareColor::Light) == EMPTY_BB
+                || bishops.on_color(SquareColor::Dark) == EMPTY_BB
+            {
                 return true;
             }
         }
@@ -580,20 +1081,25 @@ impl Board {
         false
     }
 
+    /// uncolored pieces never make it onto the board, so there are none of them to count
     pub fn piece_count(&self, piece: Piece) -> u32 {
-        self.get_piece_bb(piece).unwrap().0.count_ones()
+        self.get_piece_bb(piece)
+            .map_or(0, |bitboard| bitboard.count_ones())
     }
 
-    pub fn has_major_or_minor_piece(&self) -> bool {
-        self.piece_count(Piece::new(PieceColor::White, PieceKind::Knight))
-            + self.piece_count(Piece::new(PieceColor::Black, PieceKind::Knight))
-            + self.piece_count(Piece::new(PieceColor::White, PieceKind::Bishop))
-            + self.piece_count(Piece::new(PieceColor::Black, PieceKind::Bishop))
-            + self.piece_count(Piece::new(PieceColor::White, PieceKind::Rook))
-            + self.piece_count(Piece::new(PieceColor::Black, PieceKind::Rook))
-            + self.piece_count(Piece::new(PieceColor::White, PieceKind::Queen))
-            + self.piece_count(Piece::new(PieceColor::Black, PieceKind::Queen))
-            > 0
+    /// whether `side` has anything besides its king and pawns. without that, passing the turn is
+    /// often the best move it could make, so skipping it tells the search nothing
+    pub fn has_non_pawn_material(&self, side: Side) -> bool {
+        let color = PieceColor::from(side);
+
+        [
+            PieceKind::Knight,
+            PieceKind::Bishop,
+            PieceKind::Rook,
+            PieceKind::Queen,
+        ]
+        .into_iter()
+        .any(|kind| self.piece_count(Piece::new(color, kind)) > 0)
     }
 }
 
@@ -601,7 +1107,7 @@ fn print_board(board: &Board, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Resu
     for &rank in Rank::EVERY.iter().rev() {
         for file in File::EVERY {
             if file == File::A {
-                write!(f, "  {}", (rank + 1).to_string().cyan())?;
+                write!(f, "  {}", style::accent(rank + 1))?;
             }
 
             let square = Square::new(rank, file);
@@ -617,7 +1123,7 @@ fn print_board(board: &Board, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Resu
     write!(f, "   ")?;
 
     for file in File::EVERY {
-        write!(f, "  {}", file.to_string().cyan())?;
+        write!(f, "  {}", style::accent(file))?;
     }
 
     writeln!(f)?;
@@ -669,6 +1175,25 @@ fn print_board(board: &Board, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Resu
 
     writeln!(f, "En passant square: {:?}", board.en_passant_square)?;
 
+    writeln!(f)?;
+
+    writeln!(f, "FEN: {}", board.fen())?;
+
+    writeln!(f, "Hash: {:#018x}", board.hash)?;
+
+    let mut checkers = board.checkers();
+    let mut checker_squares = Vec::new();
+
+    while checkers != EMPTY_BB {
+        checker_squares.push(format!("{:?}", checkers.pop_bit()).to_lowercase());
+    }
+
+    if checker_squares.is_empty() {
+        writeln!(f, "Checkers: none")?;
+    } else {
+        writeln!(f, "Checkers: {}", checker_squares.join(" "))?;
+    }
+
     Ok(())
 }
 
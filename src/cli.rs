@@ -1,38 +1,81 @@
-use std::io::{self, BufRead, Write};
-
-use colored::Colorize;
+use std::{
+    io::{self, BufRead, Write},
+    thread,
+    time::{Duration, Instant},
+};
 
 use crate::{
+    attacks,
+    bench::{run_bench, BENCH_DEPTH},
     board::START_POSITION_FEN,
-    perft::{perft, run_perft_tests},
+    generate_magics::{print_magics, DEFAULT_MAGIC_SEED},
+    input::{read_line, InputLine},
+    move_generator::Move,
+    perft::{perft, run_perft_tests, run_quick_perft_tests, QUICK_PERFT_MAX_NODES},
+    perft_table::PerftTable,
+    position::{apply_fen, apply_moves, assumed_promotions},
+    profile::Phase,
+    reporter::{
+        evaluation_line, format_duration, pawns_string, CliReporter, JsonReporter, LoggingReporter,
+        ScorePerspective, SearchLog, SearchReporter,
+    },
     search::Search,
-    transposition_table::{PerftTableEntry, TranspositionTable},
-    uci::Uci,
+    search_limits::SearchLimits,
+    shutdown::Shutdown,
+    style,
+    uci::{parse_go_args, strip_word, Uci, UciExit},
 };
 
 pub struct Cli {
-    transposition_table: TranspositionTable<PerftTableEntry>,
+    perft_table: PerftTable,
     search: Search,
+    search_log: Option<SearchLog>,
+    // how `eval` and `search` show scores. UCI always uses the side to move
+    score_perspective: ScorePerspective,
 }
 
 impl Cli {
     pub fn start_loop(&mut self) {
+        self.run(io::stdin().lock());
+    }
+
+    /// runs a single command, as given on the command line, e.g. `krusty bench`, without the
+    /// greeting or the prompt
+    pub fn run_command(&mut self, command: &str) {
+        self.handle_input(command, &mut io::stdin().lock());
+        self.shutdown();
+    }
+
+    pub fn run(&mut self, mut input: impl BufRead) {
         Self::print_greeting();
 
         let mut input_buffer = String::new();
 
         loop {
-            print!("{} ", "krusty>".color("orange"));
+            print!("{} ", style::prompt("krusty>"));
             io::stdout().flush().unwrap();
 
-            io::stdin().lock().read_line(&mut input_buffer).unwrap();
-            self.handle_input(&input_buffer);
-            input_buffer.clear();
+            match read_line(&mut input, &mut input_buffer) {
+                Ok(InputLine::Line) => {
+                    // `quit` in UCI mode is meant for the whole engine, not just UCI mode
+                    if let Some(UciExit::Quit) = self.handle_input(&input_buffer, &mut input) {
+                        self.shutdown();
+                        break;
+                    }
+                }
+                Ok(InputLine::TooLong) => println!("Input line too long"),
+                // stdin was closed, so no more commands can arrive
+                Ok(InputLine::Eof) | Err(_) => {
+                    println!();
+                    self.shutdown();
+                    break;
+                }
+            }
         }
     }
 
     fn print_greeting() {
-        println!("\n\nWelcome to {}", "Krusty!".color("orange").bold());
+        println!("\n\nWelcome to {}", style::title("Krusty!"));
         println!("A chess engine written in Rust \u{1F980}");
 
         Self::print_commands();
@@ -42,32 +85,89 @@ impl Cli {
         println!();
 
         println!("Commands:");
-        println!("- {}: run full perft suite", "perft [<depth>]".cyan());
-        println!("- {}: load FEN", "fen <FEN> | startpos".cyan());
+        println!(
+            "- {}: run full perft suite",
+            style::accent("perft [<depth>]")
+        );
+        println!(
+            "- {}: run every suite position at a shallow depth, spread across all cores",
+            style::accent("perft quick [<max nodes>]")
+        );
+        println!("- {}: load FEN", style::accent("fen <FEN> | startpos"));
         println!(
             "- {}: make moves on board",
-            "moves <move1> <move2> ... ".cyan()
+            style::accent("moves <move1> <move2> ... ")
         );
         println!(
             "- {}: alias for `moves` command",
-            "mv <move1> <move2> ... ".cyan()
+            style::accent("mv <move1> <move2> ... ")
+        );
+        println!(
+            "- {}: the same as `moves`, as it's sent to the engine in UCI mode",
+            style::accent("position current moves <move1> <move2> ... ")
+        );
+        println!(
+            "- {}: hand the turn to the other side without making a move",
+            style::accent("switch")
+        );
+        println!(
+            "- {}: print evaluation of position, by default relative to current side",
+            style::accent("eval")
         );
         println!(
-            "- {}: print evaluation of position relative to current side",
-            "eval".cyan()
+            "- {}: show scores in `eval` and `search` relative to the side to move or to white",
+            style::accent("evalmode [stm | white]")
         );
         println!(
             "- {}: print best move after searching at given depth",
-            "search <depth>".cyan()
+            style::accent("search <depth>")
+        );
+        println!(
+            "- {}: search with the same limits as the UCI `go` command, e.g. `search movetime 1000` or `search nodes 100000`",
+            style::accent("search <limits>")
+        );
+        println!(
+            "- {}: the same searches, with one line of JSON per iteration and one for the result",
+            style::accent("search json <depth | limits>")
+        );
+        println!(
+            "- {}: search a fixed set of positions and report node counts and move ordering, to depth {} unless given",
+            style::accent("bench [depth]"),
+            BENCH_DEPTH
         );
-        println!("- {}: print current position", "print".cyan());
-        println!("- {}: start UCI protocol", "uci".cyan());
-        println!("- {}: print this command list", "help".cyan());
+        println!("- {}: print current position", style::accent("print"));
+        println!(
+            "- {}: print how much memory the engine's tables are using",
+            style::accent("memory")
+        );
+        println!(
+            "- {}: print freshly generated magic number tables",
+            style::accent("genmagics [<seed>] [--dense]")
+        );
+        println!(
+            "- {}: append searches to a log file, or stop logging if no path is given",
+            style::accent("log [<path>]")
+        );
+        println!(
+            "- {}: make searches reproducible, or go back to random seeds if no seed is given",
+            style::accent("seed [<seed>]")
+        );
+        println!(
+            "- {}: save the search hash table to a file",
+            style::accent("savehash <path>")
+        );
+        println!(
+            "- {}: load the search hash table from a file",
+            style::accent("loadhash <path>")
+        );
+        println!("- {}: start UCI protocol", style::accent("uci"));
+        println!("- {}: print this command list", style::accent("help"));
 
         println!();
     }
 
-    fn handle_input(&mut self, input: &str) {
+    // how UCI mode ended, if it was started
+    fn handle_input(&mut self, input: &str, reader: &mut impl BufRead) -> Option<UciExit> {
         let input = input.trim();
         let (command, args) = match input.split_once(' ') {
             Some((command, args)) => (command, args),
@@ -80,18 +180,35 @@ impl Cli {
             "perft" => self.handle_perft_command(args),
             "fen" => self.handle_fen_command(args),
             "moves" | "mv" => self.handle_moves_command(args),
+            "position" => self.handle_position_command(args),
+            "switch" => self.handle_switch_command(),
             "eval" => self.handle_eval_command(),
+            "evalmode" => self.handle_evalmode_command(args),
             "search" => self.handle_search_command(args),
+            "bench" => self.handle_bench_command(args),
             "print" => println!("{}", self.search.board),
-            "uci" => self.handle_uci_command(),
+            "memory" => self.handle_memory_command(),
+            "genmagics" => Self::handle_genmagics_command(args),
+            "log" => self.handle_log_command(args),
+            "seed" => self.handle_seed_command(args),
+            "savehash" => self.handle_save_hash_command(args),
+            "loadhash" => self.handle_load_hash_command(args),
+            "uci" => return Some(self.handle_uci_command(reader)),
             "help" => Self::print_commands(),
             _ => println!("Invalid command"),
         };
+
+        None
     }
 
     fn handle_perft_command(&mut self, args: &str) {
         if args.is_empty() {
-            run_perft_tests(include_str!("../perft.epd"), &mut self.transposition_table);
+            run_perft_tests(include_str!("../perft.epd"), &mut self.perft_table);
+            return;
+        }
+
+        if let Some(max_nodes) = args.strip_prefix("quick") {
+            Self::handle_quick_perft_command(max_nodes.trim());
             return;
         }
 
@@ -103,8 +220,36 @@ impl Cli {
             }
         };
 
-        let nodes = perft(&mut self.search.board, depth, &mut self.transposition_table).unwrap();
-        println!("nodes: {}", nodes);
+        match perft(&mut self.search.board, depth, &mut self.perft_table) {
+            Ok(nodes) => println!("nodes: {}", nodes),
+            Err(error) => println!("Perft failed: {:#}", error),
+        }
+    }
+
+    fn handle_quick_perft_command(args: &str) {
+        let max_nodes = if args.is_empty() {
+            QUICK_PERFT_MAX_NODES
+        } else {
+            match args.parse() {
+                Ok(max_nodes) => max_nodes,
+                Err(_) => {
+                    println!("Max nodes must be an integer");
+                    return;
+                }
+            }
+        };
+
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+
+        println!(
+            "Running each position up to {} nodes on {} threads",
+            max_nodes, threads
+        );
+
+        match run_quick_perft_tests(include_str!("../perft.epd"), max_nodes, threads) {
+            Ok(summary) => summary.print(),
+            Err(error) => println!("Invalid perft file: {:#}", error),
+        }
     }
 
     fn handle_fen_command(&mut self, args: &str) {
@@ -118,8 +263,8 @@ impl Cli {
             _ => args,
         };
 
-        if self.search.board.parse_fen(fen).is_err() {
-            println!("Invalid FEN");
+        if let Err(error) = apply_fen(&mut self.search.board, fen) {
+            println!("{:#}", anyhow::Error::new(error));
         }
     }
 
@@ -129,77 +274,408 @@ impl Cli {
             return;
         }
 
-        let moves: Result<Vec<_>, _> = args
-            .split_whitespace()
-            .map(|move_str| self.search.board.get_move_metadata(move_str))
-            .collect();
+        let moves: Vec<_> = args.split_whitespace().collect();
+
+        match apply_moves(&mut self.search.board, &moves) {
+            Ok(played) => {
+                for (mv, move_str) in assumed_promotions(&played, &moves) {
+                    println!("Assuming {} for {}", mv, move_str);
+                }
+            }
+            Err(error) => println!("{:#}", anyhow::Error::new(error)),
+        }
+    }
+
+    // only the non-standard `position current moves ...` from UCI, since `fen` already sets a
+    // position up
+    fn handle_position_command(&mut self, args: &str) {
+        match strip_word(args, "current").and_then(|args| strip_word(args, "moves")) {
+            Some(moves) => self.handle_moves_command(moves),
+            None => println!("Invalid position command, expected `position current moves ...`"),
+        }
+    }
+
+    fn handle_switch_command(&mut self) {
+        match self.search.board.switch_side_to_move() {
+            Ok(()) => println!("{:?} to move", self.search.board.side_to_move()),
+            Err(error) => println!("{:#}", error),
+        }
+    }
 
-        let moves = match moves {
-            Ok(m) => m,
+    fn handle_eval_command(&self) {
+        let board = &self.search.board;
+
+        let score = match board.evaluate() {
+            Ok(score) => score,
             Err(error) => {
-                println!("Invalid move: {}", error);
+                println!("Can't evaluate: {}", error);
                 return;
             }
         };
 
-        for mv in moves {
-            let found_move = self.search.board.find_matching_move(mv);
+        println!(
+            "{}",
+            evaluation_line(score, board.side_to_move(), self.score_perspective)
+        );
+    }
 
-            match found_move {
-                Some(mv) => {
-                    let is_legal = self.search.board.make_move(mv).unwrap();
-                    if !is_legal {
-                        println!("Move `{:?}` is not legal in this position", mv);
-                        self.search.board.unmake_move(mv).unwrap();
-                        return;
-                    }
-                }
-                None => {
-                    println!("Move `{:?}` is not legal in this position", mv);
+    fn handle_evalmode_command(&mut self, args: &str) {
+        if !args.is_empty() {
+            match args.parse() {
+                Ok(perspective) => self.score_perspective = perspective,
+                Err(error) => {
+                    println!("Invalid evaluation mode: {}", error);
                     return;
                 }
             }
         }
-    }
 
-    fn handle_eval_command(&self) {
-        println!("{}", self.search.board.evaluate())
+        println!("Evaluation mode: {}", self.score_perspective.name());
     }
 
     fn handle_search_command(&mut self, args: &str) {
+        // `search json ...` is the same search, printed as JSON for scripts
+        let (json, args) = match args.strip_prefix("json") {
+            Some(rest) if rest.is_empty() || rest.starts_with(' ') => (true, rest.trim()),
+            _ => (false, args),
+        };
+
         if args.is_empty() {
             println!("Please provide a search depth");
             return;
         }
 
-        let depth: u8 = match args.parse() {
-            Ok(value) => value,
-            Err(_) => {
-                println!("Depth must be an integer");
+        // a bare number is a depth, otherwise the limits are the same as for `go`
+        let limits = match args.parse() {
+            Ok(depth) => SearchLimits::depth(depth),
+            Err(_) => match parse_go_args(args, &mut self.search.board) {
+                Ok(limits) => limits,
+                Err(error) => {
+                    println!("Invalid search limits: {}", error);
+                    return;
+                }
+            },
+        };
+
+        // the info lines look the same in both modes, so say when they aren't the usual way round.
+        // JSON scores are always from the side to move
+        if self.score_perspective == ScorePerspective::White && !json {
+            println!("Scores are from white's side");
+        }
+
+        let mut cli_reporter =
+            CliReporter::new(self.score_perspective, self.search.board.side_to_move());
+        let mut json_reporter = JsonReporter::default();
+        let mut logging_reporter;
+
+        let output_reporter: &mut dyn SearchReporter = if json {
+            &mut json_reporter
+        } else {
+            &mut cli_reporter
+        };
+
+        let reporter: &mut dyn SearchReporter = match &mut self.search_log {
+            Some(log) => {
+                let fen = self.search.board.fen();
+                logging_reporter = LoggingReporter::new(output_reporter, log, fen);
+                &mut logging_reporter
+            }
+            None => output_reporter,
+        };
+
+        let started = Instant::now();
+
+        match self.search.search_position(&limits, reporter) {
+            Ok(best_move) => reporter.best_move(best_move, self.search.ponder_move(best_move)),
+            Err(error) => {
+                println!("Search failed: {:#}", error);
                 return;
             }
+        }
+
+        if !json {
+            self.print_search_summary(&cli_reporter, started.elapsed().as_millis());
+        }
+    }
+
+    fn print_search_summary(&mut self, reporter: &CliReporter, time_ms: u128) {
+        let Some(iteration) = reporter.last_iteration() else {
+            return;
         };
 
-        self.search.max_depth = depth;
+        let nodes = self.search.nodes_searched();
+        let nps = nodes as u128 * 1000 / time_ms.max(1);
+        let score = self
+            .score_perspective
+            .convert(iteration.score, self.search.board.side_to_move());
+
+        // the line was legal when it was searched, so this only falls back if something's badly
+        // wrong, and then the moves are still worth seeing
+        let pv = self
+            .search
+            .board
+            .line_to_san(iteration.pv)
+            .unwrap_or_else(|_| {
+                let moves: Vec<_> = iteration.pv.iter().map(Move::to_string).collect();
+                moves.join(" ")
+            });
+
+        println!("Summary:");
+        println!("- Time: {}", format_duration(time_ms));
+        println!("- Nodes: {}", nodes);
+        println!("- NPS: {}", nps);
+        println!(
+            "- Depth: {} (seldepth {})",
+            iteration.depth, iteration.seldepth
+        );
+        println!(
+            "- TT hit rate: {:.1}%",
+            self.search.search_info.debug.tt_hit_rate()
+        );
+        println!(
+            "- Evaluation ({}): {}",
+            self.score_perspective.name(),
+            pawns_string(score)
+        );
+        println!("- PV: {}", pv);
+    }
+
+    fn handle_log_command(&mut self, args: &str) {
+        if args.is_empty() {
+            self.search_log = None;
+            println!("Search logging disabled");
+            return;
+        }
+
+        let log = SearchLog::new(args);
+        println!("Logging searches to {}", log.path());
+        self.search_log = Some(log);
+    }
 
-        let best_move = self.search.search_position().unwrap();
+    // genmagics [<seed>] [--dense]
+    fn handle_genmagics_command(args: &str) {
+        let mut seed = DEFAULT_MAGIC_SEED;
+        let mut dense = false;
 
-        println!("{}", best_move);
+        for arg in args.split_whitespace() {
+            if arg == "--dense" {
+                dense = true;
+                continue;
+            }
+
+            match arg.parse() {
+                Ok(value) => seed = value,
+                Err(_) => {
+                    println!("Seed must be a non-negative integer");
+                    return;
+                }
+            }
+        }
+
+        print_magics(seed, dense);
     }
 
-    fn handle_uci_command(&mut self) {
+    fn handle_seed_command(&mut self, args: &str) {
+        if args.is_empty() {
+            self.search.set_seed(None);
+            println!("Seeding from entropy");
+            return;
+        }
+
+        match args.parse() {
+            Ok(seed) => self.search.set_seed(Some(seed)),
+            Err(_) => println!("Seed must be a non-negative integer"),
+        }
+    }
+
+    fn handle_bench_command(&mut self, args: &str) {
+        let depth = if args.is_empty() {
+            BENCH_DEPTH
+        } else {
+            match args.parse() {
+                Ok(depth) => depth,
+                Err(_) => {
+                    println!("Depth must be an integer");
+                    return;
+                }
+            }
+        };
+
+        let result = match run_bench(&mut self.search, depth) {
+            Ok(result) => result,
+            Err(error) => {
+                println!("Bench failed: {:#}", error);
+                return;
+            }
+        };
+
+        let elapsed_ms = result.elapsed.as_millis().max(1);
+
+        println!();
+        for (index, nodes) in result.position_nodes.iter().enumerate() {
+            println!(
+                "Position {:>2}/{}: {} nodes",
+                index + 1,
+                result.position_nodes.len(),
+                nodes
+            );
+        }
+
+        println!();
+        println!("Nodes: {}", result.nodes);
+        println!(
+            "- main search: {} ({:.1}%)",
+            result.nodes - result.quiescence_nodes,
+            Self::percentage(result.nodes - result.quiescence_nodes, result.nodes)
+        );
+        println!(
+            "- quiescence: {} ({:.1}%)",
+            result.quiescence_nodes,
+            Self::percentage(result.quiescence_nodes, result.nodes)
+        );
+        println!("Time: {:.2?}", result.elapsed);
+        println!("NPS: {}", (result.nodes as u128 * 1000) / elapsed_ms);
+        println!();
+        println!("Move ordering:");
+        println!(
+            "- first move cutoffs: {:.1}%",
+            result.ordering.first_move_cutoff_rate() * 100.0
+        );
+        println!(
+            "- average cutoff move index: {:.2}",
+            result.ordering.average_cutoff_index()
+        );
+        println!(
+            "- TT move available at cutoffs: {:.1}%",
+            result.ordering.tt_move_rate() * 100.0
+        );
+
+        if let Some(phases) = result.profile {
+            Self::print_profile(&phases);
+        }
+
+        // bench leaves the last bench position loaded, so go back to a known state
+        self.search.reset();
+    }
+
+    fn percentage(part: u64, total: u64) -> f64 {
+        part as f64 / total.max(1) as f64 * 100.0
+    }
+
+    fn print_profile(phases: &[(Phase, Duration)]) {
+        let total: Duration = phases.iter().map(|(_, time)| *time).sum();
+        let total_secs = total.as_secs_f64().max(f64::EPSILON);
+
+        println!();
+        println!("Profile:");
+
+        for (phase, time) in phases {
+            println!(
+                "- {}: {:.1}% ({:.2?})",
+                phase.name(),
+                time.as_secs_f64() / total_secs * 100.0,
+                time
+            );
+        }
+    }
+
+    // sizes are worked out from entry sizes and counts, so they leave out the allocator's
+    // overhead and anything outside the engine itself
+    fn handle_memory_command(&self) {
+        let search_tt = &self.search.transposition_table;
+        let perft_tt = &self.perft_table;
+        let board = &self.search.board;
+
+        let attack_tables = attacks::slider_tables_size_bytes();
+        let history = board.history_size_bytes();
+        // killers, history and counter moves are stored inline
+        let search_state = std::mem::size_of::<Search>();
+
+        println!(
+            "- search hash: {} ({} entries, {} MB configured)",
+            Self::format_bytes(search_tt.size_bytes()),
+            search_tt.capacity_entries(),
+            search_tt.configured_mb()
+        );
+        println!(
+            "- perft hash: {} ({} entries, {} MB configured)",
+            Self::format_bytes(perft_tt.size_bytes()),
+            perft_tt.capacity_entries(),
+            perft_tt.configured_mb()
+        );
+        println!(
+            "- slider attack tables: {}",
+            Self::format_bytes(attack_tables)
+        );
+        println!("- move history: {}", Self::format_bytes(history));
+        println!("- search heuristics: {}", Self::format_bytes(search_state));
+
+        let total =
+            search_tt.size_bytes() + perft_tt.size_bytes() + attack_tables + history + search_state;
+
+        println!("Estimated total: {}", Self::format_bytes(total));
+    }
+
+    fn format_bytes(bytes: usize) -> String {
+        const KILOBYTE: f64 = 1024.0;
+        const MEGABYTE: f64 = 1024.0 * KILOBYTE;
+
+        let bytes_f64 = bytes as f64;
+
+        if bytes_f64 >= MEGABYTE {
+            format!("{:.1} MB", bytes_f64 / MEGABYTE)
+        } else if bytes_f64 >= KILOBYTE {
+            format!("{:.1} KB", bytes_f64 / KILOBYTE)
+        } else {
+            format!("{} B", bytes)
+        }
+    }
+
+    fn handle_save_hash_command(&self, args: &str) {
+        if args.is_empty() {
+            println!("Please provide a file path");
+            return;
+        }
+
+        if let Err(error) = self.search.transposition_table.save(args) {
+            println!("Failed to save hash: {:#}", error);
+        }
+    }
+
+    fn handle_load_hash_command(&mut self, args: &str) {
+        if args.is_empty() {
+            println!("Please provide a file path");
+            return;
+        }
+
+        if let Err(error) = self.search.transposition_table.load(args) {
+            println!("Failed to load hash: {:#}", error);
+        }
+    }
+
+    fn handle_uci_command(&mut self, reader: &mut impl BufRead) -> UciExit {
         self.search.reset();
 
         let mut uci = Uci::new(&mut self.search);
-        uci.start_loop();
+        uci.run(reader)
+    }
+}
+
+impl Shutdown for Cli {
+    fn shutdown(&mut self) {
+        if let Some(log) = &mut self.search_log {
+            log.flush();
+        }
     }
 }
 
 impl Default for Cli {
     fn default() -> Self {
         Self {
-            transposition_table: TranspositionTable::new(256),
+            perft_table: PerftTable::new(256),
             search: Search::default(),
+            search_log: None,
+            score_perspective: ScorePerspective::default(),
         }
     }
 }
@@ -0,0 +1,147 @@
+use std::{fmt, ops::Sub};
+
+use crate::time_management::SearchDuration;
+
+/// counters behind the `debug` output. they're cheap enough to always be kept, and only get
+/// printed once the GUI turns debug mode on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DebugStats {
+    pub tt_probes: u64,
+    pub tt_hits: u64,
+    pub null_move_searches: u64,
+    pub null_move_cutoffs: u64,
+    // capture search nodes where doing nothing was already good enough
+    pub stand_pat_cutoffs: u64,
+    // root searches that fell outside their aspiration window and had to be done again
+    pub aspiration_researches: u64,
+}
+
+impl DebugStats {
+    /// the share of probes that found an entry, as a percentage
+    pub fn tt_hit_rate(&self) -> f64 {
+        percentage(self.tt_hits, self.tt_probes)
+    }
+}
+
+impl Sub for DebugStats {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            tt_probes: self.tt_probes - rhs.tt_probes,
+            tt_hits: self.tt_hits - rhs.tt_hits,
+            null_move_searches: self.null_move_searches - rhs.null_move_searches,
+            null_move_cutoffs: self.null_move_cutoffs - rhs.null_move_cutoffs,
+            stand_pat_cutoffs: self.stand_pat_cutoffs - rhs.stand_pat_cutoffs,
+            aspiration_researches: self.aspiration_researches - rhs.aspiration_researches,
+        }
+    }
+}
+
+/// something the search did that's worth knowing about when looking into its behaviour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugEvent {
+    // how long the search was given, after holding back the move overhead
+    TimeAllocated {
+        allowed: SearchDuration,
+        move_overhead_ms: u128,
+    },
+    // the counters for one iteration on its own, whether or not it finished
+    IterationStats {
+        depth: u8,
+        stats: DebugStats,
+    },
+    // the iteration after `depth` wasn't started, since it couldn't have finished in time
+    IterationSkipped {
+        depth: u8,
+        elapsed_ms: u128,
+        last_iteration_ms: u128,
+    },
+    // the best move failed low `elapsed_ms` into the search, so it was given until `allowed`
+    TimeExtended {
+        elapsed_ms: u128,
+        allowed: SearchDuration,
+    },
+}
+
+impl fmt::Display for DebugEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DebugEvent::TimeAllocated {
+                allowed: SearchDuration::Finite(ms),
+                move_overhead_ms,
+            } => write!(
+                f,
+                "time allocated {} ms, move overhead {} ms",
+                ms, move_overhead_ms
+            ),
+            DebugEvent::TimeAllocated {
+                allowed: SearchDuration::Infinite,
+                ..
+            } => write!(f, "time allocated without limit"),
+            DebugEvent::IterationStats { depth, stats } => write!(
+                f,
+                "depth {} tt hits {}/{} ({:.1}%) null move cutoffs {}/{} stand pat cutoffs {} \
+                 aspiration researches {}",
+                depth,
+                stats.tt_hits,
+                stats.tt_probes,
+                percentage(stats.tt_hits, stats.tt_probes),
+                stats.null_move_cutoffs,
+                stats.null_move_searches,
+                stats.stand_pat_cutoffs,
+                stats.aspiration_researches
+            ),
+            DebugEvent::IterationSkipped {
+                depth,
+                elapsed_ms,
+                last_iteration_ms,
+            } => write!(
+                f,
+                "not starting depth {}, {} ms used and depth {} took {} ms",
+                depth + 1,
+                elapsed_ms,
+                depth,
+                last_iteration_ms
+            ),
+            DebugEvent::TimeExtended {
+                elapsed_ms,
+                allowed: SearchDuration::Finite(ms),
+            } => write!(
+                f,
+                "best move failed low after {} ms, time extended to {} ms",
+                elapsed_ms, ms
+            ),
+            // only a finite allocation can be extended
+            DebugEvent::TimeExtended {
+                elapsed_ms,
+                allowed: SearchDuration::Infinite,
+            } => write!(f, "best move failed low after {} ms", elapsed_ms),
+        }
+    }
+}
+
+fn percentage(count: u64, total: u64) -> f64 {
+    count as f64 / total.max(1) as f64 * 100.0
+}
+
+/// where the search records events as they happen. they're handed to the reporter together
+/// between iterations, so they never end up in the middle of anything else being printed
+#[derive(Debug, Default)]
+pub struct DebugSink {
+    events: Vec<DebugEvent>,
+}
+
+impl DebugSink {
+    pub fn record(&mut self, event: DebugEvent) {
+        self.events.push(event);
+    }
+
+    pub fn drain(&mut self) -> impl Iterator<Item = DebugEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
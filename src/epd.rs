@@ -0,0 +1,191 @@
+use anyhow::{bail, Context};
+
+/// one line of an EPD file: a position (without move counters) followed by operations, e.g.
+/// `rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e4; id "start";`
+///
+/// the perft suite puts the halfmove and fullmove counters straight after the position like a
+/// FEN, so those are accepted too
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpdRecord {
+    pub position: String,
+    pub halfmove_clock: Option<u32>,
+    pub fullmove_number: Option<u32>,
+    pub operations: Vec<EpdOperation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpdOperation {
+    pub opcode: String,
+    pub operands: Vec<String>,
+}
+
+impl EpdRecord {
+    /// the position as a full FEN. the counters come from the `hmvc`/`fmvn` opcodes if there are
+    /// any, otherwise from the FEN-style counters, otherwise they default to `0 1`
+    pub fn fen(&self) -> String {
+        let halfmove_clock = self
+            .operand("hmvc")
+            .and_then(|operand| operand.parse().ok())
+            .or(self.halfmove_clock)
+            .unwrap_or(0);
+
+        let fullmove_number = self
+            .operand("fmvn")
+            .and_then(|operand| operand.parse().ok())
+            .or(self.fullmove_number)
+            .unwrap_or(1);
+
+        format!("{} {} {}", self.position, halfmove_clock, fullmove_number)
+    }
+
+    pub fn operands(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|operation| operation.opcode == opcode)
+            .map(|operation| operation.operands.as_slice())
+    }
+
+    /// the first operand of an opcode, for the common case where there's only one
+    pub fn operand(&self, opcode: &str) -> Option<&str> {
+        self.operands(opcode)?
+            .first()
+            .map(|operand| operand.as_str())
+    }
+}
+
+/// parses a whole file, skipping blank lines and `#` comments. errors say which line they're on
+pub fn parse_epd(text: &str) -> anyhow::Result<Vec<EpdRecord>> {
+    let mut records = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let record = parse_epd_line(line).with_context(|| format!("line {}", index + 1))?;
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+pub fn parse_epd_line(line: &str) -> anyhow::Result<EpdRecord> {
+    let tokens = tokenize(line)?;
+    let mut tokens = tokens.into_iter().peekable();
+
+    let mut position_fields = Vec::with_capacity(4);
+
+    for field in ["piece placement", "side to move", "castling", "en passant"] {
+        match tokens.next() {
+            Some(Token::Word(word)) => position_fields.push(word),
+            _ => bail!("EPD is missing the {} field", field),
+        }
+    }
+
+    let mut counters = Vec::new();
+
+    while counters.len() < 2 {
+        match tokens.peek() {
+            Some(Token::Word(word)) if word.parse::<u32>().is_ok() => {
+                counters.push(word.parse::<u32>()?);
+                tokens.next();
+            }
+            _ => break,
+        }
+    }
+
+    let mut operations = Vec::new();
+    let mut current: Option<EpdOperation> = None;
+
+    for token in tokens {
+        match (token, &mut current) {
+            (Token::Semicolon, Some(_)) => operations.extend(current.take()),
+            // stray separators, e.g. the ` ;D1 20 ;D2 400` style used by the perft suite
+            (Token::Semicolon, None) => (),
+            (Token::Word(word), Some(operation)) | (Token::Quoted(word), Some(operation)) => {
+                operation.operands.push(word)
+            }
+            (Token::Word(opcode), None) => {
+                current = Some(EpdOperation {
+                    opcode,
+                    operands: Vec::new(),
+                })
+            }
+            (Token::Quoted(string), None) => {
+                bail!("expected an opcode but found the string \"{}\"", string)
+            }
+        }
+    }
+
+    // the last operation doesn't always have a trailing semicolon
+    operations.extend(current);
+
+    Ok(EpdRecord {
+        position: position_fields.join(" "),
+        halfmove_clock: counters.first().copied(),
+        fullmove_number: counters.get(1).copied(),
+        operations,
+    })
+}
+
+enum Token {
+    Word(String),
+    Quoted(String),
+    Semicolon,
+}
+
+fn tokenize(line: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            ';' => {
+                chars.next();
+                tokens.push(Token::Semicolon);
+            }
+            '"' => {
+                chars.next();
+
+                let mut string = String::new();
+                let mut terminated = false;
+
+                for ch in chars.by_ref() {
+                    if ch == '"' {
+                        terminated = true;
+                        break;
+                    }
+
+                    string.push(ch);
+                }
+
+                if !terminated {
+                    bail!("unterminated string \"{}", string);
+                }
+
+                tokens.push(Token::Quoted(string));
+            }
+            ch if ch.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut word = String::new();
+
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || ch == ';' || ch == '"' {
+                        break;
+                    }
+
+                    word.push(ch);
+                    chars.next();
+                }
+
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
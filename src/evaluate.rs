@@ -1,7 +1,10 @@
+use std::thread;
+
 use crate::{
-    board::{Board, Side},
-    magics::{BISHOP_MAGICS, ROOK_MAGICS},
-    move_generator::KNIGHT_ATTACKS,
+    attacks,
+    bitboard::{Bitboard, EMPTY_BB},
+    board::{Board, CastlingKind, Side},
+    profile::{self, Phase},
     square::{Piece, PieceColor, PieceKind, Square},
 };
 
@@ -28,34 +31,37 @@ impl Piece {
     }
 
     fn middle_game_pst_value(&self, square: usize) -> i32 {
-        match self.kind {
-            PieceKind::Pawn => MIDDLE_GAME_PAWN_PST[square],
-            PieceKind::Knight => MIDDLE_GAME_KNIGHT_PST[square],
-            PieceKind::Bishop => MIDDLE_GAME_BISHOP_PST[square],
-            PieceKind::Rook => MIDDLE_GAME_ROOK_PST[square],
-            PieceKind::King => MIDDLE_GAME_KING_PST[square],
-            // TODO: harder to determine where queen should be aiming to go. should add this after
-            // doing some tuning
-            PieceKind::Queen => 0,
-            PieceKind::NoPiece => 0,
-        }
+        MIDDLE_GAME_PSTS[self.kind as usize][square]
     }
 
     fn end_game_pst_value(&self, square: usize) -> i32 {
-        match self.kind {
-            PieceKind::Pawn => END_GAME_PAWN_PST[square],
-            PieceKind::Knight => END_GAME_KNIGHT_PST[square],
-            PieceKind::Bishop => END_GAME_BISHOP_PST[square],
-            PieceKind::Rook => END_GAME_ROOK_PST[square],
-            PieceKind::King => END_GAME_KING_PST[square],
-            // TODO: harder to determine where queen should be aiming to go. should add this after
-            // doing some tuning
-            PieceKind::Queen => 0,
-            PieceKind::NoPiece => 0,
-        }
+        END_GAME_PSTS[self.kind as usize][square]
     }
 }
 
+// indexed by `PieceKind`
+const MIDDLE_GAME_PSTS: [&[i32; 64]; 6] = [
+    &MIDDLE_GAME_PAWN_PST,
+    &MIDDLE_GAME_KNIGHT_PST,
+    &MIDDLE_GAME_BISHOP_PST,
+    &MIDDLE_GAME_ROOK_PST,
+    &QUEEN_PST,
+    &MIDDLE_GAME_KING_PST,
+];
+
+const END_GAME_PSTS: [&[i32; 64]; 6] = [
+    &END_GAME_PAWN_PST,
+    &END_GAME_KNIGHT_PST,
+    &END_GAME_BISHOP_PST,
+    &END_GAME_ROOK_PST,
+    &QUEEN_PST,
+    &END_GAME_KING_PST,
+];
+
+// TODO: harder to determine where queen should be aiming to go. should add this after doing some
+// tuning
+const QUEEN_PST: [i32; 64] = [0; 64];
+
 #[rustfmt::skip]
 const MIDDLE_GAME_PAWN_PST: [i32; 64] = [
    0,   0,   0,   0,   0,   0,   0,   0,
@@ -199,44 +205,129 @@ const TOTAL_PHASE: i32 = KNIGHT_PHASE * 4 + BISHOP_PHASE * 4 + ROOK_PHASE * 4 +
 const BISHOP_PAIR_MIDDLE_GAME_BONUS: i32 = 25;
 const BISHOP_PAIR_END_GAME_BONUS: i32 = 50;
 
+// keeping the option to castle is worth a little, actually getting the king to safety is worth
+// more. both only matter in the middle game
+const CASTLING_RIGHT_MIDDLE_GAME_BONUS: i32 = 10;
+const CASTLED_MIDDLE_GAME_BONUS: i32 = 30;
+
+// score per square a piece can move to. rooks and queens need open lines more once the board
+// empties out
+const KNIGHT_MOBILITY_MIDDLE_GAME: i32 = 4;
+const KNIGHT_MOBILITY_END_GAME: i32 = 4;
+const BISHOP_MOBILITY_MIDDLE_GAME: i32 = 4;
+const BISHOP_MOBILITY_END_GAME: i32 = 5;
+const ROOK_MOBILITY_MIDDLE_GAME: i32 = 2;
+const ROOK_MOBILITY_END_GAME: i32 = 4;
+const QUEEN_MOBILITY_MIDDLE_GAME: i32 = 1;
+const QUEEN_MOBILITY_END_GAME: i32 = 2;
+
+/// how far the expensive terms (currently just mobility) are assumed to move the evaluation at most.
+/// a lazy evaluation clamps them to this, so the bounds it returns hold for its own full score
+pub const LAZY_EVAL_MARGIN: i32 = 200;
+
+/// the material and piece-square scores of each side, kept up to date as pieces are added and
+/// removed so the cheap part of the evaluation doesn't need to loop over the board
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Accumulator {
+    material: [i32; 2],
+    middle_game: [i32; 2],
+    end_game: [i32; 2],
+}
+
+impl Accumulator {
+    pub fn add_piece(&mut self, piece: Piece, square: Square) {
+        let (side, pst_square) = Self::perspective(piece, square);
+
+        self.material[side] += piece.material_value();
+        self.middle_game[side] += piece.middle_game_pst_value(pst_square);
+        self.end_game[side] += piece.end_game_pst_value(pst_square);
+    }
+
+    pub fn remove_piece(&mut self, piece: Piece, square: Square) {
+        let (side, pst_square) = Self::perspective(piece, square);
+
+        self.material[side] -= piece.material_value();
+        self.middle_game[side] -= piece.middle_game_pst_value(pst_square);
+        self.end_game[side] -= piece.end_game_pst_value(pst_square);
+    }
+
+    fn perspective(piece: Piece, square: Square) -> (usize, usize) {
+        match piece.color.known_side() {
+            Side::White => (Side::White.index(), FLIP_SQUARE[square.index()]),
+            Side::Black => (Side::Black.index(), square.index()),
+        }
+    }
+}
+
 impl Board {
-    pub fn evaluate(&self) -> i32 {
+    /// the full evaluation, from the perspective of the side to move
+    pub fn evaluate(&self) -> anyhow::Result<i32> {
+        self.ensure_initialized()?;
+
+        Ok(self.evaluate_unchecked())
+    }
+
+    // `evaluate` for the search, which has already checked there's a position before it starts
+    pub(crate) fn evaluate_unchecked(&self) -> i32 {
+        let _timer = profile::time(Phase::Evaluation);
+
         if self.has_insufficient_material() {
             return 0;
         }
 
-        let mut white_score = 0;
-        let mut black_score = 0;
+        self.perspective_multiplier() * (self.cheap_evaluation() + self.expensive_evaluation())
+    }
 
-        let mut white_middle_game_score = 0;
-        let mut black_middle_game_score = 0;
+    /// skips the expensive terms when the cheap score is already so far outside of the
+    /// `alpha`-`beta` window that they couldn't bring it back inside. in that case, the cheap score
+    /// is returned, which is on the same side of the window as `evaluate()` and no further than
+    /// `LAZY_EVAL_MARGIN` from it as long as mobility stays within the margin
+    pub fn evaluate_lazy(&self, alpha: i32, beta: i32) -> anyhow::Result<i32> {
+        self.ensure_initialized()?;
 
-        let mut white_end_game_score = 0;
-        let mut black_end_game_score = 0;
+        Ok(self.evaluate_lazy_unchecked(alpha, beta))
+    }
 
-        for (square_index, piece) in self.pieces().iter().enumerate() {
-            if piece.kind == PieceKind::NoPiece {
-                continue;
-            }
+    // `evaluate_lazy` for the capture search
+    pub(crate) fn evaluate_lazy_unchecked(&self, alpha: i32, beta: i32) -> i32 {
+        let _timer = profile::time(Phase::Evaluation);
 
-            match piece.color {
-                PieceColor::White => {
-                    white_score += piece.material_value();
-                    white_score += self.get_piece_mobility(*piece, square_index.into());
-                    white_middle_game_score +=
-                        piece.middle_game_pst_value(FLIP_SQUARE[square_index]);
-                    white_end_game_score += piece.end_game_pst_value(FLIP_SQUARE[square_index]);
-                }
-                PieceColor::Black => {
-                    black_score += piece.material_value();
-                    black_score += self.get_piece_mobility(*piece, square_index.into());
-                    black_middle_game_score += piece.middle_game_pst_value(square_index);
-                    black_end_game_score += piece.end_game_pst_value(square_index);
-                }
-                PieceColor::None => panic!("found a piece with no color"),
-            };
+        if self.has_insufficient_material() {
+            return 0;
         }
 
+        let cheap_score = self.perspective_multiplier() * self.cheap_evaluation();
+
+        if cheap_score + LAZY_EVAL_MARGIN <= alpha || cheap_score - LAZY_EVAL_MARGIN >= beta {
+            return cheap_score;
+        }
+
+        let expensive_score = self
+            .expensive_evaluation()
+            .clamp(-LAZY_EVAL_MARGIN, LAZY_EVAL_MARGIN);
+
+        cheap_score + self.perspective_multiplier() * expensive_score
+    }
+
+    fn perspective_multiplier(&self) -> i32 {
+        match self.side_to_move() {
+            Side::White => 1,
+            Side::Black => -1,
+        }
+    }
+
+    // material, piece-square tables and bishop pair, from white's perspective
+    fn cheap_evaluation(&self) -> i32 {
+        let accumulator = self.accumulator();
+        let white = Side::White.index();
+        let black = Side::Black.index();
+
+        let mut white_middle_game_score = accumulator.middle_game[white];
+        let mut black_middle_game_score = accumulator.middle_game[black];
+
+        let mut white_end_game_score = accumulator.end_game[white];
+        let mut black_end_game_score = accumulator.end_game[black];
+
         if self.piece_count(Piece::new(PieceColor::White, PieceKind::Bishop)) >= 2 {
             white_middle_game_score += BISHOP_PAIR_MIDDLE_GAME_BONUS;
             white_end_game_score += BISHOP_PAIR_END_GAME_BONUS;
@@ -247,20 +338,129 @@ impl Board {
             black_end_game_score += BISHOP_PAIR_END_GAME_BONUS;
         }
 
+        white_middle_game_score += self.castling_bonus(Side::White);
+        black_middle_game_score += self.castling_bonus(Side::Black);
+
         let phase = self.get_game_phase();
 
-        white_score +=
-            ((white_middle_game_score * (256 - phase)) + (white_end_game_score * phase)) / 256;
+        let white_score = accumulator.material[white]
+            + ((white_middle_game_score * (256 - phase)) + (white_end_game_score * phase)) / 256;
 
-        black_score +=
-            ((black_middle_game_score * (256 - phase)) + (black_end_game_score * phase)) / 256;
+        let black_score = accumulator.material[black]
+            + ((black_middle_game_score * (256 - phase)) + (black_end_game_score * phase)) / 256;
 
-        let multiplier = match self.side_to_move() {
-            Side::White => 1,
-            Side::Black => -1,
+        white_score - black_score
+    }
+
+    fn castling_bonus(&self, side: Side) -> i32 {
+        if self.has_castled(side) {
+            return CASTLED_MIDDLE_GAME_BONUS;
+        }
+
+        let (king_side, queen_side) = match side {
+            Side::White => (CastlingKind::WhiteKing, CastlingKind::WhiteQueen),
+            Side::Black => (CastlingKind::BlackKing, CastlingKind::BlackQueen),
         };
 
-        multiplier * (white_score - black_score)
+        let rights = self.can_castle(king_side) as i32 + self.can_castle(queen_side) as i32;
+
+        rights * CASTLING_RIGHT_MIDDLE_GAME_BONUS
+    }
+
+    // mobility, from white's perspective
+    fn expensive_evaluation(&self) -> i32 {
+        let (white_middle_game, white_end_game) = self.mobility(Side::White);
+        let (black_middle_game, black_end_game) = self.mobility(Side::Black);
+
+        let phase = self.get_game_phase();
+
+        let middle_game_score = white_middle_game - black_middle_game;
+        let end_game_score = white_end_game - black_end_game;
+
+        ((middle_game_score * (256 - phase)) + (end_game_score * phase)) / 256
+    }
+
+    // middle and end game mobility scores for `side`
+    fn mobility(&self, side: Side) -> (i32, i32) {
+        let area = self.mobility_area(side);
+
+        // the queen is worth so much that even a minor piece can chase it away, so squares those
+        // attack don't count for it
+        let queen_area = area & !self.minor_piece_attacks(!side);
+
+        let mut middle_game = 0;
+        let mut end_game = 0;
+
+        for (square_index, piece) in self.pieces().iter().enumerate() {
+            if piece.color.side() != Some(side) {
+                continue;
+            }
+
+            let square = square_index.into();
+
+            let (moves, middle_game_weight, end_game_weight) = match piece.kind {
+                PieceKind::Knight => (
+                    self.get_knight_mobility(square, area),
+                    KNIGHT_MOBILITY_MIDDLE_GAME,
+                    KNIGHT_MOBILITY_END_GAME,
+                ),
+                PieceKind::Bishop => (
+                    self.get_bishop_mobility(square, area),
+                    BISHOP_MOBILITY_MIDDLE_GAME,
+                    BISHOP_MOBILITY_END_GAME,
+                ),
+                PieceKind::Rook => (
+                    self.get_rook_mobility(square, area),
+                    ROOK_MOBILITY_MIDDLE_GAME,
+                    ROOK_MOBILITY_END_GAME,
+                ),
+                PieceKind::Queen => (
+                    self.get_queen_mobility(square, queen_area),
+                    QUEEN_MOBILITY_MIDDLE_GAME,
+                    QUEEN_MOBILITY_END_GAME,
+                ),
+                _ => continue,
+            };
+
+            middle_game += moves * middle_game_weight;
+            end_game += moves * end_game_weight;
+        }
+
+        (middle_game, end_game)
+    }
+
+    // squares worth counting as mobility for `side`. its own king and pawns rarely get out of the
+    // way, and a square an enemy pawn defends is no use to a piece
+    fn mobility_area(&self, side: Side) -> Bitboard {
+        let king = self.king_square(side).bitboard();
+        let pawns = self
+            .get_piece_bb(Piece::new(side.into(), PieceKind::Pawn))
+            .unwrap();
+
+        !(king | pawns | self.pawn_attacks_bb(!side))
+    }
+
+    fn minor_piece_attacks(&self, side: Side) -> Bitboard {
+        let occupancies = self.occupancy(Side::White) | self.occupancy(Side::Black);
+
+        let mut knights = self
+            .get_piece_bb(Piece::new(side.into(), PieceKind::Knight))
+            .unwrap();
+        let mut bishops = self
+            .get_piece_bb(Piece::new(side.into(), PieceKind::Bishop))
+            .unwrap();
+
+        let mut attacked = EMPTY_BB;
+
+        while knights != EMPTY_BB {
+            attacked |= attacks::knight(knights.pop_bit());
+        }
+
+        while bishops != EMPTY_BB {
+            attacked |= attacks::bishop(bishops.pop_bit(), occupancies);
+        }
+
+        attacked
     }
 
     // https://www.chessprogramming.org/Tapered_Eval#Implementation_example
@@ -288,91 +488,106 @@ impl Board {
         phase -= (white_rooks + black_rooks) * ROOK_PHASE;
         phase -= (white_queens + black_queens) * QUEEN_PHASE;
 
+        // extra material from promotions (or edited positions) would push the phase negative
+        phase = phase.clamp(0, TOTAL_PHASE);
+
         phase = (phase * 256 + (TOTAL_PHASE / 2)) / TOTAL_PHASE;
 
         phase
     }
 
-    fn get_piece_mobility(&self, piece: Piece, square: Square) -> i32 {
-        let side = piece.color.try_into().unwrap();
-
-        // this mobility isn't exact because the move generator generates pseudo-legal moves, but
-        // it should be a good enough approximation. probably not worth doing the computation to
-        // determine if a move is legal, but should experiment in future
-        match piece.kind {
-            PieceKind::Knight => self.get_knight_mobility(square, side),
-            PieceKind::Bishop => self.get_bishop_mobility(square, side),
-            PieceKind::Rook => self.get_rook_mobility(square, side),
-            PieceKind::Queen => self.get_queen_mobility(square, side),
-            _ => 0,
-        }
-    }
-
-    fn get_knight_mobility(&self, square: Square, side: Side) -> i32 {
-        let current_side_occupancy = self.occupancy(side);
-
-        let possible_moves = KNIGHT_ATTACKS[square.index()];
-
-        let moves = possible_moves & !current_side_occupancy;
+    // this mobility isn't exact because the move generator generates pseudo-legal moves, but it
+    // should be a good enough approximation. probably not worth doing the computation to determine
+    // if a move is legal, but should experiment in future
+    fn get_knight_mobility(&self, square: Square, area: Bitboard) -> i32 {
+        let moves = attacks::knight(square) & area;
 
         moves.count_ones() as i32
     }
 
-    fn get_bishop_mobility(&self, square: Square, side: Side) -> i32 {
-        let current_side_occupancy = self.occupancy(side);
-        let enemy_occupancy = self.occupancy(!side);
-
-        let magic = BISHOP_MAGICS[square.index()];
+    fn get_bishop_mobility(&self, square: Square, area: Bitboard) -> i32 {
+        let occupancies = self.occupancy(Side::White) | self.occupancy(Side::Black);
 
-        let occupancies = current_side_occupancy | enemy_occupancy;
+        let possible_moves = attacks::bishop(square, occupancies);
 
-        let possible_moves = self
-            .move_generator
-            .get_bishop_attacks(magic.get_magic_index(occupancies));
-
-        let moves = possible_moves & !current_side_occupancy;
+        let moves = possible_moves & area;
 
         moves.count_ones() as i32
     }
 
-    fn get_rook_mobility(&self, square: Square, side: Side) -> i32 {
-        let current_side_occupancy = self.occupancy(side);
-        let enemy_occupancy = self.occupancy(!side);
-
-        let magic = ROOK_MAGICS[square.index()];
+    fn get_rook_mobility(&self, square: Square, area: Bitboard) -> i32 {
+        let occupancies = self.occupancy(Side::White) | self.occupancy(Side::Black);
 
-        let occupancies = current_side_occupancy | enemy_occupancy;
+        let possible_moves = attacks::rook(square, occupancies);
 
-        let possible_moves = self
-            .move_generator
-            .get_rook_attacks(magic.get_magic_index(occupancies));
-
-        let moves = possible_moves & !current_side_occupancy;
+        let moves = possible_moves & area;
 
         moves.count_ones() as i32
     }
 
-    fn get_queen_mobility(&self, square: Square, side: Side) -> i32 {
-        let current_side_occupancy = self.occupancy(side);
-        let enemy_occupancy = self.occupancy(!side);
+    fn get_queen_mobility(&self, square: Square, area: Bitboard) -> i32 {
+        let occupancies = self.occupancy(Side::White) | self.occupancy(Side::Black);
 
-        let bishop_magic = BISHOP_MAGICS[square.index()];
-        let rook_magic = ROOK_MAGICS[square.index()];
+        let possible_moves = attacks::queen(square, occupancies);
 
-        let occupancies = current_side_occupancy | enemy_occupancy;
+        let moves = possible_moves & area;
 
-        let bishop_moves = self
-            .move_generator
-            .get_bishop_attacks(bishop_magic.get_magic_index(occupancies));
+        moves.count_ones() as i32
+    }
+}
 
-        let rook_moves = self
-            .move_generator
-            .get_rook_attacks(rook_magic.get_magic_index(occupancies));
+/// the evaluation of a position, from the perspective of the side to move, with nothing but a
+/// board behind it. meant for scoring positions from outside of the engine, like labelling a
+/// dataset
+pub fn evaluate_fen(fen: &str) -> anyhow::Result<i32> {
+    let mut board = Board::default();
+    board.parse_fen(fen)?;
 
-        let possible_moves = bishop_moves | rook_moves;
+    board.evaluate()
+}
 
-        let moves = possible_moves & !current_side_occupancy;
+/// `evaluate_fen` for every one of `fens`, shared out between `threads` threads. the results are
+/// in the same order as `fens`, and a FEN that can't be parsed gives an error in its place
+pub fn evaluate_many<'a>(
+    fens: impl IntoIterator<Item = &'a str>,
+    threads: usize,
+) -> Vec<anyhow::Result<i32>> {
+    let fens: Vec<&str> = fens.into_iter().collect();
+    let threads = threads.clamp(1, fens.len().max(1));
+
+    let mut results: Vec<_> = (0..fens.len()).map(|_| None).collect();
+
+    thread::scope(|scope| {
+        // every `threads`th position, the same as quick perft
+        let workers: Vec<_> = (0..threads)
+            .map(|first| {
+                let fens = &fens;
+
+                scope.spawn(move || {
+                    let mut board = Board::default();
+
+                    fens.iter()
+                        .enumerate()
+                        .skip(first)
+                        .step_by(threads)
+                        .map(|(index, fen)| {
+                            let score = board.parse_fen(fen).and_then(|_| board.evaluate());
+                            (index, score)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            for (index, score) in worker.join().expect("evaluation thread panicked") {
+                results[index] = Some(score);
+            }
+        }
+    });
 
-        moves.count_ones() as i32
-    }
+    results
+        .into_iter()
+        .map(|score| score.expect("every position is evaluated by one of the threads"))
+        .collect()
 }
@@ -1,37 +1,46 @@
-// not used by the main program. this is just a demonstration of how the magics have been
-// generated. based on this example by Tord Romstad: https://www.chessprogramming.org/Looking_for_Magics#Feeding_in_Randoms
+// the tables in `magics.rs` were generated by this, with seed 123. run `genmagics` in the CLI to
+// regenerate them. based on this example by Tord Romstad: https://www.chessprogramming.org/Looking_for_Magics#Feeding_in_Randoms
 // as well as this article by Analog Hors https://analog-hors.github.io/site/magic-bitboards/
 
 use crate::{
-    bitboard::{Bitboard, EMPTY_BB},
-    move_generator::{
+    attacks::{
         generate_sliding_attack_mask, generate_sliding_blocker_mask, BISHOP_DIRECTIONS,
         ROOK_DIRECTIONS,
     },
+    bitboard::{Bitboard, EMPTY_BB},
     prng::Prng,
     square::Square,
 };
 
+/// with seed 123 and no dense search, the output matches the tables in `magics.rs`
+pub const DEFAULT_MAGIC_SEED: u64 = 123;
+
+// how many candidates a dense search tries before giving up on finding a magic that needs one
+// fewer index bit
+const DENSE_SEARCH_ATTEMPTS: usize = 100_000;
+
 struct MagicCandidate {
     magic: u64,
     mask: Bitboard,
-    bits_in_mask: u8,
+    // normally the same as the number of bits in the mask, but a dense search looks for magics
+    // where some blocker combinations share an index because they have the same attacks
+    index_bits: u8,
     prng: Prng,
 }
 
-struct DiscoveredMagic {
-    magic: u64,
-    shift: u8,
-    mask: u64,
+pub struct DiscoveredMagic {
+    pub magic: u64,
+    pub shift: u8,
+    pub mask: u64,
 }
 
 impl MagicCandidate {
-    fn new(mask: Bitboard) -> Self {
+    fn new(mask: Bitboard, seed: u64) -> Self {
         Self {
             magic: 0,
             mask,
-            bits_in_mask: mask.0.count_ones() as u8,
-            prng: Prng::new(123),
+            index_bits: mask.0.count_ones() as u8,
+            prng: Prng::new(seed),
         }
     }
 
@@ -42,28 +51,106 @@ impl MagicCandidate {
     fn get_magic_index(&self, blockers: Bitboard) -> usize {
         let blockers = self.mask & blockers;
         let hash = self.magic.wrapping_mul(blockers.0);
-        let shift = 64 - self.bits_in_mask;
+        let shift = 64 - self.index_bits;
         (hash >> shift) as usize
     }
 }
 
-fn find_magic(square: Square, directions: [(i32, i32); 4]) -> (DiscoveredMagic, usize) {
+/// the first magic for `square` that the search seeded with `seed` finds, and the size of the attack
+/// table it needs. every square's search starts from the seed, so the result doesn't depend on the
+/// squares found before it
+pub fn find_magic(
+    square: Square,
+    directions: [(i32, i32); 4],
+    seed: u64,
+    dense: bool,
+) -> (DiscoveredMagic, usize) {
     let blocker_mask = generate_sliding_blocker_mask(square, directions);
 
-    let mut candidate = MagicCandidate::new(blocker_mask);
+    let mut candidate = MagicCandidate::new(blocker_mask, seed);
+    let mut found = search_magic(&mut candidate, square, directions, None).unwrap();
+
+    if dense {
+        while candidate.index_bits > 1 {
+            candidate.index_bits -= 1;
+
+            match search_magic(
+                &mut candidate,
+                square,
+                directions,
+                Some(DENSE_SEARCH_ATTEMPTS),
+            ) {
+                Some(smaller) => found = smaller,
+                None => break,
+            }
+        }
+    }
+
+    found
+}
+
+// tries candidates until one works, or until `max_attempts` have failed
+fn search_magic(
+    candidate: &mut MagicCandidate,
+    square: Square,
+    directions: [(i32, i32); 4],
+    max_attempts: Option<usize>,
+) -> Option<(DiscoveredMagic, usize)> {
+    let mut attempts = 0;
 
     loop {
         candidate.update_magic();
 
-        if let Some(table_size) = check_magic(&candidate, square, directions) {
-            return (
+        if let Some(table_size) = check_magic(candidate, square, directions) {
+            return Some((
                 DiscoveredMagic {
                     magic: candidate.magic,
-                    shift: 64 - candidate.bits_in_mask,
+                    shift: 64 - candidate.index_bits,
                     mask: candidate.mask.0,
                 },
                 table_size,
-            );
+            ));
+        }
+
+        attempts += 1;
+
+        if max_attempts.is_some_and(|max_attempts| attempts >= max_attempts) {
+            return None;
+        }
+    }
+}
+
+/// rebuilds the attack table from scratch and checks every blocker combination against the
+/// slow ray-walking generator, independently of the checks made while searching
+pub fn verify_magic(magic: &DiscoveredMagic, square: Square, directions: [(i32, i32); 4]) -> bool {
+    let mask = Bitboard(magic.mask);
+    let index_of = |blockers: Bitboard| {
+        (magic.magic.wrapping_mul((blockers & mask).0) >> magic.shift) as usize
+    };
+
+    let mut attack_table = vec![None; 1 << (64 - magic.shift)];
+    let mut blockers = EMPTY_BB;
+
+    loop {
+        attack_table[index_of(blockers)] =
+            Some(generate_sliding_attack_mask(square, blockers, directions));
+
+        blockers = (blockers - mask) & mask;
+        if blockers == EMPTY_BB {
+            break;
+        }
+    }
+
+    loop {
+        let expected = generate_sliding_attack_mask(square, blockers, directions);
+
+        if attack_table[index_of(blockers)] != Some(expected) {
+            return false;
+        }
+
+        blockers = (blockers - mask) & mask;
+        if blockers == EMPTY_BB {
+            return true;
         }
     }
 }
@@ -73,7 +160,7 @@ fn check_magic(
     square: Square,
     directions: [(i32, i32); 4],
 ) -> Option<usize> {
-    let max_table_size = 1 << candidate.bits_in_mask;
+    let max_table_size = 1 << candidate.index_bits;
     let mut attack_table = vec![EMPTY_BB; max_table_size];
 
     let mut blockers = EMPTY_BB;
@@ -99,34 +186,20 @@ fn check_magic(
     Some(attack_table.len())
 }
 
-fn print_rook_magics() {
-    println!("pub const ROOK_MAGICS: [MagicNumber; 64] = [");
+fn print_magic_table(name: &str, directions: [(i32, i32); 4], seed: u64, dense: bool) -> usize {
+    println!("pub const {}_MAGICS: [MagicNumber; 64] = [", name);
 
     let mut total_size = 0;
 
     for square in 0..64usize {
-        let (DiscoveredMagic { magic, shift, mask }, size) =
-            find_magic(square.into(), ROOK_DIRECTIONS);
-        println!(
-            "\tMagicNumber {{ magic: 0x{:016X}, shift: {}, offset: {}, blocker_mask: 0x{:016x} }},",
-            magic, shift, total_size, mask
-        );
-        total_size += size;
-    }
+        let (magic, size) = find_magic(square.into(), directions, seed, dense);
 
-    println!("];");
-
-    println!("pub const ROOK_ATTACK_TABLE_SIZE: usize = {total_size};");
-}
-
-fn print_bishop_magics() {
-    println!("pub const BISHOP_MAGICS: [MagicNumber; 64] = [");
+        if !verify_magic(&magic, square.into(), directions) {
+            panic!("magic for square {} failed verification", square);
+        }
 
-    let mut total_size = 0;
+        let DiscoveredMagic { magic, shift, mask } = magic;
 
-    for square in 0..64usize {
-        let (DiscoveredMagic { magic, shift, mask }, size) =
-            find_magic(square.into(), BISHOP_DIRECTIONS);
         println!(
             "\tMagicNumber {{ magic: 0x{:016X}, shift: {}, offset: {}, blocker_mask: 0x{:016x} }},",
             magic, shift, total_size, mask
@@ -136,10 +209,15 @@ fn print_bishop_magics() {
 
     println!("];");
 
-    println!("pub const BISHOP_ATTACK_TABLE_SIZE: usize = {total_size};");
+    println!("pub const {name}_ATTACK_TABLE_SIZE: usize = {total_size};");
+
+    total_size
 }
 
-pub fn print_magics() {
-    print_rook_magics();
-    print_bishop_magics();
+pub fn print_magics(seed: u64, dense: bool) {
+    let rook_size = print_magic_table("ROOK", ROOK_DIRECTIONS, seed, dense);
+    let bishop_size = print_magic_table("BISHOP", BISHOP_DIRECTIONS, seed, dense);
+
+    let total_bytes = (rook_size + bishop_size) * std::mem::size_of::<Bitboard>();
+    println!("// total attack table size: {} bytes", total_bytes);
 }
@@ -0,0 +1,33 @@
+use std::io::{self, BufRead, Read};
+
+// nothing legitimate comes close to this. a `position` command for a very long game is still only
+// a few kilobytes
+pub const MAX_LINE_LENGTH: u64 = 64 * 1024;
+
+pub enum InputLine {
+    Line,
+    TooLong,
+    Eof,
+}
+
+/// reads a single line into `buffer`, clearing it first. a final line without a trailing newline
+/// is still returned as a line. lines longer than `MAX_LINE_LENGTH` are discarded rather than
+/// buffered
+pub fn read_line(input: &mut impl BufRead, buffer: &mut String) -> io::Result<InputLine> {
+    buffer.clear();
+
+    let bytes_read = input.by_ref().take(MAX_LINE_LENGTH).read_line(buffer)?;
+
+    if bytes_read == 0 {
+        return Ok(InputLine::Eof);
+    }
+
+    if buffer.ends_with('\n') || (bytes_read as u64) < MAX_LINE_LENGTH {
+        return Ok(InputLine::Line);
+    }
+
+    buffer.clear();
+    input.skip_until(b'\n')?;
+
+    Ok(InputLine::TooLong)
+}
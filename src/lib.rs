@@ -1,17 +1,36 @@
+pub mod attacks;
+pub mod bench;
 pub mod bitboard;
 pub mod board;
 pub mod cli;
+pub mod debug_sink;
 pub mod engine_details;
+pub mod epd;
 pub mod evaluate;
 pub mod generate_magics;
+pub mod input;
 pub mod magics;
 pub mod make_move;
 pub mod move_generator;
+pub mod options;
 pub mod perft;
+pub mod perft_table;
+pub mod position;
 pub mod prng;
+pub mod profile;
+pub mod reporter;
+pub mod root_moves;
+pub mod san;
 pub mod search;
+pub mod search_config;
+pub mod search_limits;
+pub mod see;
+pub mod shutdown;
 pub mod square;
+pub mod style;
 pub mod time_management;
 pub mod transposition_table;
 pub mod uci;
 pub mod zobrist_hash;
+
+pub use evaluate::{evaluate_fen, evaluate_many};
@@ -2,8 +2,8 @@ use crate::bitboard::Bitboard;
 
 #[derive(Clone, Copy)]
 pub struct MagicNumber {
-    magic: u64,
-    shift: u8,
+    pub magic: u64,
+    pub shift: u8,
     offset: usize,
     pub blocker_mask: u64,
 }
@@ -1,8 +1,22 @@
-use krusty::cli::Cli;
+use std::env;
+
+use krusty::{cli::Cli, shutdown, style};
 
 fn main() -> anyhow::Result<()> {
+    style::init();
+    shutdown::install_interrupt_handler()?;
+
     let mut cli = Cli::default();
-    cli.start_loop();
+
+    // `krusty bench` and the like run that one command and exit, which is how testing scripts get
+    // a bench signature
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args.is_empty() {
+        cli.start_loop();
+    } else {
+        cli.run_command(&args.join(" "));
+    }
 
     Ok(())
 }
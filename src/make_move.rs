@@ -1,9 +1,12 @@
 use anyhow::bail;
 
 use crate::{
-    bitboard::EMPTY_BB,
-    board::{Board, CastlingKind, HistoryItem, Side},
-    move_generator::{pawn_attacks, Move, MoveFlag, MoveKind, MoveList},
+    attacks,
+    bitboard::{Bitboard, EMPTY_BB},
+    board::{Board, HistoryItem, Side},
+    move_generator::{Move, MoveFlag, MoveKind, MoveList},
+    position,
+    profile::{self, Phase},
     square::{Piece, PieceKind, Square},
 };
 
@@ -16,64 +19,73 @@ pub struct MoveMetadata {
     promotion: Option<PieceKind>,
 }
 
-const fn init_castling_permissions_table() -> [u8; 64] {
-    let mut table = [15; 64];
-
-    let white_queen = CastlingKind::WhiteQueen as u8;
-    let white_king = CastlingKind::WhiteKing as u8;
-    let black_queen = CastlingKind::BlackQueen as u8;
-    let black_king = CastlingKind::BlackKing as u8;
-
-    table[Square::A1 as usize] = 15 - white_queen;
-    table[Square::E1 as usize] = 15 - white_queen - white_king;
-    table[Square::H1 as usize] = 15 - white_king;
+impl From<Move> for MoveMetadata {
+    fn from(mv: Move) -> Self {
+        let promotion = match mv.kind() {
+            MoveKind::Promotion => Some(mv.flag().into()),
+            _ => None,
+        };
 
-    table[Square::A8 as usize] = 15 - black_queen;
-    table[Square::E8 as usize] = 15 - black_queen - black_king;
-    table[Square::H8 as usize] = 15 - black_king;
+        Self {
+            from: mv.from_square(),
+            to: mv.uci_to_square(),
+            promotion,
+        }
+    }
+}
 
-    table
+impl MoveMetadata {
+    /// the piece a promotion was written with, if any
+    pub fn promotion(&self) -> Option<PieceKind> {
+        self.promotion
+    }
 }
 
-/// this table allows us to update the castling rights after each move is made
-/// each castling permission is represented by its own bit in a 4-bit int (see `CastlingKind` def)
-///
-/// the values in this table represent what the new castling rights would be after removing the
-/// relevant rights (assuming you started with full rights)
-///
-/// as an example, if the rook on A1 moves or is captured, white can no longer castle queenside, so
-/// we subtract the value of that right from 15 (0b1111)
-///
-/// in practice, the value of this table will be bitwise AND'd with the current castling rights to
-/// get the updated rights
-const CASTLING_PERMISSIONS_TABLE: [u8; 64] = init_castling_permissions_table();
+/// how UCI writes a null move
+pub const NULL_MOVE_STR: &str = "0000";
 
 impl Board {
     pub fn make_move(&mut self, mv: Move) -> anyhow::Result<bool> {
+        self.ensure_initialized()?;
+        let _timer = profile::time(Phase::MakeUnmake);
+
         let old_hash = self.hash();
         let from_square = mv.from_square();
         let to_square = mv.to_square();
-        let moved_piece = self.remove_piece_and_hash(from_square)?;
+
+        // everything after this only moves pieces that are known to be there, so a move that
+        // didn't come from the move generator gets checked before the board is touched
+        if self.get_piece(from_square).kind == PieceKind::NoPiece {
+            bail!("tried to move from the empty square {:?}", from_square);
+        }
+
+        if mv.kind() == MoveKind::Castle && self.get_piece(to_square).kind != PieceKind::Rook {
+            bail!("tried to castle without a rook on {:?}", to_square);
+        }
+
+        let moved_piece = self.remove_piece_and_hash(from_square);
 
         let mut history_item = HistoryItem {
             castling_rights: self.castling_rights(),
             en_passant_square: self.en_passant_square(),
             halfmove_clock: self.halfmove_clock(),
             moved_piece,
-            captured_piece: self.get_piece(to_square),
+            // castling moves land on the king's own rook
+            captured_piece: match mv.kind() {
+                MoveKind::Castle => Piece::default(),
+                _ => self.get_piece(to_square),
+            },
             hash: old_hash,
+            has_castled: self.castled_flags(),
         };
 
         self.increment_clock();
 
-        // due to the behaviour of XOR (a ^ b ^ b == a), calling the hash function with the same square will
-        // remove its value from the hash, then we can hash it again later when we get the new en
-        // passant square
-        self.hash_en_passant_square();
-        self.set_en_passant_square(Square::None);
+        // a double push sets a new one further down
+        self.clear_en_passant_square_and_hash();
 
         match mv.kind() {
-            MoveKind::Quiet => self.add_piece_and_hash(moved_piece, to_square)?,
+            MoveKind::Quiet => self.add_piece_and_hash(moved_piece, to_square),
             MoveKind::Capture => {
                 // captures (and pawn pushes, handled lower down) reset halfmove clock for 50-move
                 // rule
@@ -85,29 +97,27 @@ impl Board {
                         Side::Black => to_square.north(),
                     };
 
-                    history_item.captured_piece = self.remove_piece_and_hash(captured_square)?;
-                    self.add_piece_and_hash(moved_piece, to_square)?;
+                    history_item.captured_piece = self.remove_piece_and_hash(captured_square);
+                    self.add_piece_and_hash(moved_piece, to_square);
                 } else {
-                    self.remove_piece_and_hash(to_square)?;
-                    self.add_piece_and_hash(moved_piece, to_square)?;
+                    self.remove_piece_and_hash(to_square);
+                    self.add_piece_and_hash(moved_piece, to_square);
                 }
             }
             MoveKind::Castle => {
-                self.add_piece_and_hash(moved_piece, to_square)?;
-
-                let (rook_from, rook_to) = match to_square {
-                    Square::G1 => (Square::H1, Square::F1),
-                    Square::C1 => (Square::A1, Square::D1),
-                    Square::G8 => (Square::H8, Square::F8),
-                    Square::C8 => (Square::A8, Square::D8),
-                    _ => bail!("tried to castle to illegal square: {:?}", to_square),
-                };
-                let rook = self.remove_piece_and_hash(rook_from)?;
-                self.add_piece_and_hash(rook, rook_to)?;
+                // in Chess960 the king or rook can end up where the other started, so both are
+                // taken off the board before either is put back
+                let (king_to, rook_to) = mv.castling_destinations();
+                let rook = self.remove_piece_and_hash(to_square);
+
+                self.add_piece_and_hash(moved_piece, king_to);
+                self.add_piece_and_hash(rook, rook_to);
+
+                self.mark_castled_and_hash(self.side_to_move());
             }
             MoveKind::Promotion => {
                 if self.get_piece(to_square).kind != PieceKind::NoPiece {
-                    self.remove_piece_and_hash(to_square)?;
+                    self.remove_piece_and_hash(to_square);
                 }
 
                 let promotion_piece = match mv.flag() {
@@ -126,7 +136,7 @@ impl Board {
                     _ => bail!("tried to make promotion move without providing a promotion flag"),
                 };
 
-                self.add_piece_and_hash(promotion_piece, to_square)?;
+                self.add_piece_and_hash(promotion_piece, to_square);
             }
         };
 
@@ -146,11 +156,10 @@ impl Board {
                 let enemy_pawns =
                     self.get_piece_bb(Piece::new((!self.side_to_move()).into(), PieceKind::Pawn))?;
 
-                let attacks = pawn_attacks(self.side_to_move())[ep_square.index()];
+                let attackers = attacks::pawn(self.side_to_move(), ep_square);
 
-                if attacks & enemy_pawns != EMPTY_BB {
-                    self.set_en_passant_square(ep_square);
-                    self.hash_en_passant_square();
+                if attackers & enemy_pawns != EMPTY_BB {
+                    self.set_en_passant_square_and_hash(ep_square);
                 }
             }
         }
@@ -158,8 +167,8 @@ impl Board {
         // clear current castling hash
         self.hash_castling_rights();
         let new_castling_rights = self.castling_rights()
-            & CASTLING_PERMISSIONS_TABLE[from_square.index()]
-            & CASTLING_PERMISSIONS_TABLE[to_square.index()];
+            & self.castling_permissions(from_square)
+            & self.castling_permissions(to_square);
         self.set_castling_rights(new_castling_rights);
         // hash new castling rights
         self.hash_castling_rights();
@@ -174,24 +183,25 @@ impl Board {
         Ok(!self.is_in_check(!self.side_to_move()))
     }
 
-    pub fn unmake_move(&mut self, mv: Move) -> anyhow::Result<()> {
+    pub fn unmake_move(&mut self, mv: Move) {
+        let _timer = profile::time(Phase::MakeUnmake);
+
         let history_item: HistoryItem = self.pop_history();
 
         self.set_castling_rights(history_item.castling_rights);
         self.set_en_passant_square(history_item.en_passant_square);
         self.set_halfmove_clock(history_item.halfmove_clock);
         self.set_hash(history_item.hash);
+        self.set_castled_flags(history_item.has_castled);
 
         self.switch_side();
 
         let from_square = mv.from_square();
         let to_square = mv.to_square();
 
-        self.add_piece(history_item.moved_piece, from_square)?;
-
         match mv.kind() {
             MoveKind::Quiet => {
-                self.remove_piece(to_square)?;
+                self.remove_piece(to_square);
             }
             MoveKind::Capture => {
                 if mv.flag() == MoveFlag::EnPassant {
@@ -200,40 +210,32 @@ impl Board {
                         Side::Black => to_square.north(),
                     };
 
-                    self.remove_piece(to_square)?;
-                    self.add_piece(history_item.captured_piece, captured_square)?;
+                    self.remove_piece(to_square);
+                    self.add_piece(history_item.captured_piece, captured_square);
                 } else {
-                    self.remove_piece(to_square)?;
-                    self.add_piece(history_item.captured_piece, to_square)?;
+                    self.remove_piece(to_square);
+                    self.add_piece(history_item.captured_piece, to_square);
                 }
             }
             MoveKind::Castle => {
-                // remove the king
-                self.remove_piece(to_square)?;
-
-                // put the rook back to its original square
-                let (rook_from, rook_to) = match to_square {
-                    Square::G1 => (Square::H1, Square::F1),
-                    Square::C1 => (Square::A1, Square::D1),
-                    Square::G8 => (Square::H8, Square::F8),
-                    Square::C8 => (Square::A8, Square::D8),
-                    _ => bail!(
-                        "tried to unmake illegal castling move with `to_square`: {:?}",
-                        to_square
-                    ),
-                };
-                let rook = self.remove_piece(rook_to)?;
-                self.add_piece(rook, rook_from)?;
+                // the same as when the move was made, both come off the board before either goes
+                // back. the king goes back below
+                let (king_to, rook_to) = mv.castling_destinations();
+
+                self.remove_piece(king_to);
+                let rook = self.remove_piece(rook_to);
+                self.add_piece(rook, to_square);
             }
             MoveKind::Promotion => {
-                self.remove_piece(to_square)?;
+                self.remove_piece(to_square);
                 if history_item.captured_piece.kind != PieceKind::NoPiece {
-                    self.add_piece(history_item.captured_piece, to_square)?;
+                    self.add_piece(history_item.captured_piece, to_square);
                 }
             }
         };
 
-        Ok(())
+        // the moved piece's square is empty by now, whatever the kind of move
+        self.add_piece(history_item.moved_piece, from_square);
     }
 
     pub fn make_null_move(&mut self) {
@@ -246,12 +248,12 @@ impl Board {
             moved_piece: Piece::default(),
             captured_piece: Piece::default(),
             hash: old_hash,
+            has_castled: self.castled_flags(),
         };
 
         self.reset_clock();
 
-        self.hash_en_passant_square();
-        self.set_en_passant_square(Square::None);
+        self.clear_en_passant_square_and_hash();
 
         self.switch_side_and_hash();
 
@@ -265,36 +267,48 @@ impl Board {
         self.set_en_passant_square(history_item.en_passant_square);
         self.set_halfmove_clock(history_item.halfmove_clock);
         self.set_hash(history_item.hash);
+        self.set_castled_flags(history_item.has_castled);
 
         self.switch_side();
     }
 
+    /// hands the turn to the other side without playing a move, for asking what the other side
+    /// would do here. it's recorded as a null move, so `unmake_null_move` takes it back. refused
+    /// when the side to move is in check, since the other side could then take the king
+    pub fn switch_side_to_move(&mut self) -> anyhow::Result<()> {
+        self.ensure_initialized()?;
+
+        if self.is_in_check(self.side_to_move()) {
+            bail!("Can't switch sides while the side to move is in check");
+        }
+
+        self.make_null_move();
+        Ok(())
+    }
+
     pub fn get_move_metadata(&self, move_str: &str) -> anyhow::Result<MoveMetadata> {
-        if move_str.len() < 4 {
+        if move_str == NULL_MOVE_STR {
             bail!("Move string `{}` is invalid", move_str)
         }
 
-        let mut chars = move_str.chars();
-        let from_file = chars.next().unwrap().try_into()?;
-        let from_rank = chars.next().unwrap().try_into()?;
-        let to_file = chars.next().unwrap().try_into()?;
-        let to_rank = chars.next().unwrap().try_into()?;
+        let mv: Move = move_str.parse()?;
 
-        let from = Square::new(from_rank, from_file);
-        let to = Square::new(to_rank, to_file);
+        Ok(mv.into())
+    }
 
-        let promotion: Option<PieceKind> = match chars.next() {
-            Some(piece) => Some(piece.try_into()?),
-            None => None,
-        };
+    /// plays a whitespace-separated list of moves in long algebraic notation, see
+    /// `position::apply_moves`. if any move can't be played, the board is left exactly as it was
+    /// before
+    pub fn make_moves_from_str(&mut self, moves: &str) -> anyhow::Result<()> {
+        let moves: Vec<_> = moves.split_whitespace().collect();
+        position::apply_moves(self, &moves)?;
 
-        Ok(MoveMetadata {
-            from,
-            to,
-            promotion,
-        })
+        Ok(())
     }
 
+    /// the pseudo-legal move matching the metadata, which may still leave the king in check. use
+    /// `find_legal_move` to rule that out. a promotion without a piece is to a queen, unless
+    /// that's been turned off with `set_assume_queen_promotion`
     pub fn find_matching_move(&self, move_metadata: MoveMetadata) -> Option<Move> {
         let MoveMetadata {
             from,
@@ -302,14 +316,19 @@ impl Board {
             promotion,
         } = move_metadata;
 
+        let promotion = match promotion {
+            None if self.assumes_queen_promotion() => Some(PieceKind::Queen),
+            promotion => promotion,
+        };
+
         let mut possible_moves = MoveList::default();
         self.generate_all_moves(&mut possible_moves).unwrap();
 
         for possible_move in possible_moves {
-            if possible_move.from_square() == from && possible_move.to_square() == to {
+            if possible_move.from_square() == from && possible_move.uci_to_square() == to {
+                // a promotion without a piece doesn't match any of the four moves
                 if possible_move.kind() == MoveKind::Promotion {
-                    let promotion_piece = promotion.expect("promotion piece missing");
-                    if promotion_piece == possible_move.flag().into() {
+                    if promotion == Some(possible_move.flag().into()) {
                         return Some(possible_move);
                     }
                 } else {
@@ -320,4 +339,82 @@ impl Board {
 
         None
     }
+
+    // whether the move would have matched a promotion if it had a piece, and one wasn't assumed
+    pub(crate) fn is_promotion_without_piece(&self, move_metadata: MoveMetadata) -> bool {
+        move_metadata.promotion.is_none()
+            && !self.assumes_queen_promotion()
+            && self
+                .find_matching_move(MoveMetadata {
+                    promotion: Some(PieceKind::Queen),
+                    ..move_metadata
+                })
+                .is_some()
+    }
+
+    /// like `find_matching_move`, but the move is tried on the board first, so a move that would
+    /// leave the king in check gives `None`. the board is the same afterwards either way
+    pub fn find_legal_move(&mut self, move_metadata: MoveMetadata) -> anyhow::Result<Option<Move>> {
+        let Some(mv) = self.find_matching_move(move_metadata) else {
+            return Ok(None);
+        };
+
+        let is_legal = self.make_move(mv)?;
+        self.unmake_move(mv);
+
+        Ok(is_legal.then_some(mv))
+    }
+
+    pub fn first_legal_move(&mut self) -> anyhow::Result<Option<Move>> {
+        let mut move_list = MoveList::default();
+        self.generate_all_moves(&mut move_list)?;
+
+        for mv in move_list {
+            let is_legal = self.make_move(mv)?;
+            self.unmake_move(mv);
+
+            if is_legal {
+                return Ok(Some(mv));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// every legal move of the piece on `from`, with each promotion as a move of its own. empty
+    /// when there's nothing of the side to move there. the board is the same afterwards
+    pub fn legal_moves_from(&mut self, from: Square) -> anyhow::Result<Vec<Move>> {
+        let mut move_list = MoveList::default();
+        self.generate_all_moves(&mut move_list)?;
+
+        let mut moves = Vec::new();
+
+        for mv in move_list {
+            if mv.from_square() != from {
+                continue;
+            }
+
+            let is_legal = self.make_move(mv)?;
+            self.unmake_move(mv);
+
+            if is_legal {
+                moves.push(mv);
+            }
+        }
+
+        Ok(moves)
+    }
+
+    /// the squares the piece on `from` can legally move to, for highlighting them. castling shows
+    /// up as the king's square in UCI notation, so g1 or c1 rather than the rook outside of
+    /// Chess960
+    pub fn legal_destinations(&mut self, from: Square) -> anyhow::Result<Bitboard> {
+        let mut destinations = EMPTY_BB;
+
+        for mv in self.legal_moves_from(from)? {
+            destinations.set_bit(mv.uci_to_square());
+        }
+
+        Ok(destinations)
+    }
 }
@@ -1,9 +1,16 @@
-use std::fmt::{Debug, Display};
+use std::{
+    fmt::{Debug, Display},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context};
 
 use crate::{
+    attacks,
     bitboard::{Bitboard, EMPTY_BB},
-    board::{Board, CastlingKind, Side},
-    magics::{BISHOP_ATTACK_TABLE_SIZE, BISHOP_MAGICS, ROOK_ATTACK_TABLE_SIZE, ROOK_MAGICS},
+    board::{castling_destinations, Board, CastlingKind, Side},
+    make_move::NULL_MOVE_STR,
+    profile::{self, Phase},
     square::{Piece, PieceKind, Rank, Square},
 };
 
@@ -35,6 +42,8 @@ pub enum MoveFlag {
     BishopPromotion = 0b011,
     RookPromotion = 0b100,
     QueenPromotion = 0b101,
+    // castling generated on a Chess960 board, which is written as the king taking its rook
+    KingTakesRook = 0b110,
 }
 
 impl From<u32> for MoveFlag {
@@ -46,6 +55,7 @@ impl From<u32> for MoveFlag {
             x if x == (MoveFlag::BishopPromotion as u32) => MoveFlag::BishopPromotion,
             x if x == (MoveFlag::RookPromotion as u32) => MoveFlag::RookPromotion,
             x if x == (MoveFlag::QueenPromotion as u32) => MoveFlag::QueenPromotion,
+            x if x == (MoveFlag::KingTakesRook as u32) => MoveFlag::KingTakesRook,
             _ => panic!("invalid move flag"),
         }
     }
@@ -68,6 +78,9 @@ impl Move {
     const MOVE_FLAG_MASK: u32 = 0b111;
     const MOVE_SCORE_MASK: u32 = 0xfffe0000;
 
+    /// the highest ordering score that fits alongside the rest of the move
+    pub const MAX_SCORE: u32 = Self::MOVE_SCORE_MASK >> 17;
+
     pub fn new(from: Square, to: Square, kind: MoveKind, flag: MoveFlag) -> Self {
         let from = from as u32;
         let to = to as u32;
@@ -93,26 +106,71 @@ impl Move {
         ((self.0 >> 14) & Self::MOVE_FLAG_MASK).into()
     }
 
+    /// whether the quiet move heuristics (killers, history and counter moves) learn from and
+    /// score this move. captures are ordered by what they take instead. castling is left out
+    /// because it's written king-takes-rook, so its squares are those of an ordinary king move in
+    /// Chess960 and it would share that move's history
+    pub fn is_quiet_for_history(&self) -> bool {
+        !matches!(self.kind(), MoveKind::Capture | MoveKind::Castle)
+    }
+
     pub fn score(&self) -> u32 {
         (self.0 & Self::MOVE_SCORE_MASK) >> 17
     }
 
+    // replaces any earlier score, rather than mixing the bits of both
     pub fn set_score(&mut self, score: u32) {
-        self.0 |= score << 17 & Self::MOVE_SCORE_MASK
+        debug_assert!(
+            score <= Self::MAX_SCORE,
+            "move score {} out of range",
+            score
+        );
+        self.0 = self.bits() | (score.min(Self::MAX_SCORE) << 17)
     }
 
     pub fn is_null(&self) -> bool {
         *self == Self::NULL_MOVE
     }
+
+    // the raw bits of the move, excluding the ordering score
+    pub fn bits(&self) -> u32 {
+        self.0 & !Self::MOVE_SCORE_MASK
+    }
+
+    pub fn from_bits(bits: u32) -> Self {
+        Self(bits & !Self::MOVE_SCORE_MASK)
+    }
+
+    /// castling moves go from the king to the rook it castles with, which is enough to tell the
+    /// castling moves apart even in Chess960. this is where the king and the rook end up
+    pub fn castling_destinations(&self) -> (Square, Square) {
+        castling_destinations(self.from_square(), self.to_square())
+    }
+
+    /// the square UCI writes the move as going to. only castling differs from `to_square`, and
+    /// only when it wasn't generated on a Chess960 board
+    pub fn uci_to_square(&self) -> Square {
+        if self.kind() == MoveKind::Castle && self.flag() != MoveFlag::KingTakesRook {
+            self.castling_destinations().0
+        } else {
+            self.to_square()
+        }
+    }
+
+    /// the move in the long algebraic notation UCI uses, e.g. `e2e4` or `e7e8q`. this is what
+    /// anything shown to a user or a GUI should use
+    pub fn to_uci(&self) -> String {
+        self.to_string()
+    }
 }
 
 impl Display for Move {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.is_null() {
-            return write!(f, "0000");
+            return write!(f, "{}", NULL_MOVE_STR);
         }
 
-        let squares = format!("{:?}{:?}", self.from_square(), self.to_square());
+        let squares = format!("{:?}{:?}", self.from_square(), self.uci_to_square());
         write!(f, "{}", squares.to_lowercase())?;
 
         let promotion = match self.flag() {
@@ -135,20 +193,87 @@ impl PartialEq for Move {
 
 impl Eq for Move {}
 
+// without a board, the kind of move can't be known beyond whether it's a promotion, so everything
+// else comes out as quiet. `Board::find_matching_move` gives the actual move in a position
+impl FromStr for Move {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == NULL_MOVE_STR {
+            return Ok(Self::NULL_MOVE);
+        }
+
+        let chars: Vec<char> = s.chars().collect();
+
+        if !(4..=5).contains(&chars.len()) {
+            bail!("Move string `{}` is invalid", s);
+        }
+
+        let square = |file: char, rank: char| -> anyhow::Result<Square> {
+            Ok(Square::new(rank.try_into()?, file.try_into()?))
+        };
+
+        let from = square(chars[0], chars[1]).with_context(|| format!("Invalid move: {}", s))?;
+        let to = square(chars[2], chars[3]).with_context(|| format!("Invalid move: {}", s))?;
+
+        let Some(&promotion) = chars.get(4) else {
+            return Ok(Self::new(from, to, MoveKind::Quiet, MoveFlag::None));
+        };
+
+        let flag = match PieceKind::try_from(promotion) {
+            Ok(PieceKind::Knight) => MoveFlag::KnightPromotion,
+            Ok(PieceKind::Bishop) => MoveFlag::BishopPromotion,
+            Ok(PieceKind::Rook) => MoveFlag::RookPromotion,
+            Ok(PieceKind::Queen) => MoveFlag::QueenPromotion,
+            _ => bail!("Invalid promotion piece `{}` in move {}", promotion, s),
+        };
+
+        Ok(Self::new(from, to, MoveKind::Promotion, flag))
+    }
+}
+
 #[derive(Clone)]
 pub struct MoveList {
     moves: Vec<Move>,
+    // whether moves were dropped for want of room, see `push`
+    truncated: bool,
 }
 
+/// more than any position from a game has room for, the most legal moves known being 218. a FEN
+/// can still set up a board of promoted queens with more pseudo-legal moves than this
+pub const MAX_MOVES: usize = 256;
+
 impl MoveList {
     pub fn new() -> Self {
-        Self { moves: Vec::new() }
+        Self {
+            moves: Vec::with_capacity(MAX_MOVES),
+            truncated: false,
+        }
     }
 
+    // a list this long means the position can't be real, so the extra moves are dropped rather
+    // than taking the engine down in the middle of a game. whoever generated the list can tell
+    // from `is_truncated`
     pub fn push(&mut self, mv: Move) {
+        debug_assert!(
+            self.moves.len() < MAX_MOVES,
+            "more than {} moves generated",
+            MAX_MOVES
+        );
+
+        if self.moves.len() >= MAX_MOVES {
+            self.truncated = true;
+            return;
+        }
+
         self.moves.push(mv);
     }
 
+    /// whether there were more moves than fit, so some were left out
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
     pub fn get(&self, index: usize) -> Move {
         self.moves[index]
     }
@@ -162,6 +287,8 @@ impl MoveList {
     }
 
     pub fn pick_ordered_move(&mut self, current_index: usize) -> Move {
+        let _timer = profile::time(Phase::Ordering);
+
         let mut best_index = current_index;
         let mut best_score = self.get(current_index).score();
 
@@ -198,10 +325,9 @@ impl IntoIterator for MoveList {
     }
 }
 
-pub struct MoveGenerator {
-    rook_attacks: Vec<Bitboard>,
-    bishop_attacks: Vec<Bitboard>,
-}
+// the attack tables are shared by every generator, see `attacks`
+#[derive(Default, Clone)]
+pub struct MoveGenerator;
 
 const fn init_white_pawn_pushes() -> [Bitboard; 64] {
     let mut square_idx: usize = 0;
@@ -217,11 +343,6 @@ const fn init_white_pawn_pushes() -> [Bitboard; 64] {
     pawn_pushes
 }
 
-const NOT_A_FILE: u64 = 18374403900871474942u64;
-const NOT_H_FILE: u64 = 9187201950435737471u64;
-const NOT_AB_FILE: u64 = 18229723555195321596u64;
-const NOT_GH_FILE: u64 = 4557430888798830399u64;
-
 const fn init_black_pawn_pushes() -> [Bitboard; 64] {
     let mut square_idx: usize = 0;
 
@@ -236,254 +357,10 @@ const fn init_black_pawn_pushes() -> [Bitboard; 64] {
     pawn_pushes
 }
 
-const fn init_white_pawn_attacks() -> [Bitboard; 64] {
-    let mut square_idx: usize = 0;
-
-    let mut pawn_attacks: [Bitboard; 64] = [EMPTY_BB; 64];
-
-    while square_idx < 64 {
-        let square_bb = 1u64 << square_idx;
-
-        let north_east = (square_bb << 9) & NOT_A_FILE;
-        let north_west = (square_bb << 7) & NOT_H_FILE;
-
-        pawn_attacks[square_idx] = Bitboard(north_west | north_east);
-
-        square_idx += 1;
-    }
-
-    pawn_attacks
-}
-
-const fn init_black_pawn_attacks() -> [Bitboard; 64] {
-    let mut square_idx: usize = 0;
-
-    let mut pawn_attacks: [Bitboard; 64] = [EMPTY_BB; 64];
-
-    while square_idx < 64 {
-        let square_bb = 1u64 << square_idx;
-
-        let south_east = (square_bb >> 7) & NOT_A_FILE;
-        let south_west = (square_bb >> 9) & NOT_H_FILE;
-
-        pawn_attacks[square_idx] = Bitboard(south_west | south_east);
-
-        square_idx += 1;
-    }
-
-    pawn_attacks
-}
-
-const fn init_knight_attacks() -> [Bitboard; 64] {
-    let mut square_idx: usize = 0;
-
-    let mut knight_attacks: [Bitboard; 64] = [EMPTY_BB; 64];
-
-    while square_idx < 64 {
-        let square_bb = 1u64 << square_idx;
-
-        // NORTH: << 8
-        // SOUTH: >> 8
-        // EAST: << 1
-        // WEST >> 1
-
-        let north_north_east = (square_bb << 17) & NOT_A_FILE;
-        let north_north_west = (square_bb << 15) & NOT_H_FILE;
-        let north_east_east = (square_bb << 10) & NOT_AB_FILE;
-        let north_west_west = (square_bb << 6) & NOT_GH_FILE;
-
-        let south_south_east = (square_bb >> 15) & NOT_A_FILE;
-        let south_south_west = (square_bb >> 17) & NOT_H_FILE;
-        let south_east_east = (square_bb >> 6) & NOT_AB_FILE;
-        let south_west_west = (square_bb >> 10) & NOT_GH_FILE;
-
-        knight_attacks[square_idx] = Bitboard(
-            north_north_east
-                | north_north_west
-                | north_east_east
-                | north_west_west
-                | south_south_east
-                | south_south_west
-                | south_east_east
-                | south_west_west,
-        );
-
-        square_idx += 1;
-    }
-
-    knight_attacks
-}
-
-const fn north_one(bits: u64) -> u64 {
-    bits << 8
-}
-const fn south_one(bits: u64) -> u64 {
-    bits >> 8
-}
-
-const fn east_one(bits: u64) -> u64 {
-    (bits << 1) & NOT_A_FILE
-}
-
-const fn west_one(bits: u64) -> u64 {
-    (bits >> 1) & NOT_H_FILE
-}
-
-const fn init_king_attacks() -> [Bitboard; 64] {
-    let mut square_idx: usize = 0;
-
-    let mut king_attacks: [Bitboard; 64] = [EMPTY_BB; 64];
-
-    while square_idx < 64 {
-        // "parallel prefix" method described here: https://www.chessprogramming.org/King_Pattern#by_Calculation
-        let mut king_bb = 1u64 << square_idx;
-        let mut attacks = east_one(king_bb) | west_one(king_bb);
-        king_bb |= attacks;
-        attacks |= north_one(king_bb) | south_one(king_bb);
-
-        king_attacks[square_idx] = Bitboard(attacks);
-
-        square_idx += 1;
-    }
-
-    king_attacks
-}
-
-pub const ROOK_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
-pub const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
-
-pub fn generate_sliding_blocker_mask(square: Square, directions: [(i32, i32); 4]) -> Bitboard {
-    let mut blockers = EMPTY_BB;
-
-    let start_rank = (square.index() / 8) as i32;
-    let start_file = (square.index() % 8) as i32;
-
-    for (rank_offset, file_offset) in directions {
-        let mut rank = start_rank;
-        let mut file = start_file;
-
-        loop {
-            let next_square = Square::new(
-                (rank as usize).try_into().unwrap(),
-                (file as usize).try_into().unwrap(),
-            )
-            .bitboard();
-
-            rank += rank_offset;
-            file += file_offset;
-
-            if (0..=7).contains(&rank) && (0..=7).contains(&file) {
-                blockers |= next_square;
-            } else {
-                break;
-            }
-        }
-    }
-
-    blockers.clear_bit(square);
-    blockers
-}
-
-pub fn generate_sliding_attack_mask(
-    square: Square,
-    blockers: Bitboard,
-    directions: [(i32, i32); 4],
-) -> Bitboard {
-    let mut attacks = EMPTY_BB;
-
-    let start_rank = (square.index() / 8) as i32;
-    let start_file = (square.index() % 8) as i32;
-
-    for (rank_offset, file_offset) in directions {
-        let mut rank = start_rank;
-        let mut file = start_file;
-
-        loop {
-            let next_square = Square::new(
-                (rank as usize).try_into().unwrap(),
-                (file as usize).try_into().unwrap(),
-            )
-            .bitboard();
-
-            rank += rank_offset;
-            file += file_offset;
-
-            attacks |= next_square;
-
-            if !(0..=7).contains(&rank) || !(0..=7).contains(&file) {
-                break;
-            }
-
-            if (blockers & next_square) != EMPTY_BB {
-                break;
-            }
-        }
-    }
-
-    attacks.clear_bit(square);
-    attacks
-}
-
-fn init_rook_attacks() -> Vec<Bitboard> {
-    let mut rook_attacks = vec![EMPTY_BB; ROOK_ATTACK_TABLE_SIZE];
-
-    for (square, magic) in ROOK_MAGICS.iter().enumerate() {
-        let mask = Bitboard(magic.blocker_mask);
-        let mut blockers = EMPTY_BB;
-
-        loop {
-            let moves = generate_sliding_attack_mask(square.into(), blockers, ROOK_DIRECTIONS);
-            rook_attacks[magic.get_magic_index(blockers)] = moves;
-
-            blockers = (blockers - mask) & mask;
-            if blockers == EMPTY_BB {
-                break;
-            }
-        }
-    }
-
-    rook_attacks
-}
-
-fn init_bishop_attacks() -> Vec<Bitboard> {
-    let mut bishop_attacks = vec![EMPTY_BB; BISHOP_ATTACK_TABLE_SIZE];
-
-    for (square, magic) in BISHOP_MAGICS.iter().enumerate() {
-        let mask = Bitboard(magic.blocker_mask);
-        let mut blockers = EMPTY_BB;
-
-        loop {
-            let moves = generate_sliding_attack_mask(square.into(), blockers, BISHOP_DIRECTIONS);
-            bishop_attacks[magic.get_magic_index(blockers)] = moves;
-
-            blockers = (blockers - mask) & mask;
-            if blockers == EMPTY_BB {
-                break;
-            }
-        }
-    }
-
-    bishop_attacks
-}
-
 // maps the `from` square to the `to` square when pushing a pawn
 pub const WHITE_PAWN_PUSHES: [Bitboard; 64] = init_white_pawn_pushes();
 pub const BLACK_PAWN_PUSHES: [Bitboard; 64] = init_black_pawn_pushes();
 
-pub const WHITE_PAWN_ATTACKS: [Bitboard; 64] = init_white_pawn_attacks();
-pub const BLACK_PAWN_ATTACKS: [Bitboard; 64] = init_black_pawn_attacks();
-
-pub const KNIGHT_ATTACKS: [Bitboard; 64] = init_knight_attacks();
-
-pub const KING_ATTACKS: [Bitboard; 64] = init_king_attacks();
-
-pub fn pawn_attacks(side: Side) -> [Bitboard; 64] {
-    match side {
-        Side::White => WHITE_PAWN_ATTACKS,
-        Side::Black => BLACK_PAWN_ATTACKS,
-    }
-}
-
 impl MoveGenerator {
     const RANK_4_MASK: Bitboard = Bitboard(4278190080u64);
     const RANK_5_MASK: Bitboard = Bitboard(1095216660480u64);
@@ -555,8 +432,8 @@ impl MoveGenerator {
                 Side::Black => Side::White,
             };
 
-            let enemy = board.occupancy(enemy_side) | en_passant_bb;
-            let pawn_attack_mask = pawn_attacks(board.side_to_move())[from_square.index()];
+            let enemy = (board.occupancy(enemy_side) & !Self::enemy_king(board)?) | en_passant_bb;
+            let pawn_attack_mask = attacks::pawn(board.side_to_move(), from_square);
 
             let mut attacks = pawn_attack_mask & enemy;
 
@@ -594,12 +471,14 @@ impl MoveGenerator {
             Side::Black => (board.occupancy(Side::Black), board.occupancy(Side::White)),
         };
 
+        let blocked = current_side_occupancy | Self::enemy_king(board)?;
+
         while knights != EMPTY_BB {
             let from_square = knights.pop_bit();
 
-            let possible_attacks = KNIGHT_ATTACKS[from_square.index()];
+            let possible_attacks = attacks::knight(from_square);
 
-            let mut knight_moves = possible_attacks & !current_side_occupancy;
+            let mut knight_moves = possible_attacks & !blocked;
 
             while knight_moves != EMPTY_BB {
                 let to_square = knight_moves.pop_bit();
@@ -619,19 +498,20 @@ impl MoveGenerator {
     }
 
     fn generate_king_moves(&self, board: &Board, move_list: &mut MoveList) -> anyhow::Result<()> {
-        let mut king =
-            board.get_piece_bb(Piece::new(board.side_to_move().into(), PieceKind::King))?;
-
         let (current_side_occupancy, enemy_occupancy) = match board.side_to_move() {
             Side::White => (board.occupancy(Side::White), board.occupancy(Side::Black)),
             Side::Black => (board.occupancy(Side::Black), board.occupancy(Side::White)),
         };
 
-        let from_square = king.pop_bit();
+        let from_square = board.king_square(board.side_to_move());
+
+        let blocked = current_side_occupancy
+            | Self::enemy_king(board)?
+            | self.king_danger(board, from_square, current_side_occupancy | enemy_occupancy);
 
-        let possible_attacks = KING_ATTACKS[from_square.index()];
+        let possible_attacks = attacks::king(from_square);
 
-        let mut king_moves = possible_attacks & !current_side_occupancy;
+        let mut king_moves = possible_attacks & !blocked;
 
         while king_moves != EMPTY_BB {
             let to_square = king_moves.pop_bit();
@@ -659,16 +539,16 @@ impl MoveGenerator {
             Side::Black => (board.occupancy(Side::Black), board.occupancy(Side::White)),
         };
 
+        let blocked = current_side_occupancy | Self::enemy_king(board)?;
+
         while rooks != EMPTY_BB {
             let from_square = rooks.pop_bit();
 
-            let magic = ROOK_MAGICS[from_square.index()];
-
             let occupancies = current_side_occupancy | enemy_occupancy;
 
-            let possible_attacks = self.get_rook_attacks(magic.get_magic_index(occupancies));
+            let possible_attacks = attacks::rook(from_square, occupancies);
 
-            let mut rook_moves = possible_attacks & !current_side_occupancy;
+            let mut rook_moves = possible_attacks & !blocked;
 
             while rook_moves != EMPTY_BB {
                 let to_square = rook_moves.pop_bit();
@@ -696,16 +576,16 @@ impl MoveGenerator {
             Side::Black => (board.occupancy(Side::Black), board.occupancy(Side::White)),
         };
 
+        let blocked = current_side_occupancy | Self::enemy_king(board)?;
+
         while bishops != EMPTY_BB {
             let from_square = bishops.pop_bit();
 
-            let magic = BISHOP_MAGICS[from_square.index()];
-
             let occupancies = current_side_occupancy | enemy_occupancy;
 
-            let possible_attacks = self.get_bishop_attacks(magic.get_magic_index(occupancies));
+            let possible_attacks = attacks::bishop(from_square, occupancies);
 
-            let mut bishop_moves = possible_attacks & !current_side_occupancy;
+            let mut bishop_moves = possible_attacks & !blocked;
 
             while bishop_moves != EMPTY_BB {
                 let to_square = bishop_moves.pop_bit();
@@ -733,19 +613,16 @@ impl MoveGenerator {
             Side::Black => (board.occupancy(Side::Black), board.occupancy(Side::White)),
         };
 
+        let blocked = current_side_occupancy | Self::enemy_king(board)?;
+
         while queens != EMPTY_BB {
             let from_square = queens.pop_bit();
 
-            let bishop_magic = BISHOP_MAGICS[from_square.index()];
-            let rook_magic = ROOK_MAGICS[from_square.index()];
-
             let occupancies = current_side_occupancy | enemy_occupancy;
 
-            let possible_attacks = self
-                .get_bishop_attacks(bishop_magic.get_magic_index(occupancies))
-                | self.get_rook_attacks(rook_magic.get_magic_index(occupancies));
+            let possible_attacks = attacks::queen(from_square, occupancies);
 
-            let mut queen_moves = possible_attacks & !current_side_occupancy;
+            let mut queen_moves = possible_attacks & !blocked;
 
             while queen_moves != EMPTY_BB {
                 let to_square = queen_moves.pop_bit();
@@ -769,72 +646,67 @@ impl MoveGenerator {
         board: &Board,
         move_list: &mut MoveList,
     ) -> anyhow::Result<()> {
-        let occupancies = board.occupancy(Side::White) | board.occupancy(Side::Black);
         let side = board.side_to_move();
 
-        if side == Side::White {
-            if board.can_castle(CastlingKind::WhiteKing)
-                && !occupancies.is_occupied(Square::F1)
-                && !occupancies.is_occupied(Square::G1)
-                && !self.is_square_attacked(board, Square::E1, !side)
-                && !self.is_square_attacked(board, Square::F1, !side)
-            {
-                move_list.push(Move::new(
-                    Square::E1,
-                    Square::G1,
-                    MoveKind::Castle,
-                    MoveFlag::None,
-                ));
-            }
+        let kinds = match side {
+            Side::White => [CastlingKind::WhiteKing, CastlingKind::WhiteQueen],
+            Side::Black => [CastlingKind::BlackKing, CastlingKind::BlackQueen],
+        };
 
-            if board.can_castle(CastlingKind::WhiteQueen)
-                && !occupancies.is_occupied(Square::D1)
-                && !occupancies.is_occupied(Square::C1)
-                && !occupancies.is_occupied(Square::B1)
-                && !self.is_square_attacked(board, Square::E1, !side)
-                && !self.is_square_attacked(board, Square::D1, !side)
-            {
-                move_list.push(Move::new(
-                    Square::E1,
-                    Square::C1,
-                    MoveKind::Castle,
-                    MoveFlag::None,
-                ));
-            }
-        } else {
-            if board.can_castle(CastlingKind::BlackKing)
-                && !occupancies.is_occupied(Square::F8)
-                && !occupancies.is_occupied(Square::G8)
-                && !self.is_square_attacked(board, Square::E8, !side)
-                && !self.is_square_attacked(board, Square::F8, !side)
-            {
-                move_list.push(Move::new(
-                    Square::E8,
-                    Square::G8,
-                    MoveKind::Castle,
-                    MoveFlag::None,
-                ));
+        // the rights are gone for most of the game, so don't even look at the board
+        let side_rights = kinds[0] as u8 | kinds[1] as u8;
+        if board.castling_rights() & side_rights == 0 {
+            return Ok(());
+        }
+
+        let occupancies = board.occupancy(Side::White) | board.occupancy(Side::Black);
+
+        // the attack map is the expensive part, so it's only worked out once a path is clear, and
+        // then shared by both sides of the board
+        let mut attacked = None;
+
+        for kind in kinds {
+            let path = board.castling_path(kind);
+
+            if !board.can_castle(kind) || occupancies & path.between != EMPTY_BB {
+                continue;
             }
 
-            if board.can_castle(CastlingKind::BlackQueen)
-                && !occupancies.is_occupied(Square::D8)
-                && !occupancies.is_occupied(Square::C8)
-                && !occupancies.is_occupied(Square::B8)
-                && !self.is_square_attacked(board, Square::E8, !side)
-                && !self.is_square_attacked(board, Square::D8, !side)
-            {
-                move_list.push(Move::new(
-                    Square::E8,
-                    Square::C8,
-                    MoveKind::Castle,
-                    MoveFlag::None,
-                ));
+            let attacked =
+                *attacked.get_or_insert_with(|| self.attacked_squares(board, !side, occupancies));
+
+            if attacked & path.king_path != EMPTY_BB {
+                continue;
             }
+
+            // how the move is written is all that differs in Chess960
+            let flag = if board.is_chess960() {
+                MoveFlag::KingTakesRook
+            } else {
+                MoveFlag::None
+            };
+
+            move_list.push(Move::new(path.king, path.rook, MoveKind::Castle, flag));
         }
 
         Ok(())
     }
 
+    // the squares the king can't step onto without being in check, which are most of the king
+    // moves `make_move` would otherwise have to try and reject. the king is looked through, or a
+    // slider checking it would seem to stop at it and leave the square behind it safe
+    fn king_danger(&self, board: &Board, king_square: Square, occupancies: Bitboard) -> Bitboard {
+        let without_king = occupancies & !king_square.bitboard();
+
+        self.attacked_squares(board, !board.side_to_move(), without_king)
+    }
+
+    // never a legal target. only a broken position could have it attacked, and capturing it would
+    // leave a board with no king
+    fn enemy_king(board: &Board) -> anyhow::Result<Bitboard> {
+        board.get_piece_bb(Piece::new((!board.side_to_move()).into(), PieceKind::King))
+    }
+
     fn pawn_pushes(side: Side) -> [Bitboard; 64] {
         match side {
             Side::White => WHITE_PAWN_PUSHES,
@@ -876,12 +748,41 @@ impl MoveGenerator {
         }
     }
 
-    pub fn is_square_attacked(&self, board: &Board, square: Square, attacker_side: Side) -> bool {
-        let pawns = board
-            .get_piece_bb(Piece::new(attacker_side.into(), PieceKind::Pawn))
-            .unwrap();
+    /// every square `side` attacks, with sliders stopped by `occupancies` rather than by what's on
+    /// the board, so a piece can be looked through
+    pub fn attacked_squares(&self, board: &Board, side: Side, occupancies: Bitboard) -> Bitboard {
+        let pieces = |kind| board.get_piece_bb(Piece::new(side.into(), kind)).unwrap();
+
+        let mut attacked =
+            attacks::pawns(side, pieces(PieceKind::Pawn)) | attacks::king(board.king_square(side));
+
+        let mut knights = pieces(PieceKind::Knight);
+        while knights != EMPTY_BB {
+            attacked |= attacks::knight(knights.pop_bit());
+        }
+
+        let queens = pieces(PieceKind::Queen);
 
-        if pawn_attacks(!attacker_side)[square.index()] & pawns != EMPTY_BB {
+        let mut diagonal = pieces(PieceKind::Bishop) | queens;
+        while diagonal != EMPTY_BB {
+            attacked |= attacks::bishop(diagonal.pop_bit(), occupancies);
+        }
+
+        let mut straight = pieces(PieceKind::Rook) | queens;
+        while straight != EMPTY_BB {
+            attacked |= attacks::rook(straight.pop_bit(), occupancies);
+        }
+
+        attacked
+    }
+
+    // en passant is deliberately ignored here: it only ever matters for whether a pawn can be
+    // captured, never a king. en passant evasions (capturing a pawn that has just double-pushed
+    // to give check) come out of the pseudo-legal generator like any other capture, and
+    // `make_move` rejects the ones that leave the king in check, including the case where both
+    // pawns leave the king's rank or diagonal
+    pub fn is_square_attacked(&self, board: &Board, square: Square, attacker_side: Side) -> bool {
+        if board.is_attacked_by_pawn(square, attacker_side) {
             return true;
         }
 
@@ -889,15 +790,11 @@ impl MoveGenerator {
             .get_piece_bb(Piece::new(attacker_side.into(), PieceKind::King))
             .unwrap();
 
-        if KING_ATTACKS[square.index()] & king != EMPTY_BB {
+        if attacks::king(square) & king != EMPTY_BB {
             return true;
         }
 
-        let knights = board
-            .get_piece_bb(Piece::new(attacker_side.into(), PieceKind::Knight))
-            .unwrap();
-
-        if KNIGHT_ATTACKS[square.index()] & knights != EMPTY_BB {
+        if board.is_attacked_by_knight(square, attacker_side) {
             return true;
         }
 
@@ -907,8 +804,7 @@ impl MoveGenerator {
             .get_piece_bb(Piece::new(attacker_side.into(), PieceKind::Bishop))
             .unwrap();
 
-        let bishop_magic = BISHOP_MAGICS[square.index()];
-        let bishop_attacks = self.get_bishop_attacks(bishop_magic.get_magic_index(occupancies));
+        let bishop_attacks = attacks::bishop(square, occupancies);
 
         if bishop_attacks & bishops != EMPTY_BB {
             return true;
@@ -918,8 +814,7 @@ impl MoveGenerator {
             .get_piece_bb(Piece::new(attacker_side.into(), PieceKind::Rook))
             .unwrap();
 
-        let rook_magic = ROOK_MAGICS[square.index()];
-        let rook_attacks = self.get_rook_attacks(rook_magic.get_magic_index(occupancies));
+        let rook_attacks = attacks::rook(square, occupancies);
 
         if rook_attacks & rooks != EMPTY_BB {
             return true;
@@ -972,8 +867,8 @@ impl MoveGenerator {
                 Side::Black => Side::White,
             };
 
-            let enemy = board.occupancy(enemy_side) | en_passant_bb;
-            let pawn_attack_mask = pawn_attacks(board.side_to_move())[from_square.index()];
+            let enemy = (board.occupancy(enemy_side) & !Self::enemy_king(board)?) | en_passant_bb;
+            let pawn_attack_mask = attacks::pawn(board.side_to_move(), from_square);
 
             let mut attacks = pawn_attack_mask & enemy;
 
@@ -1015,12 +910,14 @@ impl MoveGenerator {
             Side::Black => (board.occupancy(Side::Black), board.occupancy(Side::White)),
         };
 
+        let blocked = current_side_occupancy | Self::enemy_king(board)?;
+
         while knights != EMPTY_BB {
             let from_square = knights.pop_bit();
 
-            let possible_attacks = KNIGHT_ATTACKS[from_square.index()];
+            let possible_attacks = attacks::knight(from_square);
 
-            let mut knight_moves = possible_attacks & !current_side_occupancy;
+            let mut knight_moves = possible_attacks & !blocked;
 
             while knight_moves != EMPTY_BB {
                 let to_square = knight_moves.pop_bit();
@@ -1045,19 +942,20 @@ impl MoveGenerator {
         board: &Board,
         move_list: &mut MoveList,
     ) -> anyhow::Result<()> {
-        let mut king =
-            board.get_piece_bb(Piece::new(board.side_to_move().into(), PieceKind::King))?;
-
         let (current_side_occupancy, enemy_occupancy) = match board.side_to_move() {
             Side::White => (board.occupancy(Side::White), board.occupancy(Side::Black)),
             Side::Black => (board.occupancy(Side::Black), board.occupancy(Side::White)),
         };
 
-        let from_square = king.pop_bit();
+        let from_square = board.king_square(board.side_to_move());
 
-        let possible_attacks = KING_ATTACKS[from_square.index()];
+        let blocked = current_side_occupancy
+            | Self::enemy_king(board)?
+            | self.king_danger(board, from_square, current_side_occupancy | enemy_occupancy);
 
-        let mut king_moves = possible_attacks & !current_side_occupancy;
+        let possible_attacks = attacks::king(from_square);
+
+        let mut king_moves = possible_attacks & !blocked;
 
         while king_moves != EMPTY_BB {
             let to_square = king_moves.pop_bit();
@@ -1090,16 +988,16 @@ impl MoveGenerator {
             Side::Black => (board.occupancy(Side::Black), board.occupancy(Side::White)),
         };
 
+        let blocked = current_side_occupancy | Self::enemy_king(board)?;
+
         while rooks != EMPTY_BB {
             let from_square = rooks.pop_bit();
 
-            let magic = ROOK_MAGICS[from_square.index()];
-
             let occupancies = current_side_occupancy | enemy_occupancy;
 
-            let possible_attacks = self.get_rook_attacks(magic.get_magic_index(occupancies));
+            let possible_attacks = attacks::rook(from_square, occupancies);
 
-            let mut rook_moves = possible_attacks & !current_side_occupancy;
+            let mut rook_moves = possible_attacks & !blocked;
 
             while rook_moves != EMPTY_BB {
                 let to_square = rook_moves.pop_bit();
@@ -1132,16 +1030,16 @@ impl MoveGenerator {
             Side::Black => (board.occupancy(Side::Black), board.occupancy(Side::White)),
         };
 
+        let blocked = current_side_occupancy | Self::enemy_king(board)?;
+
         while bishops != EMPTY_BB {
             let from_square = bishops.pop_bit();
 
-            let magic = BISHOP_MAGICS[from_square.index()];
-
             let occupancies = current_side_occupancy | enemy_occupancy;
 
-            let possible_attacks = self.get_bishop_attacks(magic.get_magic_index(occupancies));
+            let possible_attacks = attacks::bishop(from_square, occupancies);
 
-            let mut bishop_moves = possible_attacks & !current_side_occupancy;
+            let mut bishop_moves = possible_attacks & !blocked;
 
             while bishop_moves != EMPTY_BB {
                 let to_square = bishop_moves.pop_bit();
@@ -1174,19 +1072,16 @@ impl MoveGenerator {
             Side::Black => (board.occupancy(Side::Black), board.occupancy(Side::White)),
         };
 
+        let blocked = current_side_occupancy | Self::enemy_king(board)?;
+
         while queens != EMPTY_BB {
             let from_square = queens.pop_bit();
 
-            let bishop_magic = BISHOP_MAGICS[from_square.index()];
-            let rook_magic = ROOK_MAGICS[from_square.index()];
-
             let occupancies = current_side_occupancy | enemy_occupancy;
 
-            let possible_attacks = self
-                .get_bishop_attacks(bishop_magic.get_magic_index(occupancies))
-                | self.get_rook_attacks(rook_magic.get_magic_index(occupancies));
+            let possible_attacks = attacks::queen(from_square, occupancies);
 
-            let mut queen_moves = possible_attacks & !current_side_occupancy;
+            let mut queen_moves = possible_attacks & !blocked;
 
             while queen_moves != EMPTY_BB {
                 let to_square = queen_moves.pop_bit();
@@ -1205,34 +1100,19 @@ impl MoveGenerator {
         }
         Ok(())
     }
-
-    pub fn get_bishop_attacks(&self, magic_index: usize) -> Bitboard {
-        self.bishop_attacks[magic_index]
-    }
-
-    pub fn get_rook_attacks(&self, magic_index: usize) -> Bitboard {
-        self.rook_attacks[magic_index]
-    }
-}
-
-impl Default for MoveGenerator {
-    fn default() -> Self {
-        Self {
-            rook_attacks: init_rook_attacks(),
-            bishop_attacks: init_bishop_attacks(),
-        }
-    }
 }
 
 impl Debug for Move {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "from: {:?}, to: {:?}, kind: {:?}, flag: {:?}",
+            "{} (from: {:?}, to: {:?}, kind: {:?}, flag: {:?}, bits: {:#07x})",
+            self,
             self.from_square(),
             self.to_square(),
             self.kind(),
-            self.flag()
+            self.flag(),
+            self.bits()
         )
     }
 }
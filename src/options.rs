@@ -0,0 +1,227 @@
+use anyhow::{bail, Context};
+
+use crate::{
+    search::DEFAULT_MUST_WIN_MARGIN,
+    search_config::DEFAULT_HASH_MB,
+    time_management::{DEFAULT_MOVE_OVERHEAD_MS, MAX_MOVE_OVERHEAD_MS},
+};
+
+// more lines than there are legal moves in any position
+pub const MAX_MULTI_PV: usize = 256;
+
+pub const MAX_THREADS: usize = 64;
+
+pub const MAX_HASH_MB: usize = 16384;
+
+pub const MAX_CONTEMPT: i32 = 100;
+
+/// variants the engine can actually play. anything else requested via `UCI_Variant` is rejected
+pub const SUPPORTED_VARIANTS: [&str; 1] = ["chess"];
+
+pub enum OptionKind {
+    Check {
+        default: bool,
+    },
+    Spin {
+        default: i64,
+        min: i64,
+        max: i64,
+    },
+    Combo {
+        default: &'static str,
+        vars: &'static [&'static str],
+    },
+    String,
+    Button,
+}
+
+pub enum OptionValue {
+    Check(bool),
+    Spin(i64),
+    Text(String),
+    Button,
+}
+
+pub struct UciOption {
+    pub name: &'static str,
+    pub kind: OptionKind,
+}
+
+/// every option advertised in response to `uci`. `setoption` is only accepted for these
+pub const UCI_OPTIONS: [UciOption; 18] = [
+    UciOption {
+        name: "UCI_Variant",
+        kind: OptionKind::Combo {
+            default: "chess",
+            vars: &SUPPORTED_VARIANTS,
+        },
+    },
+    // only changes how castling moves are written. Chess960 positions can be loaded either way
+    UciOption {
+        name: "UCI_Chess960",
+        kind: OptionKind::Check { default: false },
+    },
+    UciOption {
+        name: "Ponder",
+        kind: OptionKind::Check { default: false },
+    },
+    // how many of the best moves to show a line for. only the first one is ever played
+    UciOption {
+        name: "MultiPV",
+        kind: OptionKind::Spin {
+            default: 1,
+            min: 1,
+            max: MAX_MULTI_PV as i64,
+        },
+    },
+    UciOption {
+        name: "Threads",
+        kind: OptionKind::Spin {
+            default: 1,
+            min: 1,
+            max: MAX_THREADS as i64,
+        },
+    },
+    // the size of the transposition table in megabytes. changing it empties the table
+    UciOption {
+        name: "Hash",
+        kind: OptionKind::Spin {
+            default: DEFAULT_HASH_MB as i64,
+            min: 1,
+            max: MAX_HASH_MB as i64,
+        },
+    },
+    // empties the transposition table without changing its size
+    UciOption {
+        name: "Clear Hash",
+        kind: OptionKind::Button,
+    },
+    UciOption {
+        name: "MinimalOutput",
+        kind: OptionKind::Check { default: false },
+    },
+    UciOption {
+        name: "SaveHash",
+        kind: OptionKind::String,
+    },
+    UciOption {
+        name: "LoadHash",
+        kind: OptionKind::String,
+    },
+    UciOption {
+        name: "RootMoveInfo",
+        kind: OptionKind::Check { default: false },
+    },
+    UciOption {
+        name: "LogFile",
+        kind: OptionKind::String,
+    },
+    // 0 means a different seed every time
+    UciOption {
+        name: "Seed",
+        kind: OptionKind::Spin {
+            default: 0,
+            min: 0,
+            max: i64::MAX,
+        },
+    },
+    UciOption {
+        name: "MustWin",
+        kind: OptionKind::Check { default: false },
+    },
+    // how many centipawns a draw costs when `MustWin` is on
+    UciOption {
+        name: "MustWinMargin",
+        kind: OptionKind::Spin {
+            default: DEFAULT_MUST_WIN_MARGIN as i64,
+            min: 0,
+            max: 500,
+        },
+    },
+    // how many centipawns worse than even a draw is for the engine. positive against weaker
+    // opponents, to keep playing for a win, and negative against stronger ones
+    UciOption {
+        name: "Contempt",
+        kind: OptionKind::Spin {
+            default: 0,
+            min: -(MAX_CONTEMPT as i64),
+            max: MAX_CONTEMPT as i64,
+        },
+    },
+    // milliseconds kept back from every move for getting it to the GUI
+    UciOption {
+        name: "Move Overhead",
+        kind: OptionKind::Spin {
+            default: DEFAULT_MOVE_OVERHEAD_MS as i64,
+            min: 0,
+            max: MAX_MOVE_OVERHEAD_MS as i64,
+        },
+    },
+    // for GUIs that send a promotion without its piece, e.g. `e7e8`. when it's off, such a move is
+    // rejected
+    UciOption {
+        name: "AssumeQueenPromotion",
+        kind: OptionKind::Check { default: true },
+    },
+];
+
+impl UciOption {
+    /// option names are case-insensitive, and several GUIs rely on that
+    pub fn find(name: &str) -> Option<&'static UciOption> {
+        UCI_OPTIONS
+            .iter()
+            .find(|option| option.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn declaration(&self) -> String {
+        let kind = match &self.kind {
+            OptionKind::Check { default } => format!("check default {}", default),
+            OptionKind::Spin { default, min, max } => {
+                format!("spin default {} min {} max {}", default, min, max)
+            }
+            OptionKind::Combo { default, vars } => {
+                let vars: Vec<_> = vars.iter().map(|var| format!("var {}", var)).collect();
+                format!("combo default {} {}", default, vars.join(" "))
+            }
+            OptionKind::String => String::from("string default <empty>"),
+            OptionKind::Button => String::from("button"),
+        };
+
+        format!("option name {} type {}", self.name, kind)
+    }
+
+    pub fn parse_value(&self, value: &str) -> anyhow::Result<OptionValue> {
+        match &self.kind {
+            OptionKind::Check { .. } => match value.to_ascii_lowercase().as_str() {
+                "true" => Ok(OptionValue::Check(true)),
+                "false" => Ok(OptionValue::Check(false)),
+                _ => bail!("{} must be `true` or `false`", self.name),
+            },
+            OptionKind::Spin { min, max, .. } => {
+                let number: i64 = value
+                    .parse()
+                    .with_context(|| format!("{} must be an integer", self.name))?;
+
+                if number < *min || number > *max {
+                    bail!("{} must be between {} and {}", self.name, min, max);
+                }
+
+                Ok(OptionValue::Spin(number))
+            }
+            OptionKind::Combo { vars, .. } => {
+                match vars.iter().find(|var| var.eq_ignore_ascii_case(value)) {
+                    Some(var) => Ok(OptionValue::Text(var.to_string())),
+                    None => bail!("unsupported option {} value {}", self.name, value),
+                }
+            }
+            OptionKind::String => {
+                if value.is_empty() || value == "<empty>" {
+                    bail!("{} requires a value", self.name);
+                }
+
+                Ok(OptionValue::Text(value.into()))
+            }
+            OptionKind::Button => Ok(OptionValue::Button),
+        }
+    }
+}
@@ -1,16 +1,32 @@
-use std::io::Write;
+use std::{
+    io::Write,
+    thread,
+    time::{Duration, Instant},
+};
 
 use anyhow::Context;
-use colored::Colorize;
 
 use crate::{
     board::Board,
+    epd::{parse_epd, EpdRecord},
     move_generator::MoveList,
-    transposition_table::{PerftTableEntry, TranspositionTable},
+    perft_table::PerftTable,
+    position::apply_fen,
+    style,
 };
 
-struct PerftMetadata<'a> {
-    fen: &'a str,
+/// the quick suite only runs the depths expected to take fewer nodes than this
+pub const QUICK_PERFT_MAX_NODES: u64 = 1_000_000;
+
+// some positions are only listed at a single, deeper depth, usually for the one edge case they
+// cover. those are still run if they're no more than this many times over the limit
+const QUICK_PERFT_SINGLE_DEPTH_ALLOWANCE: u64 = 10;
+
+// every thread in the quick suite has a table of its own, so they're kept small
+const QUICK_PERFT_TABLE_MB: usize = 16;
+
+struct PerftMetadata {
+    fen: String,
     tests: Vec<Test>,
 }
 
@@ -19,26 +35,121 @@ struct Test {
     expected_nodes: u64,
 }
 
-pub fn run_perft_tests(tests: &str, transposition_table: &mut TranspositionTable<PerftTableEntry>) {
-    let start_time = std::time::Instant::now();
+/// a test that didn't count the expected number of nodes, or couldn't count them at all
+pub struct PerftFailure {
+    pub fen: String,
+    pub depth: u8,
+    pub expected_nodes: u64,
+    pub result: anyhow::Result<u64>,
+}
+
+/// how a run of the suite went. failures are collected rather than stopping the run, so one bug
+/// shows up in every position it affects
+#[derive(Default)]
+pub struct PerftSummary {
+    pub passed: usize,
+    pub failures: Vec<PerftFailure>,
+    // positions the quick suite had nothing cheap enough to run for
+    pub skipped: usize,
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
 
-    let tests: Vec<_> = tests.lines().map(parse_perft_string).collect();
+impl PerftSummary {
+    fn record(&mut self, fen: &str, test: &Test, result: anyhow::Result<u64>) -> bool {
+        if let Ok(nodes) = result {
+            self.nodes += nodes;
 
-    let number_of_tests = tests.len();
+            if nodes == test.expected_nodes {
+                self.passed += 1;
+                return true;
+            }
+        }
+
+        self.failures.push(PerftFailure {
+            fen: fen.into(),
+            depth: test.depth,
+            expected_nodes: test.expected_nodes,
+            result,
+        });
+
+        false
+    }
+
+    fn merge(&mut self, other: PerftSummary) {
+        self.passed += other.passed;
+        self.failures.extend(other.failures);
+        self.skipped += other.skipped;
+        self.nodes += other.nodes;
+    }
+
+    pub fn total(&self) -> usize {
+        self.passed + self.failures.len()
+    }
+
+    pub fn print(&self) {
+        if !self.failures.is_empty() {
+            println!("\n{}", style::failure("Failures:"));
+
+            for failure in &self.failures {
+                let result = match &failure.result {
+                    Ok(nodes) => nodes.to_string(),
+                    Err(error) => format!("error ({:#})", error),
+                };
+
+                println!(
+                    "- {} depth {}: expected {}, got {}",
+                    failure.fen, failure.depth, failure.expected_nodes, result
+                );
+            }
+        }
+
+        let pass_count = style::success(format!("{} passed", self.passed));
+        let fail_count = style::failure(format!("{} failed", self.failures.len()));
+
+        println!(
+            "\nTests: {}, {}, {} total",
+            pass_count,
+            fail_count,
+            self.total()
+        );
+
+        if self.skipped > 0 {
+            println!("Skipped positions: {}", self.skipped);
+        }
+
+        let elapsed_ms = self.elapsed.as_millis().max(1);
+
+        println!("Nodes: {}", self.nodes);
+        println!("Time: {:.2?}", self.elapsed);
+        println!("NPS: {}", (self.nodes as u128 * 1000) / elapsed_ms);
+        println!();
+    }
+}
+
+pub fn run_perft_tests(tests: &str, table: &mut PerftTable) {
+    let start_time = Instant::now();
 
-    let mut pass_count = 0;
-    let mut fail_count = 0;
+    let tests = match parse_perft_file(tests) {
+        Ok(tests) => tests,
+        Err(error) => {
+            println!("Invalid perft file: {:#}", error);
+            return;
+        }
+    };
+
+    let number_of_tests = tests.len();
 
+    let mut summary = PerftSummary::default();
     let mut board = Board::default();
 
     for (i, position) in tests.into_iter().enumerate() {
         println!();
-        let position = position.unwrap();
 
         let progress = format!("[{}/{}]", i + 1, number_of_tests);
-        println!("{} FEN: {}", progress.cyan(), position.fen);
+        println!("{} FEN: {}", style::accent(progress), position.fen);
 
-        board.parse_fen(position.fen).unwrap();
+        apply_fen(&mut board, &position.fen).unwrap();
 
         for test in position.tests {
             print!(
@@ -47,21 +158,14 @@ pub fn run_perft_tests(tests: &str, transposition_table: &mut TranspositionTable
             );
             std::io::stdout().flush().unwrap();
 
-            let result = perft(&mut board, test.depth, transposition_table).unwrap();
-            assert_eq!(result, test.expected_nodes);
-            let passed = result == test.expected_nodes;
+            let result = perft(&mut board, test.depth, table);
+            let passed = summary.record(&position.fen, &test, result);
 
             let passed_icon = match passed {
-                true => "\u{2713}".green(),
-                false => "\u{2717}".red(),
+                true => style::success("\u{2713}"),
+                false => style::failure("\u{2717}"),
             };
 
-            if passed {
-                pass_count += 1;
-            } else {
-                fail_count += 1;
-            }
-
             println!(
                 "\r\tdepth: {}, expected nodes: {} {}",
                 test.depth, test.expected_nodes, passed_icon
@@ -69,31 +173,92 @@ pub fn run_perft_tests(tests: &str, transposition_table: &mut TranspositionTable
         }
     }
 
-    let total_tests = pass_count + fail_count;
-    let pass_count = format!("{} passed", pass_count).green();
-    let fail_count = format!("{} failed", fail_count).red();
+    summary.elapsed = start_time.elapsed();
+    summary.print();
+}
+
+/// every position in the suite, but only at the deepest depth expected to take at most
+/// `max_nodes` nodes. positions are shared out between `threads` threads, each with its own board
+/// and table
+pub fn run_quick_perft_tests(
+    tests: &str,
+    max_nodes: u64,
+    threads: usize,
+) -> anyhow::Result<PerftSummary> {
+    let start_time = Instant::now();
+
+    let mut positions = Vec::new();
+    let mut skipped = 0;
+
+    for position in parse_perft_file(tests)? {
+        match quick_test(position.tests, max_nodes) {
+            Some(test) => positions.push((position.fen, test)),
+            None => skipped += 1,
+        }
+    }
+
+    let threads = threads.max(1);
+
+    let mut summary = thread::scope(|scope| {
+        // every `threads`th position, so the slow ones don't all end up on one thread
+        let workers: Vec<_> = (0..threads)
+            .map(|first| {
+                let positions = &positions;
+
+                scope.spawn(move || {
+                    let mut board = Board::default();
+                    let mut table = PerftTable::new(QUICK_PERFT_TABLE_MB);
+                    let mut summary = PerftSummary::default();
+
+                    for (fen, test) in positions.iter().skip(first).step_by(threads) {
+                        let result = apply_fen(&mut board, fen)
+                            .map_err(anyhow::Error::from)
+                            .and_then(|_| perft(&mut board, test.depth, &mut table));
+
+                        summary.record(fen, test, result);
+                    }
+
+                    summary
+                })
+            })
+            .collect();
+
+        let mut summary = PerftSummary::default();
 
-    println!(
-        "\nTests: {}, {}, {} total",
-        pass_count, fail_count, total_tests
-    );
+        for worker in workers {
+            summary.merge(worker.join().expect("perft thread panicked"));
+        }
+
+        summary
+    });
 
-    println!("Time: {:.2?}", start_time.elapsed());
-    println!();
+    summary.skipped = skipped;
+    summary.elapsed = start_time.elapsed();
+
+    Ok(summary)
 }
 
-pub fn perft(
-    board: &mut Board,
-    depth: u8,
-    transposition_table: &mut TranspositionTable<PerftTableEntry>,
-) -> anyhow::Result<u64> {
+fn quick_test(tests: Vec<Test>, max_nodes: u64) -> Option<Test> {
+    let shallowest = tests.iter().map(|test| test.depth).min()?;
+
+    tests
+        .into_iter()
+        .filter(|test| {
+            test.expected_nodes <= max_nodes
+                || (test.depth == shallowest
+                    && test.expected_nodes
+                        <= max_nodes.saturating_mul(QUICK_PERFT_SINGLE_DEPTH_ALLOWANCE))
+        })
+        .max_by_key(|test| test.depth)
+}
+
+pub fn perft(board: &mut Board, depth: u8, table: &mut PerftTable) -> anyhow::Result<u64> {
     if depth == 0 {
         return Ok(1);
     }
 
-    let entry = transposition_table.probe(board.hash());
-    if entry.depth == depth && entry.hash == board.hash() {
-        return Ok(entry.node_count);
+    if let Some(nodes) = table.probe(board.hash(), depth) {
+        return Ok(nodes);
     }
 
     let mut nodes = 0;
@@ -103,38 +268,55 @@ pub fn perft(
 
     for mv in move_list {
         if board.make_move(mv)? {
-            nodes += perft(board, depth - 1, transposition_table)?;
+            nodes += perft(board, depth - 1, table)?;
         }
 
-        board.unmake_move(mv)?;
+        board.unmake_move(mv);
     }
 
-    transposition_table.store(PerftTableEntry::new(board.hash(), nodes, depth));
+    table.store(board.hash(), depth, nodes);
 
     Ok(nodes)
 }
 
-fn parse_perft_string(perft_string: &str) -> anyhow::Result<PerftMetadata> {
-    let (fen, tests) = perft_string
-        .split_once(';')
-        .context("invalid perft string")?;
-
-    let fen = fen.trim();
-
-    let tests = tests
-        .split(" ;")
-        .map(|test| {
-            let (depth, expected_nodes) = test.trim().split_once(' ').unwrap();
-
-            let depth = depth[1..].parse().unwrap();
-            let expected_nodes = expected_nodes.parse().unwrap();
-
-            Test {
-                depth,
-                expected_nodes,
-            }
+fn parse_perft_file(text: &str) -> anyhow::Result<Vec<PerftMetadata>> {
+    parse_epd(text)?
+        .iter()
+        .enumerate()
+        .map(|(index, record)| {
+            parse_perft_record(record).with_context(|| format!("position {}", index + 1))
         })
-        .collect();
+        .collect()
+}
+
+// the expected node count for each depth is stored in the `D1`..`D6` opcodes
+fn parse_perft_record(record: &EpdRecord) -> anyhow::Result<PerftMetadata> {
+    let mut tests = Vec::new();
+
+    for operation in &record.operations {
+        let Some(depth) = operation.opcode.strip_prefix('D') else {
+            continue;
+        };
+
+        let depth = depth
+            .parse()
+            .with_context(|| format!("invalid perft depth opcode `{}`", operation.opcode))?;
+
+        let expected_nodes = operation
+            .operands
+            .first()
+            .with_context(|| format!("missing node count for depth {}", depth))?
+            .parse()
+            .with_context(|| format!("invalid node count for depth {}", depth))?;
+
+        tests.push(Test {
+            depth,
+            expected_nodes,
+        });
+    }
 
-    Ok(PerftMetadata { fen, tests })
+    Ok(PerftMetadata {
+        fen: record.fen(),
+        tests,
+    })
 }
@@ -0,0 +1,138 @@
+use crate::profile::{self, Phase};
+
+const MEGABYTE: usize = 1024 * 1024;
+
+// how many neighbouring slots a position can end up in. they're next to each other in memory, so
+// looking at all of them costs about the same as looking at one
+const PROBE_LENGTH: usize = 4;
+
+// spreads the depth over the whole key, so the same position at different depths lands in
+// unrelated slots instead of fighting over one
+const DEPTH_MIX: u64 = 0x9e37_79b9_7f4a_7c15;
+
+const DEPTH_BITS: u32 = 8;
+const DEPTH_MASK: u64 = (1 << DEPTH_BITS) - 1;
+
+/// node counts for perft, kept apart from the search's transposition table. a perft result is
+/// only valid for one depth, and a suite run fills the table with positions from every depth at
+/// once, so it's an open-addressing map keyed on the position and depth together that holds on to
+/// the biggest subtrees
+pub struct PerftTable {
+    entries: Vec<PerftEntry>,
+    // the number of entries is a power of two, so this turns a key into an index
+    mask: usize,
+    size_in_mb: usize,
+}
+
+// 16 bytes: the mixed key, and the node count packed above the depth. depth is never 0 for a
+// stored entry, so all-zero data marks an empty slot
+#[derive(Debug, Default, Clone, Copy)]
+struct PerftEntry {
+    key: u64,
+    data: u64,
+}
+
+impl PerftEntry {
+    fn new(key: u64, depth: u8, node_count: u64) -> Self {
+        Self {
+            key,
+            data: (node_count << DEPTH_BITS) | depth as u64,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data == 0
+    }
+
+    fn depth(&self) -> u8 {
+        (self.data & DEPTH_MASK) as u8
+    }
+
+    fn node_count(&self) -> u64 {
+        self.data >> DEPTH_BITS
+    }
+}
+
+impl PerftTable {
+    /// a size of 0 gives a table that never stores anything
+    pub fn new(size_in_mb: usize) -> Self {
+        let count = (size_in_mb * MEGABYTE) / std::mem::size_of::<PerftEntry>();
+
+        // rounded down to a power of two, and never smaller than one probe
+        let entries = if count < PROBE_LENGTH {
+            Vec::new()
+        } else {
+            vec![PerftEntry::default(); 1 << count.ilog2()]
+        };
+
+        Self {
+            mask: entries.len().saturating_sub(1),
+            entries,
+            size_in_mb,
+        }
+    }
+
+    pub fn configured_mb(&self) -> usize {
+        self.size_in_mb
+    }
+
+    pub fn capacity_entries(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn size_bytes(&self) -> usize {
+        self.entries.capacity() * std::mem::size_of::<PerftEntry>()
+    }
+
+    pub fn probe(&self, hash: u64, depth: u8) -> Option<u64> {
+        if self.entries.is_empty() {
+            return None;
+        }
+
+        let _timer = profile::time(Phase::TranspositionTable);
+
+        let key = Self::key(hash, depth);
+        let start = key as usize;
+
+        (0..PROBE_LENGTH)
+            .map(|offset| &self.entries[(start + offset) & self.mask])
+            .find(|entry| entry.key == key && entry.depth() == depth && !entry.is_empty())
+            .map(PerftEntry::node_count)
+    }
+
+    // goes in the first slot already holding the same position and depth, or else an empty one.
+    // when every slot is taken, the smallest subtree is the one given up, since it's the cheapest
+    // to count again
+    pub fn store(&mut self, hash: u64, depth: u8, node_count: u64) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let _timer = profile::time(Phase::TranspositionTable);
+
+        let key = Self::key(hash, depth);
+        let start = key as usize;
+
+        let mut replace = start & self.mask;
+
+        for offset in 0..PROBE_LENGTH {
+            let index = (start + offset) & self.mask;
+            let entry = &self.entries[index];
+
+            if entry.is_empty() || (entry.key == key && entry.depth() == depth) {
+                replace = index;
+                break;
+            }
+
+            if entry.node_count() < self.entries[replace].node_count() {
+                replace = index;
+            }
+        }
+
+        self.entries[replace] = PerftEntry::new(key, depth, node_count);
+    }
+
+    fn key(hash: u64, depth: u8) -> u64 {
+        hash ^ (depth as u64).wrapping_mul(DEPTH_MIX)
+    }
+}
@@ -0,0 +1,182 @@
+// setting a board up from a FEN and playing a list of moves on it, for everything that takes
+// positions from outside of the engine: the UCI `position` command, the CLI's `fen` and `moves`,
+// and the perft suite. both are all or nothing, so a board is never left halfway through
+
+use std::{error::Error, fmt};
+
+use crate::{
+    board::Board,
+    make_move::NULL_MOVE_STR,
+    move_generator::{Move, MoveKind},
+};
+
+/// why a FEN or a list of moves couldn't be applied. whichever it is, the board is left exactly as
+/// it was before
+#[derive(Debug)]
+pub enum ApplyError {
+    InvalidFen(anyhow::Error),
+    // `index` is where the move is in the list, counting from 0
+    UnparsableMove {
+        index: usize,
+        move_str: String,
+        source: anyhow::Error,
+    },
+    IllegalMove {
+        index: usize,
+        move_str: String,
+    },
+    // only when a queen isn't assumed, see `Board::set_assume_queen_promotion`
+    PromotionWithoutPiece {
+        index: usize,
+        move_str: String,
+    },
+    NullMoveInCheck {
+        index: usize,
+    },
+    // the board itself can't be played on, e.g. it was never given a position
+    Board(anyhow::Error),
+}
+
+impl ApplyError {
+    /// where the move that couldn't be played is in the list, if it was a move that went wrong
+    pub fn move_index(&self) -> Option<usize> {
+        match self {
+            ApplyError::UnparsableMove { index, .. }
+            | ApplyError::IllegalMove { index, .. }
+            | ApplyError::PromotionWithoutPiece { index, .. }
+            | ApplyError::NullMoveInCheck { index } => Some(*index),
+            ApplyError::InvalidFen(_) | ApplyError::Board(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplyError::InvalidFen(_) => write!(f, "invalid FEN"),
+            ApplyError::UnparsableMove { move_str, .. } => write!(f, "Invalid move: {}", move_str),
+            ApplyError::IllegalMove { move_str, .. } => {
+                write!(f, "Move `{}` is not legal in this position", move_str)
+            }
+            ApplyError::PromotionWithoutPiece { move_str, .. } => write!(
+                f,
+                "Move `{}` is a promotion without a promotion piece",
+                move_str
+            ),
+            ApplyError::NullMoveInCheck { .. } => {
+                write!(f, "Null move is not legal while in check")
+            }
+            ApplyError::Board(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+// the reason behind the message, which anyhow prints after it with `{:#}`
+impl Error for ApplyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ApplyError::InvalidFen(source) | ApplyError::UnparsableMove { source, .. } => {
+                Some(source.as_ref())
+            }
+            ApplyError::Board(error) => error.source(),
+            _ => None,
+        }
+    }
+}
+
+/// sets the board up from `fen`, with nothing in its history
+pub fn apply_fen(board: &mut Board, fen: &str) -> Result<(), ApplyError> {
+    // parsing starts by clearing the board, so it's done on a copy
+    let mut parsed = board.clone();
+    parsed.parse_fen(fen).map_err(ApplyError::InvalidFen)?;
+
+    *board = parsed;
+
+    Ok(())
+}
+
+/// plays each of `moves` in long algebraic notation, in order. analysis tools sometimes hand the
+/// turn over with `0000`, which is played as a null move. gives back the moves as they were
+/// played, with `Move::NULL_MOVE` for a null move and the piece filled in for a promotion that
+/// was sent without one
+pub fn apply_moves(board: &mut Board, moves: &[&str]) -> Result<Vec<Move>, ApplyError> {
+    board.ensure_initialized().map_err(ApplyError::Board)?;
+
+    let mut played = Vec::with_capacity(moves.len());
+    let result = play_moves(board, moves, &mut played);
+
+    if result.is_err() {
+        for &mv in played.iter().rev() {
+            if mv.is_null() {
+                board.unmake_null_move();
+            } else {
+                board.unmake_move(mv);
+            }
+        }
+    }
+
+    result.map(|_| played)
+}
+
+/// the moves from `apply_moves` that had a queen filled in, next to the strings they were sent
+/// as. a GUI should be told when the engine has guessed at what it meant
+pub fn assumed_promotions<'a>(
+    played: &'a [Move],
+    moves: &'a [&'a str],
+) -> impl Iterator<Item = (Move, &'a str)> + 'a {
+    played
+        .iter()
+        .zip(moves)
+        .filter(|(mv, move_str)| {
+            mv.kind() == MoveKind::Promotion
+                && move_str
+                    .parse::<Move>()
+                    .is_ok_and(|sent| sent.kind() != MoveKind::Promotion)
+        })
+        .map(|(&mv, &move_str)| (mv, move_str))
+}
+
+fn play_moves(board: &mut Board, moves: &[&str], played: &mut Vec<Move>) -> Result<(), ApplyError> {
+    for (index, &move_str) in moves.iter().enumerate() {
+        if move_str == NULL_MOVE_STR {
+            if board.is_in_check(board.side_to_move()) {
+                return Err(ApplyError::NullMoveInCheck { index });
+            }
+
+            board.make_null_move();
+            played.push(Move::NULL_MOVE);
+            continue;
+        }
+
+        let move_metadata =
+            board
+                .get_move_metadata(move_str)
+                .map_err(|source| ApplyError::UnparsableMove {
+                    index,
+                    move_str: move_str.into(),
+                    source,
+                })?;
+
+        let Some(mv) = board
+            .find_legal_move(move_metadata)
+            .map_err(ApplyError::Board)?
+        else {
+            if board.is_promotion_without_piece(move_metadata) {
+                return Err(ApplyError::PromotionWithoutPiece {
+                    index,
+                    move_str: move_str.into(),
+                });
+            }
+
+            return Err(ApplyError::IllegalMove {
+                index,
+                move_str: move_str.into(),
+            });
+        };
+
+        board.make_move(mv).map_err(ApplyError::Board)?;
+        played.push(mv);
+    }
+
+    Ok(())
+}
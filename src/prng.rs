@@ -1,10 +1,40 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// xorshift never leaves a zero state, so a zero seed is swapped for this instead
+const ZERO_SEED_REPLACEMENT: u64 = 0x9E37_79B9_7F4A_7C15;
+
+#[derive(Debug, Clone)]
 pub struct Prng {
     state: u64,
 }
 
 impl Prng {
     pub fn new(seed: u64) -> Self {
-        Self { state: seed }
+        let state = if seed == 0 {
+            ZERO_SEED_REPLACEMENT
+        } else {
+            seed
+        };
+
+        Self { state }
+    }
+
+    /// seeded differently every time, for when results don't need to be reproducible
+    pub fn from_entropy() -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos())
+            .unwrap_or(0);
+
+        hasher.write_u128(nanos);
+
+        Self::new(hasher.finish())
     }
 
     // https://en.wikipedia.org/wiki/Xorshift
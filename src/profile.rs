@@ -0,0 +1,95 @@
+// coarse timing of the main phases of the search, for when an external profiler isn't available.
+// everything here compiles to nothing unless the `profile` feature is enabled
+
+use std::time::Duration;
+#[cfg(feature = "profile")]
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    MoveGeneration,
+    MakeUnmake,
+    Evaluation,
+    TranspositionTable,
+    Ordering,
+    StaticExchange,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 6] = [
+        Phase::MoveGeneration,
+        Phase::MakeUnmake,
+        Phase::Evaluation,
+        Phase::TranspositionTable,
+        Phase::Ordering,
+        Phase::StaticExchange,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::MoveGeneration => "move generation",
+            Phase::MakeUnmake => "make/unmake",
+            Phase::Evaluation => "evaluation",
+            Phase::TranspositionTable => "TT probe/store",
+            Phase::Ordering => "move ordering",
+            Phase::StaticExchange => "SEE pruning",
+        }
+    }
+}
+
+// shared by every thread, since the search runs on a worker thread of its own and helper threads
+// can search alongside it
+#[cfg(feature = "profile")]
+static NANOS: [AtomicU64; Phase::ALL.len()] = [const { AtomicU64::new(0) }; Phase::ALL.len()];
+
+/// adds the time until it's dropped to its phase
+pub struct PhaseGuard {
+    #[cfg(feature = "profile")]
+    phase: Phase,
+    #[cfg(feature = "profile")]
+    started: Instant,
+}
+
+#[cfg(feature = "profile")]
+impl Drop for PhaseGuard {
+    fn drop(&mut self) {
+        let nanos = self.started.elapsed().as_nanos() as u64;
+        NANOS[self.phase as usize].fetch_add(nanos, Ordering::Relaxed);
+    }
+}
+
+#[inline(always)]
+#[must_use]
+pub fn time(_phase: Phase) -> PhaseGuard {
+    PhaseGuard {
+        #[cfg(feature = "profile")]
+        phase: _phase,
+        #[cfg(feature = "profile")]
+        started: Instant::now(),
+    }
+}
+
+pub fn reset() {
+    #[cfg(feature = "profile")]
+    NANOS
+        .iter()
+        .for_each(|total| total.store(0, Ordering::Relaxed));
+}
+
+/// the time spent in each phase on all threads since the last reset, or `None` if profiling
+/// isn't compiled in
+pub fn summary() -> Option<[(Phase, Duration); Phase::ALL.len()]> {
+    #[cfg(feature = "profile")]
+    {
+        Some(Phase::ALL.map(|phase| {
+            let nanos = NANOS[phase as usize].load(Ordering::Relaxed);
+            (phase, Duration::from_nanos(nanos))
+        }))
+    }
+
+    #[cfg(not(feature = "profile"))]
+    None
+}
@@ -0,0 +1,645 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::bail;
+
+use crate::{
+    board::Side,
+    debug_sink::DebugEvent,
+    move_generator::Move,
+    root_moves::{RootMove, ScoreBound},
+    search::{CHECKMATE_THRESHOLD, INFINITY},
+};
+
+/// everything known about a completed iteration of iterative deepening
+pub struct IterationReport<'a> {
+    pub depth: u8,
+    pub seldepth: u8,
+    // which of the MultiPV lines this is, counting from 1. `None` when there's only one line
+    pub multipv: Option<usize>,
+    pub score: i32,
+    // whether `score` is exact, or only a bound because the search failed high or low
+    pub bound: ScoreBound,
+    pub nodes: u64,
+    // since the search started
+    pub time_ms: u128,
+    // how full the transposition table is, in permille
+    pub hashfull: u16,
+    pub pv: &'a [Move],
+    pub root_moves: &'a [RootMove],
+}
+
+/// decides what gets printed (or recorded) while searching, so the search itself never needs to
+/// branch on output settings
+pub trait SearchReporter {
+    fn iteration(&mut self, report: &IterationReport);
+    // `ponder` is the reply the search expects, if it has one
+    fn best_move(&mut self, best_move: Move, ponder: Option<Move>);
+    // only ever called between iterations. most reporters have no use for these
+    fn debug(&mut self, _event: &DebugEvent) {}
+    // something the user should know about whatever the output settings, e.g. moves that weren't
+    // searched. also only called between iterations
+    fn warning(&mut self, _message: &str) {}
+}
+
+// an iteration held on to until the search is over, so it owns its PV
+struct LastIteration {
+    depth: u8,
+    seldepth: u8,
+    multipv: Option<usize>,
+    score: i32,
+    bound: ScoreBound,
+    nodes: u64,
+    time_ms: u128,
+    hashfull: u16,
+    pv: Vec<Move>,
+}
+
+impl LastIteration {
+    fn new(report: &IterationReport) -> Self {
+        Self {
+            depth: report.depth,
+            seldepth: report.seldepth,
+            multipv: report.multipv,
+            score: report.score,
+            bound: report.bound,
+            nodes: report.nodes,
+            time_ms: report.time_ms,
+            hashfull: report.hashfull,
+            pv: report.pv.into(),
+        }
+    }
+
+    // overwrites an earlier iteration, keeping the PV's allocation
+    fn update(&mut self, report: &IterationReport) {
+        self.depth = report.depth;
+        self.seldepth = report.seldepth;
+        self.multipv = report.multipv;
+        self.score = report.score;
+        self.bound = report.bound;
+        self.nodes = report.nodes;
+        self.time_ms = report.time_ms;
+        self.hashfull = report.hashfull;
+        self.pv.clear();
+        self.pv.extend_from_slice(report.pv);
+    }
+
+    fn report(&self) -> IterationReport<'_> {
+        IterationReport {
+            depth: self.depth,
+            seldepth: self.seldepth,
+            multipv: self.multipv,
+            score: self.score,
+            bound: self.bound,
+            nodes: self.nodes,
+            time_ms: self.time_ms,
+            hashfull: self.hashfull,
+            pv: &self.pv,
+            root_moves: &[],
+        }
+    }
+}
+
+/// prints `info` lines followed by `bestmove`. in minimal mode only the last iteration is printed
+/// (every line of it, with MultiPV), and its lines are only built once the search is over. until
+/// then each line is kept in the same slot from one depth to the next, so a deep search doesn't
+/// allocate for every iteration it won't print
+#[derive(Default)]
+pub struct UciReporter {
+    pub minimal: bool,
+    // also print the latest score of every root move after each iteration
+    pub show_root_moves: bool,
+    // print the search's debug events as `info string` lines. the GUI can turn this on and off
+    // while the search is going
+    pub debug: Arc<AtomicBool>,
+    last_iteration: Vec<LastIteration>,
+    // how many of `last_iteration` belong to the latest iteration. the rest are left over from
+    // earlier ones that had more lines
+    last_iteration_lines: usize,
+}
+
+impl UciReporter {
+    pub fn new(minimal: bool) -> Self {
+        Self {
+            minimal,
+            show_root_moves: false,
+            debug: Arc::default(),
+            last_iteration: Vec::new(),
+            last_iteration_lines: 0,
+        }
+    }
+}
+
+impl SearchReporter for UciReporter {
+    fn iteration(&mut self, report: &IterationReport) {
+        let is_first_line = report.multipv.unwrap_or(1) == 1;
+
+        if self.minimal {
+            if is_first_line {
+                self.last_iteration_lines = 0;
+            }
+
+            match self.last_iteration.get_mut(self.last_iteration_lines) {
+                Some(line) => line.update(report),
+                None => self.last_iteration.push(LastIteration::new(report)),
+            }
+            self.last_iteration_lines += 1;
+        } else {
+            println!("{}", info_line(report));
+
+            // the root moves don't change after the first line
+            if self.show_root_moves && is_first_line {
+                for root_move in report.root_moves {
+                    println!("{}", root_move_line(root_move));
+                }
+            }
+
+            // for analysis, which is when there's more than one line or debug mode is on
+            let analysing = report.multipv.is_some() || self.debug.load(Ordering::Relaxed);
+            if analysing && is_first_line {
+                for root_move in report.root_moves {
+                    if !root_move.refutation.is_empty() {
+                        println!("{}", refutation_line(root_move));
+                    }
+                }
+            }
+        }
+    }
+
+    fn best_move(&mut self, best_move: Move, ponder: Option<Move>) {
+        for line in &self.last_iteration[..self.last_iteration_lines] {
+            println!("{}", info_line(&line.report()));
+        }
+        self.last_iteration_lines = 0;
+
+        println!("{}", best_move_line(best_move, ponder));
+    }
+
+    fn debug(&mut self, event: &DebugEvent) {
+        if self.debug.load(Ordering::Relaxed) {
+            println!("info string {}", event);
+        }
+    }
+
+    fn warning(&mut self, message: &str) {
+        println!("info string {}", message);
+    }
+}
+
+/// whose side scores are shown from in the CLI. the search (and UCI, as the protocol requires)
+/// always works from the side to move, so this only changes how scores are printed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScorePerspective {
+    #[default]
+    SideToMove,
+    White,
+}
+
+impl ScorePerspective {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScorePerspective::SideToMove => "side to move",
+            ScorePerspective::White => "white",
+        }
+    }
+
+    /// `score` is from the side to move's point of view, as the search and evaluation give it
+    pub fn convert(&self, score: i32, side_to_move: Side) -> i32 {
+        match (self, side_to_move) {
+            (ScorePerspective::White, Side::Black) => -score,
+            _ => score,
+        }
+    }
+}
+
+impl std::str::FromStr for ScorePerspective {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stm" => Ok(ScorePerspective::SideToMove),
+            "white" => Ok(ScorePerspective::White),
+            _ => bail!("expected `stm` or `white`, got `{}`", s),
+        }
+    }
+}
+
+/// prints `info` lines followed by the best move, with scores from the chosen perspective
+pub struct CliReporter {
+    perspective: ScorePerspective,
+    side_to_move: Side,
+    // the best line of the deepest iteration, for the summary once the search is over
+    last_iteration: Option<LastIteration>,
+}
+
+impl CliReporter {
+    pub fn new(perspective: ScorePerspective, side_to_move: Side) -> Self {
+        Self {
+            perspective,
+            side_to_move,
+            last_iteration: None,
+        }
+    }
+
+    /// as the search reported it, so the score is from the side to move's point of view
+    pub fn last_iteration(&self) -> Option<IterationReport<'_>> {
+        self.last_iteration.as_ref().map(LastIteration::report)
+    }
+}
+
+impl SearchReporter for CliReporter {
+    fn iteration(&mut self, report: &IterationReport) {
+        let score = self.perspective.convert(report.score, self.side_to_move);
+        println!("{}", info_line(&IterationReport { score, ..*report }));
+
+        if report.multipv.unwrap_or(1) == 1 {
+            self.last_iteration = Some(LastIteration::new(report));
+        }
+    }
+
+    fn best_move(&mut self, best_move: Move, _ponder: Option<Move>) {
+        println!("{}", best_move);
+    }
+
+    fn warning(&mut self, message: &str) {
+        println!("{}", message);
+    }
+}
+
+/// for when only the result of the search matters, e.g. bench
+pub struct SilentReporter;
+
+impl SearchReporter for SilentReporter {
+    fn iteration(&mut self, _report: &IterationReport) {}
+
+    fn best_move(&mut self, _best_move: Move, _ponder: Option<Move>) {}
+}
+
+/// one line of JSON per iteration, and a result line once the search is over, for scripts that
+/// would otherwise have to parse `info` lines. scores are from the side to move, as with UCI
+pub struct JsonReporter {
+    started: Instant,
+    last_iteration: Option<LastIteration>,
+}
+
+impl Default for JsonReporter {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            last_iteration: None,
+        }
+    }
+}
+
+impl SearchReporter for JsonReporter {
+    fn iteration(&mut self, report: &IterationReport) {
+        println!("{}", json_iteration_line(report));
+        self.last_iteration = Some(LastIteration::new(report));
+    }
+
+    fn best_move(&mut self, best_move: Move, ponder: Option<Move>) {
+        let last_iteration = self.last_iteration.as_ref().map(LastIteration::report);
+
+        println!(
+            "{}",
+            json_result_line(
+                best_move,
+                ponder,
+                last_iteration.as_ref(),
+                self.started.elapsed().as_millis()
+            )
+        );
+    }
+}
+
+/// a file that searches get appended to, for looking back at what the engine was thinking after a
+/// game. if writing ever fails, the error is reported once and logging stops
+pub struct SearchLog {
+    path: String,
+    file: Option<File>,
+    disabled: bool,
+}
+
+impl SearchLog {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+            file: None,
+            disabled: false,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// makes sure everything written so far has reached the disk
+    pub fn flush(&mut self) {
+        let Some(file) = &mut self.file else {
+            return;
+        };
+
+        if let Err(error) = file.sync_all() {
+            println!(
+                "info string failed to flush log file {}: {}",
+                self.path, error
+            );
+        }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.disabled {
+            return;
+        }
+
+        if let Err(error) = self.try_write_line(line) {
+            println!(
+                "info string failed to write log file {}: {}",
+                self.path, error
+            );
+            self.disabled = true;
+            self.file = None;
+        }
+    }
+
+    fn try_write_line(&mut self, line: &str) -> io::Result<()> {
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)?,
+            ),
+        };
+
+        // `File` isn't buffered, so each line hits the disk straight away
+        writeln!(file, "{}", line)
+    }
+}
+
+/// writes every iteration and the best move to a `SearchLog`, then passes them on to another
+/// reporter so the normal output is unaffected
+pub struct LoggingReporter<'a> {
+    inner: &'a mut dyn SearchReporter,
+    log: &'a mut SearchLog,
+    fen: String,
+    started: Instant,
+}
+
+impl<'a> LoggingReporter<'a> {
+    pub fn new(inner: &'a mut dyn SearchReporter, log: &'a mut SearchLog, fen: String) -> Self {
+        Self {
+            inner,
+            log,
+            fen,
+            started: Instant::now(),
+        }
+    }
+
+    // fields are tab-separated, since the FEN contains spaces
+    fn log_line(&mut self, fields: &str) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or(0);
+
+        let line = format!("{}\t{}\t{}", timestamp, self.fen, fields);
+        self.log.write_line(&line);
+    }
+}
+
+impl SearchReporter for LoggingReporter<'_> {
+    fn iteration(&mut self, report: &IterationReport) {
+        let multipv = match report.multipv {
+            Some(line) => format!("multipv {}\t", line),
+            None => String::new(),
+        };
+
+        self.log_line(&format!(
+            "{}depth {}\tscore {}\tnodes {}\ttime {}\tpv {}",
+            multipv,
+            report.depth,
+            score_string(report.score),
+            report.nodes,
+            self.started.elapsed().as_millis(),
+            pv_string(report.pv),
+        ));
+
+        self.inner.iteration(report);
+    }
+
+    fn best_move(&mut self, best_move: Move, ponder: Option<Move>) {
+        self.log_line(&best_move_line(best_move, ponder));
+
+        self.inner.best_move(best_move, ponder);
+    }
+
+    fn debug(&mut self, event: &DebugEvent) {
+        self.inner.debug(event);
+    }
+
+    fn warning(&mut self, message: &str) {
+        self.inner.warning(message);
+    }
+}
+
+pub fn best_move_line(best_move: Move, ponder: Option<Move>) -> String {
+    match ponder {
+        Some(ponder) => format!("bestmove {} ponder {}", best_move, ponder),
+        None => format!("bestmove {}", best_move),
+    }
+}
+
+pub fn info_line(report: &IterationReport) -> String {
+    format!(
+        "info {}depth {} seldepth {} score {}{} nodes {} nps {} time {} pv {}",
+        multipv_prefix(report),
+        report.depth,
+        report.seldepth,
+        score_string(report.score),
+        bound_suffix(report.bound),
+        report.nodes,
+        nodes_per_second(report.nodes, report.time_ms),
+        report.time_ms,
+        pv_string(report.pv),
+    )
+}
+
+// ` lowerbound` or ` upperbound`, or nothing for an exact score
+fn bound_suffix(bound: ScoreBound) -> String {
+    match bound {
+        ScoreBound::Exact => String::new(),
+        _ => format!(" {}", bound.name()),
+    }
+}
+
+// e.g. `multipv 2 `, or nothing without MultiPV
+fn multipv_prefix(report: &IterationReport) -> String {
+    match report.multipv {
+        Some(line) => format!("multipv {} ", line),
+        None => String::new(),
+    }
+}
+
+pub fn root_move_line(root_move: &RootMove) -> String {
+    format!(
+        "info string rootmove {} depth {} score {} {} nodes {}",
+        root_move.mv,
+        root_move.depth,
+        score_string(root_move.score),
+        root_move.bound.name(),
+        root_move.nodes,
+    )
+}
+
+/// e.g. `info refutation d1h5 g7g6 h5e5`: the root move, then the reply that refuted it and the
+/// line after that
+pub fn refutation_line(root_move: &RootMove) -> String {
+    let line: Vec<_> = root_move.refutation.iter().map(Move::to_string).collect();
+    format!("info refutation {} {}", root_move.mv, line.join(" "))
+}
+
+/// e.g. `Evaluation (white): -35 cp (-0.35)`. `score` is from the side to move's point of view
+pub fn evaluation_line(score: i32, side_to_move: Side, perspective: ScorePerspective) -> String {
+    let score = perspective.convert(score, side_to_move);
+
+    format!(
+        "Evaluation ({}): {} cp ({:+.2})",
+        perspective.name(),
+        score,
+        score as f64 / 100.0
+    )
+}
+
+/// a score in pawns with its sign, e.g. `+0.35`, or `#3` and `#-2` for mates
+pub fn pawns_string(score: i32) -> String {
+    match uci_score(score) {
+        ("mate", moves) => format!("#{}", moves),
+        (_, centipawns) => format!("{:+.2}", centipawns as f64 / 100.0),
+    }
+}
+
+/// `999ms` under a second, `1.2s` under a minute and `2m05s` from then on. partial tenths,
+/// seconds and so on are dropped rather than rounded up
+pub fn format_duration(time_ms: u128) -> String {
+    if time_ms < 1000 {
+        format!("{}ms", time_ms)
+    } else if time_ms < 60_000 {
+        format!("{}.{}s", time_ms / 1000, time_ms % 1000 / 100)
+    } else {
+        let seconds = time_ms / 1000;
+        format!("{}m{:02}s", seconds / 60, seconds % 60)
+    }
+}
+
+/// e.g. `{"type":"iteration","depth":5,"seldepth":9,"score":{"cp":23},"bound":"exact",
+/// "nodes":5821,"nps":291050,"time_ms":20,"pv":["e2e4","e7e5"],"hashfull":3}`. a search that failed
+/// high or low has a `lowerbound` or `upperbound` instead, and is followed by another at the same
+/// depth
+pub fn json_iteration_line(report: &IterationReport) -> String {
+    let (score_kind, score) = uci_score(report.score);
+
+    format!(
+        concat!(
+            "{{\"type\":\"iteration\",\"depth\":{},\"seldepth\":{},\"score\":{{\"{}\":{}}},",
+            "\"bound\":\"{}\",\"nodes\":{},\"nps\":{},\"time_ms\":{},\"pv\":{},\"hashfull\":{}}}"
+        ),
+        report.depth,
+        report.seldepth,
+        score_kind,
+        score,
+        report.bound.name(),
+        report.nodes,
+        nodes_per_second(report.nodes, report.time_ms),
+        report.time_ms,
+        json_moves(report.pv),
+        report.hashfull,
+    )
+}
+
+/// e.g. `{"type":"result","bestmove":"e2e4","ponder":"e7e5","depth":5,"nodes":5821,"nps":291050,
+/// "time_ms":20}`. the depth and nodes are those of the last finished iteration, and `ponder` is
+/// `null` when there's no expected reply
+pub fn json_result_line(
+    best_move: Move,
+    ponder: Option<Move>,
+    last_iteration: Option<&IterationReport>,
+    time_ms: u128,
+) -> String {
+    let ponder = match ponder {
+        Some(ponder) => format!("\"{}\"", ponder),
+        None => String::from("null"),
+    };
+
+    let (depth, nodes) = match last_iteration {
+        Some(report) => (report.depth, report.nodes),
+        None => (0, 0),
+    };
+
+    format!(
+        concat!(
+            "{{\"type\":\"result\",\"bestmove\":\"{}\",\"ponder\":{},\"depth\":{},",
+            "\"nodes\":{},\"nps\":{},\"time_ms\":{}}}"
+        ),
+        best_move,
+        ponder,
+        depth,
+        nodes,
+        nodes_per_second(nodes, time_ms),
+        time_ms,
+    )
+}
+
+// moves are the only strings written, and never need escaping
+fn json_moves(moves: &[Move]) -> String {
+    let moves: Vec<String> = moves.iter().map(|mv| format!("\"{}\"", mv)).collect();
+    format!("[{}]", moves.join(","))
+}
+
+fn nodes_per_second(nodes: u64, time_ms: u128) -> u128 {
+    nodes as u128 * 1000 / time_ms.max(1)
+}
+
+pub fn score_string(score: i32) -> String {
+    let (kind, score) = uci_score(score);
+    format!("{} {}", kind, score)
+}
+
+/// the score as UCI shows it, either `cp` and centipawns or `mate` and moves to mate
+pub fn uci_score(score: i32) -> (&'static str, i32) {
+    if score.abs() > CHECKMATE_THRESHOLD {
+        let ply_to_mate = INFINITY.abs_diff(score.abs()) as i32;
+
+        // prints from engine's perspective. when mating, the engine's own moves are counted, so
+        // the last (odd) ply is a whole move. when being mated, it's the opponent's moves that
+        // count, so e.g. mated at ply 4 is `mate -2`
+        let moves_to_mate = if score > 0 {
+            (ply_to_mate + 1) / 2
+        } else {
+            -(ply_to_mate / 2)
+        };
+
+        ("mate", moves_to_mate)
+    } else {
+        ("cp", score)
+    }
+}
+
+pub fn pv_string(pv: &[Move]) -> String {
+    let mut pv_string = String::new();
+
+    for mv in pv {
+        pv_string.push(' ');
+        pv_string.push_str(&mv.to_string());
+    }
+
+    pv_string.trim().into()
+}
@@ -0,0 +1,101 @@
+use crate::move_generator::Move;
+
+/// how far a root move's score can be trusted. with PVS, most root moves are only searched with a
+/// null window, so all that's known is that they're no better than the best move so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreBound {
+    Exact,
+    // the real score is at least this
+    Lower,
+    // the real score is at most this
+    Upper,
+}
+
+impl ScoreBound {
+    /// what a fail-hard search with the window `alpha`..`beta` says about `score`
+    pub fn of(score: i32, alpha: i32, beta: i32) -> Self {
+        if score <= alpha {
+            ScoreBound::Upper
+        } else if score >= beta {
+            ScoreBound::Lower
+        } else {
+            ScoreBound::Exact
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScoreBound::Exact => "exact",
+            ScoreBound::Lower => "lowerbound",
+            ScoreBound::Upper => "upperbound",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RootMove {
+    pub mv: Move,
+    pub score: i32,
+    // nodes spent on this move in the iteration that produced `score`
+    pub nodes: u64,
+    pub bound: ScoreBound,
+    // the iteration that produced `score`
+    pub depth: u8,
+    // for a move that turned out worse than the best one, the reply that showed it and the line
+    // after it, as far as it was searched. only kept for the moves searched right after the best
+    pub refutation: Vec<Move>,
+}
+
+/// the last known result for every legal move at the root, kept across iterations so a move that
+/// wasn't reached before time ran out still has the score from the previous iteration
+#[derive(Debug, Clone, Default)]
+pub struct RootMoves {
+    moves: Vec<RootMove>,
+    // the last move to raise alpha in a fully searched subtree. that's either the best move of the
+    // deepest complete iteration, or a move that beat it in a later iteration that didn't finish
+    best_move: Option<Move>,
+}
+
+impl RootMoves {
+    pub fn clear(&mut self) {
+        self.moves.clear();
+        self.best_move = None;
+    }
+
+    pub fn update(&mut self, mv: Move, score: i32, nodes: u64, depth: u8, alpha: i32, beta: i32) {
+        let bound = ScoreBound::of(score, alpha, beta);
+
+        let root_move = RootMove {
+            mv,
+            score,
+            nodes,
+            bound,
+            depth,
+            refutation: Vec::new(),
+        };
+
+        if bound != ScoreBound::Upper {
+            self.best_move = Some(mv);
+        }
+
+        match self.moves.iter_mut().find(|existing| existing.mv == mv) {
+            Some(existing) => *existing = root_move,
+            None => self.moves.push(root_move),
+        }
+    }
+
+    /// `line` starts with the reply to `mv`, which has to have been updated already
+    pub fn set_refutation(&mut self, mv: Move, line: &[Move]) {
+        if let Some(root_move) = self.moves.iter_mut().find(|existing| existing.mv == mv) {
+            root_move.refutation = line.to_vec();
+        }
+    }
+
+    pub fn moves(&self) -> &[RootMove] {
+        &self.moves
+    }
+
+    pub fn best_move(&self) -> Option<Move> {
+        self.best_move
+    }
+}
@@ -0,0 +1,149 @@
+// standard algebraic notation, e.g. `Nbd7`, `exd6` or `e8=Q+`, for showing moves to people. UCI
+// and everything else that reads moves back in keeps to the long algebraic notation
+
+use anyhow::bail;
+
+use crate::{
+    board::Board,
+    move_generator::{Move, MoveFlag, MoveKind, MoveList},
+    square::{Piece, PieceColor, PieceKind, Square},
+};
+
+impl Board {
+    /// `mv` in SAN, which has to be a legal move in the position. the board is the same
+    /// afterwards
+    pub fn to_san(&mut self, mv: Move) -> anyhow::Result<String> {
+        let mut san = self.san_without_check(mv)?;
+
+        if !self.make_move(mv)? {
+            self.unmake_move(mv);
+            bail!("Move `{}` is not legal in this position", mv);
+        }
+
+        if self.is_in_check(self.side_to_move()) {
+            let is_mate = self.first_legal_move()?.is_none();
+            san.push(if is_mate { '#' } else { '+' });
+        }
+
+        self.unmake_move(mv);
+
+        Ok(san)
+    }
+
+    /// every move of `line` in SAN, separated by spaces, with each one played from the position
+    /// the one before it leaves. the board is the same afterwards
+    pub fn line_to_san(&mut self, line: &[Move]) -> anyhow::Result<String> {
+        let mut moves = Vec::with_capacity(line.len());
+        let mut result = Ok(());
+
+        for &mv in line {
+            match self.to_san(mv) {
+                Ok(san) => moves.push(san),
+                Err(error) => {
+                    result = Err(error);
+                    break;
+                }
+            }
+
+            self.make_move(mv)?;
+        }
+
+        for &mv in line[..moves.len()].iter().rev() {
+            self.unmake_move(mv);
+        }
+
+        result.map(|_| moves.join(" "))
+    }
+
+    fn san_without_check(&mut self, mv: Move) -> anyhow::Result<String> {
+        if mv.kind() == MoveKind::Castle {
+            // castling is king-takes-rook internally, so the rook says which side it is
+            let kingside = mv.to_square().index() > mv.from_square().index();
+
+            return Ok(String::from(if kingside { "O-O" } else { "O-O-O" }));
+        }
+
+        let piece = self.get_piece(mv.from_square());
+        if piece.kind == PieceKind::NoPiece {
+            bail!(
+                "There is no piece on {} to move",
+                square_name(mv.from_square())
+            );
+        }
+
+        let is_capture = mv.flag() == MoveFlag::EnPassant
+            || self.get_piece(mv.to_square()).kind != PieceKind::NoPiece;
+
+        let mut san = String::new();
+
+        if piece.kind == PieceKind::Pawn {
+            // pawn captures are told apart by the file they're made from, and nothing else
+            if is_capture {
+                san.push_str(&square_name(mv.from_square())[..1]);
+            }
+        } else {
+            san.push(piece_letter(piece.kind));
+            san.push_str(&self.disambiguation(mv, piece)?);
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+
+        san.push_str(&square_name(mv.to_square()));
+
+        if mv.kind() == MoveKind::Promotion {
+            san.push('=');
+            san.push(piece_letter(PieceKind::from(mv.flag())));
+        }
+
+        Ok(san)
+    }
+
+    // whatever of the starting square is needed to tell `mv` apart from the other legal moves of
+    // the same kind of piece to the same square: the file if that's enough, otherwise the rank,
+    // otherwise both
+    fn disambiguation(&mut self, mv: Move, piece: Piece) -> anyhow::Result<String> {
+        let mut move_list = MoveList::default();
+        self.generate_all_moves(&mut move_list)?;
+
+        let mut rivals = Vec::new();
+
+        for other in move_list {
+            if other.to_square() != mv.to_square()
+                || other.from_square() == mv.from_square()
+                || other.kind() == MoveKind::Castle
+                || self.get_piece(other.from_square()) != piece
+            {
+                continue;
+            }
+
+            let is_legal = self.make_move(other)?;
+            self.unmake_move(other);
+
+            if is_legal {
+                rivals.push(square_name(other.from_square()));
+            }
+        }
+
+        let from = square_name(mv.from_square());
+
+        if rivals.is_empty() {
+            Ok(String::new())
+        } else if rivals.iter().all(|rival| rival[..1] != from[..1]) {
+            Ok(from[..1].into())
+        } else if rivals.iter().all(|rival| rival[1..] != from[1..]) {
+            Ok(from[1..].into())
+        } else {
+            Ok(from)
+        }
+    }
+}
+
+fn square_name(square: Square) -> String {
+    format!("{:?}", square).to_lowercase()
+}
+
+fn piece_letter(kind: PieceKind) -> char {
+    Piece::new(PieceColor::White, kind).fen_char()
+}
@@ -1,19 +1,38 @@
+use std::{
+    ops::AddAssign,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+};
+
 use crate::{
-    board::{Board, START_POSITION_FEN},
-    evaluate::{BISHOP_VALUE, KING_VALUE, KNIGHT_VALUE, PAWN_VALUE, QUEEN_VALUE, ROOK_VALUE},
-    move_generator::{Move, MoveKind, MoveList},
-    square::PieceKind,
+    board::{Board, Side, START_POSITION_FEN},
+    debug_sink::{DebugEvent, DebugSink, DebugStats},
+    evaluate::{KING_VALUE, KNIGHT_VALUE, PAWN_VALUE, QUEEN_VALUE},
+    move_generator::{Move, MoveFlag, MoveKind, MoveList, MAX_MOVES},
+    position::apply_fen,
+    prng::Prng,
+    profile::{self, Phase},
+    reporter::{IterationReport, SearchReporter, SilentReporter},
+    root_moves::{RootMoves, ScoreBound},
+    search_config::SearchConfig,
+    search_limits::SearchLimits,
+    square::{Piece, PieceColor, PieceKind},
     time_management::SearchTimer,
     transposition_table::{SearchEntryFlag, SearchTableEntry, TranspositionTable},
+    zobrist_hash::{excluded_move_key, DRAW_PERSPECTIVE_KEY},
 };
 
-// if the score is higher than this, it's definitely checkmate
-pub const CHECKMATE_THRESHOLD: i32 = PAWN_VALUE * 8
-    + KNIGHT_VALUE * 8
-    + BISHOP_VALUE * 8
-    + ROOK_VALUE * 8
-    + QUEEN_VALUE * 8
-    + KING_VALUE;
+// if the score is higher than this, it's definitely checkmate. mate scores are offset from
+// `INFINITY` by the ply they're found at, so this doesn't depend on how much material is on the
+// board (positions from editors can have more than the usual amount)
+pub const CHECKMATE_THRESHOLD: i32 = INFINITY - SearchDepth::MAX as i32 - 1;
+
+/// debug builds panic once a search has gone through this many nodes, if it's set, so tests can
+/// check the engine recovers from a bug in the search
+pub const PANIC_AFTER_NODES_VAR: &str = "KRUSTY_PANIC_AFTER_NODES";
 
 #[derive(Debug, Clone, Copy)]
 pub enum SearchDepth {
@@ -22,7 +41,7 @@ pub enum SearchDepth {
 }
 
 impl SearchDepth {
-    const MAX: u8 = 64;
+    pub const MAX: u8 = 64;
 }
 
 impl From<SearchDepth> for u8 {
@@ -34,30 +53,170 @@ impl From<SearchDepth> for u8 {
     }
 }
 
-const INFINITY: i32 = 100_000;
+pub const INFINITY: i32 = 100_000;
 const CAPTURE_SCORE_OFFSET: i32 = 1000;
-const TT_SCORE_OFFSET: i32 = CAPTURE_SCORE_OFFSET + 10000;
+// taking the piece that just moved goes before any other capture of a piece worth the same, since
+// it's usually the only way to get the material back. it's more than the gap between any two
+// attackers, and less than the gap between victims worth different amounts
+const RECAPTURE_BONUS: i32 = 1000;
+const TT_SCORE_OFFSET: i32 = CAPTURE_SCORE_OFFSET + 10000 + RECAPTURE_BONUS;
+// every capture, even a king taking a queen on a promotion square, has to stay between the killers
+// and the TT move, and the TT move has to fit in the move's score bits
+const _: () = assert!(
+    CAPTURE_SCORE_OFFSET + 10 * PAWN_VALUE - QUEEN_VALUE > FIRST_KILLER_SCORE
+        && CAPTURE_SCORE_OFFSET + 10 * QUEEN_VALUE - KING_VALUE + RECAPTURE_BONUS < TT_SCORE_OFFSET
+        && TT_SCORE_OFFSET as u32 <= Move::MAX_SCORE
+        && RECAPTURE_BONUS > QUEEN_VALUE - KING_VALUE
+        && RECAPTURE_BONUS < 10 * (KNIGHT_VALUE - PAWN_VALUE)
+);
 const FIRST_KILLER_SCORE: i32 = CAPTURE_SCORE_OFFSET - 1;
 const SECOND_KILLER_SCORE: i32 = CAPTURE_SCORE_OFFSET - 2;
 const COUNTER_MOVE_BONUS: i32 = 1;
 // history heuristic must always be lower in move ordering than killer heuristic
 const MAX_HISTORY_SCORE: i32 = SECOND_KILLER_SCORE - COUNTER_MOVE_BONUS - 1;
+// quiet moves causing a cutoff get a history bonus of the depth squared, up to
+// `HISTORY_BONUS_MAX`. cutoffs shallower than `HISTORY_MIN_DEPTH` are mostly noise, so they don't
+// get one at all. see `history_bonus`
+pub const HISTORY_MIN_DEPTH: u8 = 2;
+pub const HISTORY_BONUS_MAX: u32 = 400;
+// taken off a quiet move's score when the reply that refuted it last time can still be played.
+// about the history bonus of a depth 4 cutoff, which came out with the fewest bench nodes at
+// depths 8 to 11
+pub const REFUTED_MOVE_PENALTY: i32 = 16;
+
+// the null move search is `NULL_MOVE_REDUCTION` plies shallower than the node's own, and another
+// ply shallower for every `NULL_MOVE_REDUCTION_DEPTH_DIVISOR` plies of depth. it's only worth it
+// from `NULL_MOVE_MIN_DEPTH`
+const NULL_MOVE_MIN_DEPTH: u8 = 3;
+const NULL_MOVE_REDUCTION: u8 = 3;
+const NULL_MOVE_REDUCTION_DEPTH_DIVISOR: u8 = 6;
+
+// quiet moves losing more than `SEE_QUIET_MARGIN` per remaining ply to a static exchange get
+// skipped at depths up to `SEE_PRUNING_DEPTH`. without it, a depth 10 bench searches about half as
+// many nodes again
+const SEE_PRUNING_DEPTH: u8 = 3;
+const SEE_QUIET_MARGIN: i32 = 50;
+
+pub const DEFAULT_MUST_WIN_MARGIN: i32 = 50;
+
+// from `ASPIRATION_MIN_DEPTH`, the first line of the root is searched with a window of
+// `ASPIRATION_WINDOW` either side of the previous iteration's score. a bound the score falls
+// outside of is moved `ASPIRATION_GROWTH` times as far away each time, and after
+// `MAX_ASPIRATION_RESEARCHES` of those the window is the full one
+const ASPIRATION_MIN_DEPTH: u8 = 4;
+const ASPIRATION_WINDOW: i32 = 30;
+const ASPIRATION_GROWTH: i32 = 4;
+const MAX_ASPIRATION_RESEARCHES: u32 = 4;
+
+// a root move scoring this much below what the previous iteration gave it has failed low, and the
+// search gets extra time to look for a better one. scores swing too much from one shallow
+// iteration to the next for that to mean anything before `PANIC_MIN_DEPTH`
+pub const PANIC_FAIL_LOW_MARGIN: i32 = 75;
+pub const PANIC_MIN_DEPTH: u8 = 5;
+
+// forcing lines can otherwise balloon the tree, so no path gets more extensions than this
+const MAX_EXTENSIONS: u8 = 6;
+
+// positions full of hanging pieces can keep the capture search going for dozens of plies, so it
+// settles for the static evaluation after this many captures past the horizon
+const MAX_QUIESCENCE_DEPTH: u8 = 8;
+
+// when the root position evaluates at least `DEAD_DRAW_THRESHOLD` in the engine's favour, a root
+// move that leaves too little material to win scores `DEAD_DRAW_PENALTY` below a draw, so anything
+// that doesn't lose is preferred to it
+pub const DEAD_DRAW_THRESHOLD: i32 = 200;
+pub const DEAD_DRAW_PENALTY: i32 = 50;
+
+// how many root moves after the best one keep the line that refuted them. they're searched in
+// order of how good they looked, so these are the closest alternatives
+pub const REFUTED_CANDIDATES: usize = 3;
+
+/// how much a quiet move causing a cutoff at `depth` adds to its history score
+pub fn history_bonus(depth: u8) -> u32 {
+    if depth < HISTORY_MIN_DEPTH {
+        return 0;
+    }
 
-const DEPTH_REDUCTION_FACTOR: u8 = 2;
+    (depth as u32 * depth as u32).min(HISTORY_BONUS_MAX)
+}
+
+// how many centipawns a draw is below even for the engine's side
+fn draw_cost(must_win: bool, must_win_margin: i32, contempt: i32) -> i32 {
+    if must_win {
+        contempt + must_win_margin
+    } else {
+        contempt
+    }
+}
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct SearchInfo {
     pub ply: u8,
+    // the extra plies added along the current path, and how many the path is allowed in total
+    pub extensions: u8,
+    pub extension_budget: u8,
     pub nodes_searched: u64,
+    // the deepest ply reached so far, counting the capture search
+    pub seldepth: u8,
+    // the part of `nodes_searched` spent in the capture search past the horizon
+    pub quiescence_nodes: u64,
+    pub ordering: OrderingStats,
+    pub debug: DebugStats,
+    // a position had more moves than a move list holds, so some of them weren't searched
+    pub moves_truncated: bool,
+}
+
+// cheap counters for judging how well moves are ordered. the better the ordering, the more often
+// the first move searched causes the beta cutoff
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrderingStats {
+    pub beta_cutoffs: u64,
+    pub first_move_cutoffs: u64,
+    // sum of the (0-based) index of the move causing each cutoff
+    pub cutoff_index_total: u64,
+    // cutoffs at nodes where the transposition table provided a move
+    pub tt_move_cutoffs: u64,
+}
+
+impl OrderingStats {
+    pub fn first_move_cutoff_rate(&self) -> f64 {
+        Self::ratio(self.first_move_cutoffs, self.beta_cutoffs)
+    }
+
+    pub fn average_cutoff_index(&self) -> f64 {
+        Self::ratio(self.cutoff_index_total, self.beta_cutoffs)
+    }
+
+    pub fn tt_move_rate(&self) -> f64 {
+        Self::ratio(self.tt_move_cutoffs, self.beta_cutoffs)
+    }
+
+    fn ratio(count: u64, total: u64) -> f64 {
+        if total == 0 {
+            0.0
+        } else {
+            count as f64 / total as f64
+        }
+    }
+}
+
+impl AddAssign for OrderingStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.beta_cutoffs += rhs.beta_cutoffs;
+        self.first_move_cutoffs += rhs.first_move_cutoffs;
+        self.cutoff_index_total += rhs.cutoff_index_total;
+        self.tt_move_cutoffs += rhs.tt_move_cutoffs;
+    }
 }
 
 pub struct Search {
-    transposition_table: TranspositionTable<SearchTableEntry>,
+    pub transposition_table: TranspositionTable<SearchTableEntry>,
     pub board: Board,
 
     pub search_info: SearchInfo,
     pub timer: SearchTimer,
-    pub max_depth: u8,
+    // what the current search was asked for
+    limits: SearchLimits,
 
     // quiet moves that caused a beta-cutoff, indexed by search ply
     pub killer_moves: [[Move; 2]; SearchDepth::MAX as usize + 1],
@@ -69,113 +228,596 @@ pub struct Search {
     // keeps track of any cutoffs caused by a particular from-to move, the idea being that it might
     // also be a good counter move to the same from-to move in other positions
     pub counter_moves: [[[Move; 64]; 64]; 2],
+
+    // the other side of the counter moves: the reply that caused a cutoff against a quiet from-to
+    // move, captures included. indexed the same way, by the side that played the reply
+    pub refutations: [[[Move; 64]; 64]; 2],
+
+    // every random decision the search makes draws from this, so a fixed seed makes searches
+    // reproducible. kept separate from the generator used for zobrist keys
+    pub rng: Prng,
+    seed: Option<u64>,
+
+    // whether to steer away from draws, and how much of a loss a draw counts as when doing so
+    pub must_win: bool,
+    pub must_win_margin: i32,
+    // how much worse than even a draw is for the engine, whether or not it must win
+    pub contempt: i32,
+
+    // the side the engine is searching for, i.e. the side to move at the root
+    root_side: Side,
+    // whether the root position is good enough that throwing it away for a dead draw is a mistake
+    avoid_dead_draws: bool,
+
+    pub root_moves: RootMoves,
+
+    // the principal variation of the last finished iteration
+    principal_variation: Vec<Move>,
+
+    // how many of the best root moves get their own line, each searched with the ones before it
+    // left out
+    pub multi_pv: usize,
+    excluded_root_moves: Vec<Move>,
+    // a move left out of the node at each ply, e.g. the TT move while checking that nothing else
+    // comes close to it. a null move when nothing is
+    excluded_moves: [Move; SearchDepth::MAX as usize + 1],
+
+    // how many threads search at once. all but one are helpers, see `search_position`
+    pub threads: usize,
+    is_helper: bool,
+    // the depth iterative deepening starts at. helpers don't all start at 1, so they're less
+    // likely to all be searching the same thing at once
+    first_depth: u8,
+    // nodes searched by the helpers so far, each adding its own every time it checks the timer
+    helper_nodes: Arc<AtomicU64>,
+    helper_nodes_added: u64,
+
+    // events for `debug` output, passed on to the reporter between iterations
+    debug_sink: DebugSink,
+}
+
+// sets the flag however the scope it's in is left. a scope waits for every thread spawned in it,
+// so helpers that weren't stopped after a panic would keep it waiting forever
+struct StopOnDrop<'a>(&'a AtomicBool);
+
+impl Drop for StopOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
 }
 
 impl Default for Search {
     fn default() -> Self {
+        SearchConfig::default().build().unwrap()
+    }
+}
+
+impl Search {
+    pub(crate) fn with_config(config: &SearchConfig) -> anyhow::Result<Self> {
+        let mut search = Self::with_transposition_table(TranspositionTable::new(config.hash_mb));
+        apply_fen(&mut search.board, &config.fen)?;
+        search.configure(config);
+
+        Ok(search)
+    }
+
+    fn with_transposition_table(transposition_table: TranspositionTable<SearchTableEntry>) -> Self {
         let mut board = Board::default();
         board.parse_fen(START_POSITION_FEN).unwrap();
 
         Self {
-            transposition_table: TranspositionTable::new(64),
+            transposition_table,
             board,
             search_info: SearchInfo::default(),
             timer: SearchTimer::default(),
-            max_depth: SearchDepth::MAX,
+            limits: SearchLimits::default(),
             killer_moves: [[Move::NULL_MOVE; 2]; SearchDepth::MAX as usize + 1],
             history: [[[0; 64]; 64]; 2],
             counter_moves: [[[Move::NULL_MOVE; 64]; 64]; 2],
+            refutations: [[[Move::NULL_MOVE; 64]; 64]; 2],
+            rng: Prng::from_entropy(),
+            seed: None,
+            must_win: false,
+            must_win_margin: DEFAULT_MUST_WIN_MARGIN,
+            contempt: 0,
+            root_side: Side::White,
+            avoid_dead_draws: false,
+            root_moves: RootMoves::default(),
+            principal_variation: Vec::new(),
+            multi_pv: 1,
+            excluded_root_moves: Vec::new(),
+            excluded_moves: [Move::NULL_MOVE; SearchDepth::MAX as usize + 1],
+            threads: 1,
+            is_helper: false,
+            first_depth: 1,
+            helper_nodes: Arc::default(),
+            helper_nodes_added: 0,
+            debug_sink: DebugSink::default(),
         }
     }
-}
 
-impl Search {
+    /// back to the start position with nothing learnt, keeping the configuration
     pub fn reset(&mut self) {
-        *self = Self::default();
+        let config = SearchConfig {
+            fen: START_POSITION_FEN.into(),
+            ..self.config()
+        };
+
+        *self = config.build().unwrap();
+    }
+
+    /// the configuration the search has now, starting from the current position
+    pub fn config(&self) -> SearchConfig {
+        SearchConfig {
+            hash_mb: self.transposition_table.configured_mb(),
+            threads: self.threads,
+            multi_pv: self.multi_pv,
+            seed: self.seed,
+            must_win: self.must_win,
+            must_win_margin: self.must_win_margin,
+            contempt: self.contempt,
+            move_overhead_ms: self.timer.move_overhead_ms,
+            fen: self.board.fen(),
+        }
+    }
+
+    /// applies everything but the position. the transposition table is only replaced, and so
+    /// emptied, when its size changes, and the random numbers only start again for a new seed.
+    /// the table is also emptied when draws are scored differently, since its scores include them
+    pub fn configure(&mut self, config: &SearchConfig) {
+        if config.hash_mb != self.transposition_table.configured_mb() {
+            self.transposition_table = TranspositionTable::new(config.hash_mb);
+        } else if draw_cost(config.must_win, config.must_win_margin, config.contempt)
+            != self.draw_cost()
+        {
+            self.transposition_table.clear();
+        }
+
+        if config.seed != self.seed {
+            self.set_seed(config.seed);
+        }
+
+        self.threads = config.threads;
+        self.multi_pv = config.multi_pv;
+        self.must_win = config.must_win;
+        self.must_win_margin = config.must_win_margin;
+        self.contempt = config.contempt;
+        self.timer.move_overhead_ms = config.move_overhead_ms;
     }
 
-    pub fn search_position(&mut self) -> anyhow::Result<Move> {
-        self.search_info.nodes_searched = 0;
-        self.search_info.ply = 0;
+    /// `None` goes back to seeding from entropy
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+        self.rng = match seed {
+            Some(seed) => Prng::new(seed),
+            None => Prng::from_entropy(),
+        };
+    }
 
-        let max_depth = self.max_depth;
+    /// the only way into the search. starts the timer, so the time spent on setting anything else
+    /// up beforehand doesn't count against the search
+    pub fn search_position(
+        &mut self,
+        limits: &SearchLimits,
+        reporter: &mut dyn SearchReporter,
+    ) -> anyhow::Result<Move> {
+        self.board.ensure_initialized()?;
+
+        self.limits = limits.clone();
+        self.timer.initialize_with_limits(limits);
+        self.timer.start();
+
+        self.search_info = SearchInfo::default();
+        self.root_side = self.board.side_to_move();
+        self.root_moves.clear();
+        self.principal_variation.clear();
+        self.excluded_root_moves.clear();
+        self.debug_sink.clear();
+
+        // a dead draw can't be won or lost, so there's nothing to gain by searching. any legal move
+        // will do
+        if self.board.has_insufficient_material() {
+            let best_move = self.board.first_legal_move()?.unwrap_or(Move::NULL_MOVE);
+
+            reporter.iteration(&IterationReport {
+                depth: 1,
+                seldepth: 1,
+                multipv: None,
+                score: 0,
+                bound: ScoreBound::Exact,
+                nodes: self.search_info.nodes_searched,
+                time_ms: self.timer.search_time_ms(),
+                hashfull: self.transposition_table.hashfull(),
+                pv: &[best_move],
+                root_moves: &[],
+            });
+
+            return Ok(best_move);
+        }
+
+        self.avoid_dead_draws = self.board.evaluate_unchecked() >= DEAD_DRAW_THRESHOLD;
+
+        // the helpers count into the main thread's total, which starts again with every search
+        if !self.is_helper {
+            self.helper_nodes = Arc::default();
+        }
+
+        if self.threads <= 1 {
+            return self.iterative_deepening(reporter);
+        }
+
+        // lazy SMP: the helpers search the same position with nothing in common but the
+        // transposition table, which they fill with results the main thread can use. only the
+        // main thread reports and decides the move, and the helpers are stopped once it's done
+        let stop_helpers = Arc::<AtomicBool>::default();
+        let helper_limits = SearchLimits {
+            searchmoves: self.limits.searchmoves.clone(),
+            ..SearchLimits::default()
+        };
+
+        let mut helpers: Vec<Search> = (1..self.threads)
+            .map(|id| self.helper(id, &stop_helpers))
+            .collect();
+
+        thread::scope(|scope| {
+            for helper in &mut helpers {
+                let helper_limits = &helper_limits;
+                scope.spawn(move || helper.search_position(helper_limits, &mut SilentReporter));
+            }
+
+            let _stop_helpers = StopOnDrop(&stop_helpers);
+            self.iterative_deepening(reporter)
+        })
+    }
+
+    // a search of the same position for helper thread `id`, sharing the transposition table and
+    // stopped through `stop_signal`
+    fn helper(&self, id: usize, stop_signal: &Arc<AtomicBool>) -> Search {
+        let mut helper = Search::with_transposition_table(self.transposition_table.share());
+
+        helper.board = self.board.clone();
+        helper.timer = SearchTimer::with_stop_signal(Arc::clone(stop_signal));
+        helper.set_seed(self.seed.map(|seed| seed.wrapping_add(id as u64)));
+        helper.must_win = self.must_win;
+        helper.must_win_margin = self.must_win_margin;
+        helper.contempt = self.contempt;
+        helper.is_helper = true;
+        helper.first_depth = 1 + (id % 2) as u8;
+        helper.helper_nodes = Arc::clone(&self.helper_nodes);
+
+        helper
+    }
+
+    fn iterative_deepening(&mut self, reporter: &mut dyn SearchReporter) -> anyhow::Result<Move> {
+        let max_depth = self.limits.max_depth();
 
         let mut best_move = Move::NULL_MOVE;
         let mut pv = Vec::new();
+        // the first line's score in the last iteration that finished
+        let mut previous_score = None;
+
+        let mut iteration_start_ms = 0;
+        let mut search_time_ms = 0;
+        let mut truncation_reported = false;
+
+        self.debug_sink.record(DebugEvent::TimeAllocated {
+            allowed: self.timer.allowed_duration,
+            move_overhead_ms: self.timer.move_overhead_ms,
+        });
+        self.flush_debug_events(reporter);
+
+        for depth in self.first_depth..=max_depth {
+            self.search_info.extension_budget = depth.min(MAX_EXTENSIONS);
+            self.excluded_root_moves.clear();
+
+            let stats_before = self.search_info.debug;
+
+            for line in 1..=self.multi_pv {
+                let aspiration_score = previous_score.filter(|_| line == 1);
+                let score = self.search_root(depth, line, aspiration_score, &mut pv, reporter)?;
+
+                if self.timer.is_stopped() {
+                    break;
+                }
 
-        for depth in 1..=max_depth {
-            let score = self.negamax(depth, -INFINITY, INFINITY, &mut pv, Move::NULL_MOVE, true)?;
+                search_time_ms = self.timer.search_time_ms();
+
+                // the first line is the same search as without MultiPV, and the only one that
+                // decides the move played. the others stop once every root move has a line
+                if line == 1 {
+                    best_move = pv.first().copied().unwrap_or(Move::NULL_MOVE);
+                    self.principal_variation.clone_from(&pv);
+                    previous_score = Some(score);
+                } else if pv.is_empty() {
+                    break;
+                }
+
+                reporter.iteration(&self.iteration_report(depth, line, score, search_time_ms, &pv));
+
+                match pv.first() {
+                    Some(mv) => self.excluded_root_moves.push(*mv),
+                    None => break,
+                }
+            }
+
+            self.debug_sink.record(DebugEvent::IterationStats {
+                depth,
+                stats: self.search_info.debug - stats_before,
+            });
+
+            // once for each search that runs into one, however many times it did
+            if self.search_info.moves_truncated && !truncation_reported {
+                reporter.warning(&format!(
+                    "more than {} moves in a position, ignoring the rest",
+                    MAX_MOVES
+                ));
+                truncation_reported = true;
+            }
 
             if self.timer.is_stopped() {
                 break;
             }
 
-            best_move = match pv.first() {
-                Some(mv) => *mv,
-                None => Move::NULL_MOVE,
+            let elapsed_ms = self.timer.elapsed_ms_at(search_time_ms);
+            let iteration_ms = elapsed_ms - iteration_start_ms;
+            iteration_start_ms = elapsed_ms;
+
+            if !self.timer.can_start_iteration(elapsed_ms, iteration_ms) {
+                self.debug_sink.record(DebugEvent::IterationSkipped {
+                    depth,
+                    elapsed_ms,
+                    last_iteration_ms: iteration_ms,
+                });
+                break;
+            }
+
+            self.flush_debug_events(reporter);
+        }
+
+        self.flush_debug_events(reporter);
+        self.excluded_root_moves.clear();
+
+        // an unfinished iteration is only worth trusting where it found something better than the
+        // last finished one, which the root moves keep track of
+        Ok(self.root_moves.best_move().unwrap_or(best_move))
+    }
+
+    // searches one MultiPV line of the root. with the previous iteration's score to go on, the
+    // window starts narrow around it, which is much quicker when the score hardly changes. a
+    // search that fails high or low is reported with its bound and searched again with that side
+    // of the window further out, so only an exact score ever decides the move
+    fn search_root(
+        &mut self,
+        depth: u8,
+        line: usize,
+        previous_score: Option<i32>,
+        pv: &mut Vec<Move>,
+        reporter: &mut dyn SearchReporter,
+    ) -> anyhow::Result<i32> {
+        // a mate score is exact or nothing, so there's no window around it
+        let aspiration_score = previous_score
+            .filter(|score| depth >= ASPIRATION_MIN_DEPTH && score.abs() < CHECKMATE_THRESHOLD);
+
+        let mut delta = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) = match aspiration_score {
+            Some(score) => (score - delta, score + delta),
+            None => (-INFINITY, INFINITY),
+        };
+
+        let mut researches = 0;
+
+        loop {
+            pv.clear();
+
+            let score = self.negamax(depth, alpha, beta, pv, Move::NULL_MOVE, true)?;
+
+            // the edges of the full window aren't bounds, since no score can be outside them. a
+            // side with no moves at all scores the bottom edge exactly
+            let bound = match ScoreBound::of(score, alpha, beta) {
+                ScoreBound::Upper if alpha == -INFINITY => ScoreBound::Exact,
+                ScoreBound::Lower if beta == INFINITY => ScoreBound::Exact,
+                bound => bound,
             };
 
-            println!(
-                "info depth {} score {} nodes {} pv {}",
-                depth,
-                Self::get_score_string(score),
-                self.search_info.nodes_searched,
-                Self::get_pv_string(&pv),
-            );
+            if bound == ScoreBound::Exact || self.timer.is_stopped() {
+                return Ok(score);
+            }
+
+            // the whole line is much worse than it was, so it gets the time to be searched again
+            if bound == ScoreBound::Upper
+                && line == 1
+                && depth >= PANIC_MIN_DEPTH
+                && previous_score.is_some_and(|previous| score <= previous - PANIC_FAIL_LOW_MARGIN)
+            {
+                self.extend_time_after_fail_low();
+            }
+
+            self.report_bound(depth, line, score, bound, pv, reporter);
+
+            researches += 1;
+            self.search_info.debug.aspiration_researches += 1;
+            delta = delta.saturating_mul(ASPIRATION_GROWTH);
+
+            // a score that keeps jumping from one side of the window to the other could go on
+            // forever, and one heading for a mate may as well be searched without a bound
+            let give_up =
+                researches >= MAX_ASPIRATION_RESEARCHES || score.abs() >= CHECKMATE_THRESHOLD;
+
+            match bound {
+                _ if give_up => (alpha, beta) = (-INFINITY, INFINITY),
+                ScoreBound::Upper => alpha = (score - delta).max(-INFINITY),
+                _ => beta = (score + delta).min(INFINITY),
+            }
+        }
+    }
+
+    // a fail-hard root search doesn't find a line for a bound, so the report shows what there is:
+    // the move that failed high, or the last line that was exact
+    fn report_bound(
+        &self,
+        depth: u8,
+        line: usize,
+        score: i32,
+        bound: ScoreBound,
+        pv: &[Move],
+        reporter: &mut dyn SearchReporter,
+    ) {
+        let fallback: Vec<Move> = match bound {
+            ScoreBound::Lower => self.root_moves.best_move().into_iter().collect(),
+            _ if line == 1 => self.principal_variation.clone(),
+            _ => Vec::new(),
+        };
+
+        let pv = if pv.is_empty() { &fallback } else { pv };
+
+        if pv.is_empty() {
+            return;
+        }
+
+        let time_ms = self.timer.search_time_ms();
+        reporter.iteration(&IterationReport {
+            bound,
+            ..self.iteration_report(depth, line, score, time_ms, pv)
+        });
+    }
+
+    // the report of an exact score
+    fn iteration_report<'a>(
+        &'a self,
+        depth: u8,
+        line: usize,
+        score: i32,
+        time_ms: u128,
+        pv: &'a [Move],
+    ) -> IterationReport<'a> {
+        IterationReport {
+            depth,
+            seldepth: self.search_info.seldepth,
+            multipv: (self.multi_pv > 1).then_some(line),
+            score,
+            bound: ScoreBound::Exact,
+            nodes: self.nodes_searched(),
+            time_ms,
+            hashfull: self.transposition_table.hashfull(),
+            pv,
+            root_moves: self.root_moves.moves(),
         }
+    }
 
-        Ok(best_move)
+    fn flush_debug_events(&mut self, reporter: &mut dyn SearchReporter) {
+        for event in self.debug_sink.drain() {
+            reporter.debug(&event);
+        }
+    }
+
+    /// by every thread, helpers included
+    pub fn nodes_searched(&self) -> u64 {
+        self.search_info.nodes_searched + self.helper_nodes.load(Ordering::Relaxed)
+    }
+
+    /// the reply expected to `best_move`, taken from the last finished iteration, or from the
+    /// transposition table when that iteration's line stopped at the best move. there isn't one if
+    /// the best move came from an unfinished iteration instead, or if the reply isn't legal
+    pub fn ponder_move(&mut self, best_move: Move) -> Option<Move> {
+        let reply = match self.principal_variation.as_slice() {
+            [mv, rest @ ..] if *mv == best_move => rest.first().copied(),
+            _ => return None,
+        };
+
+        self.legal_reply(best_move, reply).ok().flatten()
+    }
+
+    // a move from the table could be from another position with the same hash index, so even the
+    // line's reply is checked rather than trusted
+    fn legal_reply(
+        &mut self,
+        best_move: Move,
+        reply: Option<Move>,
+    ) -> anyhow::Result<Option<Move>> {
+        if !self.board.make_move(best_move)? {
+            self.board.unmake_move(best_move);
+            return Ok(None);
+        }
+
+        let reply = reply.or_else(|| {
+            self.transposition_table
+                .probe(self.transposition_key())
+                .map(|entry| entry.best_move)
+        });
+
+        let legal_reply = match reply.filter(|reply| !reply.is_null()) {
+            Some(reply) => self
+                .board
+                .find_legal_move(reply.into())?
+                .filter(|legal_move| *legal_move == reply),
+            None => None,
+        };
+
+        self.board.unmake_move(best_move);
+
+        Ok(legal_reply)
     }
 
     fn negamax(
         &mut self,
-        mut depth: u8,
+        depth: u8,
         alpha: i32,
         beta: i32,
         pv: &mut Vec<Move>,
         previous_move: Move,
         do_null_search: bool,
     ) -> anyhow::Result<i32> {
-        // search a bit further if in check
-        if self.board.is_in_check(self.board.side_to_move()) {
-            depth += 1;
-        }
-
         if depth == 0 {
-            return self.quiescence_search(alpha, beta, pv);
+            return self.quiescence_search(MAX_QUIESCENCE_DEPTH, alpha, beta, pv);
         }
 
         if self.search_info.ply >= SearchDepth::MAX {
-            return Ok(self.board.evaluate());
+            return Ok(self.board.evaluate_unchecked());
         }
 
-        let table_entry = self.transposition_table.probe(self.board.hash());
-        let (transposition_score, transposition_move) =
-            table_entry.get(self.board.hash(), depth, self.search_info.ply, alpha, beta);
+        let (transposition_score, transposition_move) = {
+            let _timer = profile::time(Phase::TranspositionTable);
+            self.search_info.debug.tt_probes += 1;
+
+            match self.transposition_table.probe(self.transposition_key()) {
+                Some(entry) => {
+                    self.search_info.debug.tt_hits += 1;
+                    entry.get(depth, self.search_info.ply, alpha, beta)
+                }
+                None => (None, Move::NULL_MOVE),
+            }
+        };
 
         if let Some(score) = transposition_score {
             if self.search_info.ply != 0 {
+                // the table's move is what refutes a root move that ends up here
+                if self.search_info.ply == 1 && !transposition_move.is_null() {
+                    pv.clear();
+                    pv.push(transposition_move);
+                }
+
                 return Ok(score);
             }
         }
 
         self.search_info.nodes_searched += 1;
+        self.search_info.seldepth = self.search_info.seldepth.max(self.search_info.ply);
 
-        // check move time expiry every 2048 nodes
-        if (self.search_info.nodes_searched & 2047) == 0 {
-            self.timer.check();
-        }
+        self.check_limits();
 
         if self.timer.is_stopped() {
             return Ok(0);
         }
 
-        if self.search_info.ply != 0 && self.board.is_draw() {
-            return Ok(0);
+        if self.search_info.ply != 0
+            && (self.board.is_draw() || self.board.has_insufficient_material())
+        {
+            return Ok(self.draw_score());
         }
 
         let mut move_list = MoveList::default();
         self.board.generate_all_moves(&mut move_list)?;
+        self.search_info.moves_truncated |= move_list.is_truncated();
 
         let mut legal_move_count = 0;
         let old_alpha = alpha;
@@ -184,12 +826,22 @@ impl Search {
         let mut best_score_from_node = -INFINITY;
         let mut best_move_from_node = Move::NULL_MOVE;
 
-        if do_null_search && depth >= DEPTH_REDUCTION_FACTOR && self.can_do_null_search() {
+        let has_exclusions = self.has_exclusions();
+
+        // if passing the turn still isn't enough for the opponent, a real move won't be either.
+        // `do_null_search` is off straight after a null move, so two in a row can't cancel out
+        if do_null_search && !has_exclusions && self.can_do_null_search(depth, beta) {
             let mut current_pv = Vec::new();
 
-            let reduced_depth = depth.saturating_sub(DEPTH_REDUCTION_FACTOR + 1);
+            let reduction = NULL_MOVE_REDUCTION + depth / NULL_MOVE_REDUCTION_DEPTH_DIVISOR;
+            let reduced_depth = depth.saturating_sub(reduction + 1);
 
+            self.search_info.debug.null_move_searches += 1;
+
+            // the null move is a ply like any other. without counting it, the search below would
+            // think it was at the root
             self.board.make_null_move();
+            self.search_info.ply += 1;
             let score = -self.negamax(
                 reduced_depth,
                 -beta,
@@ -198,48 +850,126 @@ impl Search {
                 Move::NULL_MOVE,
                 false,
             )?;
+            self.search_info.ply -= 1;
             self.board.unmake_null_move();
             current_pv.clear();
 
+            if self.timer.is_stopped() {
+                return Ok(0);
+            }
+
+            // a mate found after passing isn't one the position really has, so only the bound is
+            // kept
             if score >= beta {
-                return Ok(score);
+                self.search_info.debug.null_move_cutoffs += 1;
+                return Ok(beta);
             }
         }
 
         let mut pvs_enabled = false;
 
-        self.score_moves(&mut move_list, transposition_move, previous_move);
+        let can_prune_quiets = self.search_info.ply != 0
+            && depth <= SEE_PRUNING_DEPTH
+            && beta - alpha == 1
+            && !self.board.is_in_check(self.board.side_to_move());
+
+        // the root's hash entry can get overwritten during the search, but the best move found so
+        // far should always be searched first
+        let first_move = match self.root_moves.best_move() {
+            Some(mv) if self.search_info.ply == 0 => mv,
+            _ => transposition_move,
+        };
+
+        self.score_moves(&mut move_list, first_move, previous_move);
 
         for i in 0..move_list.length() {
             let mv = move_list.pick_ordered_move(i);
 
+            if self.is_excluded(mv)
+                || (self.search_info.ply == 0 && !self.limits.is_root_move_allowed(mv))
+            {
+                continue;
+            }
+
+            // this close to the horizon, a quiet move that just gives material away is very
+            // unlikely to be the best one
+            if can_prune_quiets
+                && legal_move_count > 0
+                && mv.kind() == MoveKind::Quiet
+                && !self.see_ge(mv, -SEE_QUIET_MARGIN * depth as i32)
+            {
+                continue;
+            }
+
+            // worked out before the move is made, since the exchange starts from this position
+            let is_losing_capture = mv.kind() == MoveKind::Capture && !self.see_ge(mv, 0);
+
             if !self.board.make_move(mv)? {
-                self.board.unmake_move(mv)?;
+                self.board.unmake_move(mv);
                 continue;
             }
 
             let mut current_pv = Vec::new();
 
+            let extension = self.check_extension(is_losing_capture);
+            let child_depth = depth - 1 + extension;
+
+            let nodes_before_move = self.search_info.nodes_searched;
+
             self.search_info.ply += 1;
+            self.search_info.extensions += extension;
             legal_move_count += 1;
 
-            let score = if pvs_enabled {
-                let mut pvs_score = -self.negamax(depth - 1, -alpha - 1, -alpha, pv, mv, true)?;
+            // a root move that brings about the same position for the third time is a draw, no
+            // matter what the transposition table remembers about the position. if every move
+            // does that, they'll all score as draws and one of them gets played anyway
+            let score = if self.search_info.ply == 1 && self.board.repetition_count() >= 2 {
+                -self.draw_score()
+            } else if self.search_info.ply == 1
+                && self.avoid_dead_draws
+                && self.board.has_insufficient_material()
+            {
+                -self.draw_score() - DEAD_DRAW_PENALTY
+            } else if pvs_enabled {
+                let mut pvs_score =
+                    -self.negamax(child_depth, -alpha - 1, -alpha, &mut current_pv, mv, true)?;
 
                 if pvs_score > alpha && pvs_score < beta {
                     // we assumed the move would be really bad, but it wasn't, so we have to do a
                     // full-window search to verify the score
                     pvs_score =
-                        -self.negamax(depth - 1, -beta, -alpha, &mut current_pv, mv, true)?;
+                        -self.negamax(child_depth, -beta, -alpha, &mut current_pv, mv, true)?;
                 }
 
                 pvs_score
             } else {
-                -self.negamax(depth - 1, -beta, -alpha, &mut current_pv, mv, true)?
+                -self.negamax(child_depth, -beta, -alpha, &mut current_pv, mv, true)?
             };
 
-            self.board.unmake_move(mv)?;
+            self.board.unmake_move(mv);
             self.search_info.ply -= 1;
+            self.search_info.extensions -= extension;
+
+            // the score from a search that was cut off is made up, so nothing can be learned from
+            // it. the node is left the way it was found, without a hash entry or a cutoff move
+            if self.timer.is_stopped() {
+                return Ok(0);
+            }
+
+            // the root moves follow the first MultiPV line, which searches all of them
+            if self.search_info.ply == 0 && !has_exclusions {
+                // the move the search had settled on is always searched first
+                if legal_move_count == 1 && depth >= PANIC_MIN_DEPTH {
+                    self.check_root_fail_low(mv, score);
+                }
+
+                let nodes = self.search_info.nodes_searched - nodes_before_move;
+                self.root_moves.update(mv, score, nodes, depth, alpha, beta);
+
+                if score <= alpha && legal_move_count <= REFUTED_CANDIDATES as u64 + 1 {
+                    self.root_moves.set_refutation(mv, &current_pv);
+                }
+            }
 
             if score > best_score_from_node {
                 best_score_from_node = score;
@@ -248,8 +978,15 @@ impl Search {
 
             // move is very good for our opponent, disregard it
             if score >= beta {
+                // the line that refutes the root move before this one, for reporting
+                if self.search_info.ply == 1 {
+                    pv.clear();
+                    pv.push(mv);
+                    pv.append(&mut current_pv);
+                }
+
                 self.transposition_table.store(SearchTableEntry::new(
-                    self.board.hash(),
+                    self.transposition_key(),
                     depth,
                     beta,
                     self.search_info.ply,
@@ -260,6 +997,8 @@ impl Search {
                 self.store_killer_move(mv);
                 self.update_history_score(mv, depth);
                 self.store_counter_move(previous_move, mv);
+                self.store_refutation(previous_move, mv);
+                self.record_cutoff(legal_move_count, transposition_move);
                 return Ok(beta);
             }
 
@@ -274,17 +1013,22 @@ impl Search {
             }
         }
 
+        // with every move left out, there was nothing to beat alpha
+        if legal_move_count == 0 && has_exclusions {
+            return Ok(alpha);
+        }
+
         // no legal moves means it's either checkmate or stalemate
         if legal_move_count == 0 {
             if self.board.is_in_check(self.board.side_to_move()) {
                 return Ok(-INFINITY + self.search_info.ply as i32);
             } else {
-                return Ok(0);
+                return Ok(self.draw_score());
             }
         }
 
         self.transposition_table.store(SearchTableEntry::new(
-            self.board.hash(),
+            self.transposition_key(),
             depth,
             alpha,
             self.search_info.ply,
@@ -299,30 +1043,105 @@ impl Search {
         Ok(alpha)
     }
 
+    // stopping now would mean playing a move that's just turned out to be bad, so the rest of the
+    // root moves get the time to find something better. called before the root moves are updated
+    // with `score`, so they still have the previous iteration's
+    fn check_root_fail_low(&mut self, mv: Move, score: i32) {
+        let Some(previous) = self
+            .root_moves
+            .moves()
+            .iter()
+            .find(|root_move| root_move.mv == mv && root_move.bound == ScoreBound::Exact)
+        else {
+            return;
+        };
+
+        if score <= previous.score - PANIC_FAIL_LOW_MARGIN {
+            self.extend_time_after_fail_low();
+        }
+    }
+
+    fn extend_time_after_fail_low(&mut self) {
+        if self.timer.extend_to_maximum() {
+            self.debug_sink.record(DebugEvent::TimeExtended {
+                elapsed_ms: self.timer.elapsed_ms(),
+                allowed: self.timer.allowed_duration,
+            });
+        }
+    }
+
+    // search a bit further after a move that gives check, unless the move just throws material
+    // away or the current path has already used up its extensions. called after the move is made
+    fn check_extension(&self, is_losing_capture: bool) -> u8 {
+        if is_losing_capture
+            || self.search_info.extensions >= self.search_info.extension_budget
+            || !self.board.is_in_check(self.board.side_to_move())
+        {
+            return 0;
+        }
+
+        1
+    }
+
+    // the clock is too slow to read at every node, but the node count isn't. there's no move to
+    // play until the first iteration is over, so the timer never cuts that one short
+    fn check_limits(&mut self) {
+        let nodes = self.search_info.nodes_searched;
+
+        if !self.principal_variation.is_empty() && self.timer.should_check(nodes) {
+            self.timer.check();
+
+            if self.is_helper {
+                self.helper_nodes
+                    .fetch_add(nodes - self.helper_nodes_added, Ordering::Relaxed);
+                self.helper_nodes_added = nodes;
+            }
+        }
+
+        if self.limits.nodes_exceeded(nodes) {
+            self.timer.stop();
+        }
+
+        #[cfg(debug_assertions)]
+        if injected_panic_nodes().is_some_and(|limit| nodes >= limit) {
+            panic!("injected panic after {} nodes", nodes);
+        }
+    }
+
     fn quiescence_search(
         &mut self,
+        depth: u8,
         mut alpha: i32,
         beta: i32,
         pv: &mut Vec<Move>,
     ) -> anyhow::Result<i32> {
         self.search_info.nodes_searched += 1;
+        self.search_info.quiescence_nodes += 1;
+        self.search_info.seldepth = self.search_info.seldepth.max(self.search_info.ply);
 
-        // check move time expiry every 2048 nodes
-        if (self.search_info.nodes_searched & 2047) == 0 {
-            self.timer.check();
-        }
+        self.check_limits();
 
         if self.timer.is_stopped() {
             return Ok(0);
         }
 
-        if self.search_info.ply >= SearchDepth::MAX {
-            return Ok(self.board.evaluate());
+        // the main search hands over at the horizon before checking for draws itself, so the
+        // position may already be a repetition or past the 50-move count. a capture may also have
+        // left a position that's impossible to win
+        if self.board.is_draw() || self.board.has_insufficient_material() {
+            return Ok(self.draw_score());
+        }
+
+        // the ply ceiling keeps indexing into the per-ply tables in bounds. with the depth cap, it's
+        // only reached when the main search has already gone almost that deep
+        if depth == 0 || self.search_info.ply >= SearchDepth::MAX {
+            return Ok(self.board.evaluate_unchecked());
         }
 
-        let stand_pat = self.board.evaluate();
+        let stand_pat = self.board.evaluate_lazy_unchecked(alpha, beta);
 
         if stand_pat >= beta {
+            self.search_info.debug.stand_pat_cutoffs += 1;
             return Ok(beta);
         }
 
@@ -330,14 +1149,15 @@ impl Search {
 
         let mut move_list = MoveList::default();
         self.board.generate_all_captures(&mut move_list)?;
+        self.search_info.moves_truncated |= move_list.is_truncated();
 
-        self.score_moves(&mut move_list, Move::NULL_MOVE, Move::NULL_MOVE);
+        self.score_captures(&mut move_list);
 
         for i in 0..move_list.length() {
             let mv = move_list.pick_ordered_move(i);
 
             if !self.board.make_move(mv)? {
-                self.board.unmake_move(mv)?;
+                self.board.unmake_move(mv);
                 continue;
             }
 
@@ -345,9 +1165,9 @@ impl Search {
 
             self.search_info.ply += 1;
 
-            let score = -self.quiescence_search(-beta, -alpha, &mut current_pv)?;
+            let score = -self.quiescence_search(depth - 1, -beta, -alpha, &mut current_pv)?;
 
-            self.board.unmake_move(mv)?;
+            self.board.unmake_move(mv);
             self.search_info.ply -= 1;
 
             if score >= beta {
@@ -366,30 +1186,59 @@ impl Search {
         Ok(alpha)
     }
 
-    fn score_moves(&self, move_list: &mut MoveList, transposition_move: Move, previous_move: Move) {
+    /// gives every move its ordering score, for `pick_ordered_move` to go through them best first.
+    /// `previous_move` is the move that led to the position, or a null move at the root
+    pub fn score_moves(
+        &self,
+        move_list: &mut MoveList,
+        transposition_move: Move,
+        previous_move: Move,
+    ) {
+        let _timer = profile::time(Phase::Ordering);
+
         for i in 0..move_list.length() {
             let mv = move_list.get_mut(i);
 
-            let victim = self.board.get_piece(mv.to_square());
+            // castling moves land on the king's own rook
+            let victim = match mv.kind() {
+                MoveKind::Castle => Piece::default(),
+                _ => self.board.get_piece(mv.to_square()),
+            };
 
             let score = if *mv == transposition_move {
                 TT_SCORE_OFFSET
             } else if victim.kind != PieceKind::NoPiece {
                 let attacker = self.board.get_piece(mv.from_square());
-                CAPTURE_SCORE_OFFSET + (10 * victim.material_value()) - attacker.material_value()
-            } else if *mv == self.get_killer_moves()[0] {
+                let score = Self::mvv_lva(victim.material_value(), attacker.material_value());
+
+                if !previous_move.is_null() && mv.to_square() == previous_move.to_square() {
+                    score + RECAPTURE_BONUS
+                } else {
+                    score
+                }
+            } else if !mv.is_quiet_for_history() {
+                // castling isn't learned about, so there's nothing to go on
+                0
+            } else if self.is_safe_killer(*mv, 0) {
                 FIRST_KILLER_SCORE
-            } else if *mv == self.get_killer_moves()[1] {
+            } else if self.is_safe_killer(*mv, 1) {
                 SECOND_KILLER_SCORE
             } else {
-                self.get_history_score(mv) + self.get_counter_move_bonus(previous_move, *mv)
+                let score =
+                    self.get_history_score(mv) + self.get_counter_move_bonus(previous_move, *mv);
+
+                if self.is_refutation_available(*mv) {
+                    (score - REFUTED_MOVE_PENALTY).max(0)
+                } else {
+                    score
+                }
             };
 
             assert!(score >= 0, "score must be above 0, got {}", score);
             assert!(
-                score <= (1 << 15),
-                "score must be below {}, got {}",
-                1 << 15,
+                score as u32 <= Move::MAX_SCORE,
+                "score must be at most {}, got {}",
+                Move::MAX_SCORE,
                 score
             );
 
@@ -397,33 +1246,39 @@ impl Search {
         }
     }
 
-    fn get_score_string(score: i32) -> String {
-        if score.abs() > CHECKMATE_THRESHOLD {
-            let ply_to_mate = INFINITY.abs_diff(score.abs()) as i32;
-            let moves_to_mate = ply_to_mate / 2 + ply_to_mate % 2;
+    // the capture search only ever sees captures, so none of the quiet move heuristics or the checks
+    // on the score range are worth paying for
+    fn score_captures(&self, move_list: &mut MoveList) {
+        let _timer = profile::time(Phase::Ordering);
 
-            // prints from engine's perspective, so if e.g. engine is being mated in 2
-            // moves, we print `mate -2`
-            format!("mate {}", moves_to_mate * score.signum())
-        } else {
-            format!("cp {}", score)
-        }
-    }
+        for i in 0..move_list.length() {
+            let mv = move_list.get_mut(i);
+
+            // en passant is the only capture that doesn't land on its victim
+            let victim = if mv.flag() == MoveFlag::EnPassant {
+                PAWN_VALUE
+            } else {
+                self.board.get_piece(mv.to_square()).material_value()
+            };
 
-    fn get_pv_string(pv: &[Move]) -> String {
-        let mut pv_string = String::new();
+            let attacker = self.board.get_piece(mv.from_square()).material_value();
 
-        for mv in pv {
-            pv_string.push(' ');
-            pv_string.push_str(&mv.to_string());
+            mv.set_score(Self::mvv_lva(victim, attacker) as u32);
         }
+    }
 
-        pv_string.trim().into()
+    // most valuable victim, least valuable attacker
+    fn mvv_lva(victim_value: i32, attacker_value: i32) -> i32 {
+        CAPTURE_SCORE_OFFSET + (10 * victim_value) - attacker_value
+    }
+
+    // a killer that now just hangs the moved piece was only good in the position it was found in
+    fn is_safe_killer(&self, mv: Move, slot: usize) -> bool {
+        mv == self.get_killer_moves()[slot] && self.board.see_ge(mv, 0)
     }
 
     fn store_killer_move(&mut self, mv: Move) {
-        // quiet moves only
-        if mv.kind() == MoveKind::Capture {
+        if !mv.is_quiet_for_history() {
             return;
         }
 
@@ -436,15 +1291,19 @@ impl Search {
     }
 
     fn update_history_score(&mut self, mv: Move, depth: u8) {
-        if mv.kind() == MoveKind::Capture {
+        if !mv.is_quiet_for_history() {
             return;
         }
 
-        let depth = depth as u32;
+        let bonus = history_bonus(depth);
+
+        if bonus == 0 {
+            return;
+        }
 
         let history = self.get_history_mut();
 
-        history[mv.from_square().index()][mv.to_square().index()] += depth * depth;
+        history[mv.from_square().index()][mv.to_square().index()] += bonus;
 
         // ensure the score is always less than that of the killer moves
         if history[mv.from_square().index()][mv.to_square().index()] > MAX_HISTORY_SCORE as u32 {
@@ -458,7 +1317,7 @@ impl Search {
     }
 
     fn store_counter_move(&mut self, previous_move: Move, current_move: Move) {
-        if current_move.kind() == MoveKind::Capture {
+        if !current_move.is_quiet_for_history() {
             return;
         }
 
@@ -468,6 +1327,41 @@ impl Search {
             current_move;
     }
 
+    // the exchanges the search itself looks at, timed apart from the ones move ordering does
+    fn see_ge(&self, mv: Move, threshold: i32) -> bool {
+        let _timer = profile::time(Phase::StaticExchange);
+
+        self.board.see_ge(mv, threshold)
+    }
+
+    fn store_refutation(&mut self, previous_move: Move, current_move: Move) {
+        if previous_move.is_null() || previous_move.kind() == MoveKind::Capture {
+            return;
+        }
+
+        let refutations = &mut self.refutations[self.board.side_to_move().index()];
+
+        refutations[previous_move.from_square().index()][previous_move.to_square().index()] =
+            current_move;
+    }
+
+    // whether the reply that last refuted `mv` could be played again after it. only the squares are
+    // checked, which is enough to tell that the refuting piece hasn't moved or been taken
+    fn is_refutation_available(&self, mv: Move) -> bool {
+        let opponent = !self.board.side_to_move();
+        let refutation =
+            self.refutations[opponent.index()][mv.from_square().index()][mv.to_square().index()];
+
+        if refutation.is_null() {
+            return false;
+        }
+
+        let opponent_color = PieceColor::from(opponent);
+
+        self.board.get_piece(refutation.from_square()).color == opponent_color
+            && self.board.get_piece(refutation.to_square()).color != opponent_color
+    }
+
     fn get_killer_moves(&self) -> &[Move] {
         &self.killer_moves[self.search_info.ply as usize]
     }
@@ -493,6 +1387,22 @@ impl Search {
         &mut self.counter_moves[self.board.side_to_move().index()]
     }
 
+    // from the perspective of the side to move. normally a draw is worth nothing to either side,
+    // but with contempt or in must-win mode it's treated as a small loss for the side the engine
+    // is playing, so it'll take small risks to avoid one. it still prefers a draw to losing by
+    // more than that. negative contempt makes a draw a small win instead
+    fn draw_score(&self) -> i32 {
+        if self.board.side_to_move() == self.root_side {
+            -self.draw_cost()
+        } else {
+            self.draw_cost()
+        }
+    }
+
+    fn draw_cost(&self) -> i32 {
+        draw_cost(self.must_win, self.must_win_margin, self.contempt)
+    }
+
     fn get_counter_move_bonus(&self, previous_move: Move, mv: Move) -> i32 {
         let counter = self.get_counter_moves();
 
@@ -503,7 +1413,95 @@ impl Search {
         }
     }
 
-    fn can_do_null_search(&self) -> bool {
-        !self.board.is_in_check(self.board.side_to_move()) && self.board.has_major_or_minor_piece()
+    fn record_cutoff(&mut self, legal_move_count: u64, transposition_move: Move) {
+        let ordering = &mut self.search_info.ordering;
+
+        ordering.beta_cutoffs += 1;
+        ordering.cutoff_index_total += legal_move_count - 1;
+
+        if legal_move_count == 1 {
+            ordering.first_move_cutoffs += 1;
+        }
+
+        if !transposition_move.is_null() {
+            ordering.tt_move_cutoffs += 1;
+        }
+    }
+
+    /// searches the position on the board with `excluded` left out, with a null window just below
+    /// `beta`. a score under `beta` means no other move comes close to `excluded`. the result is
+    /// kept apart from the position's own table entry, which is left as it was
+    pub fn search_excluding(
+        &mut self,
+        excluded: Move,
+        depth: u8,
+        beta: i32,
+    ) -> anyhow::Result<i32> {
+        let ply = self.search_info.ply as usize;
+        let previous = std::mem::replace(&mut self.excluded_moves[ply], excluded);
+
+        let mut pv = Vec::new();
+        let score = self.negamax(depth, beta - 1, beta, &mut pv, Move::NULL_MOVE, false);
+
+        self.excluded_moves[ply] = previous;
+        score
+    }
+
+    // whether any moves are left out of the node at the current ply, either MultiPV lines already
+    // found at the root or a move being checked for being the only good one
+    fn has_exclusions(&self) -> bool {
+        (self.search_info.ply == 0 && !self.excluded_root_moves.is_empty())
+            || !self.excluded_moves[self.search_info.ply as usize].is_null()
     }
+
+    fn is_excluded(&self, mv: Move) -> bool {
+        (self.search_info.ply == 0 && self.excluded_root_moves.contains(&mv))
+            || self.excluded_moves[self.search_info.ply as usize] == mv
+    }
+
+    // the key of the node's table entry. a node with moves left out has a different score and best
+    // move from the node without, so it gets a key of its own. so does every node when draws
+    // aren't even, since they're scored for the engine's side and the engine can search for
+    // either side
+    fn transposition_key(&self) -> u64 {
+        let mut key = self.board.hash();
+
+        if self.root_side == Side::Black && self.draw_cost() != 0 {
+            key ^= DRAW_PERSPECTIVE_KEY;
+        }
+
+        if self.search_info.ply == 0 {
+            for mv in &self.excluded_root_moves {
+                key ^= excluded_move_key(*mv);
+            }
+        }
+
+        let excluded = self.excluded_moves[self.search_info.ply as usize];
+        if !excluded.is_null() {
+            key ^= excluded_move_key(excluded);
+        }
+
+        key
+    }
+
+    // passing is never legal in check, and in pawn endings it's too often better than any move
+    // (zugzwang). near a mate the margin the null move search gives is meaningless
+    fn can_do_null_search(&self, depth: u8, beta: i32) -> bool {
+        let side = self.board.side_to_move();
+
+        self.search_info.ply != 0
+            && depth >= NULL_MOVE_MIN_DEPTH
+            && beta.abs() < CHECKMATE_THRESHOLD
+            && !self.board.is_in_check(side)
+            && self.board.has_non_pawn_material(side)
+            && self.board.evaluate_unchecked() >= beta
+    }
+}
+
+// read once, since it's checked at every node
+#[cfg(debug_assertions)]
+fn injected_panic_nodes() -> Option<u64> {
+    static NODES: std::sync::OnceLock<Option<u64>> = std::sync::OnceLock::new();
+
+    *NODES.get_or_init(|| std::env::var(PANIC_AFTER_NODES_VAR).ok()?.parse().ok())
 }
@@ -0,0 +1,70 @@
+use crate::{
+    board::START_POSITION_FEN,
+    options::OptionValue,
+    search::{Search, DEFAULT_MUST_WIN_MARGIN},
+    time_management::DEFAULT_MOVE_OVERHEAD_MS,
+};
+
+pub const DEFAULT_HASH_MB: usize = 64;
+
+/// everything about a search that's set up once, rather than for every `go`. `Search::default()`
+/// is built from the defaults, and UCI options are applied onto one of these taken from the search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchConfig {
+    pub hash_mb: usize,
+    pub threads: usize,
+    pub multi_pv: usize,
+    // `None` seeds from entropy, so every search is different
+    pub seed: Option<u64>,
+    pub must_win: bool,
+    pub must_win_margin: i32,
+    pub contempt: i32,
+    pub move_overhead_ms: u128,
+    // where the search starts out. only used when building, since it's a position rather than a
+    // setting
+    pub fen: String,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            hash_mb: DEFAULT_HASH_MB,
+            threads: 1,
+            multi_pv: 1,
+            seed: None,
+            must_win: false,
+            must_win_margin: DEFAULT_MUST_WIN_MARGIN,
+            contempt: 0,
+            move_overhead_ms: DEFAULT_MOVE_OVERHEAD_MS,
+            fen: START_POSITION_FEN.into(),
+        }
+    }
+}
+
+impl SearchConfig {
+    /// the transposition table is the only thing allocated, and it's `hash_mb` in size
+    pub fn build(&self) -> anyhow::Result<Search> {
+        Search::with_config(self)
+    }
+
+    /// applies `value` if `name` is one of the options that make up the configuration, which
+    /// `setoption` has already checked it against. returns whether it was
+    pub fn apply_option(&mut self, name: &str, value: &OptionValue) -> bool {
+        match (name, value) {
+            ("Hash", OptionValue::Spin(mb)) => self.hash_mb = *mb as usize,
+            ("Threads", OptionValue::Spin(threads)) => self.threads = *threads as usize,
+            ("MultiPV", OptionValue::Spin(lines)) => self.multi_pv = *lines as usize,
+            // 0 means a different seed every time
+            ("Seed", OptionValue::Spin(seed)) => self.seed = (*seed != 0).then_some(*seed as u64),
+            ("MustWin", OptionValue::Check(enabled)) => self.must_win = *enabled,
+            ("MustWinMargin", OptionValue::Spin(margin)) => self.must_win_margin = *margin as i32,
+            ("Contempt", OptionValue::Spin(contempt)) => self.contempt = *contempt as i32,
+            ("Move Overhead", OptionValue::Spin(overhead)) => {
+                self.move_overhead_ms = *overhead as u128
+            }
+            _ => return false,
+        }
+
+        true
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{move_generator::Move, search::SearchDepth};
+
+/// the clock of the side to move, as the GUI sent it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeControls {
+    pub time_remaining: Option<u128>,
+    pub increment: u128,
+    pub moves_to_go: Option<u64>,
+}
+
+/// everything that decides when a search stops. every limit that's set applies, so the search
+/// stops at whichever is reached first, except that an infinite search ignores the clock and any
+/// fixed move time
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    // milliseconds to spend on this move, instead of working it out from the clock
+    pub movetime: Option<u128>,
+    // look for a mate in this many moves
+    pub mate: Option<u8>,
+    pub infinite: bool,
+    // searching on the opponent's time. the clock only applies once the GUI sends `ponderhit`
+    pub ponder: bool,
+    pub time_controls: TimeControls,
+    // only these root moves get searched. empty means all of them
+    pub searchmoves: Vec<Move>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u8) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Self::default()
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        Self {
+            nodes: Some(nodes),
+            ..Self::default()
+        }
+    }
+
+    /// the deepest iteration to search. a mate in `n` moves is found by `2n - 1` plies, so there's
+    /// no point going any deeper than that. there's always at least one iteration, since that's
+    /// what finds a move to play
+    pub fn max_depth(&self) -> u8 {
+        let mate_depth = self
+            .mate
+            .map(|moves| (moves as u16 * 2).saturating_sub(1).min(u8::MAX as u16) as u8);
+
+        [self.depth, mate_depth]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(SearchDepth::MAX)
+            .clamp(1, SearchDepth::MAX)
+    }
+
+    /// whether the search should stop because of the node limit alone. this is checked at every
+    /// node, so it has to stay a single comparison
+    pub fn nodes_exceeded(&self, nodes: u64) -> bool {
+        self.nodes.is_some_and(|limit| nodes >= limit)
+    }
+
+    pub fn is_root_move_allowed(&self, mv: Move) -> bool {
+        self.searchmoves.is_empty() || self.searchmoves.contains(&mv)
+    }
+}
@@ -0,0 +1,233 @@
+// static exchange evaluation: plays out every capture on a move's destination square, cheapest
+// attacker first, to see how much material the move wins or loses without searching it
+
+use crate::{
+    attacks,
+    bitboard::{Bitboard, EMPTY_BB},
+    board::{Board, Side},
+    move_generator::{Move, MoveFlag, MoveKind},
+    square::{Piece, PieceKind, Square},
+};
+
+// the order pieces join an exchange in
+const EXCHANGE_ORDER: [PieceKind; 6] = [
+    PieceKind::Pawn,
+    PieceKind::Knight,
+    PieceKind::Bishop,
+    PieceKind::Rook,
+    PieceKind::Queen,
+    PieceKind::King,
+];
+
+// an exchange can't have more captures than there are pieces on the board
+const MAX_EXCHANGE_LENGTH: usize = 32;
+
+// the state of the board once the move itself has been made
+struct Exchange {
+    // material won by the move itself
+    gain: i32,
+    // value of the piece now standing on the destination square, i.e. the next one to be captured
+    at_risk: i32,
+    occupied: Bitboard,
+    attackers: Bitboard,
+}
+
+impl Board {
+    /// the material `mv` wins (negative if it loses material) once the exchange on its destination
+    /// square has been played out. either side can stop capturing whenever carrying on would lose
+    /// them material
+    pub fn see(&self, mv: Move) -> i32 {
+        let Some(mut exchange) = self.start_exchange(mv) else {
+            return 0;
+        };
+
+        let to = mv.to_square();
+        let mut gains = [0; MAX_EXCHANGE_LENGTH];
+        gains[0] = exchange.gain;
+
+        let mut length = 1;
+        let mut side = self.side_to_move();
+
+        while length < MAX_EXCHANGE_LENGTH {
+            side = !side;
+            exchange.attackers &= exchange.occupied;
+
+            let Some((square, kind)) = self.least_valuable_attacker(exchange.attackers, side)
+            else {
+                break;
+            };
+
+            // the king can only capture if nothing can take it back
+            if kind == PieceKind::King && exchange.attackers & self.occupancy(!side) != EMPTY_BB {
+                break;
+            }
+
+            // assume the capture gets played, and let the minimax below decide whether it should
+            gains[length] = exchange.at_risk - gains[length - 1];
+            length += 1;
+
+            exchange.at_risk = Piece::new(side.into(), kind).material_value();
+            exchange.occupied ^= square.bitboard();
+            exchange.attackers |= self.slider_attackers(to, exchange.occupied);
+        }
+
+        for index in (1..length).rev() {
+            gains[index - 1] = -(-gains[index - 1]).max(gains[index]);
+        }
+
+        gains[0]
+    }
+
+    /// whether `see(mv) >= threshold`, but gives up on the exchange as soon as the answer is known
+    pub fn see_ge(&self, mv: Move, threshold: i32) -> bool {
+        let Some(mut exchange) = self.start_exchange(mv) else {
+            return threshold <= 0;
+        };
+
+        // even if nothing recaptures, the move doesn't win enough
+        let mut balance = exchange.gain - threshold;
+        if balance < 0 {
+            return false;
+        }
+
+        // even losing the moved piece for nothing still leaves enough
+        balance = exchange.at_risk - balance;
+        if balance <= 0 {
+            return true;
+        }
+
+        let to = mv.to_square();
+        let mut side = self.side_to_move();
+
+        // whether the side that made `mv` comes out at or above the threshold, assuming the
+        // exchange stops here
+        let mut result = true;
+
+        loop {
+            side = !side;
+            exchange.attackers &= exchange.occupied;
+
+            let Some((square, kind)) = self.least_valuable_attacker(exchange.attackers, side)
+            else {
+                break;
+            };
+
+            if kind == PieceKind::King {
+                // a king capture only counts if nothing can take it back
+                return if exchange.attackers & self.occupancy(!side) != EMPTY_BB {
+                    result
+                } else {
+                    !result
+                };
+            }
+
+            result = !result;
+
+            balance = Piece::new(side.into(), kind).material_value() - balance;
+            if balance < result as i32 {
+                break;
+            }
+
+            exchange.occupied ^= square.bitboard();
+            exchange.attackers |= self.slider_attackers(to, exchange.occupied);
+        }
+
+        result
+    }
+
+    // `None` for castling, which can't start an exchange
+    fn start_exchange(&self, mv: Move) -> Option<Exchange> {
+        if mv.kind() == MoveKind::Castle {
+            return None;
+        }
+
+        let from = mv.from_square();
+        let to = mv.to_square();
+
+        let moved = self.get_piece(from);
+        let mut occupied =
+            (self.occupancy(Side::White) | self.occupancy(Side::Black)) ^ from.bitboard();
+
+        let mut gain = self.get_piece(to).material_value();
+        let mut at_risk = moved.material_value();
+
+        match mv.flag() {
+            MoveFlag::None => (),
+            MoveFlag::EnPassant => {
+                let captured_square = match self.side_to_move() {
+                    Side::White => Square::from(to.index() - 8),
+                    Side::Black => Square::from(to.index() + 8),
+                };
+
+                occupied ^= captured_square.bitboard();
+                gain = moved.material_value();
+            }
+            promotion => {
+                let promoted = Piece::new(moved.color, promotion.into()).material_value();
+                gain += promoted - at_risk;
+                at_risk = promoted;
+            }
+        }
+
+        Some(Exchange {
+            gain,
+            at_risk,
+            occupied,
+            attackers: self.attackers_to(to, occupied) & occupied,
+        })
+    }
+
+    fn least_valuable_attacker(
+        &self,
+        attackers: Bitboard,
+        side: Side,
+    ) -> Option<(Square, PieceKind)> {
+        let attackers = attackers & self.occupancy(side);
+
+        if attackers == EMPTY_BB {
+            return None;
+        }
+
+        EXCHANGE_ORDER.into_iter().find_map(|kind| {
+            let pieces = attackers & self.pieces_of(side, kind);
+            (pieces != EMPTY_BB).then(|| (pieces.get_lsb_square(), kind))
+        })
+    }
+
+    // pieces of both sides attacking `square`, seeing through anything not in `occupied`
+    pub(crate) fn attackers_to(&self, square: Square, occupied: Bitboard) -> Bitboard {
+        let mut attackers = self.slider_attackers(square, occupied);
+
+        for side in [Side::White, Side::Black] {
+            // a pawn of `side` attacks `square` if a pawn of the other side on `square` would
+            // attack it back
+            attackers |= attacks::pawn(!side, square) & self.pieces_of(side, PieceKind::Pawn);
+            attackers |= attacks::knight(square) & self.pieces_of(side, PieceKind::Knight);
+            attackers |= attacks::king(square) & self.pieces_of(side, PieceKind::King);
+        }
+
+        attackers
+    }
+
+    // removing a piece from an exchange can uncover a slider behind it, so these get recalculated
+    // after every capture
+    fn slider_attackers(&self, square: Square, occupied: Bitboard) -> Bitboard {
+        let diagonal = attacks::bishop(square, occupied);
+        let straight = attacks::rook(square, occupied);
+
+        let mut attackers = EMPTY_BB;
+
+        for side in [Side::White, Side::Black] {
+            let queens = self.pieces_of(side, PieceKind::Queen);
+
+            attackers |= diagonal & (self.pieces_of(side, PieceKind::Bishop) | queens);
+            attackers |= straight & (self.pieces_of(side, PieceKind::Rook) | queens);
+        }
+
+        attackers
+    }
+
+    fn pieces_of(&self, side: Side, kind: PieceKind) -> Bitboard {
+        self.get_piece_bb(Piece::new(side.into(), kind)).unwrap()
+    }
+}
@@ -0,0 +1,27 @@
+use std::io::{self, Write};
+
+use crate::style;
+
+/// teardown for anything that owns state worth keeping when the engine exits, e.g. log files.
+/// called on `quit` and when stdin is closed
+pub trait Shutdown {
+    fn shutdown(&mut self);
+}
+
+/// Ctrl-C would otherwise kill the process halfway through a line of output, leaving the
+/// terminal without a trailing newline (and possibly still coloured)
+pub fn install_interrupt_handler() -> anyhow::Result<()> {
+    ctrlc::set_handler(|| {
+        if style::is_color_enabled() {
+            print!("\x1b[0m");
+        }
+
+        println!();
+        let _ = io::stdout().flush();
+
+        // search logs are written unbuffered, so there's nothing left to flush by now
+        std::process::exit(130);
+    })?;
+
+    Ok(())
+}
@@ -7,7 +7,7 @@ use std::{
 use anyhow::{bail, Context};
 
 use crate::{
-    bitboard::{Bitboard, EMPTY_BB},
+    bitboard::{Bitboard, EMPTY_BB, LIGHT_SQUARES},
     board::Side,
     move_generator::MoveFlag,
 };
@@ -63,29 +63,13 @@ define_squares! (
     A8, B8, C8, D8, E8, F8, G8, H8 
 );
 
-const fn square_color_mask() -> u64 {
-    let mut mask = 0;
-
-    let mut rank = 0;
-    let mut file = 0;
-
-    while rank < 8 {
-        while file < 8 {
-            if (file + rank) % 2 == 0 {
-                mask |= 1u64 << (rank * 8 + file);
-            }
-            file += 1;
-        }
-
-        file = 0;
-        rank += 1;
-    }
-
-    mask
+/// the color of the square itself, nothing to do with the piece on it. a1 is dark
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SquareColor {
+    Light,
+    Dark,
 }
 
-pub const DARK_SQUARE_MASK: u64 = square_color_mask();
-
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Rank {
     First,
@@ -134,6 +118,28 @@ pub enum PieceColor {
     None,
 }
 
+// `PieceColor` is a `Side` plus `None` for empty squares. all conversions between the two live
+// here
+
+impl PieceColor {
+    pub const fn side(self) -> Option<Side> {
+        match self {
+            PieceColor::White => Some(Side::White),
+            PieceColor::Black => Some(Side::Black),
+            PieceColor::None => None,
+        }
+    }
+
+    /// for the color of a piece that's known to be on the board, which always has one
+    pub const fn known_side(self) -> Side {
+        match self {
+            PieceColor::White => Side::White,
+            PieceColor::Black => Side::Black,
+            PieceColor::None => panic!("found a piece with no color"),
+        }
+    }
+}
+
 impl From<Side> for PieceColor {
     fn from(value: Side) -> Self {
         match value {
@@ -143,6 +149,14 @@ impl From<Side> for PieceColor {
     }
 }
 
+impl TryFrom<PieceColor> for Side {
+    type Error = anyhow::Error;
+
+    fn try_from(value: PieceColor) -> Result<Self, Self::Error> {
+        value.side().context("piece has no color")
+    }
+}
+
 impl TryFrom<char> for PieceKind {
     type Error = anyhow::Error;
 
@@ -224,8 +238,20 @@ impl Square {
         (index1).abs_diff(index2)
     }
 
+    pub fn is_light(&self) -> bool {
+        LIGHT_SQUARES.is_occupied(*self)
+    }
+
+    pub fn color(&self) -> SquareColor {
+        if self.is_light() {
+            SquareColor::Light
+        } else {
+            SquareColor::Dark
+        }
+    }
+
     pub fn is_same_color(&self, other_square: Square) -> bool {
-        self.bitboard().0 & DARK_SQUARE_MASK == other_square.bitboard().0 & DARK_SQUARE_MASK
+        self.is_light() == other_square.is_light()
     }
 }
 
@@ -452,10 +478,42 @@ impl TryFrom<char> for File {
     }
 }
 
+/// the number of distinct pieces, i.e. every kind in both colors
+pub const PIECE_COUNT: usize = 12;
+
+// `Piece::index` for each color and kind, worked out once
+const PIECE_INDICES: [[usize; 6]; 2] = [[0, 1, 2, 3, 4, 5], [6, 7, 8, 9, 10, 11]];
+
 impl Piece {
     pub fn new(color: PieceColor, kind: PieceKind) -> Self {
         Self { color, kind }
     }
+
+    /// a compact index below `PIECE_COUNT`: white pieces first, each color in `PieceKind` order.
+    /// only defined for actual pieces
+    pub fn index(&self) -> usize {
+        debug_assert!(self.kind != PieceKind::NoPiece, "empty square has no index");
+
+        PIECE_INDICES[self.color as usize][self.kind as usize]
+    }
+
+    /// the letter used for this piece in FEN, e.g. `N` for a white knight
+    pub fn fen_char(&self) -> char {
+        let ch = match self.kind {
+            PieceKind::Pawn => 'p',
+            PieceKind::Knight => 'n',
+            PieceKind::Bishop => 'b',
+            PieceKind::Rook => 'r',
+            PieceKind::Queen => 'q',
+            PieceKind::King => 'k',
+            PieceKind::NoPiece => '.',
+        };
+
+        match self.color {
+            PieceColor::White => ch.to_ascii_uppercase(),
+            _ => ch,
+        }
+    }
 }
 
 impl Default for Piece {
@@ -0,0 +1,58 @@
+use std::{
+    fmt::Display,
+    io::IsTerminal,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+// off until `init` is called, so library users and tests always get plain output
+static COLOR_ENABLED: AtomicBool = AtomicBool::new(false);
+
+const CYAN: &str = "36";
+const GREEN: &str = "32";
+const RED: &str = "31";
+const ORANGE: &str = "38;5;208";
+const BOLD_ORANGE: &str = "1;38;5;208";
+
+/// enables colour only when stdout is a terminal and the user hasn't opted out via `NO_COLOR`
+/// (https://no-color.org)
+pub fn init() {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    set_color_enabled(std::io::stdout().is_terminal() && !no_color);
+}
+
+pub fn set_color_enabled(enabled: bool) {
+    COLOR_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_color_enabled() -> bool {
+    COLOR_ENABLED.load(Ordering::Relaxed)
+}
+
+/// commands, rank/file labels and other things that should stand out a little
+pub fn accent(text: impl Display) -> String {
+    paint(text, CYAN)
+}
+
+pub fn prompt(text: impl Display) -> String {
+    paint(text, ORANGE)
+}
+
+pub fn title(text: impl Display) -> String {
+    paint(text, BOLD_ORANGE)
+}
+
+pub fn success(text: impl Display) -> String {
+    paint(text, GREEN)
+}
+
+pub fn failure(text: impl Display) -> String {
+    paint(text, RED)
+}
+
+fn paint(text: impl Display, code: &str) -> String {
+    if is_color_enabled() {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
@@ -1,4 +1,93 @@
-use std::time::Instant;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use crate::search_limits::{SearchLimits, TimeControls};
+
+// time the GUI needs to receive the move, which is never available for searching. laggy GUIs and
+// play over a network need more of it, so it can be changed with `Move Overhead`
+pub const DEFAULT_MOVE_OVERHEAD_MS: u128 = 30;
+pub const MAX_MOVE_OVERHEAD_MS: u128 = 1000;
+
+// however few moves are left before the time control, one move never gets more than this share
+// of the clock, so something is left over for the moves that turn out to be complicated
+pub const MAX_MOVE_SHARE: f64 = 0.7;
+// nor more than this many times the even share of the moves to go
+const MAX_SHARES_PER_MOVE: f64 = 3.0;
+// the increment only arrives after the move is made, so not all of it is counted on
+const INCREMENT_SHARE: f64 = 0.8;
+// when the move the search had settled on turns out to be bad, it can have up to this many times
+// its usual time to find a better one, still within `MAX_MOVE_SHARE` of the clock
+pub const PANIC_TIME_FACTOR: f64 = 3.0;
+
+// the clock is read every so many nodes, aiming for once every `TARGET_CHECK_MS`. the interval
+// starts as small as it can be, so a tiny budget isn't overshot before the search speed is known,
+// and adapts to the speed measured between readings
+pub const MIN_CHECK_INTERVAL: u64 = 32;
+pub const MAX_CHECK_INTERVAL: u64 = 16384;
+const TARGET_CHECK_MS: u64 = 3;
+
+/// a source of time for the search timer. only the difference between two readings means
+/// anything, so the starting point is up to the implementation
+pub trait Clock: Send {
+    fn now_ms(&self) -> u128;
+}
+
+/// the real clock, used everywhere outside of tests
+pub struct SystemClock {
+    epoch: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            epoch: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u128 {
+        self.epoch.elapsed().as_millis()
+    }
+}
+
+/// a clock that only moves when told to. clones share the same time, so one can be given to the
+/// timer while another is kept to control it
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now: Arc<AtomicU64>,
+    // how far each reading by the timer moves the clock, to simulate the work done between checks
+    step: u64,
+}
+
+impl ManualClock {
+    pub fn with_step(step: u64) -> Self {
+        Self {
+            now: Arc::default(),
+            step,
+        }
+    }
+
+    pub fn advance(&self, ms: u64) {
+        self.now.fetch_add(ms, Ordering::Relaxed);
+    }
+
+    /// reads the time without advancing it
+    pub fn current_ms(&self) -> u128 {
+        self.now.load(Ordering::Relaxed) as u128
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_ms(&self) -> u128 {
+        self.now.fetch_add(self.step, Ordering::Relaxed) as u128
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SearchTimerStatus {
@@ -7,56 +96,203 @@ pub enum SearchTimerStatus {
     NotStarted,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SearchDuration {
     Finite(u128),
     Infinite,
 }
 
 pub struct SearchTimer {
-    pub start_time: Option<Instant>,
+    pub start_time: Option<u128>,
+    // when the search started, which is also when a ponder search started, even though its clock
+    // only starts on `ponderhit`. only used for reporting how long the search has taken
+    searching_since: u128,
     pub allowed_duration: SearchDuration,
+    // the most `allowed_duration` can be extended to. the same as it for a fixed move time
+    pub maximum_duration: SearchDuration,
     pub status: SearchTimerStatus,
+    pub move_overhead_ms: u128,
+    clock: Box<dyn Clock>,
+    // set from another thread to end the search early, e.g. on `stop`. the timer only ever reads
+    // it, so whoever sets it is also the one to clear it before the next search
+    stop_signal: Arc<AtomicBool>,
+    // set before a ponder search starts, and cleared from another thread on `ponderhit`. the
+    // clock doesn't start until then
+    pondering: Arc<AtomicBool>,
+    waiting_for_ponderhit: bool,
+    check_interval: u64,
+    next_check_nodes: u64,
+    // the node count and search time at the last reading of the clock, for measuring the speed
+    last_reading: (u64, u128),
+    nodes_at_check: u64,
 }
 
 impl Default for SearchTimer {
     fn default() -> Self {
+        Self::with_clock(SystemClock::default())
+    }
+}
+
+impl SearchTimer {
+    pub fn with_clock(clock: impl Clock + 'static) -> Self {
         Self {
             start_time: None,
+            searching_since: 0,
             allowed_duration: SearchDuration::Infinite,
+            maximum_duration: SearchDuration::Infinite,
             status: SearchTimerStatus::NotStarted,
+            move_overhead_ms: DEFAULT_MOVE_OVERHEAD_MS,
+            clock: Box::new(clock),
+            stop_signal: Arc::default(),
+            pondering: Arc::default(),
+            waiting_for_ponderhit: false,
+            check_interval: MIN_CHECK_INTERVAL,
+            next_check_nodes: MIN_CHECK_INTERVAL,
+            last_reading: (0, 0),
+            nodes_at_check: 0,
         }
     }
-}
 
-impl SearchTimer {
+    /// a timer that never runs out, for a search that only ends when `stop_signal` is set
+    pub fn with_stop_signal(stop_signal: Arc<AtomicBool>) -> Self {
+        Self {
+            stop_signal,
+            ..Self::default()
+        }
+    }
+
+    /// a handle for stopping the search from another thread. it's seen the next time the clock is
+    /// checked
+    pub fn stop_signal(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop_signal)
+    }
+
+    /// a handle for turning a ponder search into a timed one from another thread. it has to be
+    /// set before the search starts, and the clock starts the next time it's checked after the
+    /// handle is cleared
+    pub fn pondering(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.pondering)
+    }
+
     pub fn initialize(
         &mut self,
         time_remaining: Option<u128>,
         increment: u128,
         moves_to_go: Option<u64>,
     ) {
-        let moves_to_go = moves_to_go.unwrap_or(30);
+        let moves_to_go = moves_to_go.unwrap_or(30).max(1);
+
+        let (duration, maximum) = match time_remaining {
+            Some(time) => {
+                let share = time as f64 / moves_to_go as f64;
+                let cap =
+                    time as f64 * (MAX_SHARES_PER_MOVE / moves_to_go as f64).min(MAX_MOVE_SHARE);
+                let allocated = (share + increment as f64 * INCREMENT_SHARE)
+                    .min(cap)
+                    .round();
+                let maximum = (allocated * PANIC_TIME_FACTOR)
+                    .min(time as f64 * MAX_MOVE_SHARE)
+                    .round();
 
-        let duration = match time_remaining {
-            Some(time) => SearchDuration::Finite(
-                (time as f64 / moves_to_go as f64).round() as u128 + increment - 50,
-            ),
-            None => SearchDuration::Infinite,
+                (
+                    SearchDuration::Finite(
+                        (allocated as u128).saturating_sub(self.move_overhead_ms),
+                    ),
+                    SearchDuration::Finite((maximum as u128).saturating_sub(self.move_overhead_ms)),
+                )
+            }
+            None => (SearchDuration::Infinite, SearchDuration::Infinite),
         };
 
         self.start_time = None;
         self.allowed_duration = duration;
+        self.maximum_duration = maximum;
+    }
+
+    /// a fixed move time takes the place of the clock, and an infinite search has neither. a move
+    /// time is used as it is, less the time needed to get the move to the GUI
+    pub fn initialize_with_limits(&mut self, limits: &SearchLimits) {
+        if limits.infinite {
+            self.start_time = None;
+            self.allowed_duration = SearchDuration::Infinite;
+            self.maximum_duration = SearchDuration::Infinite;
+            return;
+        }
+
+        if let Some(movetime) = limits.movetime {
+            self.start_time = None;
+            self.allowed_duration =
+                SearchDuration::Finite(movetime.saturating_sub(self.move_overhead_ms));
+            self.maximum_duration = self.allowed_duration;
+            return;
+        }
+
+        let TimeControls {
+            time_remaining,
+            increment,
+            moves_to_go,
+        } = limits.time_controls;
+
+        self.initialize(time_remaining, increment, moves_to_go);
     }
 
     pub fn start(&mut self) {
         self.status = SearchTimerStatus::Running;
-        self.start_time = Some(Instant::now());
+        self.waiting_for_ponderhit = self.pondering.load(Ordering::Relaxed);
+        self.searching_since = self.clock.now_ms();
+
+        self.check_interval = MIN_CHECK_INTERVAL;
+        self.next_check_nodes = MIN_CHECK_INTERVAL;
+        self.last_reading = (0, 0);
+        self.nodes_at_check = 0;
+
+        self.start_time = if self.waiting_for_ponderhit {
+            None
+        } else {
+            Some(self.searching_since)
+        };
+    }
+
+    /// whether the search has got through enough nodes since the last check to call `check` again
+    pub fn should_check(&mut self, nodes: u64) -> bool {
+        if nodes < self.next_check_nodes {
+            return false;
+        }
+
+        self.nodes_at_check = nodes;
+        self.next_check_nodes = nodes + self.check_interval;
+        true
+    }
+
+    /// how many nodes `should_check` currently waits between checks
+    pub fn check_interval(&self) -> u64 {
+        self.check_interval
     }
 
     pub fn check(&mut self) {
+        if self.stop_signal.load(Ordering::Relaxed) {
+            self.status = SearchTimerStatus::Stopped;
+            return;
+        }
+
+        if self.waiting_for_ponderhit {
+            if self.pondering.load(Ordering::Relaxed) {
+                return;
+            }
+
+            // the predicted move was played, so the time allocated from the clock in the original
+            // `go` starts counting down now
+            self.waiting_for_ponderhit = false;
+            self.start_time = Some(self.clock.now_ms());
+        }
+
+        // only a search against the clock needs to read it
         let is_time_up = match self.allowed_duration {
-            SearchDuration::Finite(duration) => self.elapsed_ms() >= duration,
+            SearchDuration::Finite(duration) => {
+                let search_time_ms = self.search_time_ms();
+                self.adapt_check_interval(search_time_ms);
+                self.elapsed_ms_at(search_time_ms) >= duration
+            }
             SearchDuration::Infinite => false,
         };
 
@@ -65,13 +301,72 @@ impl SearchTimer {
         }
     }
 
+    // less than a millisecond since the last reading says nothing about the speed, other than
+    // that the interval is too short
+    fn adapt_check_interval(&mut self, search_time_ms: u128) {
+        let (last_nodes, last_ms) = self.last_reading;
+        let nodes = self.nodes_at_check.saturating_sub(last_nodes);
+        let ms = search_time_ms.saturating_sub(last_ms) as u64;
+
+        let interval = match (nodes * TARGET_CHECK_MS).checked_div(ms) {
+            Some(interval) => interval,
+            None => self.check_interval * 2,
+        };
+
+        self.check_interval = interval.clamp(MIN_CHECK_INTERVAL, MAX_CHECK_INTERVAL);
+        self.next_check_nodes = self.nodes_at_check + self.check_interval;
+        self.last_reading = (self.nodes_at_check, search_time_ms);
+    }
+
+    /// gives the search all the time it could have, for when it's in trouble. whether that's any
+    /// more than it had already
+    pub fn extend_to_maximum(&mut self) -> bool {
+        if self.allowed_duration == self.maximum_duration {
+            return false;
+        }
+
+        self.allowed_duration = self.maximum_duration;
+        true
+    }
+
+    pub fn stop(&mut self) {
+        self.status = SearchTimerStatus::Stopped;
+    }
+
     pub fn is_stopped(&self) -> bool {
         self.status == SearchTimerStatus::Stopped
     }
 
-    fn elapsed_ms(&self) -> u128 {
+    /// an iteration that can't finish in the time left is wasted, and each one takes at least as
+    /// long as the one before it
+    pub fn can_start_iteration(&self, elapsed_ms: u128, last_iteration_ms: u128) -> bool {
+        if self.waiting_for_ponderhit {
+            return true;
+        }
+
+        match self.allowed_duration {
+            SearchDuration::Finite(duration) => {
+                duration.saturating_sub(elapsed_ms) >= last_iteration_ms
+            }
+            SearchDuration::Infinite => true,
+        }
+    }
+
+    pub fn elapsed_ms(&self) -> u128 {
+        self.elapsed_ms_at(self.search_time_ms())
+    }
+
+    /// how long the search has been going, for `info` lines. unlike `elapsed_ms`, this includes
+    /// any time spent pondering
+    pub fn search_time_ms(&self) -> u128 {
+        self.clock.now_ms().saturating_sub(self.searching_since)
+    }
+
+    /// `elapsed_ms` as it was when `search_time_ms` gave `search_time_ms`, so a search that
+    /// needs both only reads the clock once
+    pub fn elapsed_ms_at(&self, search_time_ms: u128) -> u128 {
         match self.start_time {
-            Some(time) => time.elapsed().as_millis(),
+            Some(time) => (self.searching_since + search_time_ms).saturating_sub(time),
             None => 0,
         }
     }
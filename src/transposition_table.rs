@@ -1,25 +1,89 @@
-use crate::{move_generator::Move, search::CHECKMATE_THRESHOLD};
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::{bail, Context};
+
+use crate::{
+    engine_details::ENGINE_VERSION,
+    move_generator::Move,
+    profile::{self, Phase},
+    search::CHECKMATE_THRESHOLD,
+    zobrist_hash::ZobristHasher,
+};
 
 pub trait TableEntry {
     fn hash(&self) -> u64;
+    /// everything but the hash, packed into the low 63 bits. the table uses the top bit to mark a
+    /// slot as taken
+    fn data(&self) -> u64;
+    fn from_parts(hash: u64, data: u64) -> Self;
 }
 
-pub struct TranspositionTable<Entry>
-where
-    Entry: TableEntry + Default + Clone,
-{
-    entries: Vec<Entry>,
-    size: usize,
+/// entries that can be written to and read back from a hash file
+pub trait PersistentEntry: Sized {
+    const ENCODED_SIZE: usize;
+
+    fn encode(&self, bytes: &mut Vec<u8>);
+    fn decode(bytes: &[u8]) -> anyhow::Result<Self>;
 }
 
-#[derive(Debug, Default, Clone)]
-pub struct PerftTableEntry {
-    pub hash: u64,
-    pub node_count: u64,
-    pub depth: u8,
+/// shared by every search thread without any locking. see `share`
+pub struct TranspositionTable<Entry> {
+    slots: Arc<Vec<Slot>>,
+    // what the table was created with. loading a hash file can change the actual size
+    size_in_mb: usize,
+    entry: PhantomData<Entry>,
+}
+
+// the key is the hash xor-ed with the data, so a slot torn by two threads writing to it at once
+// no longer matches either hash and is ignored, rather than read back as a mix of both entries.
+// the data's top bit marks the slot as taken, so any hash can be stored, 0 included
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+const OCCUPIED: u64 = 1 << 63;
+
+impl Slot {
+    fn pack(hash: u64, data: u64) -> (u64, u64) {
+        debug_assert_eq!(data & OCCUPIED, 0, "entry data overlaps the occupied bit");
+
+        let data = data | OCCUPIED;
+        (hash ^ data, data)
+    }
+
+    // (hash, data) of the entry in the slot, if there is one
+    fn read(&self) -> Option<(u64, u64)> {
+        let data = self.data.load(Ordering::Relaxed);
+        let key = self.key.load(Ordering::Relaxed);
+
+        (data & OCCUPIED != 0).then_some((key ^ data, data & !OCCUPIED))
+    }
+
+    fn clear(&self) {
+        self.key.store(0, Ordering::Relaxed);
+        self.data.store(0, Ordering::Relaxed);
+    }
+
+    fn write(&self, hash: u64, data: u64) {
+        let (key, data) = Self::pack(hash, data);
+
+        self.key.store(key, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct SearchTableEntry {
     pub hash: u64,
     pub depth: u8,
@@ -28,7 +92,7 @@ pub struct SearchTableEntry {
     pub best_move: Move,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum SearchEntryFlag {
     #[default]
     Exact,
@@ -36,22 +100,6 @@ pub enum SearchEntryFlag {
     Beta,
 }
 
-impl PerftTableEntry {
-    pub fn new(hash: u64, node_count: u64, depth: u8) -> Self {
-        Self {
-            hash,
-            node_count,
-            depth,
-        }
-    }
-}
-
-impl TableEntry for PerftTableEntry {
-    fn hash(&self) -> u64 {
-        self.hash
-    }
-}
-
 impl SearchTableEntry {
     pub fn new(
         hash: u64,
@@ -80,78 +128,338 @@ impl SearchTableEntry {
         }
     }
 
-    pub fn get(&self, hash: u64, depth: u8, ply: u8, alpha: i32, beta: i32) -> (Option<i32>, Move) {
+    /// the entry is assumed to be for the current position, which `probe` has already checked
+    pub fn get(&self, depth: u8, ply: u8, alpha: i32, beta: i32) -> (Option<i32>, Move) {
         let mut score = None;
-        let mut best_move = Move::NULL_MOVE;
 
-        if self.hash() == hash {
-            best_move = self.best_move;
+        if self.depth >= depth {
+            let mut entry_score = self.score;
 
-            if self.depth >= depth {
-                let mut entry_score = self.score;
+            if entry_score > CHECKMATE_THRESHOLD {
+                entry_score -= ply as i32;
+            }
 
-                if entry_score > CHECKMATE_THRESHOLD {
-                    entry_score -= ply as i32;
-                }
+            if entry_score < -CHECKMATE_THRESHOLD {
+                entry_score += ply as i32;
+            }
 
-                if entry_score < -CHECKMATE_THRESHOLD {
-                    entry_score += ply as i32;
+            match self.flag {
+                SearchEntryFlag::Exact => {
+                    score = Some(entry_score);
                 }
-
-                match self.flag {
-                    SearchEntryFlag::Exact => {
-                        score = Some(entry_score);
+                SearchEntryFlag::Alpha => {
+                    if alpha >= entry_score {
+                        score = Some(alpha);
                     }
-                    SearchEntryFlag::Alpha => {
-                        if alpha >= entry_score {
-                            score = Some(alpha);
-                        }
-                    }
-                    SearchEntryFlag::Beta => {
-                        if beta <= entry_score {
-                            score = Some(beta);
-                        }
+                }
+                SearchEntryFlag::Beta => {
+                    if beta <= entry_score {
+                        score = Some(beta);
                     }
-                };
-            }
+                }
+            };
         }
 
-        (score, best_move)
+        (score, self.best_move)
     }
 }
 
+// 17 bits: best move
+// 8 bits: depth
+// 2 bits: flag
+// 32 bits: score
 impl TableEntry for SearchTableEntry {
     fn hash(&self) -> u64 {
         self.hash
     }
+
+    fn data(&self) -> u64 {
+        let flag = match self.flag {
+            SearchEntryFlag::Exact => 0,
+            SearchEntryFlag::Alpha => 1,
+            SearchEntryFlag::Beta => 2,
+        };
+
+        self.best_move.bits() as u64
+            | (self.depth as u64) << 17
+            | flag << 25
+            | (self.score as u32 as u64) << 27
+    }
+
+    fn from_parts(hash: u64, data: u64) -> Self {
+        let flag = match (data >> 25) & 0b11 {
+            0 => SearchEntryFlag::Exact,
+            1 => SearchEntryFlag::Alpha,
+            _ => SearchEntryFlag::Beta,
+        };
+
+        Self {
+            hash,
+            depth: (data >> 17) as u8,
+            score: (data >> 27) as u32 as i32,
+            flag,
+            best_move: Move::from_bits(data as u32 & 0x1ffff),
+        }
+    }
+}
+
+impl PersistentEntry for SearchTableEntry {
+    // hash (8) + depth (1) + score (4) + flag (1) + best move (4)
+    const ENCODED_SIZE: usize = 18;
+
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.hash.to_le_bytes());
+        bytes.push(self.depth);
+        bytes.extend_from_slice(&self.score.to_le_bytes());
+        bytes.push(match self.flag {
+            SearchEntryFlag::Exact => 0,
+            SearchEntryFlag::Alpha => 1,
+            SearchEntryFlag::Beta => 2,
+        });
+        bytes.extend_from_slice(&self.best_move.bits().to_le_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.len() != Self::ENCODED_SIZE {
+            bail!("search table entry must be {} bytes", Self::ENCODED_SIZE);
+        }
+
+        let flag = match bytes[13] {
+            0 => SearchEntryFlag::Exact,
+            1 => SearchEntryFlag::Alpha,
+            2 => SearchEntryFlag::Beta,
+            other => bail!("invalid search entry flag: {}", other),
+        };
+
+        Ok(Self {
+            hash: u64::from_le_bytes(bytes[0..8].try_into()?),
+            depth: bytes[8],
+            score: i32::from_le_bytes(bytes[9..13].try_into()?),
+            flag,
+            best_move: Move::from_bits(u32::from_le_bytes(bytes[14..18].try_into()?)),
+        })
+    }
 }
 
 const MEGABYTE: usize = 1024 * 1024;
 
+const HASH_FILE_MAGIC: &[u8; 8] = b"KRUSTYTT";
+
+// `hashfull` only looks at the start of the table, since scanning all of it after every
+// iteration would be too slow
+const HASHFULL_SAMPLE_SIZE: usize = 1000;
+
 impl<Entry> TranspositionTable<Entry>
 where
-    Entry: TableEntry + Default + Clone,
+    Entry: TableEntry,
 {
+    /// what each entry takes up in the table, whatever `Entry` packs into it
+    pub const ENTRY_SIZE_BYTES: usize = std::mem::size_of::<Slot>();
+
+    /// there's always at least one entry, however small the size, so every hash has somewhere to
+    /// go
     pub fn new(size_in_mb: usize) -> Self {
-        let size = (size_in_mb * MEGABYTE) / std::mem::size_of::<Entry>();
+        let size = ((size_in_mb * MEGABYTE) / Self::ENTRY_SIZE_BYTES).max(1);
+
+        Self {
+            slots: Arc::new((0..size).map(|_| Slot::default()).collect()),
+            size_in_mb,
+            entry: PhantomData,
+        }
+    }
 
+    /// another handle to the same entries, for another search thread. anything stored through
+    /// one handle can be probed through all of them. loading a hash file only replaces the
+    /// entries behind the handle it's loaded into
+    pub fn share(&self) -> Self {
         Self {
-            entries: vec![Entry::default(); size],
-            size,
+            slots: Arc::clone(&self.slots),
+            size_in_mb: self.size_in_mb,
+            entry: PhantomData,
+        }
+    }
+
+    pub fn configured_mb(&self) -> usize {
+        self.size_in_mb
+    }
+
+    pub fn capacity_entries(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn size_bytes(&self) -> usize {
+        self.slots.capacity() * Self::ENTRY_SIZE_BYTES
+    }
+
+    pub fn store(&self, entry: Entry) {
+        let _timer = profile::time(Phase::TranspositionTable);
+
+        let hash = entry.hash();
+        self.slots[self.get_index(hash)].write(hash, entry.data());
+    }
+
+    /// empties every slot in place, so the table keeps its size and every shared handle sees it
+    /// empty. nothing else needs resetting, since `hashfull` samples the slots themselves
+    pub fn clear(&self) {
+        for slot in self.slots.iter() {
+            slot.clear();
         }
     }
 
-    pub fn store(&mut self, entry: Entry) {
-        let index = self.get_index(entry.hash());
-        self.entries[index] = entry;
+    /// the entry stored for this hash, if the slot it maps to holds one for the same hash
+    pub fn probe(&self, hash: u64) -> Option<Entry> {
+        match self.slots[self.get_index(hash)].read() {
+            Some((stored_hash, data)) if stored_hash == hash => Some(Entry::from_parts(hash, data)),
+            _ => None,
+        }
     }
 
-    pub fn probe(&self, hash: u64) -> &Entry {
-        let index = self.get_index(hash);
-        &self.entries[index]
+    /// how full the table is, in permille, as UCI reports it
+    pub fn hashfull(&self) -> u16 {
+        let sample = &self.slots[..self.slots.len().min(HASHFULL_SAMPLE_SIZE)];
+
+        let used = sample.iter().filter(|slot| slot.read().is_some()).count();
+
+        (used * 1000 / sample.len()) as u16
     }
 
     fn get_index(&self, hash: u64) -> usize {
-        (hash as usize) % self.size
+        (hash as usize) % self.slots.len()
+    }
+}
+
+// hash file layout (all integers little-endian):
+// - magic bytes `KRUSTYTT`
+// - engine version: length (u8) followed by the UTF-8 bytes
+// - slot size in bytes (u32)
+// - slot count (u64)
+// - zobrist key checksum (u64)
+// - the slots themselves: 1 if the slot holds an entry and 0 if not, then the entry, or zeros for
+//   an empty slot. only slots holding an entry are saved, so the file is as big as what's in the
+//   table rather than the table itself
+impl<Entry> TranspositionTable<Entry>
+where
+    Entry: TableEntry + PersistentEntry,
+{
+    const SLOT_SIZE: usize = Entry::ENCODED_SIZE + 1;
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let file = File::create(path.as_ref()).context("could not create hash file")?;
+        let mut writer = BufWriter::new(file);
+
+        writer.write_all(HASH_FILE_MAGIC)?;
+        writer.write_all(&[ENGINE_VERSION.len() as u8])?;
+        writer.write_all(ENGINE_VERSION.as_bytes())?;
+        writer.write_all(&(Self::SLOT_SIZE as u32).to_le_bytes())?;
+
+        // the count isn't known until the entries have been written, so it's filled in after
+        let count_offset = writer.stream_position()?;
+        writer.write_all(&0u64.to_le_bytes())?;
+        writer.write_all(&ZobristHasher::default().checksum().to_le_bytes())?;
+
+        let mut bytes = Vec::with_capacity(Self::SLOT_SIZE);
+        let mut entry_count = 0u64;
+
+        for (hash, data) in self.slots.iter().filter_map(Slot::read) {
+            bytes.clear();
+            bytes.push(1);
+            Entry::from_parts(hash, data).encode(&mut bytes);
+
+            writer.write_all(&bytes)?;
+            entry_count += 1;
+        }
+
+        writer.seek(SeekFrom::Start(count_offset))?;
+        writer.write_all(&entry_count.to_le_bytes())?;
+
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    // the entries are stored again into a table of the configured size, so a file saved with a
+    // bigger `Hash` still loads into a smaller one, keeping as many entries as fit. the count in
+    // the header is checked against the file's length before anything is read, so a corrupt header
+    // fails cleanly
+    pub fn load(&mut self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let file = File::open(path.as_ref()).context("could not open hash file")?;
+        let file_length = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0; 8];
+        reader
+            .read_exact(&mut magic)
+            .context("hash file is missing its header")?;
+
+        if &magic != HASH_FILE_MAGIC {
+            bail!("file is not a krusty hash file");
+        }
+
+        let mut version_length = [0; 1];
+        reader.read_exact(&mut version_length)?;
+        let mut version = vec![0; version_length[0] as usize];
+        reader.read_exact(&mut version)?;
+
+        if version != ENGINE_VERSION.as_bytes() {
+            bail!(
+                "hash file was saved by engine version {} but this is version {}",
+                String::from_utf8_lossy(&version),
+                ENGINE_VERSION
+            );
+        }
+
+        let mut entry_size = [0; 4];
+        reader.read_exact(&mut entry_size)?;
+        let entry_size = u32::from_le_bytes(entry_size) as usize;
+
+        if entry_size != Self::SLOT_SIZE {
+            bail!(
+                "hash file entries are {} bytes but expected {}",
+                entry_size,
+                Self::SLOT_SIZE
+            );
+        }
+
+        let mut entry_count = [0; 8];
+        reader.read_exact(&mut entry_count)?;
+        let entry_count = u64::from_le_bytes(entry_count);
+
+        let mut checksum = [0; 8];
+        reader.read_exact(&mut checksum)?;
+
+        if u64::from_le_bytes(checksum) != ZobristHasher::default().checksum() {
+            bail!("hash file was created with different zobrist keys");
+        }
+
+        let header_length = (HASH_FILE_MAGIC.len() + 1 + version.len() + 4 + 8 + 8) as u64;
+        let entries_length = file_length.saturating_sub(header_length);
+
+        if entry_count.checked_mul(Self::SLOT_SIZE as u64) != Some(entries_length) {
+            bail!(
+                "hash file says it has {} entries, but has room for {}",
+                entry_count,
+                entries_length / Self::SLOT_SIZE as u64
+            );
+        }
+
+        let table = Self::new(self.size_in_mb);
+
+        let mut bytes = vec![0; Self::SLOT_SIZE];
+
+        for _ in 0..entry_count {
+            reader
+                .read_exact(&mut bytes)
+                .context("hash file is truncated")?;
+
+            match bytes[0] {
+                0 => (),
+                1 => table.store(Entry::decode(&bytes[1..])?),
+                other => bail!("invalid hash file slot marker: {}", other),
+            }
+        }
+
+        *self = table;
+
+        Ok(())
     }
 }
@@ -1,239 +1,944 @@
-use std::io::{self, BufRead};
+use std::{
+    any::Any,
+    fs::OpenOptions,
+    io::{self, BufRead, Write},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, Thread},
+    time::{Duration, Instant},
+};
 
 use anyhow::{bail, Context};
 
 use crate::{
-    board::{Side, START_POSITION_FEN},
+    bench::{run_bench, BENCH_DEPTH},
+    board::{Board, Side, START_POSITION_FEN},
     engine_details::{ENGINE_AUTHOR, ENGINE_NAME, ENGINE_VERSION},
+    input::{read_line, InputLine},
+    move_generator::Move,
+    options::{OptionValue, UciOption, UCI_OPTIONS},
+    position::{apply_fen, apply_moves, assumed_promotions},
+    reporter::{LoggingReporter, SearchLog, SearchReporter, UciReporter},
     search::{Search, SearchDepth},
+    search_limits::SearchLimits,
+    shutdown::Shutdown,
+    style,
 };
 
+const CRASH_LOG_PATH: &str = "krusty_crash.log";
+
+// how long `quit` waits for a stopped search to notice before exiting anyway. a search checks the
+// stop signal every few thousand nodes, so this is only ever reached if something's gone wrong
+const QUIT_TIMEOUT: Duration = Duration::from_millis(500);
+
+// what the input loop should do once a search, or anything else it was listening alongside, has
+// finished
+enum AfterWorker {
+    Continue,
+    // a line the worker couldn't be interrupted for arrived while it was going. it's still in the
+    // buffer
+    HandleLine,
+    Quit(UciExit),
+}
+
+/// why [`Uci::run`] returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UciExit {
+    /// the GUI sent `quit`, so the whole program should end
+    Quit,
+    /// the input was closed
+    InputClosed,
+}
+
+// work that can take long enough for the GUI to notice, like allocating a huge transposition
+// table, so it's done on a thread of its own while the input loop keeps listening
+type SlowTask = Box<dyn FnOnce(&mut Search) + Send>;
+
+// `isready` can't be answered until the slow task before it has been applied, so it's held back
+// until then. whichever thread finds the other one already finished answers it
+#[derive(Default)]
+struct PendingReady {
+    task_done: bool,
+    count: usize,
+}
+
 pub struct Uci<'a> {
     search: &'a mut Search,
+    minimal_output: bool,
+    show_root_moves: bool,
+    // `debug on` from the GUI. shared with the reporter, since it can change mid-search
+    debug: Arc<AtomicBool>,
+    search_log: Option<SearchLog>,
+
+    // the arguments of the last `position` command, so the position can be rebuilt after a crash
+    position_args: String,
 }
 
 impl<'a> Uci<'a> {
     pub fn new(search: &'a mut Search) -> Self {
-        Self { search }
+        Self {
+            search,
+            minimal_output: false,
+            show_root_moves: false,
+            debug: Arc::default(),
+            search_log: None,
+            position_args: String::from("startpos"),
+        }
     }
 
-    pub fn start_loop(&mut self) {
-        Self::handle_uci_command();
+    pub fn start_loop(&mut self) -> UciExit {
+        self.run(io::stdin().lock())
+    }
+
+    pub fn run(&mut self, mut input: impl BufRead) -> UciExit {
+        // GUIs don't expect escape codes in anything the engine prints
+        let color_enabled = style::is_color_enabled();
+        style::set_color_enabled(false);
+
+        // `UCI_Chess960` and `AssumeQueenPromotion` only last as long as the GUI is connected
+        let chess960 = self.search.board.is_chess960();
+        let assume_queen_promotion = self.search.board.assumes_queen_promotion();
+
+        self.handle_uci_command();
 
         let mut input_buffer = String::new();
+        let mut has_pending_line = false;
 
-        loop {
-            io::stdin().lock().read_line(&mut input_buffer).unwrap();
+        let exit = loop {
+            if !has_pending_line {
+                match read_line(&mut input, &mut input_buffer) {
+                    Ok(InputLine::Line) => (),
+                    Ok(InputLine::TooLong) => {
+                        println!("info string input line too long, ignoring it");
+                        continue;
+                    }
+                    // the GUI closed the pipe, which is as good as `quit`
+                    Ok(InputLine::Eof) | Err(_) => {
+                        self.shutdown();
+                        break UciExit::InputClosed;
+                    }
+                }
+            }
+
+            has_pending_line = false;
 
-            let input = input_buffer.trim();
-            let (command, args) = match input.split_once(' ') {
+            let line = input_buffer.trim();
+            let (command, args) = match line.split_once(' ') {
                 Some((command, args)) => (command, args),
-                None => (input, ""),
+                None => (line, ""),
             };
 
             let args = args.trim();
 
-            match command {
-                "uci" => Self::handle_uci_command(),
-                "isready" => println!("readyok"),
-                "position" => self.handle_position_command(args),
-                "go" => self.handle_go_command(args),
-                "quit" => {
-                    self.search.reset();
-                    break;
+            let after = match command {
+                "uci" => {
+                    self.handle_uci_command();
+                    AfterWorker::Continue
                 }
-                _ => (),
+                "isready" => {
+                    println!("readyok");
+                    AfterWorker::Continue
+                }
+                "debug" => {
+                    set_debug(&self.debug, args);
+                    AfterWorker::Continue
+                }
+                "setoption" => match self.handle_setoption_command(args) {
+                    Some(task) => self.run_slow_task(task, &mut input, &mut input_buffer),
+                    None => AfterWorker::Continue,
+                },
+                "position" => {
+                    self.handle_position_command(args);
+                    AfterWorker::Continue
+                }
+                "go" => match parse_go_args(args, &mut self.search.board) {
+                    Ok(limits) => self.handle_go_command(&limits, &mut input, &mut input_buffer),
+                    Err(error) => {
+                        println!("info string {:#}, ignoring the `go` command", error);
+                        AfterWorker::Continue
+                    }
+                },
+                "bench" => match parse_bench_depth(args) {
+                    Ok(depth) => self.handle_bench_command(depth, &mut input, &mut input_buffer),
+                    Err(error) => {
+                        println!("info string {}", error);
+                        AfterWorker::Continue
+                    }
+                },
+                "quit" => AfterWorker::Quit(UciExit::Quit),
+                _ => AfterWorker::Continue,
             };
 
-            input_buffer.clear();
-        }
+            match after {
+                AfterWorker::Continue => (),
+                AfterWorker::HandleLine => has_pending_line = true,
+                AfterWorker::Quit(exit) => {
+                    self.shutdown();
+                    break exit;
+                }
+            }
+        };
+
+        style::set_color_enabled(color_enabled);
+        self.search.board.set_chess960(chess960);
+        self.search
+            .board
+            .set_assume_queen_promotion(assume_queen_promotion);
+
+        exit
     }
 
-    fn handle_uci_command() {
+    fn handle_uci_command(&self) {
         println!("id name {} v{}", ENGINE_NAME, ENGINE_VERSION);
         println!("id author {}", ENGINE_AUTHOR);
+        for option in UCI_OPTIONS.iter() {
+            println!("{}", option.declaration());
+        }
         println!("uciok");
     }
 
+    // setoption name <id> [value <x>]
+    //
+    // anything that could take a while is handed back to be run as a slow task instead
+    fn handle_setoption_command(&mut self, args: &str) -> Option<SlowTask> {
+        let Some(args) = args.strip_prefix("name ") else {
+            println!("info string invalid `setoption` command");
+            return None;
+        };
+
+        let (name, value) = match args.split_once(" value ") {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => (args.trim(), ""),
+        };
+
+        let Some(option) = UciOption::find(name) else {
+            println!("info string unsupported option {}", name);
+            return None;
+        };
+
+        // a rejected value leaves the previous state untouched
+        let value = match option.parse_value(value) {
+            Ok(value) => value,
+            Err(error) => {
+                println!("info string {:#}", error);
+                return None;
+            }
+        };
+
+        let mut config = self.search.config();
+        if config.apply_option(option.name, &value) {
+            // a new size means allocating a new table, which takes a while when it's large
+            if config.hash_mb != self.search.config().hash_mb {
+                return Some(Box::new(move |search: &mut Search| {
+                    search.configure(&config)
+                }));
+            }
+
+            self.search.configure(&config);
+
+            // setting the same seed again still starts its numbers over, like a fresh engine
+            if option.name == "Seed" {
+                self.search.set_seed(config.seed);
+            }
+
+            return None;
+        }
+
+        match (option.name, value) {
+            // the GUI only sets it to say it may send `go ponder`, which needs nothing set up
+            ("Ponder", _) => (),
+            ("Clear Hash", _) => self.search.transposition_table.clear(),
+            ("MinimalOutput", OptionValue::Check(enabled)) => self.minimal_output = enabled,
+            ("RootMoveInfo", OptionValue::Check(enabled)) => self.show_root_moves = enabled,
+            ("SaveHash", OptionValue::Text(path)) => {
+                if let Err(error) = self.search.transposition_table.save(&path) {
+                    println!("info string failed to save hash: {:#}", error);
+                }
+            }
+            ("LoadHash", OptionValue::Text(path)) => {
+                return Some(Box::new(move |search: &mut Search| {
+                    if let Err(error) = search.transposition_table.load(&path) {
+                        println!("info string failed to load hash: {:#}", error);
+                    }
+                }));
+            }
+            ("LogFile", OptionValue::Text(path)) => self.search_log = Some(SearchLog::new(&path)),
+            // `parse_value` only accepts supported variants, so there's nothing left to do
+            ("UCI_Variant", _) => (),
+            ("UCI_Chess960", OptionValue::Check(enabled)) => {
+                self.search.board.set_chess960(enabled)
+            }
+            ("AssumeQueenPromotion", OptionValue::Check(enabled)) => {
+                self.search.board.set_assume_queen_promotion(enabled)
+            }
+            _ => (),
+        }
+
+        None
+    }
+
     // possible examples:
     // position startpos
     // position fen <fen>
     // position startpos moves e2e4 e7e5 ...
     // position fen <fen> moves e2e4 e7e5 ...
+    // position current moves e2e4 e7e5 ... (non-standard)
     fn handle_position_command(&mut self, args: &str) {
-        let position_kind = match args.split_whitespace().nth(0) {
-            Some(kind) => kind,
-            None => {
-                println!("Invalid `position` command");
-                return;
-            }
-        };
+        // everything is played out on a copy, which only replaces the position once the whole
+        // command has worked. one that only got part of the way would leave the engine out of step
+        // with the GUI for the rest of the game
+        let mut board = self.search.board.clone();
 
-        let moves_start_index = args.find("moves");
+        let result = match strip_word(args, "current") {
+            Some(args) => self.current_position(&mut board, args),
+            None => self.updated_position(&mut board, args),
+        };
 
-        let fen = match position_kind {
-            "startpos" => START_POSITION_FEN,
-            "fen" => {
-                let fen_start_index = "fen".len() + 1;
-                match moves_start_index {
-                    Some(index) => &args[fen_start_index..(index - 1)],
-                    None => &args[fen_start_index..],
-                }
-            }
-            _ => {
-                println!("Invalid `position` command");
-                return;
+        match result {
+            Ok(position_args) => {
+                self.search.board = board;
+                self.position_args = position_args;
             }
-        };
+            Err(error) => println!("info string {:#}, keeping the previous position", error),
+        }
+    }
 
-        if self.search.board.parse_fen(fen).is_err() {
-            println!("Invalid FEN");
-            return;
+    // GUIs send the whole game again before every move. when it's the last position with moves
+    // added, only the new ones are played, so the history behind repetition detection is kept
+    // exactly as it was built up. returns the arguments that now rebuild the position
+    fn updated_position(&self, board: &mut Board, args: &str) -> anyhow::Result<String> {
+        match moves_after(&self.position_args, args) {
+            Some(new_moves) => play_position_moves(board, &new_moves)?,
+            None => set_up_position(board, args)?,
         }
 
-        if let Some(index) = moves_start_index {
-            let start_index = index + "moves ".len();
-            let moves: Result<Vec<_>, _> = args[start_index..]
-                .split_whitespace()
-                .map(|move_str| self.search.board.get_move_metadata(move_str))
-                .collect();
+        Ok(args.into())
+    }
+
+    // sets the position up from scratch, for when the board can't be trusted or has been used for
+    // something else
+    fn set_position(&mut self, args: &str) -> anyhow::Result<()> {
+        let mut board = self.search.board.clone();
+        set_up_position(&mut board, args)?;
 
-            let moves = match moves {
-                Ok(m) => m,
-                Err(error) => {
-                    println!("Invalid move: {}", error);
-                    return;
-                }
+        self.search.board = board;
+        self.position_args = args.into();
+
+        Ok(())
+    }
+
+    // plays moves on top of the position as it stands, for frontends that only send what's new.
+    // the stored command is kept in step, so the position can still be rebuilt after a crash
+    fn current_position(&self, board: &mut Board, args: &str) -> anyhow::Result<String> {
+        let moves = strip_word(args, "moves").context("invalid `position current` command")?;
+
+        play_position_moves(board, &moves.split_whitespace().collect::<Vec<_>>())?;
+
+        let mut position_args = self.position_args.clone();
+
+        if !moves.is_empty() {
+            let separator = if position_args.contains("moves") {
+                " "
+            } else {
+                " moves "
             };
 
-            for mv in moves {
-                let found_move = self.search.board.find_matching_move(mv);
-
-                match found_move {
-                    Some(mv) => {
-                        let is_legal = self.search.board.make_move(mv).unwrap();
-                        if !is_legal {
-                            println!("Move `{:?}` is not legal in this position", mv);
-                            self.search.board.unmake_move(mv).unwrap();
-                            return;
-                        }
-                    }
-                    None => {
-                        println!("Move `{:?}` is not legal in this position", mv);
-                        return;
+            position_args.push_str(separator);
+            position_args.push_str(moves);
+        }
+
+        Ok(position_args)
+    }
+
+    // the search runs on a thread of its own, so `stop`, `ponderhit`, `isready` and `debug` are
+    // still answered while it's going. any other command waits for the search to finish before it's
+    // handled
+    fn handle_go_command(
+        &mut self,
+        limits: &SearchLimits,
+        input: &mut impl BufRead,
+        input_buffer: &mut String,
+    ) -> AfterWorker {
+        let stop_signal = self.search.timer.stop_signal();
+        stop_signal.store(false, Ordering::Relaxed);
+
+        let pondering = self.search.timer.pondering();
+        pondering.store(limits.ponder, Ordering::Relaxed);
+
+        let debug = Arc::clone(&self.debug);
+        let quitting = AtomicBool::new(false);
+
+        thread::scope(|scope| {
+            let worker =
+                scope.spawn(|| self.search_and_report(limits, &stop_signal, &pondering, &quitting));
+            let after_search = Self::listen_while_searching(
+                input,
+                input_buffer,
+                &stop_signal,
+                &pondering,
+                &quitting,
+                &debug,
+                worker.thread(),
+            );
+
+            // infinite and ponder searches never end by themselves, and the GUI shouldn't send
+            // anything but `stop`, `ponderhit` or `quit` before they're over. if it does anyway,
+            // the search ends there
+            if limits.infinite || pondering.load(Ordering::Relaxed) {
+                stop_signal.store(true, Ordering::Relaxed);
+            }
+
+            // in case it's waiting to send `bestmove`
+            worker.thread().unpark();
+
+            if quitting.load(Ordering::Relaxed) {
+                let deadline = Instant::now() + QUIT_TIMEOUT;
+
+                while !worker.is_finished() {
+                    // a search that won't stop can't be allowed to keep the engine running
+                    if Instant::now() >= deadline {
+                        let _ = io::stdout().flush();
+                        std::process::exit(0);
                     }
+
+                    thread::sleep(Duration::from_millis(1));
                 }
             }
-        }
+
+            if let Err(payload) = worker.join() {
+                panic::resume_unwind(payload);
+            }
+
+            after_search
+        })
     }
 
-    fn handle_go_command(&mut self, args: &str) {
-        let mut args = args.split_whitespace();
+    // returns as soon as there's a line that can't be handled until the search is over, which for
+    // a search that ends on its own is usually the GUI's next `position`
+    fn listen_while_searching(
+        input: &mut impl BufRead,
+        input_buffer: &mut String,
+        stop_signal: &AtomicBool,
+        pondering: &AtomicBool,
+        quitting: &AtomicBool,
+        debug: &AtomicBool,
+        worker: &Thread,
+    ) -> AfterWorker {
+        loop {
+            match read_line(input, input_buffer) {
+                Ok(InputLine::Line) => (),
+                Ok(InputLine::TooLong) => {
+                    println!("info string input line too long, ignoring it");
+                    continue;
+                }
+                Ok(InputLine::Eof) | Err(_) => {
+                    stop_signal.store(true, Ordering::Relaxed);
+                    return AfterWorker::Quit(UciExit::InputClosed);
+                }
+            }
 
-        let mut time_remaining = None;
-        let mut increment = 0;
-        let mut max_depth = SearchDepth::Infinite;
-        let mut moves_to_go = None;
+            let line = input_buffer.trim();
 
-        while let Some(arg) = args.next() {
-            match arg {
-                "depth" => {
-                    max_depth = match args.next().try_into() {
-                        Ok(depth) => depth,
-                        Err(error) => {
-                            println!("{}", error);
-                            return;
-                        }
-                    };
+            if let Some(args) = line.strip_prefix("debug") {
+                if args.is_empty() || args.starts_with(' ') {
+                    set_debug(debug, args.trim());
+                    continue;
                 }
-                "wtime" => {
-                    if self.search.board.side_to_move() == Side::White {
-                        time_remaining = match args.next() {
-                            Some(time) => time.parse::<u128>().ok(),
-                            None => {
-                                println!("missing wtime value");
-                                return;
-                            }
-                        };
-                    }
+            }
+
+            match line {
+                "stop" => {
+                    stop_signal.store(true, Ordering::Relaxed);
+                    return AfterWorker::Continue;
                 }
-                "btime" => {
-                    if self.search.board.side_to_move() == Side::Black {
-                        time_remaining = match args.next() {
-                            Some(time) => time.parse::<u128>().ok(),
-                            None => {
-                                println!("missing btime value");
-                                return;
-                            }
-                        };
-                    }
+                "isready" => println!("readyok"),
+                // the opponent played the expected move, so the search carries on against the
+                // clock
+                "ponderhit" => {
+                    pondering.store(false, Ordering::Relaxed);
+                    worker.unpark();
                 }
-                "winc" => {
-                    if self.search.board.side_to_move() == Side::White {
-                        increment = match args.next() {
-                            Some(time) => time.parse().unwrap_or(0),
-                            None => {
-                                println!("missing winc value");
-                                return;
-                            }
-                        }
-                    }
+                // the search still has to end before the engine can, but nothing is waiting for
+                // its move any more
+                "quit" => {
+                    quitting.store(true, Ordering::Relaxed);
+                    stop_signal.store(true, Ordering::Relaxed);
+                    return AfterWorker::Quit(UciExit::Quit);
                 }
-                "binc" => {
-                    if self.search.board.side_to_move() == Side::Black {
-                        increment = match args.next() {
-                            Some(time) => time.parse().unwrap_or(0),
-                            None => {
-                                println!("missing binc value");
-                                return;
-                            }
-                        }
+                _ => return AfterWorker::HandleLine,
+            }
+        }
+    }
+
+    // bench [depth] (non-standard). the results are `info string`s so a GUI that sends it by
+    // mistake isn't thrown by them
+    fn handle_bench_command(
+        &mut self,
+        depth: u8,
+        input: &mut impl BufRead,
+        input_buffer: &mut String,
+    ) -> AfterWorker {
+        let after = self.run_slow_task(
+            Box::new(move |search| match run_bench(search, depth) {
+                Ok(result) => {
+                    for (index, nodes) in result.position_nodes.iter().enumerate() {
+                        println!("info string bench position {} nodes {}", index + 1, nodes);
                     }
+
+                    let elapsed_ms = result.elapsed.as_millis().max(1);
+                    println!(
+                        "info string bench nodes {} time {} nps {}",
+                        result.nodes,
+                        elapsed_ms,
+                        (result.nodes as u128 * 1000) / elapsed_ms
+                    );
                 }
-                "movestogo" => {
-                    moves_to_go = match args.next() {
-                        Some(moves) => moves.parse().ok(),
-                        None => {
-                            println!("missing movestogo values");
-                            return;
-                        }
+                Err(error) => println!("info string bench failed: {:#}", error),
+            }),
+            input,
+            input_buffer,
+        );
+
+        // the last bench position is still loaded, so the GUI's is set up again
+        self.search.reset();
+        let position_args = self.position_args.clone();
+        if let Err(error) = self.set_position(&position_args) {
+            println!("info string failed to restore the position: {:#}", error);
+        }
+
+        after
+    }
+
+    // like a search, the task runs on a thread of its own while `isready` and `debug` are still
+    // handled. any other command waits for it to finish
+    fn run_slow_task(
+        &mut self,
+        task: SlowTask,
+        input: &mut impl BufRead,
+        input_buffer: &mut String,
+    ) -> AfterWorker {
+        let pending_ready = Mutex::new(PendingReady::default());
+        let debug = Arc::clone(&self.debug);
+
+        thread::scope(|scope| {
+            let worker = scope.spawn(|| {
+                task(self.search);
+
+                let mut pending_ready = pending_ready.lock().unwrap();
+                pending_ready.task_done = true;
+
+                for _ in 0..pending_ready.count {
+                    println!("readyok");
+                }
+            });
+
+            let after_task = Self::listen_while_busy(input, input_buffer, &pending_ready, &debug);
+
+            if let Err(payload) = worker.join() {
+                panic::resume_unwind(payload);
+            }
+
+            after_task
+        })
+    }
+
+    fn listen_while_busy(
+        input: &mut impl BufRead,
+        input_buffer: &mut String,
+        pending_ready: &Mutex<PendingReady>,
+        debug: &AtomicBool,
+    ) -> AfterWorker {
+        loop {
+            match read_line(input, input_buffer) {
+                Ok(InputLine::Line) => (),
+                Ok(InputLine::TooLong) => {
+                    println!("info string input line too long, ignoring it");
+                    continue;
+                }
+                Ok(InputLine::Eof) | Err(_) => return AfterWorker::Quit(UciExit::InputClosed),
+            }
+
+            let line = input_buffer.trim();
+
+            if let Some(args) = line.strip_prefix("debug") {
+                if args.is_empty() || args.starts_with(' ') {
+                    set_debug(debug, args.trim());
+                    continue;
+                }
+            }
+
+            match line {
+                "isready" => {
+                    let mut pending_ready = pending_ready.lock().unwrap();
+
+                    if pending_ready.task_done {
+                        println!("readyok");
+                    } else {
+                        pending_ready.count += 1;
                     }
                 }
-                _ => (),
+                // there's no stopping it part of the way through, so this has to wait too
+                "quit" => return AfterWorker::Quit(UciExit::Quit),
+                _ => return AfterWorker::HandleLine,
             }
         }
+    }
 
-        self.search.max_depth = max_depth.into();
+    fn search_and_report(
+        &mut self,
+        limits: &SearchLimits,
+        stop_signal: &AtomicBool,
+        pondering: &AtomicBool,
+        quitting: &AtomicBool,
+    ) {
+        let mut uci_reporter = UciReporter::new(self.minimal_output);
+        uci_reporter.show_root_moves = self.show_root_moves;
+        uci_reporter.debug = Arc::clone(&self.debug);
 
-        self.search
-            .timer
-            .initialize(time_remaining, increment, moves_to_go);
+        // taken out for the duration of the search so the reporter can borrow it
+        let mut search_log = self.search_log.take();
+        let mut logging_reporter;
 
-        self.search.search_info.nodes_searched = 0;
+        let reporter: &mut dyn SearchReporter = match &mut search_log {
+            Some(log) => {
+                let fen = self.search.board.fen();
+                logging_reporter = LoggingReporter::new(&mut uci_reporter, log, fen);
+                &mut logging_reporter
+            }
+            None => &mut uci_reporter,
+        };
 
-        self.search.timer.start();
+        // a bug in the search shouldn't forfeit the game, so recover and play any legal move
+        let search_result = panic::catch_unwind(AssertUnwindSafe(|| {
+            self.search.search_position(limits, reporter)
+        }));
 
-        let best_move = match self.search.search_position() {
-            Ok(mv) => mv,
-            Err(error) => {
-                println!("{}", error);
-                return;
+        let best_move = match search_result {
+            Ok(Ok(mv)) => mv,
+            // the GUI still needs a `bestmove`, even if there was nothing to search
+            Ok(Err(error)) => {
+                println!("info string search failed: {:#}", error);
+                Move::NULL_MOVE
             }
+            Err(payload) => self.recover_from_panic(payload.as_ref()),
         };
 
-        println!("bestmove {}", best_move);
+        // `go infinite` only gets a `bestmove` once the GUI sends `stop`, and `go ponder` once it
+        // sends `stop` or `ponderhit`, even when the search runs out of depth before then
+        while !stop_signal.load(Ordering::Relaxed)
+            && (limits.infinite || pondering.load(Ordering::Relaxed))
+        {
+            // the input loop unparks the search thread after changing either signal
+            thread::park();
+        }
+
+        // the GUI has already moved on after `quit`, so a `bestmove` would only be noise
+        if !quitting.load(Ordering::Relaxed) {
+            let ponder_move = self.search.ponder_move(best_move);
+            reporter.best_move(best_move, ponder_move);
+        }
+
+        self.search_log = search_log;
+    }
+
+    fn recover_from_panic(&mut self, payload: &(dyn Any + Send)) -> Move {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            String::from("unknown panic")
+        };
+
+        println!("info string PANIC: {}", message);
+
+        if let Err(error) = self.write_crash_log(&message) {
+            println!("info string failed to write crash log: {}", error);
+        }
+
+        // the board could have been left in the middle of making a move, so rebuild everything
+        // from scratch
+        self.search.reset();
+        let position_args = self.position_args.clone();
+
+        if let Err(error) = self.set_position(&position_args) {
+            println!("info string failed to restore the position: {:#}", error);
+        }
+
+        match self.search.board.first_legal_move() {
+            Ok(Some(mv)) => mv,
+            _ => Move::NULL_MOVE,
+        }
+    }
+
+    fn write_crash_log(&self, message: &str) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(CRASH_LOG_PATH)?;
+
+        writeln!(file, "panic: {}", message)?;
+        // where the search had got to, which may be partway through making a move
+        writeln!(file, "fen {}", self.search.board.fen())?;
+        writeln!(file, "position {}", self.position_args)?;
+        writeln!(file)?;
+
+        Ok(())
+    }
+}
+
+impl Shutdown for Uci<'_> {
+    fn shutdown(&mut self) {
+        // searches are always waited for before getting here, so nothing is still writing to the
+        // log
+        self.search.reset();
+
+        if let Some(log) = &mut self.search_log {
+            log.flush();
+        }
     }
 }
 
+// the moves `args` plays on top of the position set up by `previous`, if it starts with the same
+// position and moves
+fn moves_after<'a>(previous: &str, args: &'a str) -> Option<Vec<&'a str>> {
+    let mut tokens = args.split_whitespace();
+
+    for previous_token in previous.split_whitespace() {
+        if tokens.next() != Some(previous_token) {
+            return None;
+        }
+    }
+
+    let rest: Vec<_> = tokens.collect();
+
+    if previous.split_whitespace().any(|token| token == "moves") {
+        return Some(rest);
+    }
+
+    match rest.split_first() {
+        None => Some(rest),
+        Some((&"moves", moves)) => Some(moves.to_vec()),
+        Some(_) => None,
+    }
+}
+
+/// what's left of `args` after `word`, which has to be all of the first word, e.g. `current` in
+/// `current moves e2e4` but not in `currently`
+pub(crate) fn strip_word<'a>(args: &'a str, word: &str) -> Option<&'a str> {
+    let rest = args.trim_start().strip_prefix(word)?;
+
+    match rest.chars().next() {
+        None => Some(rest),
+        Some(next) if next.is_whitespace() => Some(rest.trim_start()),
+        Some(_) => None,
+    }
+}
+
+// sets the position up from scratch, replaying every move so each one is in the history
+fn set_up_position(board: &mut Board, args: &str) -> anyhow::Result<()> {
+    let position_kind = args
+        .split_whitespace()
+        .next()
+        .context("invalid `position` command")?;
+
+    let moves_start_index = args.find("moves");
+
+    let fen = match position_kind {
+        "startpos" => START_POSITION_FEN,
+        "fen" => {
+            let fen_start_index = "fen".len() + 1;
+            let fen_end_index = moves_start_index.map_or(args.len(), |index| index - 1);
+
+            // `position fen` with nothing after it
+            args.get(fen_start_index..fen_end_index)
+                .context("invalid `position` command")?
+        }
+        _ => bail!("invalid `position` command"),
+    };
+
+    apply_fen(board, fen)?;
+
+    if let Some(index) = moves_start_index {
+        let start_index = index + "moves ".len();
+        let moves: Vec<_> = args[start_index..].split_whitespace().collect();
+        play_position_moves(board, &moves)?;
+    }
+
+    Ok(())
+}
+
+// an error says which move in the list it was
+fn play_position_moves(board: &mut Board, moves: &[&str]) -> anyhow::Result<()> {
+    let played = apply_moves(board, moves).map_err(|error| match error.move_index() {
+        Some(index) => anyhow::Error::new(error)
+            .context(format!("move {} of the `position` command", index + 1)),
+        None => error.into(),
+    })?;
+
+    // the GUI should know the engine filled in a piece it left off
+    for (mv, move_str) in assumed_promotions(&played, moves) {
+        println!("info string assuming {} for {}", mv, move_str);
+    }
+
+    Ok(())
+}
+
+// debug [ on | off ]
+fn set_debug(debug: &AtomicBool, args: &str) {
+    match args {
+        "on" => debug.store(true, Ordering::Relaxed),
+        "off" => debug.store(false, Ordering::Relaxed),
+        _ => println!("info string invalid `debug` command, expected `on` or `off`"),
+    }
+}
+
+// bench [depth]
+fn parse_bench_depth(args: &str) -> anyhow::Result<u8> {
+    if args.is_empty() {
+        return Ok(BENCH_DEPTH);
+    }
+
+    args.parse().context("bench depth must be an integer")
+}
+
+// everything `go` can be followed by, which also marks the end of a `searchmoves` list
+const GO_KEYWORDS: [&str; 12] = [
+    "searchmoves",
+    "ponder",
+    "wtime",
+    "btime",
+    "winc",
+    "binc",
+    "movestogo",
+    "depth",
+    "nodes",
+    "mate",
+    "movetime",
+    "infinite",
+];
+
+/// the limits of a `go` command, for a search of `board`. only the clock of the side to move is
+/// kept, and `searchmoves` that aren't legal moves in the position are left out with a warning
+pub fn parse_go_args(args: &str, board: &mut Board) -> anyhow::Result<SearchLimits> {
+    let side = board.side_to_move();
+    let mut args = args.split_whitespace().peekable();
+    let mut limits = SearchLimits::default();
+    let time_controls = &mut limits.time_controls;
+
+    while let Some(arg) = args.next() {
+        // the other side's clock is left unread, and its value skipped over like any other word
+        // that isn't a limit
+        #[allow(clippy::collapsible_match)]
+        match arg {
+            "depth" => {
+                let value = args.next();
+                let depth = SearchDepth::try_from(value)?.into();
+
+                // both searches still come up with a move, they just aren't the depth asked for
+                if depth == 0 {
+                    println!("info string depth 0 searches to depth 1");
+                } else if depth > SearchDepth::MAX {
+                    println!(
+                        "info string depth {} is beyond the deepest search, searching to depth {}",
+                        value.unwrap_or_default(),
+                        SearchDepth::MAX
+                    );
+                }
+
+                limits.depth = Some(depth);
+            }
+            "nodes" => {
+                let nodes = args.next().context("missing nodes value")?;
+                limits.nodes = Some(nodes.parse().context("invalid nodes value")?);
+            }
+            "movetime" => {
+                let movetime = args.next().context("missing movetime value")?;
+                limits.movetime = Some(movetime.parse().context("invalid movetime value")?);
+            }
+            "mate" => {
+                let mate = args.next().context("missing mate value")?;
+                limits.mate = Some(mate.parse().context("invalid mate value")?);
+            }
+            "infinite" => limits.infinite = true,
+            "ponder" => limits.ponder = true,
+            "wtime" => {
+                if side == Side::White {
+                    let time = args.next().context("missing wtime value")?;
+                    time_controls.time_remaining = time.parse().ok();
+                }
+            }
+            "btime" => {
+                if side == Side::Black {
+                    let time = args.next().context("missing btime value")?;
+                    time_controls.time_remaining = time.parse().ok();
+                }
+            }
+            "winc" => {
+                if side == Side::White {
+                    let time = args.next().context("missing winc value")?;
+                    time_controls.increment = time.parse().unwrap_or(0);
+                }
+            }
+            "binc" => {
+                if side == Side::Black {
+                    let time = args.next().context("missing binc value")?;
+                    time_controls.increment = time.parse().unwrap_or(0);
+                }
+            }
+            "movestogo" => {
+                let moves = args.next().context("missing movestogo values")?;
+                time_controls.moves_to_go = moves.parse().ok();
+            }
+            "searchmoves" => {
+                let mut listed = false;
+
+                while let Some(move_str) = args.next_if(|arg| !GO_KEYWORDS.contains(arg)) {
+                    listed = true;
+
+                    let mv = match board.get_move_metadata(move_str) {
+                        Ok(metadata) => board.find_legal_move(metadata)?,
+                        Err(_) => None,
+                    };
+
+                    match mv {
+                        Some(mv) => limits.searchmoves.push(mv),
+                        None => println!("info string ignoring searchmoves {}", move_str),
+                    }
+                }
+
+                // an empty list searches everything, rather than nothing
+                if listed && limits.searchmoves.is_empty() {
+                    println!("info string no legal searchmoves, searching every move");
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Ok(limits)
+}
+
 impl TryFrom<Option<&str>> for SearchDepth {
     type Error = anyhow::Error;
 
+    // anything too deep to fit is as good as the deepest search, which it gets clamped to anyway
     fn try_from(depth: Option<&str>) -> Result<Self, Self::Error> {
-        if let Some(depth) = depth {
-            let depth = depth.parse().context("invalid depth provided")?;
-            Ok(SearchDepth::Finite(depth))
-        } else {
+        let Some(depth) = depth else {
             bail!("no depth provided")
+        };
+
+        let plies: i128 = depth
+            .parse()
+            .with_context(|| format!("invalid depth `{}`", depth))?;
+
+        if plies < 0 {
+            bail!("depth can't be negative, got {}", plies);
         }
+
+        Ok(SearchDepth::Finite(plies.min(u8::MAX as i128) as u8))
     }
 }
@@ -1,23 +1,39 @@
 use crate::{
     board::{Board, CastlingRights, Side},
+    move_generator::Move,
     prng::Prng,
-    square::{Piece, PieceKind, Square},
+    square::{Piece, PieceKind, Square, PIECE_COUNT},
 };
 
-const SIDE_OFFSET: usize = 768; // 12 * 64 pieces before it
+const SIDE_OFFSET: usize = PIECE_COUNT * 64; // every piece on every square before it
 const CASTLE_OFFSET: usize = 769; // + 1 bit for side
 const EN_PASSANT_OFFSET: usize = 785; // + 16 bits for castling
+const CASTLED_OFFSET: usize = 794; // + 9 bits for en passant files
 
-const ZOBRIST_NUMBERS_SIZE: usize = 794; // + 9 bits for en passant files
+const ZOBRIST_NUMBERS_SIZE: usize = 796; // + 2 bits for sides that have castled
 
 const INVALID_EP_SQUARE: usize = 8;
 
-const fn init_zobrist_en_passant_files() -> [usize; 65] {
-    let mut files = [0; 65];
+// mixed with a move's bits to seed its excluded move key
+const EXCLUDED_MOVE_SEED: u64 = 0x5851_F42D_4C95_7F2D;
+
+/// XORed into the hash of a position searched with `mv` left out, so that search gets a table
+/// entry of its own rather than replacing the entry of the full search. the same for every board,
+/// since it only needs to tell the two searches apart
+pub fn excluded_move_key(mv: Move) -> u64 {
+    Prng::new(mv.bits() as u64 ^ EXCLUDED_MOVE_SEED).random_u64()
+}
+
+/// XORed into the hash of every position searched for black when draws aren't scored as even,
+/// since the score of a draw then depends on which side the engine is playing
+pub const DRAW_PERSPECTIVE_KEY: u64 = 0x9E37_79B9_7F4A_7C15;
+
+const fn init_zobrist_en_passant_files() -> [usize; 64] {
+    let mut files = [0; 64];
 
     let mut square = 0;
 
-    while square < 65 {
+    while square < 64 {
         let rank = square / 8;
 
         if rank == 2 || rank == 5 {
@@ -33,8 +49,9 @@ const fn init_zobrist_en_passant_files() -> [usize; 65] {
     files
 }
 
-const ZOBRIST_EN_PASSANT_FILES: [usize; 65] = init_zobrist_en_passant_files();
+const ZOBRIST_EN_PASSANT_FILES: [usize; 64] = init_zobrist_en_passant_files();
 
+#[derive(Clone)]
 pub struct ZobristHasher {
     numbers: [u64; ZOBRIST_NUMBERS_SIZE],
 }
@@ -44,6 +61,8 @@ pub enum ZobristKey {
     Side,
     Castling(CastlingRights),
     EnPassantFile(Square),
+    // the evaluation gives a bonus for having castled, so it has to be part of the position
+    Castled(Side),
 }
 
 impl ZobristHasher {
@@ -64,6 +83,12 @@ impl ZobristHasher {
 
         hash ^= self.get_key_part(ZobristKey::EnPassantFile(board.en_passant_square()));
 
+        for side in [Side::White, Side::Black] {
+            if board.has_castled(side) {
+                hash ^= self.get_key_part(ZobristKey::Castled(side));
+            }
+        }
+
         hash
     }
 
@@ -74,6 +99,9 @@ impl ZobristHasher {
             ZobristKey::Castling(castling_rights) => {
                 self.numbers[CASTLE_OFFSET + (castling_rights as usize)]
             }
+            // no en passant square contributes nothing, so a position's hash is the same whether
+            // or not the square was ever hashed in and out
+            ZobristKey::EnPassantFile(Square::None) => 0,
             ZobristKey::EnPassantFile(square) => {
                 let file_offset = ZOBRIST_EN_PASSANT_FILES[square as usize];
                 // we have values for all squares in a table for lookup speed. however, if the
@@ -86,14 +114,22 @@ impl ZobristHasher {
                     self.numbers[EN_PASSANT_OFFSET + file_offset]
                 }
             }
+            ZobristKey::Castled(side) => self.numbers[CASTLED_OFFSET + side.index()],
         }
     }
 
+    // folds every key into a single number so that anything persisted alongside hashes (e.g. a
+    // saved transposition table) can check it was produced with the same keys
+    pub fn checksum(&self) -> u64 {
+        self.numbers
+            .iter()
+            .fold(0u64, |checksum, number| checksum.rotate_left(1) ^ number)
+    }
+
     fn index_piece(&self, piece: Piece, square: Square) -> u64 {
         assert!(piece.kind != PieceKind::NoPiece);
 
-        let piece_offset = 6 * (piece.color as usize) + (piece.kind as usize);
-        self.numbers[piece_offset + (square.index() * 12)]
+        self.numbers[piece.index() + (square.index() * PIECE_COUNT)]
     }
 }
 
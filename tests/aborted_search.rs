@@ -0,0 +1,68 @@
+// a search that's stopped unwinds with made-up scores. none of them may end up in the
+// transposition table or the move ordering tables, or the next search from the same position
+// would trust them
+
+mod common;
+
+use krusty::{move_generator::Move, search::Search, search_limits::SearchLimits};
+
+use common::RecordingReporter;
+
+const FENS: [&str; 3] = [
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+];
+
+// the first search finishes this many iterations, then gets stopped early on in the next one
+const FINISHED_ITERATIONS: u8 = 4;
+const FOLLOW_UP_DEPTH: u8 = 5;
+
+// few enough nodes into the unfinished iteration that none of them has been fully searched, so
+// there's nothing from it worth keeping
+const NODES_INTO_ITERATION: [u64; 3] = [2, 3, 4];
+
+// (best move, score, nodes)
+fn run(search: &mut Search, limits: &SearchLimits) -> (Move, Option<i32>, u64) {
+    let mut reporter = RecordingReporter::default();
+    let best_move = search.search_position(limits, &mut reporter).unwrap();
+
+    (best_move, reporter.score(), reporter.nodes())
+}
+
+fn new_search(fen: &str) -> Search {
+    let mut search = Search::default();
+    search.board.parse_fen(fen).unwrap();
+    search.set_seed(Some(1));
+    search
+}
+
+#[test]
+fn stopped_searches_leave_nothing_behind() {
+    for fen in FENS {
+        let finished_limits = SearchLimits::depth(FINISHED_ITERATIONS);
+        let follow_up_limits = SearchLimits::depth(FOLLOW_UP_DEPTH);
+
+        // the same iterations, but finished cleanly
+        let mut finished = new_search(fen);
+        let (_, _, finished_nodes) = run(&mut finished, &finished_limits);
+        let expected = run(&mut finished, &follow_up_limits);
+
+        for extra_nodes in NODES_INTO_ITERATION {
+            let mut stopped = new_search(fen);
+            run(
+                &mut stopped,
+                &SearchLimits::nodes(finished_nodes + extra_nodes),
+            );
+            assert!(stopped.timer.is_stopped());
+
+            assert_eq!(
+                run(&mut stopped, &follow_up_limits),
+                expected,
+                "{} stopped {} nodes into an iteration",
+                fen,
+                extra_nodes
+            );
+        }
+    }
+}
@@ -0,0 +1,276 @@
+// the attack tables against walking the rays square by square, which is slow but hard to get wrong
+
+use krusty::{
+    attacks,
+    bitboard::Bitboard,
+    board::{Board, Side, START_POSITION_FEN},
+    move_generator::MoveList,
+    prng::Prng,
+    square::{Piece, PieceKind, Square},
+};
+
+const OCCUPANCIES_PER_SQUARE: usize = 200;
+
+const KNIGHT_JUMPS: [(i32, i32); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+const STRAIGHT: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+const DIAGONAL: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+// (file, rank), counting from 0
+fn coordinates(square: usize) -> (i32, i32) {
+    ((square % 8) as i32, (square / 8) as i32)
+}
+
+fn on_board(file: i32, rank: i32) -> bool {
+    (0..8).contains(&file) && (0..8).contains(&rank)
+}
+
+// every square reached by going up to `max_steps` in each direction, stopping at the first
+// occupied square
+fn walk(square: usize, occupancy: u64, directions: &[(i32, i32)], max_steps: usize) -> u64 {
+    let mut reached = 0;
+
+    for (file_step, rank_step) in directions {
+        let (mut file, mut rank) = coordinates(square);
+
+        for _ in 0..max_steps {
+            file += file_step;
+            rank += rank_step;
+
+            if !on_board(file, rank) {
+                break;
+            }
+
+            let bit = 1 << (rank * 8 + file);
+            reached |= bit;
+
+            if occupancy & bit != 0 {
+                break;
+            }
+        }
+    }
+
+    reached
+}
+
+fn between(a: usize, b: usize) -> u64 {
+    let (a_file, a_rank) = coordinates(a);
+    let (b_file, b_rank) = coordinates(b);
+
+    let file_distance = b_file - a_file;
+    let rank_distance = b_rank - a_rank;
+
+    let is_aligned = a != b
+        && (file_distance == 0 || rank_distance == 0 || file_distance.abs() == rank_distance.abs());
+
+    if !is_aligned {
+        return 0;
+    }
+
+    let steps = file_distance.abs().max(rank_distance.abs());
+    let (file_step, rank_step) = (file_distance.signum(), rank_distance.signum());
+
+    (1..steps).fold(0, |squares, step| {
+        squares | 1 << ((a_rank + rank_step * step) * 8 + a_file + file_step * step)
+    })
+}
+
+fn random_occupancies(prng: &mut Prng) -> Vec<u64> {
+    let mut occupancies = vec![0, u64::MAX];
+
+    // a mix of crowded and sparse boards
+    while occupancies.len() < OCCUPANCIES_PER_SQUARE {
+        occupancies.push(prng.random_u64());
+        occupancies.push(prng.sparse_random_u64());
+    }
+
+    occupancies
+}
+
+#[test]
+fn leapers_match_single_steps() {
+    for index in 0..64 {
+        let square = Square::from(index);
+
+        assert_eq!(
+            attacks::pawn(Side::White, square),
+            Bitboard(walk(index, 0, &[(1, 1), (-1, 1)], 1)),
+            "white pawn on {:?}",
+            square
+        );
+        assert_eq!(
+            attacks::pawn(Side::Black, square),
+            Bitboard(walk(index, 0, &[(1, -1), (-1, -1)], 1)),
+            "black pawn on {:?}",
+            square
+        );
+        assert_eq!(
+            attacks::knight(square),
+            Bitboard(walk(index, 0, &KNIGHT_JUMPS, 1)),
+            "knight on {:?}",
+            square
+        );
+        assert_eq!(
+            attacks::king(square),
+            Bitboard(walk(index, 0, &[STRAIGHT, DIAGONAL].concat(), 1)),
+            "king on {:?}",
+            square
+        );
+    }
+}
+
+#[test]
+fn pawn_sets_match_single_pawns() {
+    let mut prng = Prng::new(2262);
+
+    for _ in 0..OCCUPANCIES_PER_SQUARE {
+        let pawns = prng.random_u64();
+
+        for side in [Side::White, Side::Black] {
+            let one_at_a_time = (0..64usize)
+                .filter(|index| pawns & 1 << index != 0)
+                .fold(Bitboard(0), |attacked, index| {
+                    attacked | attacks::pawn(side, Square::from(index))
+                });
+
+            assert_eq!(attacks::pawns(side, Bitboard(pawns)), one_at_a_time);
+        }
+    }
+}
+
+#[test]
+fn sliders_match_walking_the_rays() {
+    let mut prng = Prng::new(2262);
+
+    for index in 0..64 {
+        let square = Square::from(index);
+
+        for occupancy in random_occupancies(&mut prng) {
+            let bishop = walk(index, occupancy, &DIAGONAL, 8);
+            let rook = walk(index, occupancy, &STRAIGHT, 8);
+
+            assert_eq!(
+                attacks::bishop(square, Bitboard(occupancy)),
+                Bitboard(bishop),
+                "bishop on {:?} with {:#018x}",
+                square,
+                occupancy
+            );
+            assert_eq!(
+                attacks::rook(square, Bitboard(occupancy)),
+                Bitboard(rook),
+                "rook on {:?} with {:#018x}",
+                square,
+                occupancy
+            );
+            assert_eq!(
+                attacks::queen(square, Bitboard(occupancy)),
+                Bitboard(bishop | rook),
+                "queen on {:?} with {:#018x}",
+                square,
+                occupancy
+            );
+        }
+    }
+}
+
+#[test]
+fn between_matches_walking_the_line() {
+    for a in 0..64 {
+        for b in 0..64 {
+            assert_eq!(
+                attacks::between(Square::from(a), Square::from(b)),
+                Bitboard(between(a, b)),
+                "between {:?} and {:?}",
+                Square::from(a),
+                Square::from(b)
+            );
+        }
+    }
+
+    // symmetric, and empty for neighbours and squares a knight's jump apart
+    assert_eq!(
+        attacks::between(Square::E1, Square::E8),
+        attacks::between(Square::E8, Square::E1)
+    );
+    assert_eq!(attacks::between(Square::E1, Square::E8).count_ones(), 6);
+    assert_eq!(attacks::between(Square::E1, Square::E2), Bitboard(0));
+    assert_eq!(attacks::between(Square::E1, Square::F3), Bitboard(0));
+}
+
+// the board's pawn and knight helpers, at every position of a few random games
+#[test]
+fn board_attack_helpers_match_single_pieces() {
+    let mut prng = Prng::new(2223);
+
+    for _ in 0..20 {
+        let mut board = Board::default();
+        board.parse_fen(START_POSITION_FEN).unwrap();
+
+        for _ in 0..80 {
+            for side in [Side::White, Side::Black] {
+                let pawns = board
+                    .get_piece_bb(Piece::new(side.into(), PieceKind::Pawn))
+                    .unwrap();
+                let knights = board
+                    .get_piece_bb(Piece::new(side.into(), PieceKind::Knight))
+                    .unwrap();
+
+                let attacked_by = |pieces: Bitboard, attacks: &dyn Fn(Square) -> Bitboard| {
+                    (0..64usize)
+                        .filter(|index| pieces.0 & 1 << index != 0)
+                        .fold(Bitboard(0), |attacked, index| {
+                            attacked | attacks(Square::from(index))
+                        })
+                };
+
+                let pawn_attacks = attacked_by(pawns, &|square| attacks::pawn(side, square));
+                let knight_attacks = attacked_by(knights, &attacks::knight);
+                assert_eq!(board.pawn_attacks_bb(side), pawn_attacks, "{}", board.fen());
+
+                for index in 0..64usize {
+                    let square = Square::from(index);
+
+                    assert_eq!(
+                        board.is_attacked_by_pawn(square, side),
+                        pawn_attacks.0 & 1 << index != 0,
+                        "{:?} in {}",
+                        square,
+                        board.fen()
+                    );
+                    assert_eq!(
+                        board.is_attacked_by_knight(square, side),
+                        knight_attacks.0 & 1 << index != 0,
+                        "{:?} in {}",
+                        square,
+                        board.fen()
+                    );
+                }
+            }
+
+            let mut moves = MoveList::new();
+            board.generate_all_moves(&mut moves).unwrap();
+
+            let legal: Vec<_> = (0..moves.length())
+                .map(|index| moves.get(index))
+                .filter(|&mv| board.clone().make_move(mv).unwrap())
+                .collect();
+
+            if legal.is_empty() {
+                break;
+            }
+
+            let mv = legal[prng.random_u64() as usize % legal.len()];
+            board.make_move(mv).unwrap();
+        }
+    }
+}
@@ -0,0 +1,165 @@
+// `bench` searches a fixed set of positions to a fixed depth. its total node count is the
+// signature of the search, so it has to come out the same every time and from every way in
+
+mod common;
+
+use std::process::Command;
+
+use common::{assert_uci_output, start_uci};
+use krusty::{
+    bench::{run_bench, BENCH_FENS},
+    search::{OrderingStats, Search},
+};
+
+const DEPTH: u8 = 2;
+
+// black's king has one way out of check
+const ONE_MOVE_FEN: &str = "k7/8/8/8/8/8/1R6/KR6 b - - 0 1";
+
+fn nodes_line(output: &str) -> u64 {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("Nodes: "))
+        .expect("no node count")
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn bench_nodes_are_the_same_every_time() {
+    let mut search = Search::default();
+
+    let first = run_bench(&mut search, DEPTH).unwrap();
+    let second = run_bench(&mut search, DEPTH).unwrap();
+
+    assert_eq!(first.position_nodes.len(), BENCH_FENS.len());
+    assert_eq!(first.position_nodes.iter().sum::<u64>(), first.nodes);
+    assert!(first.position_nodes.iter().all(|&nodes| nodes > 0));
+
+    assert_eq!(first.nodes, second.nodes);
+    assert_eq!(first.position_nodes, second.position_nodes);
+}
+
+#[test]
+fn a_deeper_bench_searches_more() {
+    let mut search = Search::default();
+
+    let shallow = run_bench(&mut search, DEPTH - 1).unwrap();
+    let deep = run_bench(&mut search, DEPTH).unwrap();
+
+    assert!(deep.nodes > shallow.nodes);
+}
+
+#[test]
+fn a_bench_needs_some_depth() {
+    assert!(run_bench(&mut Search::default(), 0).is_err());
+}
+
+// a good ordering finds the cutoff with the first move nearly every time, so anything much worse
+// is a regression rather than noise
+const MIN_FIRST_MOVE_CUTOFF_RATE: f64 = 0.7;
+
+fn assert_sane_ordering(ordering: &OrderingStats) {
+    assert!(ordering.beta_cutoffs > 0);
+    assert!(ordering.first_move_cutoffs <= ordering.beta_cutoffs);
+    assert!(ordering.tt_move_cutoffs <= ordering.beta_cutoffs);
+
+    // every cutoff that wasn't on the first move was on the second or later
+    assert!(ordering.cutoff_index_total >= ordering.beta_cutoffs - ordering.first_move_cutoffs);
+
+    assert!(
+        ordering.first_move_cutoff_rate() > MIN_FIRST_MOVE_CUTOFF_RATE,
+        "{:?}",
+        ordering
+    );
+    assert!(ordering.average_cutoff_index() < 1.0, "{:?}", ordering);
+    assert!(
+        ordering.tt_move_rate() > 0.0 && ordering.tt_move_rate() < 1.0,
+        "{:?}",
+        ordering
+    );
+}
+
+#[test]
+fn moves_are_ordered_well_enough() {
+    let result = run_bench(&mut Search::default(), 4).unwrap();
+
+    assert_sane_ordering(&result.ordering);
+}
+
+// a couple of minutes without optimizations, so only run by `cargo test --release`
+#[test]
+#[cfg_attr(debug_assertions, ignore)]
+fn moves_are_ordered_well_enough_at_depth_8() {
+    let result = run_bench(&mut Search::default(), 8).unwrap();
+
+    assert_sane_ordering(&result.ordering);
+}
+
+#[test]
+fn bench_runs_from_the_command_line() {
+    let output = Command::new(env!("CARGO_BIN_EXE_krusty"))
+        .args(["bench", &DEPTH.to_string()])
+        .output()
+        .expect("failed to start the engine");
+
+    assert!(output.status.success());
+
+    let output = String::from_utf8(output.stdout).unwrap();
+    let positions = output
+        .lines()
+        .filter(|line| line.starts_with("Position "))
+        .count();
+    assert_eq!(positions, BENCH_FENS.len());
+    assert!(output.lines().any(|line| line.starts_with("NPS: ")));
+
+    for metric in [
+        "- first move cutoffs: ",
+        "- average cutoff move index: ",
+        "- TT move available at cutoffs: ",
+    ] {
+        assert!(
+            output.lines().any(|line| line.starts_with(metric)),
+            "{}",
+            metric
+        );
+    }
+
+    let expected = run_bench(&mut Search::default(), DEPTH).unwrap();
+    assert_eq!(nodes_line(&output), expected.nodes);
+}
+
+#[test]
+fn bench_runs_over_uci_and_keeps_the_position() {
+    let mut engine = start_uci();
+
+    engine.send(&format!("position fen {}", ONE_MOVE_FEN));
+    engine.send(&format!("bench {}", DEPTH));
+    engine.send("isready");
+
+    let output = engine.read_until(|line| line == "readyok");
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    let expected = run_bench(&mut Search::default(), DEPTH).unwrap();
+    let positions = output
+        .iter()
+        .filter(|line| line.starts_with("info string bench position "))
+        .count();
+    assert_eq!(positions, BENCH_FENS.len());
+    assert!(output
+        .iter()
+        .any(|line| line.starts_with(&format!("info string bench nodes {} ", expected.nodes))));
+
+    engine.send("go depth 2");
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    assert!(output.last().unwrap().starts_with("bestmove a8a7"));
+
+    engine.send("bench deep");
+    engine.send("isready");
+    assert_eq!(
+        engine.read_until(|line| line == "readyok"),
+        ["info string bench depth must be an integer", "readyok"]
+    );
+
+    assert!(engine.quit().success());
+}
@@ -0,0 +1,67 @@
+// the `print` command's output, which bug reports get copied from
+
+use krusty::board::{Board, START_POSITION_FEN};
+
+fn print(fen: &str) -> String {
+    let mut board = Board::default();
+    board.parse_fen(fen).unwrap();
+    board.to_string()
+}
+
+#[test]
+fn start_position() {
+    let expected = concat!(
+        "  8  ♜  ♞  ♝  ♛  ♚  ♝  ♞  ♜\n",
+        "  7  ♟  ♟  ♟  ♟  ♟  ♟  ♟  ♟\n",
+        "  6  0  0  0  0  0  0  0  0\n",
+        "  5  0  0  0  0  0  0  0  0\n",
+        "  4  0  0  0  0  0  0  0  0\n",
+        "  3  0  0  0  0  0  0  0  0\n",
+        "  2  ♙  ♙  ♙  ♙  ♙  ♙  ♙  ♙\n",
+        "  1  ♖  ♘  ♗  ♕  ♔  ♗  ♘  ♖\n",
+        "     a  b  c  d  e  f  g  h\n",
+        "\n",
+        "Side to play: White\n",
+        "White king castle: yes\n",
+        "White queen castle: yes\n",
+        "Black king castle: yes\n",
+        "Black queen castle: yes\n",
+        "Halfmove clock: 0\n",
+        "En passant square: None\n",
+        "\n",
+        "FEN: rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\n",
+        "Hash: 0x6702662ed3ff1f0d\n",
+        "Checkers: none\n",
+    );
+
+    assert_eq!(print(START_POSITION_FEN), expected);
+}
+
+#[test]
+fn double_check_lists_both_checkers() {
+    let expected = concat!(
+        "  8  0  0  0  0  ♚  0  0  0\n",
+        "  7  0  0  0  0  0  0  0  0\n",
+        "  6  0  0  0  0  0  0  0  0\n",
+        "  5  0  ♗  0  0  0  0  0  0\n",
+        "  4  0  0  0  0  0  0  0  0\n",
+        "  3  0  0  0  0  0  0  0  0\n",
+        "  2  0  0  0  0  0  0  0  0\n",
+        "  1  0  0  0  0  ♖  0  ♔  0\n",
+        "     a  b  c  d  e  f  g  h\n",
+        "\n",
+        "Side to play: Black\n",
+        "White king castle: no\n",
+        "White queen castle: no\n",
+        "Black king castle: no\n",
+        "Black queen castle: no\n",
+        "Halfmove clock: 0\n",
+        "En passant square: None\n",
+        "\n",
+        "FEN: 4k3/8/8/1B6/8/8/8/4R1K1 b - - 0 1\n",
+        "Hash: 0x7be18ece19f605fc\n",
+        "Checkers: e1 b5\n",
+    );
+
+    assert_eq!(print("4k3/8/8/1B6/8/8/8/4R1K1 b - - 0 1"), expected);
+}
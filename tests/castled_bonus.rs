@@ -0,0 +1,149 @@
+// castling rights are worth a little in the middle game, and having castled is worth more. whether
+// a side has castled is part of the hash, so positions that evaluate differently never share a
+// table entry. a FEN can't say whether a side has castled, so one loaded from it hasn't
+
+mod common;
+
+use krusty::{
+    board::{Board, Side},
+    move_generator::{Move, MoveKind, MoveList},
+};
+
+use common::board;
+
+// both sides can castle either way
+const CASTLING_FEN: &str = "r3k2r/pppq1ppp/2npbn2/2b1p3/2B1P3/2NPBN2/PPPQ1PPP/R3K2R w KQkq - 0 1";
+
+// white after castling short from `CASTLING_FEN`
+const WHITE_CASTLED_FEN: &str =
+    "r3k2r/pppq1ppp/2npbn2/2b1p3/2B1P3/2NPBN2/PPPQ1PPP/R4RK1 b kq - 1 1";
+
+fn legal_moves(board: &mut Board) -> Vec<Move> {
+    let mut move_list = MoveList::new();
+    board.generate_all_moves(&mut move_list).unwrap();
+
+    move_list
+        .into_iter()
+        .filter(|&mv| {
+            let legal = board.make_move(mv).unwrap();
+            board.unmake_move(mv);
+            legal
+        })
+        .collect()
+}
+
+fn castle(board: &mut Board, to_square_name: &str) -> Move {
+    let mv = legal_moves(board)
+        .into_iter()
+        .find(|mv| mv.kind() == MoveKind::Castle && mv.to_string().ends_with(to_square_name))
+        .unwrap();
+
+    board.make_move(mv).unwrap();
+    mv
+}
+
+#[test]
+fn having_castled_is_worth_more_than_the_rights() {
+    let mut castled = board(CASTLING_FEN);
+    castle(&mut castled, "g1");
+    assert!(castled.has_castled(Side::White));
+
+    // the same position, only without the flag
+    let mut not_castled = castled.clone();
+    not_castled.set_castled_flags([false, false]);
+
+    // black to move, so a better position for white is a lower score
+    assert!(castled.evaluate().unwrap() < not_castled.evaluate().unwrap());
+
+    // the rights are worth something on their own, but less than castling
+    let with_rights = board(CASTLING_FEN).evaluate().unwrap();
+    let without_rights = board(&CASTLING_FEN.replace("KQkq", "kq"))
+        .evaluate()
+        .unwrap();
+    assert!(with_rights > without_rights);
+    assert!(
+        with_rights - without_rights
+            < not_castled.evaluate().unwrap() - castled.evaluate().unwrap()
+    );
+}
+
+#[test]
+fn having_castled_is_part_of_the_hash() {
+    let mut castled = board(CASTLING_FEN);
+    castle(&mut castled, "g1");
+
+    let mut not_castled = castled.clone();
+    not_castled.set_castled_flags([false, false]);
+
+    assert_ne!(castled.hash(), not_castled.hash_from_scratch());
+    assert_eq!(castled.hash(), castled.hash_from_scratch());
+}
+
+#[test]
+fn a_position_loaded_from_a_fen_has_not_castled() {
+    let loaded = board(WHITE_CASTLED_FEN);
+    assert!(!loaded.has_castled(Side::White));
+    assert!(!loaded.has_castled(Side::Black));
+
+    let mut played = board(CASTLING_FEN);
+    castle(&mut played, "g1");
+    assert_eq!(loaded.fen(), played.fen());
+    assert_ne!(loaded.hash(), played.hash());
+
+    // so it's the same position as the king and rook walking there, not as castling
+    let mut walked = board("4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+    walked
+        .make_moves_from_str("e1f2 e8d8 h1f1 d8e8 f2g1")
+        .unwrap();
+    assert!(!walked.has_castled(Side::White));
+
+    let loaded = board("4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+    assert_eq!(loaded.hash(), walked.hash());
+    assert_eq!(loaded.evaluate().unwrap(), walked.evaluate().unwrap());
+}
+
+#[test]
+fn the_flags_round_trip_through_make_and_unmake() {
+    let mut board = board(CASTLING_FEN);
+
+    // every move from the start, and every reply, castling or not
+    for mv in legal_moves(&mut board) {
+        let flags = board.castled_flags();
+        let hash = board.hash();
+
+        board.make_move(mv).unwrap();
+        let side = !board.side_to_move();
+        assert_eq!(
+            board.has_castled(side),
+            mv.kind() == MoveKind::Castle,
+            "{}",
+            mv
+        );
+        assert_eq!(board.hash(), board.hash_from_scratch(), "{}", mv);
+
+        for reply in legal_moves(&mut board) {
+            let reply_flags = board.castled_flags();
+            let reply_hash = board.hash();
+
+            board.make_move(reply).unwrap();
+            assert_eq!(board.hash(), board.hash_from_scratch(), "{} {}", mv, reply);
+            board.unmake_move(reply);
+
+            assert_eq!(board.castled_flags(), reply_flags, "{} {}", mv, reply);
+            assert_eq!(board.hash(), reply_hash, "{} {}", mv, reply);
+        }
+
+        board.unmake_move(mv);
+
+        assert_eq!(board.castled_flags(), flags, "{}", mv);
+        assert_eq!(board.hash(), hash, "{}", mv);
+    }
+
+    // a null move keeps them too
+    castle(&mut board, "g1");
+    let flags = board.castled_flags();
+    board.make_null_move();
+    assert_eq!(board.castled_flags(), flags);
+    board.unmake_null_move();
+    assert_eq!(board.castled_flags(), flags);
+}
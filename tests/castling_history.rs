@@ -0,0 +1,89 @@
+// castling is written king-takes-rook, so with the rook next to the king in Chess960 it has the
+// same squares as an ordinary king move. it's left out of the quiet move heuristics, so its
+// cutoffs don't end up boosting that king move in positions where it's just a king walk
+
+use krusty::{
+    board::{Board, Side},
+    move_generator::{Move, MoveKind, MoveList},
+    reporter::SilentReporter,
+    search::Search,
+    search_limits::SearchLimits,
+    square::Square,
+};
+
+// white can castle with the king on f1 and the rook right next to it on g1
+const CASTLING_FEN: &str = "r3k2r/pppq1ppp/2np1n2/2b1p3/2B1P1b1/2NP1N2/PPPQ1PPP/R4KR1 w GAkq - 0 1";
+
+// the same squares, but the rook is elsewhere, so Kf1-g1 is nothing more than a king move
+const KING_WALK_FEN: &str = "r3k2r/pppq1ppp/2np1n2/2b1p3/2B1P1b1/2NP1N2/PPPQ1PPP/R4K1R w kq - 0 1";
+
+fn legal_moves(board: &mut Board) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    board.generate_all_moves(&mut moves).unwrap();
+
+    moves
+        .into_iter()
+        .filter(|&mv| {
+            let legal = board.make_move(mv).unwrap();
+            board.unmake_move(mv);
+            legal
+        })
+        .collect()
+}
+
+fn searched(fen: &str, depth: u8) -> Search {
+    let mut search = Search::default();
+    search.board.parse_fen(fen).unwrap();
+
+    search
+        .search_position(&SearchLimits::depth(depth), &mut SilentReporter)
+        .unwrap();
+
+    search
+}
+
+#[test]
+fn castling_is_not_quiet_for_history() {
+    let mut board = Board::default();
+    board.parse_fen(CASTLING_FEN).unwrap();
+
+    let castle = legal_moves(&mut board)
+        .into_iter()
+        .find(|mv| mv.kind() == MoveKind::Castle && mv.to_square() == Square::G1)
+        .expect("white can castle with the rook on g1");
+
+    assert_eq!(castle.from_square(), Square::F1);
+    assert!(!castle.is_quiet_for_history());
+
+    let walk = board.get_move_metadata("f1e1").unwrap();
+    let walk = board.find_legal_move(walk).unwrap().unwrap();
+    assert!(walk.is_quiet_for_history());
+}
+
+#[test]
+fn castling_cutoffs_leave_the_king_walk_alone() {
+    let mut search = searched(CASTLING_FEN, 6);
+
+    let history = &search.history[Side::White.index()];
+    assert_eq!(history[Square::F1.index()][Square::G1.index()], 0);
+
+    // with the rook gone from g1, Kf1-g1 is an ordinary move and scores like one that has never
+    // caused a cutoff
+    search.board.parse_fen(KING_WALK_FEN).unwrap();
+
+    let king_walk = legal_moves(&mut search.board)
+        .into_iter()
+        .find(|mv| mv.from_square() == Square::F1 && mv.to_square() == Square::G1)
+        .expect("the king can step to g1");
+
+    let mut moves = MoveList::new();
+    search.board.generate_all_moves(&mut moves).unwrap();
+    search.score_moves(&mut moves, Move::NULL_MOVE, Move::NULL_MOVE);
+
+    let score = moves
+        .into_iter()
+        .find(|mv| mv.bits() == king_walk.bits())
+        .unwrap()
+        .score();
+    assert_eq!(score, 0);
+}
@@ -0,0 +1,77 @@
+// castling is checked against one map of the squares the other side attacks. a king can't castle
+// out of, through or into check, but the rook can pass over an attacked square, and none of that
+// can change the perft counts
+
+mod common;
+
+use krusty::{move_generator::MoveList, perft::perft, perft_table::PerftTable};
+
+use common::board;
+
+// (FEN, depth, nodes) from the standard perft suites
+const ATTACKED_PATHS: [(&str, u8, u64); 4] = [
+    // the bishops and queen cover squares either side of both kings
+    ("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", 4, 1274206),
+    ("r3k2r/7b/8/8/8/8/1B4BQ/R3K2R b KQkq - 0 1", 4, 1274206),
+    // the queens attack both kings' paths on either side
+    ("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", 4, 1720476),
+    ("r3k2r/8/5Q2/8/8/3q4/8/R3K2R w KQkq - 0 1", 4, 1720476),
+];
+
+fn castles(fen: &str) -> Vec<String> {
+    let board = board(fen);
+
+    let mut moves = MoveList::new();
+    board.generate_all_moves(&mut moves).unwrap();
+
+    // the landing square is left to the same legality check as any other king move
+    let mut castles: Vec<_> = (0..moves.length())
+        .map(|i| moves.get(i))
+        .filter(|&mv| board.clone().make_move(mv).unwrap())
+        .map(|mv| mv.to_string())
+        .filter(|mv| matches!(mv.as_str(), "e1g1" | "e1c1" | "e8g8" | "e8c8"))
+        .collect();
+    castles.sort();
+    castles
+}
+
+#[test]
+fn perft_is_unchanged_where_castling_paths_are_attacked() {
+    let mut table = PerftTable::new(16);
+
+    for (fen, depth, nodes) in ATTACKED_PATHS {
+        assert_eq!(
+            perft(&mut board(fen), depth, &mut table).unwrap(),
+            nodes,
+            "{}",
+            fen
+        );
+    }
+}
+
+#[test]
+fn the_king_can_not_castle_through_an_attacked_square() {
+    // the queen on d6 covers d8 and f8, so black can't castle either way
+    assert!(castles("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1").is_empty());
+
+    // the bishop on g2 covers f1, but nothing covers the queen side
+    assert_eq!(castles("4k3/8/8/8/8/8/6b1/R3K2R w KQ - 0 1"), ["e1c1"]);
+
+    // and the king can't land on an attacked square either
+    assert_eq!(castles("4k3/8/8/8/8/8/7b/R3K2R w KQ - 0 1"), ["e1c1"]);
+}
+
+#[test]
+fn the_rook_can_pass_over_an_attacked_square() {
+    // the rook on b2 only covers b1, which the king never crosses
+    assert_eq!(
+        castles("4k3/8/8/8/8/8/1r6/R3K2R w KQ - 0 1"),
+        ["e1c1", "e1g1"]
+    );
+}
+
+#[test]
+fn the_king_can_not_castle_out_of_check() {
+    assert!(castles("4k3/8/8/8/8/8/8/R3K1rR w KQ - 0 1").is_empty());
+    assert!(castles("4k3/8/8/8/8/8/4r3/R3K2R w KQ - 0 1").is_empty());
+}
@@ -0,0 +1,149 @@
+// Chess960 castling: any rook files in FEN, kings and rooks starting anywhere on the back rank,
+// and castling written as the king taking its own rook when the notation is switched on
+
+mod common;
+
+use common::board;
+
+use krusty::{
+    board::Board,
+    move_generator::{Move, MoveKind, MoveList},
+    perft::run_quick_perft_tests,
+};
+
+// published Chess960 perft results, castling rights in Shredder-FEN
+const PERFT_SUITE: &str = "\
+    bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9 ;D1 21 ;D2 528 ;D3 12189 ;D4 326672\n\
+    2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9 ;D1 21 ;D2 807 ;D3 18002 ;D4 667366\n\
+    b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9 ;D1 20 ;D2 479 ;D3 10471 ;D4 273318\n\
+    qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9 ;D1 22 ;D2 593 ;D3 13440 ;D4 382958\n\
+    1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9 ;D1 28 ;D2 1120 ;D3 31058 ;D4 1171749";
+
+#[test]
+fn published_perft_results_match() {
+    let summary = run_quick_perft_tests(PERFT_SUITE, 2_000_000, 2).unwrap();
+
+    for failure in &summary.failures {
+        println!(
+            "{} depth {}: expected {}, got {:?}",
+            failure.fen, failure.depth, failure.expected_nodes, failure.result
+        );
+    }
+
+    assert!(summary.failures.is_empty());
+    assert_eq!(summary.passed, 5);
+}
+
+#[test]
+fn castling_rights_are_written_in_x_fen() {
+    // the outermost rooks get the usual letters, whichever way they were given
+    let shredder = board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1");
+    let standard = board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(shredder.fen(), standard.fen());
+    assert_eq!(shredder.hash(), standard.hash());
+
+    let frc = board("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9");
+    assert_eq!(
+        frc.fen(),
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 1"
+    );
+
+    // an inner rook needs its file
+    let inner = board("1r2k3/8/8/8/8/8/8/RR2K3 w Bb - 0 1");
+    assert_eq!(inner.fen(), "1r2k3/8/8/8/8/8/8/RR2K3 w Bq - 0 1");
+
+    let outer = board("4k3/8/8/8/8/8/8/RR2K3 w Q - 0 1");
+    assert_eq!(outer.fen(), "4k3/8/8/8/8/8/8/RR2K3 w Q - 0 1");
+}
+
+#[test]
+fn castling_rights_need_a_rook_and_a_king_on_the_back_rank() {
+    let mut board = Board::default();
+
+    for fen in [
+        "4k3/8/8/8/8/8/8/4K3 w K - 0 1",
+        "4k3/8/8/8/8/8/8/R3K3 w C - 0 1",
+        "4k3/8/8/8/8/8/4K3/R7 w Q - 0 1",
+        "4k3/8/8/8/8/8/8/R3K3 w X - 0 1",
+    ] {
+        assert!(board.parse_fen(fen).is_err(), "{}", fen);
+    }
+}
+
+fn castling_move(board: &mut Board) -> Move {
+    let mut moves = MoveList::new();
+    board.generate_all_moves(&mut moves).unwrap();
+    moves
+        .into_iter()
+        .find(|mv| mv.kind() == MoveKind::Castle)
+        .unwrap()
+}
+
+#[test]
+fn chess960_castling_is_written_as_the_king_taking_its_rook() {
+    // the king and rook swap squares, so making the move can't put either down before the other
+    // is picked up
+    let mut board = board("4k3/8/8/8/8/8/8/5KR1 w G - 0 1");
+    board.set_chess960(true);
+    assert_eq!(castling_move(&mut board).to_string(), "f1g1");
+    board.make_moves_from_str("f1g1").unwrap();
+    assert_eq!(board.fen(), "4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+    assert_eq!(board.hash(), board.hash_from_scratch());
+
+    // the king doesn't move at all, but it still castles
+    board.parse_fen("4k3/8/8/8/8/8/8/6KR w K - 0 1").unwrap();
+    assert_eq!(castling_move(&mut board).to_string(), "g1h1");
+    board.make_moves_from_str("g1h1").unwrap();
+    assert_eq!(board.fen(), "4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+
+    // the king's two-square move isn't castling any more
+    board.parse_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    assert!(board.make_moves_from_str("e1c1").is_err());
+    board.make_moves_from_str("e1a1").unwrap();
+    assert_eq!(board.fen(), "4k3/8/8/8/8/8/8/2KR3R b - - 1 1");
+
+    // the notation belongs to the board, so any other board still writes castling as usual
+    let mut standard = common::board("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+    assert_eq!(castling_move(&mut standard).to_string(), "e1g1");
+}
+
+#[test]
+fn standard_castling_is_written_as_the_kings_move() {
+    // and the rook's square isn't a castling move
+    let mut board = board("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+    assert_eq!(castling_move(&mut board).to_string(), "e1g1");
+    assert!(board.make_moves_from_str("e1h1").is_err());
+    board.make_moves_from_str("e1c1").unwrap();
+    assert_eq!(board.fen(), "4k3/8/8/8/8/8/8/2KR3R b - - 1 1");
+}
+
+#[test]
+fn unmaking_castling_restores_the_position() {
+    for fen in [
+        "4k3/8/8/8/8/8/8/5KR1 w K - 0 1",
+        "4k3/8/8/8/8/8/8/6KR w K - 0 1",
+        "4k3/8/8/8/8/8/8/1RK5 w Q - 0 1",
+        "rk6/8/8/8/8/8/8/RK6 b Aa - 0 1",
+        "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+    ] {
+        let mut board = board(fen);
+        let (loaded_fen, hash) = (board.fen(), board.hash());
+
+        let mut moves = MoveList::new();
+        board.generate_all_moves(&mut moves).unwrap();
+        let castles: Vec<_> = moves
+            .into_iter()
+            .filter(|mv| mv.kind() == MoveKind::Castle)
+            .collect();
+        assert!(!castles.is_empty(), "{}", fen);
+
+        for mv in castles {
+            assert!(board.make_move(mv).unwrap(), "{}", fen);
+            assert_eq!(board.hash(), board.hash_from_scratch(), "{}", fen);
+            board.unmake_move(mv);
+
+            assert_eq!(board.fen(), loaded_fen, "{}", fen);
+            assert_eq!(board.hash(), hash, "{}", fen);
+        }
+    }
+}
@@ -0,0 +1,247 @@
+// drives the engine binary over its stdin and stdout, the same way a GUI would, along with the
+// boards and reporters the library tests share. not every test uses every helper
+#![allow(dead_code)]
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, ExitStatus, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use krusty::{
+    board::Board,
+    debug_sink::DebugEvent,
+    move_generator::Move,
+    reporter::{info_line, IterationReport, SearchReporter},
+    root_moves::ScoreBound,
+};
+
+// far longer than any reply should take, so a hung engine fails the test instead of blocking it
+pub const REPLY_TIMEOUT: Duration = Duration::from_secs(20);
+
+const UCI_PREFIXES: [&str; 6] = ["id ", "option ", "uciok", "readyok", "info ", "bestmove "];
+
+pub struct Engine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Engine {
+    pub fn start() -> Self {
+        Self::start_with(|command| command)
+    }
+
+    // for tests that need the engine started in a directory of its own, or with an environment
+    // variable set
+    pub fn start_with(configure: impl FnOnce(&mut Command) -> &mut Command) -> Self {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_krusty"));
+
+        let mut child = configure(&mut command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("failed to start the engine");
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let (sender, lines) = mpsc::channel();
+
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            child,
+            stdin,
+            lines,
+        }
+    }
+
+    pub fn send(&mut self, command: &str) {
+        writeln!(self.stdin, "{}", command).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    pub fn next_line(&self) -> String {
+        self.lines
+            .recv_timeout(REPLY_TIMEOUT)
+            .expect("the engine stopped responding")
+    }
+
+    // every line up to and including the first one that `is_last` accepts
+    pub fn read_until(&self, is_last: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        loop {
+            let line = self.next_line();
+            let done = is_last(&line);
+            lines.push(line);
+
+            if done {
+                return lines;
+            }
+        }
+    }
+
+    pub fn quit(mut self) -> ExitStatus {
+        self.send("quit");
+        drop(self.stdin);
+
+        self.child.wait().unwrap()
+    }
+
+    // what a GUI that has gone away looks like to the engine. every line printed after that, until
+    // the engine's output is closed too
+    pub fn close_input(self) -> (Vec<String>, ExitStatus) {
+        drop(self.stdin);
+
+        Self::read_to_exit(self.child, self.lines)
+    }
+
+    // every line printed until the engine exits by itself, with its input still open
+    pub fn wait_for_exit(self) -> (Vec<String>, ExitStatus) {
+        let Self {
+            child,
+            stdin,
+            lines,
+        } = self;
+
+        let output = Self::read_to_exit(child, lines);
+        drop(stdin);
+
+        output
+    }
+
+    fn read_to_exit(mut child: Child, receiver: Receiver<String>) -> (Vec<String>, ExitStatus) {
+        let mut lines = Vec::new();
+
+        loop {
+            match receiver.recv_timeout(REPLY_TIMEOUT) {
+                Ok(line) => lines.push(line),
+                Err(RecvTimeoutError::Disconnected) => break,
+                Err(RecvTimeoutError::Timeout) => panic!("the engine kept running without input"),
+            }
+        }
+
+        (lines, child.wait().unwrap())
+    }
+}
+
+/// an engine that has already answered `uci`
+pub fn start_uci() -> Engine {
+    let mut engine = Engine::start();
+
+    engine.send("uci");
+    engine.read_until(|line| line.ends_with("uciok"));
+
+    engine
+}
+
+/// the same, making sure `option` was among the options it advertised
+pub fn start_uci_advertising(option: &str) -> Engine {
+    let mut engine = Engine::start();
+
+    engine.send("uci");
+    let output = engine.read_until(|line| line.ends_with("uciok"));
+    assert!(
+        output.iter().any(|line| line == option),
+        "{:?} wasn't advertised",
+        option
+    );
+
+    engine
+}
+
+pub fn assert_uci_output(line: &str) {
+    assert!(!line.contains("PANIC"), "the engine panicked: {}", line);
+    assert!(
+        UCI_PREFIXES.iter().any(|prefix| line.starts_with(prefix)),
+        "not a UCI message: {:?}",
+        line
+    );
+}
+
+pub fn board(fen: &str) -> Board {
+    let mut board = Board::default();
+    board.parse_fen(fen).unwrap();
+    board
+}
+
+/// an iteration as the search reported it, kept after the search is over
+#[derive(Debug, Clone)]
+pub struct Iteration {
+    pub depth: u8,
+    pub multipv: Option<usize>,
+    pub score: i32,
+    pub bound: ScoreBound,
+    pub nodes: u64,
+    pub pv: Vec<Move>,
+    pub info: String,
+}
+
+/// keeps everything a search reports, for tests that need more than the best move
+#[derive(Default)]
+pub struct RecordingReporter {
+    pub iterations: Vec<Iteration>,
+    // iterations and debug events in the order they arrived, as `Ok(depth)` and `Err(event)`
+    pub events: Vec<Result<u8, DebugEvent>>,
+    pub warnings: Vec<String>,
+}
+
+impl RecordingReporter {
+    /// the last iteration's score
+    pub fn score(&self) -> Option<i32> {
+        self.iterations.last().map(|iteration| iteration.score)
+    }
+
+    /// the last score that wasn't only a bound
+    pub fn exact_score(&self) -> Option<i32> {
+        self.iterations
+            .iter()
+            .rev()
+            .find(|iteration| iteration.bound == ScoreBound::Exact)
+            .map(|iteration| iteration.score)
+    }
+
+    /// the nodes the last iteration had searched by the time it finished
+    pub fn nodes(&self) -> u64 {
+        self.iterations
+            .last()
+            .map_or(0, |iteration| iteration.nodes)
+    }
+}
+
+impl SearchReporter for RecordingReporter {
+    fn iteration(&mut self, report: &IterationReport) {
+        self.iterations.push(Iteration {
+            depth: report.depth,
+            multipv: report.multipv,
+            score: report.score,
+            bound: report.bound,
+            nodes: report.nodes,
+            pv: report.pv.into(),
+            info: info_line(report),
+        });
+        self.events.push(Ok(report.depth));
+    }
+
+    fn best_move(&mut self, _best_move: Move, _ponder: Option<Move>) {}
+
+    fn debug(&mut self, event: &DebugEvent) {
+        self.events.push(Err(*event));
+    }
+
+    fn warning(&mut self, message: &str) {
+        self.warnings.push(message.into());
+    }
+}
@@ -0,0 +1,112 @@
+// `Contempt` makes a draw worth less than even to the engine, or more when it's negative, so it
+// plays on for a win against a weaker opponent and settles for a draw against a stronger one
+
+mod common;
+
+use common::{assert_uci_output, start_uci_advertising, Engine};
+
+// level material, with pawns keeping the queens off each other's files. whichever side is to
+// move is a little better, for the tempo
+const POSITION: &str = "3qk3/3p4/8/8/8/8/3P4/3QK3";
+
+// the kings step aside and back twice, starting with the side to move, after which the side to
+// move repeats a position for the third time by stepping back again
+const WHITE_SHUFFLE: &str = "e1f1 e8f8 f1e1 f8e8 e1f1 e8f8 f1e1";
+const BLACK_SHUFFLE: &str = "e8f8 e1f1 f8e8 f1e1 e8f8 e1f1 f8e8";
+
+const CONTEMPT_OPTION: &str = "option name Contempt type spin default 0 min -100 max 100";
+
+// where black, or white, can repeat the position for the third time, and the move that does it
+fn to_repeat(side: char) -> (String, &'static str) {
+    match side {
+        'b' => (
+            format!(
+                "position fen {} w - - 0 1 moves {}",
+                POSITION, WHITE_SHUFFLE
+            ),
+            "f8e8",
+        ),
+        _ => (
+            format!(
+                "position fen {} b - - 0 1 moves {}",
+                POSITION, BLACK_SHUFFLE
+            ),
+            "f1e1",
+        ),
+    }
+}
+
+// the last iteration's score and the best move
+fn search(engine: &mut Engine, go: &str) -> (String, String) {
+    engine.send(go);
+
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    let info = output
+        .iter()
+        .rev()
+        .find(|line| line.starts_with("info depth"))
+        .unwrap();
+    let tokens: Vec<_> = info.split_whitespace().collect();
+    let score = tokens.iter().position(|&token| token == "score").unwrap();
+
+    let best_move = output.last().unwrap().split_whitespace().nth(1).unwrap();
+
+    (tokens[score + 1..score + 3].join(" "), best_move.into())
+}
+
+#[test]
+fn a_repetition_costs_the_engine_its_contempt_whichever_side_it_plays() {
+    let mut engine = start_uci_advertising(CONTEMPT_OPTION);
+
+    for side in ['w', 'b'] {
+        let (position, repeating_move) = to_repeat(side);
+        engine.send(&position);
+
+        for contempt in [0, 40, -40, 100] {
+            engine.send(&format!("setoption name Contempt value {}", contempt));
+
+            let go = format!("go depth 3 searchmoves {}", repeating_move);
+            let (score, _) = search(&mut engine, &go);
+            assert_eq!(
+                score,
+                format!("cp {}", -contempt),
+                "{} with contempt {}",
+                side,
+                contempt
+            );
+        }
+    }
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn positive_contempt_declines_a_repetition_when_ahead() {
+    let mut engine = start_uci_advertising(CONTEMPT_OPTION);
+
+    let (position, repeating_move) = to_repeat('w');
+    engine.send(&position);
+
+    // the draw is worth more than white's edge, so it takes it
+    engine.send("setoption name Contempt value -100");
+    let (score, best_move) = search(&mut engine, "go depth 5");
+    assert_eq!(
+        (score.as_str(), best_move.as_str()),
+        ("cp 100", repeating_move)
+    );
+
+    // without contempt, and with it, the edge is worth more
+    for contempt in [0, 100] {
+        engine.send(&format!("setoption name Contempt value {}", contempt));
+        let (score, best_move) = search(&mut engine, "go depth 5");
+
+        assert_ne!(best_move, repeating_move, "contempt {}", contempt);
+
+        let centipawns: i32 = score.strip_prefix("cp ").unwrap().parse().unwrap();
+        assert!(centipawns > 0, "{}", score);
+    }
+
+    assert!(engine.quit().success());
+}
@@ -0,0 +1,102 @@
+// a winning position shouldn't be traded into one with too little material left to win. the
+// root scores such a move below a draw, but a draw is still taken over losing. a position that's
+// already a dead draw isn't searched at all
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{assert_uci_output, start_uci, Engine};
+use krusty::{
+    move_generator::Move,
+    reporter::SilentReporter,
+    search::{Search, DEAD_DRAW_PENALTY},
+    search_limits::SearchLimits,
+};
+
+fn search(fen: &str, depth: u8) -> (Search, Move) {
+    let mut search = Search::default();
+    search.board.parse_fen(fen).unwrap();
+    search.set_seed(Some(1));
+
+    let best_move = search
+        .search_position(&SearchLimits::depth(depth), &mut SilentReporter)
+        .unwrap();
+
+    (search, best_move)
+}
+
+fn root_score(search: &Search, mv: &str) -> i32 {
+    search
+        .root_moves
+        .moves()
+        .iter()
+        .find(|root_move| root_move.mv.to_string() == mv)
+        .unwrap_or_else(|| panic!("{} wasn't searched at the root", mv))
+        .score
+}
+
+#[test]
+fn last_pawn_is_left_alone_when_it_matters() {
+    // taking the pawn leaves a knight against a bare king, while Nc7 is mate
+    let (_, best_move) = search("k1K5/p7/8/1N6/8/8/8/8 w - - 0 1", 4);
+    assert_eq!(best_move.to_string(), "b5c7");
+
+    // with no mate around, taking the pawn still gives away any chance of winning
+    let (search, best_move) = search("k7/p7/8/1N6/8/8/8/6K1 w - - 0 1", 4);
+    assert_ne!(best_move.to_string(), "b5a7");
+    assert_eq!(root_score(&search, "b5a7"), -DEAD_DRAW_PENALTY);
+}
+
+#[test]
+fn dead_draw_is_taken_over_losing() {
+    // the pawn queens unless the knight takes it
+    let (search, best_move) = search("7k/8/8/8/8/2N5/p7/7K w - - 0 1", 6);
+    assert_eq!(best_move.to_string(), "c3a2");
+    assert!(root_score(&search, "c3a2") >= -DEAD_DRAW_PENALTY);
+}
+
+// a knight can't mate a bare king
+const KNIGHT_VS_KING_FEN: &str = "8/8/3k4/8/8/2N5/8/4K3 w - - 0 1";
+
+fn start_uci_on_knight_vs_king() -> Engine {
+    let mut engine = start_uci();
+    engine.send(&format!("position fen {}", KNIGHT_VS_KING_FEN));
+
+    engine
+}
+
+#[test]
+fn knight_against_king_scores_exactly_zero() {
+    let mut engine = start_uci_on_knight_vs_king();
+
+    engine.send("go depth 20");
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    // one iteration, not twenty
+    assert_eq!(output.len(), 2, "{:?}", output);
+    assert!(output[0].contains(" score cp 0 "), "{}", output[0]);
+    assert_ne!(output[1], "bestmove 0000");
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn an_infinite_search_of_a_dead_draw_stops_straight_away() {
+    let mut engine = start_uci_on_knight_vs_king();
+
+    engine.send("go infinite");
+    let info = engine.next_line();
+    assert!(info.contains(" score cp 0 "), "{}", info);
+
+    let stopped = Instant::now();
+    engine.send("stop");
+    let bestmove = engine.next_line();
+
+    assert!(bestmove.starts_with("bestmove "), "{}", bestmove);
+    assert_ne!(bestmove, "bestmove 0000");
+    assert!(stopped.elapsed() < Duration::from_secs(1));
+
+    assert!(engine.quit().success());
+}
@@ -0,0 +1,186 @@
+// EPD lines are a position followed by opcodes and their operands, separated by semicolons.
+// strings in quotes can hold spaces and semicolons, and anything malformed is an error naming its
+// line rather than a panic
+
+use krusty::{
+    epd::{parse_epd, parse_epd_line, EpdOperation},
+    perft::run_quick_perft_tests,
+};
+
+const PERFT_SUITE: &str = include_str!("../perft.epd");
+
+fn operation(opcode: &str, operands: &[&str]) -> EpdOperation {
+    EpdOperation {
+        opcode: opcode.into(),
+        operands: operands.iter().map(|&operand| operand.into()).collect(),
+    }
+}
+
+fn error(text: &str) -> String {
+    format!("{:#}", parse_epd(text).unwrap_err())
+}
+
+#[test]
+fn operations_follow_the_position() {
+    let record = parse_epd_line(
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - bm Bb5 Bc4; id \"WAC.001\";",
+    )
+    .unwrap();
+
+    assert_eq!(
+        record.position,
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq -"
+    );
+    assert_eq!(
+        record.operations,
+        [
+            operation("bm", &["Bb5", "Bc4"]),
+            operation("id", &["WAC.001"])
+        ]
+    );
+
+    assert_eq!(record.operands("bm").unwrap(), ["Bb5", "Bc4"]);
+    assert_eq!(record.operand("id"), Some("WAC.001"));
+    assert_eq!(record.operand("am"), None);
+
+    // without counters or `hmvc` and `fmvn`, a new game's
+    assert_eq!(
+        record.fen(),
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 1"
+    );
+}
+
+#[test]
+fn quoted_strings_keep_their_spaces_and_semicolons() {
+    let record = parse_epd_line(
+        "4k3/8/8/8/8/8/8/4K3 w - - c0 \"draw; bare kings\"; c9 \"1/2-1/2\"; id \"\";",
+    )
+    .unwrap();
+
+    assert_eq!(
+        record.operations,
+        [
+            operation("c0", &["draw; bare kings"]),
+            operation("c9", &["1/2-1/2"]),
+            operation("id", &[""]),
+        ]
+    );
+
+    // and a quote ends a word, with or without a space before it
+    let record = parse_epd_line("4k3/8/8/8/8/8/8/4K3 w - - id\"kings\"").unwrap();
+    assert_eq!(record.operations, [operation("id", &["kings"])]);
+}
+
+#[test]
+fn counters_can_come_from_the_position_or_opcodes() {
+    // FEN-style, as the perft suite has them
+    let record = parse_epd_line("4k3/8/8/8/8/8/8/4K3 b - - 12 40 ;D1 5").unwrap();
+    assert_eq!(
+        (record.halfmove_clock, record.fullmove_number),
+        (Some(12), Some(40))
+    );
+    assert_eq!(record.fen(), "4k3/8/8/8/8/8/8/4K3 b - - 12 40");
+
+    // the opcodes win over them
+    let record = parse_epd_line("4k3/8/8/8/8/8/8/4K3 b - - 12 40 hmvc 3; fmvn 7;").unwrap();
+    assert_eq!(record.fen(), "4k3/8/8/8/8/8/8/4K3 b - - 3 7");
+
+    // a halfmove clock on its own leaves the default move number
+    let record = parse_epd_line("4k3/8/8/8/8/8/8/4K3 b - - 12").unwrap();
+    assert_eq!(record.fen(), "4k3/8/8/8/8/8/8/4K3 b - - 12 1");
+}
+
+#[test]
+fn comments_and_blank_lines_are_skipped() {
+    let text = "# a comment\n\n   \n4k3/8/8/8/8/8/8/4K3 w - - id \"one\";\n  # another\n4k3/8/8/8/8/8/8/3K4 b - - id \"two\"";
+
+    let ids: Vec<_> = parse_epd(text)
+        .unwrap()
+        .iter()
+        .map(|record| record.operand("id").unwrap().to_string())
+        .collect();
+    assert_eq!(ids, ["one", "two"]);
+
+    assert!(parse_epd("").unwrap().is_empty());
+}
+
+#[test]
+fn malformed_lines_are_errors_with_their_line_number() {
+    let valid = "4k3/8/8/8/8/8/8/4K3 w - - id \"ok\";";
+
+    for (line, message) in [
+        (
+            "4k3/8/8/8/8/8/8/4K3 w KQ",
+            "EPD is missing the en passant field",
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3",
+            "EPD is missing the side to move field",
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - id \"unfinished;",
+            "unterminated string \"unfinished;",
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - \"stray\" bm e4;",
+            "expected an opcode but found the string \"stray\"",
+        ),
+        // a semicolon can't stand in for a field
+        (
+            "4k3/8/8/8/8/8/8/4K3 w ; - -",
+            "EPD is missing the castling field",
+        ),
+    ] {
+        let text = format!("# header\n{}\n\n{}\n", valid, line);
+        assert_eq!(error(&text), format!("line 4: {}", message), "{}", line);
+    }
+}
+
+#[test]
+fn every_perft_suite_line_reads_as_it_did_before() {
+    let records = parse_epd(PERFT_SUITE).unwrap();
+    let lines: Vec<_> = PERFT_SUITE
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+
+    assert_eq!(records.len(), lines.len());
+
+    for (record, line) in records.iter().zip(lines) {
+        // the suite was read by splitting on semicolons, which gives the FEN and `D<depth> <nodes>`
+        let (fen, tests) = line.split_once(';').unwrap();
+        assert_eq!(record.fen(), fen.trim(), "{}", line);
+
+        let expected: Vec<_> = tests
+            .split(" ;")
+            .map(|test| {
+                let (depth, nodes) = test.trim().split_once(' ').unwrap();
+                operation(depth, &[nodes])
+            })
+            .collect();
+        assert_eq!(record.operations, expected, "{}", line);
+    }
+}
+
+// what the quick perft runner says about a suite it can't read
+fn suite_error(suite: &str) -> String {
+    let error = run_quick_perft_tests(suite, 1000, 1).err().unwrap();
+    format!("{:#}", error)
+}
+
+#[test]
+fn a_malformed_perft_suite_is_an_error_not_a_panic() {
+    let suite = "4k3/8/8/8/8/8/8/4K2R w K - 0 1 ;D1 15 ;D2 66\n4k3/8/8/8/8/8/8/4K2R w K - 0 1 ;D1 15 ;D2 lots";
+    assert_eq!(
+        suite_error(suite),
+        "position 2: invalid node count for depth 2: invalid digit found in string"
+    );
+
+    assert!(suite_error("4k3/8/8/8/8/8/8/4K2R w K - 0 1 ;Dx 15")
+        .starts_with("position 1: invalid perft depth opcode `Dx`"));
+
+    assert_eq!(
+        suite_error("4k3/8/8/8/8/8/8/4K2R w K\n"),
+        "line 1: EPD is missing the en passant field"
+    );
+}
@@ -0,0 +1,55 @@
+// positions can be scored straight from their FENs, one at a time or in parallel batches, without
+// setting up a search
+
+use krusty::{bench::BENCH_FENS, board::Board, evaluate_fen, evaluate_many};
+
+const INVALID_FEN: &str = "8/8/8 w - - 0 1";
+
+fn evaluate(fen: &str) -> i32 {
+    let mut board = Board::default();
+    board.parse_fen(fen).unwrap();
+    board.evaluate().unwrap()
+}
+
+#[test]
+fn a_fen_scores_the_same_as_its_board() {
+    for fen in BENCH_FENS {
+        assert_eq!(evaluate_fen(fen).unwrap(), evaluate(fen), "{}", fen);
+    }
+
+    assert!(evaluate_fen(INVALID_FEN).is_err());
+}
+
+#[test]
+fn batches_come_out_the_same_on_any_number_of_threads() {
+    let expected: Vec<_> = BENCH_FENS.iter().map(|fen| evaluate(fen)).collect();
+
+    for threads in [0, 1, 2, 3, 8, 64] {
+        let scores: Vec<_> = evaluate_many(BENCH_FENS, threads)
+            .into_iter()
+            .map(|score| score.unwrap())
+            .collect();
+
+        assert_eq!(scores, expected, "{} threads", threads);
+    }
+}
+
+#[test]
+fn an_invalid_fen_is_an_error_in_its_place() {
+    let mut fens = BENCH_FENS.to_vec();
+    fens.insert(5, INVALID_FEN);
+
+    let scores = evaluate_many(fens.iter().copied(), 4);
+    assert_eq!(scores.len(), fens.len());
+
+    for (index, score) in scores.iter().enumerate() {
+        assert_eq!(score.is_err(), index == 5, "{}", fens[index]);
+    }
+
+    assert_eq!(*scores[6].as_ref().unwrap(), evaluate(fens[6]));
+}
+
+#[test]
+fn an_empty_batch_has_no_scores() {
+    assert!(evaluate_many([], 4).is_empty());
+}
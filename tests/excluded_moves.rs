@@ -0,0 +1,83 @@
+// a search with moves left out, for MultiPV lines or for checking that the TT move is the only good
+// one, keeps its results under a key of its own. the entry a normal search finds is left alone
+
+use krusty::{
+    reporter::SilentReporter, search::Search, search_limits::SearchLimits,
+    transposition_table::SearchTableEntry, zobrist_hash::excluded_move_key,
+};
+
+const FEN: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+fn searched(multi_pv: usize) -> Search {
+    let mut search = Search::default();
+    search.set_seed(Some(1));
+    search.multi_pv = multi_pv;
+    search.board.parse_fen(FEN).unwrap();
+
+    search
+        .search_position(&SearchLimits::depth(5), &mut SilentReporter)
+        .unwrap();
+
+    search
+}
+
+fn entry(search: &Search, key: u64) -> SearchTableEntry {
+    search.transposition_table.probe(key).unwrap()
+}
+
+fn fields(entry: SearchTableEntry) -> (u8, i32, u32) {
+    (entry.depth, entry.score, entry.best_move.bits())
+}
+
+#[test]
+fn a_search_excluding_the_best_move_leaves_its_entry_alone() {
+    let mut search = searched(1);
+    let hash = search.board.hash();
+    let before = entry(&search, hash);
+
+    let best_move = before.best_move;
+    assert!(!best_move.is_null());
+
+    let score = search
+        .search_excluding(best_move, before.depth, before.score)
+        .unwrap();
+    assert!(score <= before.score);
+
+    assert_eq!(fields(entry(&search, hash)), fields(before));
+    assert_eq!(search.board.fen(), FEN);
+
+    // the excluded search has its own entry, with some other move as the best
+    let excluded = entry(&search, hash ^ excluded_move_key(best_move));
+    assert_ne!(excluded.best_move, best_move);
+
+    // and a normal search of the same position still starts from the full search's entry
+    search
+        .search_position(&SearchLimits::depth(1), &mut SilentReporter)
+        .unwrap();
+    assert_eq!(entry(&search, hash).best_move, best_move);
+}
+
+#[test]
+fn excluding_the_only_move_fails_low() {
+    let mut search = Search::default();
+    // the rook covers the g-file, so black's king can only go to h7
+    search
+        .board
+        .parse_fen("7k/8/8/8/8/8/8/K5R1 b - - 0 1")
+        .unwrap();
+
+    let metadata = search.board.get_move_metadata("h8h7").unwrap();
+    let only_move = search.board.find_legal_move(metadata).unwrap().unwrap();
+
+    assert_eq!(search.search_excluding(only_move, 3, 0).unwrap(), -1);
+}
+
+#[test]
+fn multi_pv_lines_dont_replace_the_root_entry() {
+    let search = searched(3);
+
+    let hash = search.board.hash();
+    let best_move = search.root_moves.best_move().unwrap();
+
+    assert_eq!(entry(&search, hash).best_move, best_move);
+}
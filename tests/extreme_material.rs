@@ -0,0 +1,183 @@
+// positions from editors and composition tools can have more of a piece than a game ever could.
+// the evaluation, search and move generation can't assume otherwise, so none of them overflows or
+// panics on one
+
+mod common;
+
+use krusty::{
+    perft::perft,
+    perft_table::PerftTable,
+    reporter::score_string,
+    search::{Search, CHECKMATE_THRESHOLD, INFINITY},
+    search_limits::SearchLimits,
+};
+
+use common::{board, RecordingReporter};
+
+// nine queens against a bare king, and the same with the colors swapped
+const NINE_QUEENS_FEN: &str = "4k3/8/8/8/8/QQQ5/QQQ5/KQQQ4 w - - 0 1";
+const NINE_BLACK_QUEENS_FEN: &str = "kqqq4/qqq5/qqq5/8/8/8/8/4K3 b - - 0 1";
+
+// more of every minor and major piece than promotions could give, with the kings safe
+const EXTREME_FENS: [&str; 4] = [
+    NINE_QUEENS_FEN,
+    NINE_BLACK_QUEENS_FEN,
+    "nnnnk3/nnnn4/8/8/8/8/NNNN4/NNNNK3 w - - 0 1",
+    "rrbbk3/rrbb4/8/8/8/8/BBRR4/BBRRK3 b - - 0 1",
+];
+
+#[test]
+fn extra_material_is_never_mistaken_for_mate() {
+    let white = board(NINE_QUEENS_FEN).evaluate().unwrap();
+    let black = board(NINE_BLACK_QUEENS_FEN).evaluate().unwrap();
+
+    // each is from the side to move, and it's the side with the queens both times
+    assert!(white > 0 && white < CHECKMATE_THRESHOLD, "{}", white);
+    assert_eq!(white, black);
+
+    for fen in EXTREME_FENS {
+        let score = board(fen).evaluate().unwrap();
+        assert!(score.abs() < CHECKMATE_THRESHOLD, "{}: {}", fen, score);
+    }
+}
+
+// the squares of `NINE_QUEENS_FEN`'s queens, none of which sees black's king on e8
+const QUEEN_SQUARES: [(usize, usize); 9] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (1, 0),
+    (1, 1),
+    (1, 2),
+    (2, 0),
+    (2, 1),
+    (2, 2),
+];
+
+// white's king on a1 and black's on e8, with white queens on the first `queens` squares
+fn queens_fen(queens: usize) -> String {
+    queens_fen_with_king(queens, (7, 4))
+}
+
+// the only other square the queens leave black's king is h4
+fn queens_fen_with_king(queens: usize, (king_rank, king_file): (usize, usize)) -> String {
+    let mut ranks = [['1'; 8]; 8];
+    ranks[0][0] = 'K';
+    ranks[king_rank][king_file] = 'k';
+
+    for &(rank, file) in &QUEEN_SQUARES[..queens] {
+        ranks[rank][file] = 'Q';
+    }
+
+    let placement: Vec<String> = ranks
+        .iter()
+        .rev()
+        .map(|rank| {
+            let mut text = String::new();
+            let mut empty = 0;
+
+            for &square in rank {
+                if square == '1' {
+                    empty += 1;
+                    continue;
+                }
+                if empty > 0 {
+                    text += &empty.to_string();
+                    empty = 0;
+                }
+                text.push(square);
+            }
+            if empty > 0 {
+                text += &empty.to_string();
+            }
+
+            text
+        })
+        .collect();
+
+    format!("{} w - - 0 1", placement.join("/"))
+}
+
+#[test]
+fn more_material_never_scores_less() {
+    assert_eq!(queens_fen(9), NINE_QUEENS_FEN);
+
+    // each queen is worth far more than anything it could cost in the other terms
+    let mut previous = board(&queens_fen(0)).evaluate().unwrap();
+
+    for queens in 1..=9 {
+        let fen = queens_fen(queens);
+        let score = board(&fen).evaluate().unwrap();
+
+        assert!(score > previous, "{}: {} after {}", fen, score, previous);
+        previous = score;
+    }
+}
+
+// material and the piece-square tables, without mobility
+fn cheap_evaluation(fen: &str) -> i32 {
+    board(fen).evaluate_lazy(INFINITY - 1, INFINITY).unwrap()
+}
+
+#[test]
+fn the_game_phase_stops_at_a_full_board() {
+    // six queens are a full board's worth of material, so any more can't make the position any
+    // more of a middlegame, and where black's king stands is worth the same
+    let king_move_with = |queens| {
+        cheap_evaluation(&queens_fen_with_king(queens, (7, 4)))
+            - cheap_evaluation(&queens_fen_with_king(queens, (3, 7)))
+    };
+
+    for queens in 7..=9 {
+        assert_eq!(
+            king_move_with(queens),
+            king_move_with(6),
+            "{} queens",
+            queens
+        );
+    }
+}
+
+#[test]
+fn the_search_finds_mate_with_nine_queens() {
+    let mut search = Search::default();
+    search.board.parse_fen(NINE_QUEENS_FEN).unwrap();
+
+    let mut reporter = RecordingReporter::default();
+    let best_move = search
+        .search_position(&SearchLimits::depth(4), &mut reporter)
+        .unwrap();
+
+    let score = reporter.score().unwrap();
+    assert!(score > CHECKMATE_THRESHOLD && score < INFINITY, "{}", score);
+    assert!(score_string(score).starts_with("mate "));
+
+    assert!(search.board.clone().make_move(best_move).unwrap());
+}
+
+#[test]
+fn every_extreme_position_can_be_searched() {
+    for fen in EXTREME_FENS {
+        let mut search = Search::default();
+        search.board.parse_fen(fen).unwrap();
+
+        let mut reporter = RecordingReporter::default();
+        let best_move = search
+            .search_position(&SearchLimits::depth(3), &mut reporter)
+            .unwrap();
+
+        assert!(!best_move.is_null(), "{}", fen);
+        assert!(reporter.score().unwrap().abs() < INFINITY, "{}", fen);
+    }
+}
+
+#[test]
+fn perft_runs_on_every_extreme_position() {
+    for fen in EXTREME_FENS {
+        let with_table = perft(&mut board(fen), 3, &mut PerftTable::new(1)).unwrap();
+        let without_table = perft(&mut board(fen), 3, &mut PerftTable::new(0)).unwrap();
+
+        assert!(with_table > 0, "{}", fen);
+        assert_eq!(with_table, without_table, "{}", fen);
+    }
+}
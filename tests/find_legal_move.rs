@@ -0,0 +1,107 @@
+// moves typed by a user or sent by a GUI are checked for legality as they're read, instead of
+// being played and then taken back
+
+mod common;
+
+use common::board;
+
+// the knight on e2 is pinned to its king by the rook on e7
+const PINNED_KNIGHT_FEN: &str = "4k3/4r3/8/8/8/8/4N2P/4K3 w - - 0 1";
+
+const PROMOTION_FEN: &str = "8/4P3/8/8/8/8/k7/4K3 w - - 0 1";
+
+#[test]
+fn pinned_piece_moves_are_found_but_not_legal() {
+    let mut board = board(PINNED_KNIGHT_FEN);
+    let metadata = board.get_move_metadata("e2c3").unwrap();
+
+    assert!(board.find_matching_move(metadata).is_some());
+    assert!(board.find_legal_move(metadata).unwrap().is_none());
+
+    // trying the move leaves nothing behind
+    assert_eq!(board.fen(), PINNED_KNIGHT_FEN);
+}
+
+#[test]
+fn legal_moves_are_returned_unplayed() {
+    let mut board = board(PINNED_KNIGHT_FEN);
+    let metadata = board.get_move_metadata("e1d1").unwrap();
+
+    let mv = board.find_legal_move(metadata).unwrap().unwrap();
+
+    assert_eq!(mv.to_uci(), "e1d1");
+    assert_eq!(board.fen(), PINNED_KNIGHT_FEN);
+}
+
+#[test]
+fn moving_a_pinned_piece_is_a_clear_error() {
+    let mut board = board(PINNED_KNIGHT_FEN);
+
+    let error = board.make_moves_from_str("h2h3 e8d8 e2c3").unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Move `e2c3` is not legal in this position"
+    );
+
+    // the moves before it are taken back too
+    assert_eq!(board.fen(), PINNED_KNIGHT_FEN);
+}
+
+// the option is global, so both settings are checked in the one test
+#[test]
+fn promotion_without_a_piece_follows_the_queen_assumption() {
+    let mut board = board(PROMOTION_FEN);
+    let metadata = board.get_move_metadata("e7e8").unwrap();
+
+    let mv = board.find_legal_move(metadata).unwrap().unwrap();
+    assert_eq!(mv.to_uci(), "e7e8q");
+
+    // a piece that's given is never swapped for a queen
+    let knight = board.get_move_metadata("e7e8n").unwrap();
+    assert_eq!(
+        board.find_legal_move(knight).unwrap().unwrap().to_uci(),
+        "e7e8n"
+    );
+
+    board.make_moves_from_str("e7e8").unwrap();
+    assert_eq!(board.fen(), "4Q3/8/8/8/8/8/k7/4K3 b - - 0 1");
+
+    // a new position keeps the setting
+    board.set_assume_queen_promotion(false);
+    board.parse_fen(PROMOTION_FEN).unwrap();
+    assert!(!board.assumes_queen_promotion());
+
+    assert!(board.find_matching_move(metadata).is_none());
+    assert!(board.find_legal_move(metadata).unwrap().is_none());
+
+    let error = board.make_moves_from_str("e7e8").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Move `e7e8` is a promotion without a promotion piece"
+    );
+    assert_eq!(board.fen(), PROMOTION_FEN);
+
+    // and so does a copy, which is what the UCI `position` command plays on
+    assert!(board.clone().find_matching_move(metadata).is_none());
+}
+
+#[test]
+fn an_assumed_promotion_is_still_checked_for_legality() {
+    // the pawn is pinned to its king by the rook on h7, so it can't promote at all
+    let fen = "4r3/K2P3r/8/8/8/8/8/k7 w - - 0 1";
+    let mut board = board(fen);
+
+    for move_str in ["d7d8", "d7e8"] {
+        let metadata = board.get_move_metadata(move_str).unwrap();
+
+        assert!(board.find_matching_move(metadata).is_some());
+        assert!(board.find_legal_move(metadata).unwrap().is_none());
+        assert_eq!(
+            board.make_moves_from_str(move_str).unwrap_err().to_string(),
+            format!("Move `{}` is not legal in this position", move_str)
+        );
+    }
+
+    assert_eq!(board.fen(), fen);
+}
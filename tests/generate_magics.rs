@@ -0,0 +1,56 @@
+// `genmagics` with the default seed regenerates the tables the engine ships with. a full run takes
+// minutes in a debug build, so this only finds a few squares' magics, which is enough to show the
+// seed still leads to the same ones
+
+use krusty::{
+    attacks::{BISHOP_DIRECTIONS, ROOK_DIRECTIONS},
+    generate_magics::{find_magic, verify_magic, DEFAULT_MAGIC_SEED},
+    magics::{MagicNumber, BISHOP_MAGICS, ROOK_MAGICS},
+    square::Square,
+};
+
+// squares with the most and fewest relevant blockers: a1 and d4 for a rook, e4 and c1 for a
+// bishop
+const ROOK_SQUARES: [usize; 2] = [0, 27];
+const BISHOP_SQUARES: [usize; 2] = [2, 28];
+
+fn assert_rediscovered(
+    table: &[MagicNumber; 64],
+    squares: [usize; 2],
+    directions: [(i32, i32); 4],
+) {
+    assert_eq!(DEFAULT_MAGIC_SEED, 123);
+
+    for square in squares {
+        let (magic, size) = find_magic(Square::from(square), directions, DEFAULT_MAGIC_SEED, false);
+
+        assert!(verify_magic(&magic, Square::from(square), directions));
+
+        let shipped = table[square];
+        assert_eq!(magic.magic, shipped.magic, "square {}", square);
+        assert_eq!(magic.shift, shipped.shift, "square {}", square);
+        assert_eq!(magic.mask, shipped.blocker_mask, "square {}", square);
+        assert_eq!(size, 1 << (64 - shipped.shift), "square {}", square);
+    }
+}
+
+#[test]
+fn the_default_seed_finds_the_shipped_rook_magics() {
+    assert_rediscovered(&ROOK_MAGICS, ROOK_SQUARES, ROOK_DIRECTIONS);
+}
+
+#[test]
+fn the_default_seed_finds_the_shipped_bishop_magics() {
+    assert_rediscovered(&BISHOP_MAGICS, BISHOP_SQUARES, BISHOP_DIRECTIONS);
+}
+
+#[test]
+fn a_magic_that_maps_different_attacks_to_one_index_fails_verification() {
+    let square = Square::from(ROOK_SQUARES[0]);
+    let (mut magic, _) = find_magic(square, ROOK_DIRECTIONS, DEFAULT_MAGIC_SEED, false);
+
+    // every blocker combination ends up at index 0
+    magic.magic = 0;
+
+    assert!(!verify_magic(&magic, square, ROOK_DIRECTIONS));
+}
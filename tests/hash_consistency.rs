@@ -0,0 +1,163 @@
+// the hash is updated a piece at a time as moves are made, and has to match the one worked out
+// from the position alone. random games lean towards double pushes, en passant captures and null
+// moves, since those are where the en passant key is hashed in and out
+
+use krusty::{
+    board::Board,
+    move_generator::{Move, MoveFlag, MoveList},
+    prng::Prng,
+    square::{PieceKind, Square},
+};
+
+const FENS: [&str; 4] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    // en passant available straight away
+    "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+];
+
+const GAMES_PER_POSITION: usize = 40;
+const MAX_GAME_LENGTH: usize = 80;
+
+enum Played {
+    Move(Move),
+    Null,
+}
+
+fn assert_hash_matches(board: &Board) {
+    assert_eq!(
+        board.hash(),
+        board.hash_from_scratch(),
+        "incremental hash is out of step in {}",
+        board.fen()
+    );
+}
+
+fn legal_moves(board: &mut Board) -> Vec<Move> {
+    let mut move_list = MoveList::new();
+    board.generate_all_moves(&mut move_list).unwrap();
+
+    let mut legal = Vec::new();
+
+    for index in 0..move_list.length() {
+        let mv = move_list.get(index);
+
+        if board.make_move(mv).unwrap() {
+            legal.push(mv);
+        }
+
+        board.unmake_move(mv);
+        assert_hash_matches(board);
+    }
+
+    legal
+}
+
+fn is_double_push(board: &Board, mv: Move) -> bool {
+    board.get_piece(mv.from_square()).kind == PieceKind::Pawn
+        && mv.from_square().index().abs_diff(mv.to_square().index()) == 16
+}
+
+// moves touching the en passant square are played half the time they're available
+fn pick_move(board: &Board, moves: &[Move], prng: &mut Prng) -> Move {
+    moves
+        .iter()
+        .copied()
+        .find(|mv| mv.flag() == MoveFlag::EnPassant || is_double_push(board, *mv))
+        .filter(|_| prng.random_u64().is_multiple_of(2))
+        .unwrap_or_else(|| moves[prng.random_u64() as usize % moves.len()])
+}
+
+#[test]
+fn incremental_hash_matches_through_random_games_with_null_moves() {
+    let mut prng = Prng::new(2258);
+    let mut board = Board::default();
+
+    let mut en_passant_captures = 0;
+    let mut null_moves_clearing_en_passant = 0;
+
+    for _ in 0..GAMES_PER_POSITION {
+        for fen in FENS {
+            board.parse_fen(fen).unwrap();
+            assert_hash_matches(&board);
+
+            let start_hash = board.hash();
+            let mut played = Vec::new();
+
+            while played.len() < MAX_GAME_LENGTH {
+                let in_check = board.is_in_check(board.side_to_move());
+
+                if !in_check && prng.random_u64().is_multiple_of(4) {
+                    if board.en_passant_square() != Square::None {
+                        null_moves_clearing_en_passant += 1;
+                    }
+
+                    board.make_null_move();
+                    assert_hash_matches(&board);
+                    played.push(Played::Null);
+                    continue;
+                }
+
+                let moves = legal_moves(&mut board);
+
+                if moves.is_empty() {
+                    break;
+                }
+
+                let mv = pick_move(&board, &moves, &mut prng);
+
+                if mv.flag() == MoveFlag::EnPassant {
+                    en_passant_captures += 1;
+                }
+
+                assert!(board.make_move(mv).unwrap());
+                assert_hash_matches(&board);
+                played.push(Played::Move(mv));
+            }
+
+            while let Some(played) = played.pop() {
+                match played {
+                    Played::Move(mv) => board.unmake_move(mv),
+                    Played::Null => board.unmake_null_move(),
+                }
+
+                assert_hash_matches(&board);
+            }
+
+            assert_eq!(board.hash(), start_hash);
+        }
+    }
+
+    assert!(en_passant_captures > 0, "no en passant captures");
+    assert!(
+        null_moves_clearing_en_passant > 0,
+        "no null moves with an en passant square"
+    );
+}
+
+#[test]
+fn no_en_passant_square_adds_nothing_to_the_hash() {
+    let mut board = Board::default();
+
+    // the same position, except that black can capture en passant in one of them
+    board
+        .parse_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1")
+        .unwrap();
+    let with_en_passant = board.hash();
+
+    board.parse_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap();
+    let without_en_passant = board.hash();
+
+    assert_ne!(with_en_passant, without_en_passant);
+
+    // a null move only takes the square out, it never puts a key for "none" in
+    board
+        .parse_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1")
+        .unwrap();
+    board.make_null_move();
+    board.make_null_move();
+
+    assert_eq!(board.en_passant_square(), Square::None);
+    assert_eq!(board.hash(), without_en_passant);
+}
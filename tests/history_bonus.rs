@@ -0,0 +1,42 @@
+// the history bonus is the depth squared, but nothing for the shallowest cutoffs and never more
+// than the cap, so a single deep cutoff can't swamp the table
+
+use krusty::search::{history_bonus, HISTORY_BONUS_MAX, HISTORY_MIN_DEPTH};
+
+#[test]
+fn shallow_cutoffs_get_no_bonus() {
+    assert_eq!(HISTORY_MIN_DEPTH, 2);
+
+    assert_eq!(history_bonus(0), 0);
+    assert_eq!(history_bonus(1), 0);
+}
+
+#[test]
+fn bonus_is_the_depth_squared_up_to_the_cap() {
+    assert_eq!(HISTORY_BONUS_MAX, 400);
+
+    // (depth, bonus)
+    let bonuses = [
+        (2, 4),
+        (3, 9),
+        (4, 16),
+        (6, 36),
+        (10, 100),
+        (19, 361),
+        (20, 400),
+        (21, 400),
+        (64, 400),
+        (u8::MAX, 400),
+    ];
+
+    for (depth, bonus) in bonuses {
+        assert_eq!(history_bonus(depth), bonus, "depth {}", depth);
+    }
+}
+
+#[test]
+fn bonus_never_decreases_with_depth() {
+    for depth in 1..u8::MAX {
+        assert!(history_bonus(depth + 1) >= history_bonus(depth));
+    }
+}
@@ -0,0 +1,37 @@
+// the board's history has room for a long game and the deepest search line from the start, so
+// once a position is set up, searching it never has to grow the history
+
+use krusty::{
+    position::{apply_fen, apply_moves},
+    reporter::SilentReporter,
+    search::Search,
+    search_limits::SearchLimits,
+};
+
+const ENDGAME_FEN: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+
+#[test]
+fn a_depth_12_search_never_grows_the_history() {
+    let mut search = Search::default();
+
+    apply_fen(&mut search.board, ENDGAME_FEN).unwrap();
+    apply_moves(&mut search.board, &["b4b1", "h5h6"]).unwrap();
+    let reserved = search.board.history_size_bytes();
+
+    // the GUI's next `position` command only adds moves, which UCI plays on a copy of the board
+    let mut board = search.board.clone();
+    apply_moves(&mut board, &["b1b4", "h6h5"]).unwrap();
+    search.board = board;
+
+    let before = search.board.history_size_bytes();
+    assert_eq!(before, reserved);
+
+    search
+        .search_position(&SearchLimits::depth(12), &mut SilentReporter)
+        .unwrap();
+
+    assert_eq!(search.board.history_size_bytes(), before);
+
+    // and neither does copying the board, the way each helper thread gets one
+    assert_eq!(search.board.clone().history_size_bytes(), before);
+}
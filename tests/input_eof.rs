@@ -0,0 +1,173 @@
+// once the input is closed no more commands can arrive, so the engine finishes whatever it's
+// doing and exits, the same as after `quit`, rather than reading nothing over and over. a last
+// line cut off without its newline is still a line, and one too long to be a command is skipped
+
+mod common;
+
+use std::{io::Cursor, sync::mpsc, thread};
+
+use common::{assert_uci_output, start_uci, Engine, REPLY_TIMEOUT};
+use krusty::{
+    board::START_POSITION_FEN,
+    cli::Cli,
+    input::{read_line, InputLine, MAX_LINE_LENGTH},
+    search::Search,
+    uci::{Uci, UciExit},
+};
+
+// every line `read_line` gives back from `input`, with `None` for one that was too long
+fn read_lines(input: &str) -> Vec<Option<String>> {
+    let mut input = Cursor::new(input);
+    let mut buffer = String::new();
+    let mut lines = Vec::new();
+
+    loop {
+        match read_line(&mut input, &mut buffer).unwrap() {
+            InputLine::Line => lines.push(Some(buffer.clone())),
+            InputLine::TooLong => lines.push(None),
+            InputLine::Eof => break,
+        }
+    }
+
+    // and it stays at the end, rather than giving back the last line again
+    assert!(matches!(
+        read_line(&mut input, &mut buffer).unwrap(),
+        InputLine::Eof
+    ));
+    assert!(buffer.is_empty());
+
+    lines
+}
+
+#[test]
+fn input_that_ends_mid_line_still_gives_that_line() {
+    assert_eq!(
+        read_lines("isready\nposition star"),
+        [
+            Some("isready\n".to_string()),
+            Some("position star".to_string())
+        ]
+    );
+    assert!(read_lines("").is_empty());
+}
+
+#[test]
+fn a_line_that_is_too_long_is_skipped_and_the_next_one_read() {
+    let too_long = "a".repeat(MAX_LINE_LENGTH as usize + 10);
+
+    assert_eq!(
+        read_lines(&format!("{}\nisready\n", too_long)),
+        [None, Some("isready\n".to_string())]
+    );
+
+    // even when the input ends before the line does
+    assert_eq!(read_lines(&too_long), [None]);
+}
+
+#[test]
+fn a_line_right_at_the_limit_is_kept() {
+    let longest = format!("{}\n", "a".repeat(MAX_LINE_LENGTH as usize - 1));
+
+    assert_eq!(read_lines(&longest), [Some(longest.clone())]);
+}
+
+// runs `run` on a thread of its own, and fails if it hasn't returned in time
+fn returns_in_time(run: impl FnOnce() + Send + 'static) {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        run();
+        sender.send(()).unwrap();
+    });
+
+    receiver
+        .recv_timeout(REPLY_TIMEOUT)
+        .expect("still running after the input ran out");
+}
+
+#[test]
+fn the_uci_loop_returns_at_the_end_of_its_input() {
+    returns_in_time(|| {
+        let mut search = Search::default();
+
+        // the last line has no newline, and is still handled
+        let input = Cursor::new("isready\nposition startpos moves e2e4\ngo depth 2");
+        Uci::new(&mut search).run(input);
+
+        // left the same way as after `quit`
+        assert_eq!(search.board.fen(), START_POSITION_FEN);
+    });
+}
+
+#[test]
+fn the_uci_loop_returns_when_the_input_ends_mid_command() {
+    returns_in_time(|| {
+        let mut search = Search::default();
+
+        let exit = Uci::new(&mut search).run(Cursor::new("uci\nisready\ngo dep"));
+        assert_eq!(exit, UciExit::InputClosed);
+    });
+}
+
+#[test]
+fn a_search_without_end_is_stopped_at_the_end_of_the_input() {
+    returns_in_time(|| {
+        let mut search = Search::default();
+        Uci::new(&mut search).run(Cursor::new("go infinite\n"));
+    });
+}
+
+#[test]
+fn the_cli_loop_returns_at_the_end_of_its_input() {
+    returns_in_time(|| Cli::default().run(Cursor::new("perft 2\nuci\ngo depth 1\n")));
+}
+
+#[test]
+fn closing_the_input_during_a_search_still_gives_a_move() {
+    let mut engine = start_uci();
+
+    engine.send("go infinite");
+    engine.read_until(|line| line.starts_with("info depth"));
+
+    let (output, status) = engine.close_input();
+    assert!(status.success());
+
+    let uci_output: Vec<_> = output
+        .iter()
+        .take_while(|line| !line.starts_with("bestmove"))
+        .collect();
+    uci_output.iter().for_each(|line| assert_uci_output(line));
+
+    let bestmoves = output
+        .iter()
+        .filter(|line| line.starts_with("bestmove"))
+        .count();
+    assert_eq!(bestmoves, 1);
+}
+
+#[test]
+fn the_engine_carries_on_after_a_line_that_is_too_long() {
+    let mut engine = start_uci();
+
+    engine.send(&format!(
+        "position startpos moves {}",
+        "e2e4 ".repeat(20_000)
+    ));
+    assert_eq!(
+        engine.next_line(),
+        "info string input line too long, ignoring it"
+    );
+
+    engine.send("isready");
+    assert_eq!(engine.next_line(), "readyok");
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn closing_the_input_of_the_cli_exits() {
+    let engine = Engine::start();
+
+    let (_, status) = engine.close_input();
+    assert!(status.success());
+}
@@ -0,0 +1,205 @@
+// `search json` prints the same numbers as the `info` lines, as JSON a script can parse without
+// guessing at the format
+
+mod common;
+
+use krusty::{
+    move_generator::Move,
+    reporter::{info_line, json_iteration_line, json_result_line, IterationReport, SearchReporter},
+    root_moves::ScoreBound,
+    search::Search,
+    search_limits::SearchLimits,
+};
+use serde_json::Value;
+
+use common::Engine;
+
+const MIDDLEGAME_FEN: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+const ITERATION_KEYS: [&str; 10] = [
+    "type", "depth", "seldepth", "score", "bound", "nodes", "nps", "time_ms", "pv", "hashfull",
+];
+const RESULT_KEYS: [&str; 7] = [
+    "type", "bestmove", "ponder", "depth", "nodes", "nps", "time_ms",
+];
+
+// the time each iteration is reported as having taken
+const TIME_MS: u128 = 125;
+
+// both ways of printing every iteration
+struct BothFormatsReporter {
+    lines: Vec<(String, String)>,
+}
+
+impl SearchReporter for BothFormatsReporter {
+    fn iteration(&mut self, report: &IterationReport) {
+        let report = IterationReport {
+            time_ms: TIME_MS,
+            ..*report
+        };
+
+        self.lines
+            .push((info_line(&report), json_iteration_line(&report)));
+    }
+
+    fn best_move(&mut self, _best_move: Move, _ponder: Option<Move>) {}
+}
+
+fn search(fen: &str, depth: u8) -> Vec<(String, String)> {
+    let mut search = Search::default();
+    search.board.parse_fen(fen).unwrap();
+
+    let mut reporter = BothFormatsReporter { lines: Vec::new() };
+    search
+        .search_position(&SearchLimits::depth(depth), &mut reporter)
+        .unwrap();
+
+    reporter.lines
+}
+
+fn parse_object(line: &str, keys: &[&str]) -> Value {
+    let value: Value = serde_json::from_str(line).unwrap_or_else(|error| {
+        panic!("{} is not valid JSON: {}", line, error);
+    });
+
+    let object = value.as_object().expect("expected a JSON object");
+    let mut found: Vec<&str> = object.keys().map(String::as_str).collect();
+    let mut expected = keys.to_vec();
+
+    found.sort_unstable();
+    expected.sort_unstable();
+    assert_eq!(found, expected, "{}", line);
+
+    value
+}
+
+// the value after `name` in an `info` line
+fn info_field<'a>(info: &'a str, name: &str) -> &'a str {
+    let mut fields = info.split_whitespace();
+    fields.find(|field| *field == name).unwrap();
+    fields.next().unwrap()
+}
+
+#[test]
+fn iterations_match_the_info_lines() {
+    let lines = search(MIDDLEGAME_FEN, 5);
+    assert_eq!(lines.len(), 5);
+
+    for (info, json) in &lines {
+        let value = parse_object(json, &ITERATION_KEYS);
+
+        assert_eq!(value["type"], "iteration");
+        assert_eq!(value["bound"] == "exact", !info.contains("bound "));
+
+        let depth = value["depth"].as_u64().unwrap();
+        assert_eq!(depth.to_string(), info_field(info, "depth"));
+        assert!(value["seldepth"].as_u64().unwrap() >= depth);
+
+        let nodes = value["nodes"].as_u64().unwrap();
+        assert_eq!(nodes.to_string(), info_field(info, "nodes"));
+        assert_eq!(value["time_ms"].as_u64().unwrap(), TIME_MS as u64);
+        assert_eq!(TIME_MS.to_string(), info_field(info, "time"));
+        assert_eq!(value["nps"].to_string(), info_field(info, "nps"));
+        assert_eq!(value["seldepth"].to_string(), info_field(info, "seldepth"));
+        assert_eq!(
+            value["nps"].as_u64().unwrap(),
+            nodes * 1000 / TIME_MS as u64
+        );
+
+        let score = value["score"].as_object().unwrap();
+        assert_eq!(score.len(), 1);
+        assert_eq!(score["cp"].to_string(), info_field(info, "cp"));
+
+        let pv: Vec<&str> = value["pv"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|mv| mv.as_str().unwrap())
+            .collect();
+        assert_eq!(pv.join(" "), info.split(" pv ").nth(1).unwrap());
+
+        assert!(value["hashfull"].as_u64().unwrap() <= 1000);
+    }
+}
+
+#[test]
+fn mate_scores_are_counted_in_moves() {
+    // Ra8 is mate
+    let lines = search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3);
+    let (info, json) = lines.last().unwrap();
+
+    let value = parse_object(json, &ITERATION_KEYS);
+
+    assert_eq!(info_field(info, "mate"), "1");
+    assert_eq!(value["score"]["mate"], 1);
+    assert_eq!(value["pv"][0], "a1a8");
+}
+
+#[test]
+fn result_has_a_null_ponder_move_when_there_is_no_reply() {
+    let best_move: Move = "e2e4".parse().unwrap();
+    let reply: Move = "e7e5".parse().unwrap();
+
+    let report = IterationReport {
+        depth: 6,
+        seldepth: 11,
+        multipv: None,
+        score: 30,
+        bound: ScoreBound::Exact,
+        nodes: 50_000,
+        time_ms: 500,
+        hashfull: 12,
+        pv: &[best_move, reply],
+        root_moves: &[],
+    };
+
+    let line = json_result_line(best_move, Some(reply), Some(&report), 500);
+    let value = parse_object(&line, &RESULT_KEYS);
+
+    assert_eq!(value["type"], "result");
+    assert_eq!(value["bestmove"], "e2e4");
+    assert_eq!(value["ponder"], "e7e5");
+    assert_eq!(value["depth"], 6);
+    assert_eq!(value["nodes"], 50_000);
+    assert_eq!(value["nps"], 100_000);
+
+    let line = json_result_line(best_move, None, None, 0);
+    let value = parse_object(&line, &RESULT_KEYS);
+
+    assert!(value["ponder"].is_null());
+    assert_eq!(value["depth"], 0);
+}
+
+#[test]
+fn cli_prints_a_line_per_iteration_then_the_result() {
+    let mut engine = Engine::start();
+
+    engine.send("search json 4");
+    let output = engine.read_until(|line| line.contains(r#""type":"result""#));
+
+    // the prompt is printed without a newline, so it ends up in front of the first line
+    let json: Vec<&str> = output
+        .iter()
+        .filter_map(|line| line.find('{').map(|start| &line[start..]))
+        .collect();
+
+    // a search that fails out of its window has another line at the same depth
+    let (result, iterations) = json.split_last().unwrap();
+    let exact: Vec<_> = iterations
+        .iter()
+        .map(|line| parse_object(line, &ITERATION_KEYS))
+        .filter(|value| value["bound"] == "exact")
+        .collect();
+
+    assert_eq!(exact.len(), 4, "{:?}", output);
+
+    for (depth, value) in exact.iter().enumerate() {
+        assert_eq!(value["depth"], depth as u64 + 1);
+    }
+
+    let result = parse_object(result, &RESULT_KEYS);
+    assert_eq!(result["depth"], 4);
+    assert_eq!(result["bestmove"].as_str().unwrap().len(), 4);
+
+    assert!(engine.quit().success());
+}
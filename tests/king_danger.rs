@@ -0,0 +1,111 @@
+// the move generator leaves out king moves onto attacked squares, which `make_move` would only
+// have rejected. nothing else about the moves changes, so perft counts stay the same
+
+mod common;
+
+use krusty::{
+    board::Board,
+    move_generator::{Move, MoveKind, MoveList},
+    prng::Prng,
+    square::{PieceKind, Square},
+};
+
+use common::board;
+
+const FENS: [&str; 4] = [
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "4k3/8/8/8/3q4/8/8/4K3 w - - 0 1",
+];
+
+const GAMES_PER_POSITION: usize = 20;
+const MAX_GAME_LENGTH: usize = 100;
+
+fn king_moves(board: &Board, captures_only: bool) -> Vec<Move> {
+    let mut move_list = MoveList::new();
+
+    if captures_only {
+        board.generate_all_captures(&mut move_list).unwrap();
+    } else {
+        board.generate_all_moves(&mut move_list).unwrap();
+    }
+
+    move_list
+        .into_iter()
+        .filter(|&mv| {
+            mv.kind() != MoveKind::Castle
+                && board.get_piece(mv.from_square()).kind == PieceKind::King
+        })
+        .collect()
+}
+
+fn king_destinations(board: &Board) -> Vec<Square> {
+    king_moves(board, false)
+        .iter()
+        .map(|mv| mv.to_square())
+        .collect()
+}
+
+#[test]
+fn the_king_doesnt_step_back_along_a_check() {
+    // the rook is looked through the king, so e2 is as attacked as e4
+    let board = board("4r1k1/8/8/8/8/8/8/4K3 w - - 0 1");
+
+    let destinations = king_destinations(&board);
+    assert!(!destinations.contains(&Square::E2));
+    assert_eq!(destinations.len(), 4);
+}
+
+#[test]
+fn defended_pieces_are_not_captured_by_the_king() {
+    // the pawn on d2 is defended by the bishop, the one on f2 isn't
+    let board = board("4k3/8/8/8/1b6/8/3p1p2/4K3 w - - 0 1");
+
+    let captures: Vec<_> = king_moves(&board, true)
+        .iter()
+        .map(|mv| mv.to_square())
+        .collect();
+    assert_eq!(captures, [Square::F2]);
+
+    assert!(!king_destinations(&board).contains(&Square::D2));
+}
+
+#[test]
+fn every_king_move_generated_is_legal_through_random_games() {
+    let mut prng = Prng::new(2279);
+
+    for fen in FENS {
+        for _ in 0..GAMES_PER_POSITION {
+            let mut board = board(fen);
+
+            for _ in 0..MAX_GAME_LENGTH {
+                for captures_only in [false, true] {
+                    for mv in king_moves(&board, captures_only) {
+                        assert!(board.make_move(mv).unwrap(), "{} in {}", mv, board.fen());
+                        board.unmake_move(mv);
+                    }
+                }
+
+                let mut move_list = MoveList::new();
+                board.generate_all_moves(&mut move_list).unwrap();
+
+                let legal: Vec<_> = move_list
+                    .into_iter()
+                    .filter(|&mv| {
+                        let is_legal = board.make_move(mv).unwrap();
+                        board.unmake_move(mv);
+                        is_legal
+                    })
+                    .collect();
+
+                if legal.is_empty() {
+                    break;
+                }
+
+                let mv = legal[prng.random_u64() as usize % legal.len()];
+                board.make_move(mv).unwrap();
+            }
+        }
+    }
+}
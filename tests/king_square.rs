@@ -0,0 +1,112 @@
+// the board keeps track of where the kings are as they move instead of looking them up in their
+// bitboards. random games check the two never disagree, while making and unmaking moves and
+// loading new positions over old ones
+
+use krusty::{
+    board::{Board, Side},
+    move_generator::{Move, MoveKind, MoveList},
+    prng::Prng,
+    square::{Piece, PieceKind},
+};
+
+const FENS: [&str; 4] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+];
+
+const GAMES_PER_POSITION: usize = 50;
+const MAX_GAME_LENGTH: usize = 120;
+
+fn assert_king_squares_match(board: &Board) {
+    for side in [Side::White, Side::Black] {
+        let king = board
+            .get_piece_bb(Piece::new(side.into(), PieceKind::King))
+            .unwrap();
+
+        assert_eq!(
+            board.king_square(side),
+            king.get_lsb_square(),
+            "cached {:?} king square is stale in {}",
+            side,
+            board.fen()
+        );
+    }
+}
+
+fn legal_moves(board: &mut Board) -> Vec<Move> {
+    let mut move_list = MoveList::new();
+    board.generate_all_moves(&mut move_list).unwrap();
+
+    let mut legal = Vec::new();
+
+    for index in 0..move_list.length() {
+        let mv = move_list.get(index);
+
+        if board.make_move(mv).unwrap() {
+            legal.push(mv);
+        }
+
+        board.unmake_move(mv);
+        assert_king_squares_match(board);
+    }
+
+    legal
+}
+
+// castling is rare in random games, so it's always played when it's available
+fn pick_move(moves: &[Move], prng: &mut Prng) -> Move {
+    moves
+        .iter()
+        .copied()
+        .find(|mv| mv.kind() == MoveKind::Castle)
+        .unwrap_or_else(|| moves[prng.random_u64() as usize % moves.len()])
+}
+
+#[test]
+fn cached_king_squares_match_the_bitboards_through_random_games() {
+    let mut prng = Prng::new(2243);
+    let mut board = Board::default();
+    let mut castles = 0;
+
+    for game in 0..GAMES_PER_POSITION {
+        for fen in FENS {
+            // loading over whatever the last game left behind
+            board.parse_fen(fen).unwrap();
+            assert_king_squares_match(&board);
+
+            let mut played = Vec::new();
+
+            while played.len() < MAX_GAME_LENGTH {
+                let moves = legal_moves(&mut board);
+
+                if moves.is_empty() {
+                    break;
+                }
+
+                let mv = pick_move(&moves, &mut prng);
+
+                if mv.kind() == MoveKind::Castle {
+                    castles += 1;
+                }
+
+                assert!(board.make_move(mv).unwrap());
+                assert_king_squares_match(&board);
+                played.push(mv);
+            }
+
+            // unwind half the games so the last load starts from a board that has been unmade
+            if game % 2 == 0 {
+                while let Some(mv) = played.pop() {
+                    board.unmake_move(mv);
+                    assert_king_squares_match(&board);
+                }
+
+                assert_eq!(board.fen(), fen);
+            }
+        }
+    }
+
+    assert!(castles > 0, "no game castled");
+}
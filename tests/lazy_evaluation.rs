@@ -0,0 +1,65 @@
+// quiescence stands pat on a lazy evaluation, which only skips mobility when it couldn't change
+// which side of the window the score ends up on, and never strays far from the full evaluation
+
+use krusty::{bench::BENCH_FENS, board::Board, evaluate::LAZY_EVAL_MARGIN, search::INFINITY};
+
+const PERFT_SUITE: &str = include_str!("../perft.epd");
+
+fn boards() -> Vec<Board> {
+    let perft_fens = PERFT_SUITE
+        .lines()
+        .filter_map(|line| line.split(';').next())
+        .map(str::trim)
+        .filter(|fen| !fen.is_empty());
+
+    BENCH_FENS
+        .iter()
+        .copied()
+        .chain(perft_fens)
+        .map(|fen| {
+            let mut board = Board::default();
+            board.parse_fen(fen).unwrap();
+            board
+        })
+        .collect()
+}
+
+#[test]
+fn an_open_window_gets_the_full_evaluation() {
+    for board in boards() {
+        assert_eq!(
+            board.evaluate_lazy(-INFINITY, INFINITY).unwrap(),
+            board.evaluate().unwrap(),
+            "{}",
+            board.fen()
+        );
+    }
+}
+
+#[test]
+fn a_lazy_evaluation_stays_within_the_margin() {
+    for board in boards() {
+        let full = board.evaluate().unwrap();
+
+        for offset in (-3 * LAZY_EVAL_MARGIN..=3 * LAZY_EVAL_MARGIN).step_by(25) {
+            let alpha = full + offset;
+            let beta = alpha + 1;
+
+            let lazy = board.evaluate_lazy(alpha, beta).unwrap();
+
+            assert!(
+                (lazy - full).abs() <= LAZY_EVAL_MARGIN,
+                "{} in ({}, {}): lazy {} full {}",
+                board.fen(),
+                alpha,
+                beta,
+                lazy,
+                full
+            );
+
+            // a fail-hard stand pat only cares which side of the window the score is on
+            assert_eq!(lazy <= alpha, full <= alpha, "{}", board.fen());
+            assert_eq!(lazy >= beta, full >= beta, "{}", board.fen());
+        }
+    }
+}
@@ -0,0 +1,112 @@
+// with more than one thread, helpers search the same position alongside the main thread and share
+// its transposition table. the nodes they search are reported with the main thread's, and the
+// move played is still the main thread's
+
+mod common;
+
+use krusty::{
+    board::Board,
+    move_generator::{Move, MoveList},
+    search::Search,
+    search_limits::SearchLimits,
+};
+
+use common::{assert_uci_output, start_uci_advertising, RecordingReporter};
+
+const FENS: [&str; 2] = [
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+];
+
+// (best move, nodes reported for the last iteration)
+fn search(fen: &str, threads: usize, limits: &SearchLimits) -> (Search, Move, u64) {
+    let mut search = Search::default();
+    search.board.parse_fen(fen).unwrap();
+    search.set_seed(Some(1));
+    search.threads = threads;
+
+    let mut reporter = RecordingReporter::default();
+    let best_move = search.search_position(limits, &mut reporter).unwrap();
+
+    (search, best_move, reporter.nodes())
+}
+
+fn is_legal(board: &mut Board, mv: Move) -> bool {
+    let mut move_list = MoveList::default();
+    board.generate_all_moves(&mut move_list).unwrap();
+
+    (0..move_list.length()).any(|index| {
+        let candidate = move_list.get(index);
+        let legal = board.make_move(candidate).unwrap();
+        board.unmake_move(candidate);
+
+        legal && candidate == mv
+    })
+}
+
+// how many nodes the helpers searched for every one the main thread did
+fn helper_share(fen: &str, threads: usize) -> f64 {
+    // long enough for every thread to get plenty of turns, even with fewer cores than threads
+    let limits = SearchLimits {
+        movetime: Some(500),
+        ..SearchLimits::default()
+    };
+
+    let (mut search, best_move, _) = search(fen, threads, &limits);
+    assert!(is_legal(&mut search.board, best_move), "{}", fen);
+
+    let main_nodes = search.search_info.nodes_searched;
+    (search.nodes_searched() - main_nodes) as f64 / main_nodes as f64
+}
+
+#[test]
+fn helpers_add_to_the_nodes_searched() {
+    for fen in FENS {
+        // the helpers are searching for as long as the main thread is, so the more of them there
+        // are, the more they search between them
+        let two_threads = helper_share(fen, 2);
+        let four_threads = helper_share(fen, 4);
+
+        assert!(two_threads > 0.0, "{}", fen);
+        assert!(
+            four_threads > two_threads,
+            "{}: helpers searched {:.2} nodes per main thread node with 4 threads, {:.2} with 2",
+            fen,
+            four_threads,
+            two_threads
+        );
+    }
+}
+
+#[test]
+fn single_thread_search_is_unchanged() {
+    for fen in FENS {
+        let limits = SearchLimits::depth(4);
+        let (_, best_move, nodes) = search(fen, 1, &limits);
+        let (search, best_move_again, nodes_again) = search(fen, 1, &limits);
+
+        assert_eq!(best_move, best_move_again);
+        assert_eq!(nodes, nodes_again);
+        assert_eq!(nodes, search.search_info.nodes_searched);
+    }
+}
+
+#[test]
+fn uci_threads_option() {
+    let mut engine = start_uci_advertising("option name Threads type spin default 1 min 1 max 64");
+
+    engine.send("setoption name Threads value 3");
+    engine.send("position startpos moves e2e4");
+    engine.send("go depth 6");
+
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+    assert_ne!(output.last().unwrap(), "bestmove 0000");
+
+    // and again, with the helpers started afresh
+    engine.send("go depth 6");
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    assert_ne!(output.last().unwrap(), "bestmove 0000");
+
+    assert!(engine.quit().success());
+}
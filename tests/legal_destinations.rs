@@ -0,0 +1,111 @@
+// a GUI or anything else using the library can ask where the piece on a square can go, to
+// highlight it, without playing every move itself
+
+mod common;
+
+use krusty::{
+    bitboard::{Bitboard, EMPTY_BB},
+    move_generator::MoveKind,
+    square::Square,
+};
+
+use common::board;
+
+// the rook on e2 is pinned along the file, so it can still move up and down it
+const PINNED_ROOK_FEN: &str = "4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1";
+// the knight on d2 is pinned along the diagonal, which no knight move stays on
+const PINNED_KNIGHT_FEN: &str = "4k3/8/8/8/8/4q3/3N4/2K5 w - - 0 1";
+const CASTLING_FEN: &str = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+// the bishop on a6 attacks f1, so castling short would pass through check
+const CASTLING_THROUGH_CHECK_FEN: &str = "r3k2r/8/b7/8/8/8/8/R3K2R w KQkq - 0 1";
+const EN_PASSANT_FEN: &str = "4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 2";
+const PROMOTION_FEN: &str = "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1";
+
+fn squares(squares: &[Square]) -> Bitboard {
+    let mut bitboard = EMPTY_BB;
+
+    for &square in squares {
+        bitboard.set_bit(square);
+    }
+
+    bitboard
+}
+
+#[test]
+fn a_pinned_piece_can_only_move_along_the_pin() {
+    let mut board = board(PINNED_ROOK_FEN);
+
+    assert_eq!(
+        board.legal_destinations(Square::E2).unwrap(),
+        squares(&[Square::E3, Square::E4, Square::E5, Square::E6, Square::E7])
+    );
+}
+
+#[test]
+fn a_pinned_knight_cant_move() {
+    let mut board = board(PINNED_KNIGHT_FEN);
+
+    assert_eq!(board.legal_destinations(Square::D2).unwrap(), EMPTY_BB);
+    assert!(board.legal_moves_from(Square::D2).unwrap().is_empty());
+}
+
+#[test]
+fn the_king_can_castle_to_either_side() {
+    let mut board = board(CASTLING_FEN);
+
+    let destinations = board.legal_destinations(Square::E1).unwrap();
+    assert!(destinations.is_occupied(Square::G1));
+    assert!(destinations.is_occupied(Square::C1));
+    assert!(!destinations.is_occupied(Square::H1));
+
+    let castles = board
+        .legal_moves_from(Square::E1)
+        .unwrap()
+        .into_iter()
+        .filter(|mv| mv.kind() == MoveKind::Castle)
+        .count();
+    assert_eq!(castles, 2);
+
+    assert_eq!(board.fen(), CASTLING_FEN);
+}
+
+#[test]
+fn the_king_cant_castle_through_check() {
+    let mut board = board(CASTLING_THROUGH_CHECK_FEN);
+
+    let destinations = board.legal_destinations(Square::E1).unwrap();
+    assert!(!destinations.is_occupied(Square::G1));
+    assert!(destinations.is_occupied(Square::C1));
+}
+
+#[test]
+fn a_pawn_can_take_en_passant() {
+    let mut board = board(EN_PASSANT_FEN);
+
+    assert_eq!(
+        board.legal_destinations(Square::D5).unwrap(),
+        squares(&[Square::D6, Square::E6])
+    );
+}
+
+#[test]
+fn each_promotion_is_a_move_of_its_own() {
+    let mut board = board(PROMOTION_FEN);
+
+    let moves = board.legal_moves_from(Square::B7).unwrap();
+    assert_eq!(moves.len(), 4);
+    assert!(moves.iter().all(|mv| mv.kind() == MoveKind::Promotion));
+
+    assert_eq!(
+        board.legal_destinations(Square::B7).unwrap(),
+        squares(&[Square::B8])
+    );
+}
+
+#[test]
+fn only_the_side_to_move_has_destinations() {
+    let mut board = board(CASTLING_FEN);
+
+    assert_eq!(board.legal_destinations(Square::E8).unwrap(), EMPTY_BB);
+    assert_eq!(board.legal_destinations(Square::D4).unwrap(), EMPTY_BB);
+}
@@ -0,0 +1,67 @@
+// mates are shown in whole moves: the engine's own when it's mating, and the opponent's when it's
+// getting mated, the same way other engines count them
+
+mod common;
+
+use common::{assert_uci_output, start_uci};
+use krusty::{reporter::score_string, search::INFINITY};
+
+// Philidor's legacy two moves in: black's king has to go back to h8, then the queen is given up
+// and the knight smothers it
+const MATED_IN_2_FEN: &str = "5r1k/6pp/8/6N1/2Q5/8/8/6K1 w - - 0 1";
+const MATED_IN_2_MOVES: &str = "g5f7 h8g8 f7h6";
+
+#[test]
+fn mating_counts_the_engines_own_moves() {
+    let expected = [1, 1, 2, 2, 3, 3, 4, 4];
+
+    for (ply, moves) in (1..=8).zip(expected) {
+        assert_eq!(
+            score_string(INFINITY - ply),
+            format!("mate {}", moves),
+            "ply {}",
+            ply
+        );
+    }
+}
+
+#[test]
+fn getting_mated_counts_the_opponents_moves() {
+    let expected = [0, -1, -1, -2, -2, -3, -3, -4];
+
+    for (ply, moves) in (1..=8).zip(expected) {
+        assert_eq!(
+            score_string(-INFINITY + ply),
+            format!("mate {}", moves),
+            "ply {}",
+            ply
+        );
+    }
+}
+
+#[test]
+fn a_position_mated_in_2_is_reported_as_mate_minus_2() {
+    let mut engine = start_uci();
+    engine.send(&format!(
+        "position fen {} moves {}",
+        MATED_IN_2_FEN, MATED_IN_2_MOVES
+    ));
+    engine.send("go depth 5");
+
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    let last_info = output
+        .iter()
+        .rfind(|line| line.starts_with("info depth ") && !line.contains("bound "))
+        .unwrap();
+    assert!(last_info.contains(" score mate -2 "), "{}", last_info);
+
+    // the king only has the one square
+    assert_eq!(
+        output.last().unwrap().split_whitespace().nth(1),
+        Some("g8h8")
+    );
+
+    assert!(engine.quit().success());
+}
@@ -0,0 +1,45 @@
+// forced mates, mostly strings of checks. the search has to find each one at the depth it takes,
+// with checking moves extended and losing captures that give check left alone
+
+mod common;
+
+use krusty::{reporter::uci_score, search::Search, search_limits::SearchLimits};
+
+use common::RecordingReporter;
+
+// (FEN, moves to mate)
+const MATES: [(&str, i32); 5] = [
+    // back rank
+    ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 1),
+    // smothered
+    ("6rk/6pp/8/6N1/8/8/8/6K1 w - - 0 1", 1),
+    // the rooks take the king up the board a rank at a time
+    ("6k1/8/8/8/8/8/R7/1R4K1 w - - 0 1", 2),
+    // the queen is given up so the knight can smother the king
+    ("r6k/6pp/7N/8/2Q5/8/8/6K1 w - - 0 1", 2),
+    // Philidor's legacy, every white move a check
+    ("5r1k/6pp/8/6N1/2Q5/8/8/6K1 w - - 0 1", 4),
+];
+
+#[test]
+fn every_mate_is_found_at_the_depth_it_takes() {
+    for (fen, moves) in MATES {
+        let mut search = Search::default();
+        search.board.parse_fen(fen).unwrap();
+
+        // the mating side's last move is the deepest ply
+        let depth = (moves * 2 - 1) as u8;
+
+        let mut reporter = RecordingReporter::default();
+        search
+            .search_position(&SearchLimits::depth(depth), &mut reporter)
+            .unwrap();
+
+        assert_eq!(
+            uci_score(reporter.exact_score().unwrap()),
+            ("mate", moves),
+            "{}",
+            fen
+        );
+    }
+}
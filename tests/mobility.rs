@@ -0,0 +1,89 @@
+// mobility only counts squares a piece could safely use, so an early queen sortie isn't worth
+// more than developing a minor piece. and like the rest of the evaluation, it doesn't care which
+// color is which
+
+mod common;
+
+use krusty::{bench::BENCH_FENS, board::Board, search::INFINITY};
+
+use common::board;
+
+const AFTER_E4_E5: &str = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1";
+
+// a lazy evaluation with a window it can't reach skips mobility and returns the rest, so the
+// difference is what mobility adds for the side to move
+fn mobility(board: &Board) -> i32 {
+    board.evaluate().unwrap() - board.evaluate_lazy(INFINITY - 1, INFINITY).unwrap()
+}
+
+fn white_mobility_after(uci: &str) -> i32 {
+    let mut board = board(AFTER_E4_E5);
+    board.make_moves_from_str(uci).unwrap();
+
+    // black is to move, so the score is from black's side
+    -mobility(&board)
+}
+
+#[test]
+fn an_early_queen_sortie_gains_no_more_mobility_than_a_knight() {
+    let queen = white_mobility_after("d1h5");
+    let knight = white_mobility_after("g1f3");
+
+    assert!(queen <= knight, "Qh5 {} against Nf3 {}", queen, knight);
+}
+
+// the same position with the board turned around and the colors swapped
+fn mirror(fen: &str) -> String {
+    let fields: Vec<_> = fen.split_whitespace().collect();
+
+    let swap_case = |text: &str| -> String {
+        text.chars()
+            .map(|ch| match ch {
+                'a'..='z' => ch.to_ascii_uppercase(),
+                'A'..='Z' => ch.to_ascii_lowercase(),
+                _ => ch,
+            })
+            .collect()
+    };
+
+    let placement: Vec<_> = fields[0].split('/').rev().map(swap_case).collect();
+    let side = if fields[1] == "w" { "b" } else { "w" };
+
+    let mut castling: Vec<_> = swap_case(fields[2]).chars().collect();
+    castling.sort_by_key(|ch| (ch.is_ascii_lowercase(), *ch));
+    let castling: String = castling.into_iter().collect();
+
+    let en_passant = match fields[3] {
+        "-" => "-".to_string(),
+        square => {
+            let rank = square.as_bytes()[1] - b'0';
+            format!("{}{}", &square[..1], 9 - rank)
+        }
+    };
+
+    format!(
+        "{} {} {} {} {} {}",
+        placement.join("/"),
+        side,
+        castling,
+        en_passant,
+        fields[4],
+        fields[5]
+    )
+}
+
+#[test]
+fn mirrored_positions_evaluate_the_same() {
+    for fen in BENCH_FENS.iter().copied().chain([AFTER_E4_E5]) {
+        let original = board(fen);
+        let mirrored = board(&mirror(fen));
+
+        assert_eq!(
+            original.evaluate().unwrap(),
+            mirrored.evaluate().unwrap(),
+            "{}",
+            fen
+        );
+        assert_eq!(mobility(&original), mobility(&mirrored), "{}", fen);
+    }
+}
@@ -0,0 +1,190 @@
+// positions set up from a FEN can go well past anything seen in a game, with hundreds of moves or
+// a rank full of pawns promoting by capture. the move list and the ordering scores have to cope
+
+mod common;
+
+use krusty::{
+    board::Board,
+    move_generator::{Move, MoveKind, MoveList, MAX_MOVES},
+    reporter::SilentReporter,
+    search::Search,
+    search_limits::SearchLimits,
+    square::PieceKind,
+};
+
+use common::board;
+#[cfg(not(debug_assertions))]
+use common::RecordingReporter;
+
+// the most legal moves known in a position that can come from a game
+const MOST_MOVES_FEN: &str = "R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1";
+
+// every white pawn can promote, and most of them by taking a knight either way
+const PROMOTION_STORM_FEN: &str = "1n1n1n1n/PPPPPPPP/k7/8/8/8/8/7K w - - 0 1";
+
+// a ring of queens, with more moves than a move list has room for
+#[cfg(not(debug_assertions))]
+const OVERFULL_FEN: &str = "knQQQQQQ/ppQ4R/QQ5Q/Q6Q/Q6Q/Q6Q/Q5QQ/QQQQQQ1K w - - 0 1";
+
+fn legal_moves(board: &mut Board) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    board.generate_all_moves(&mut moves).unwrap();
+
+    moves
+        .into_iter()
+        .filter(|&mv| {
+            let legal = board.make_move(mv).unwrap();
+            board.unmake_move(mv);
+            legal
+        })
+        .collect()
+}
+
+fn search(fen: &str, depth: u8) -> (Search, Move) {
+    let mut search = Search::default();
+    search.board.parse_fen(fen).unwrap();
+
+    let best_move = search
+        .search_position(&SearchLimits::depth(depth), &mut SilentReporter)
+        .unwrap();
+
+    (search, best_move)
+}
+
+#[test]
+fn every_move_of_the_most_crowded_position_is_generated() {
+    let mut board = board(MOST_MOVES_FEN);
+
+    let mut moves = MoveList::new();
+    board.generate_all_moves(&mut moves).unwrap();
+    assert!(moves.length() <= MAX_MOVES);
+
+    assert_eq!(legal_moves(&mut board).len(), 218);
+    assert_eq!(board.fen(), MOST_MOVES_FEN);
+}
+
+#[test]
+fn ordering_picks_every_move_exactly_once() {
+    let board = board(MOST_MOVES_FEN);
+
+    let mut moves = MoveList::new();
+    board.generate_all_moves(&mut moves).unwrap();
+
+    // scores spread over the whole range, with plenty of ties
+    for i in 0..moves.length() {
+        moves
+            .get_mut(i)
+            .set_score((i as u32 * 251) % (Move::MAX_SCORE + 1));
+    }
+
+    let mut picked: Vec<_> = (0..moves.length())
+        .map(|i| moves.pick_ordered_move(i))
+        .collect();
+
+    assert!(picked
+        .windows(2)
+        .all(|pair| pair[0].score() >= pair[1].score()));
+
+    picked.sort_by_key(|mv| mv.bits());
+    picked.dedup();
+    assert_eq!(picked.len(), moves.length());
+}
+
+#[test]
+fn scoring_a_move_again_replaces_its_score() {
+    let mut board = board(PROMOTION_STORM_FEN);
+    let mut mv = legal_moves(&mut board)[0];
+
+    mv.set_score(Move::MAX_SCORE);
+    mv.set_score(1);
+
+    assert_eq!(mv.score(), 1);
+}
+
+#[test]
+fn the_most_crowded_position_can_be_searched() {
+    let (mut search, best_move) = search(MOST_MOVES_FEN, 4);
+
+    assert!(legal_moves(&mut search.board).contains(&best_move));
+    assert_eq!(search.board.fen(), MOST_MOVES_FEN);
+}
+
+#[test]
+fn a_promotion_storm_can_be_searched() {
+    let mut board = board(PROMOTION_STORM_FEN);
+    let moves = legal_moves(&mut board);
+
+    let promotions: Vec<_> = moves
+        .iter()
+        .filter(|mv| mv.kind() == MoveKind::Promotion)
+        .collect();
+    assert_eq!(promotions.len(), 44);
+
+    // promotions by capture score as captures of whatever is on the promotion square
+    let captures = promotions
+        .iter()
+        .filter(|mv| board.get_piece(mv.to_square()).kind != PieceKind::NoPiece)
+        .count();
+    assert_eq!(captures, 28);
+
+    let (mut search, best_move) = search(PROMOTION_STORM_FEN, 4);
+    assert!(legal_moves(&mut search.board).contains(&best_move));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "more than 256 moves generated")]
+fn overfilling_a_move_list_is_caught_in_debug_builds() {
+    let mut moves = MoveList::new();
+
+    for _ in 0..=MAX_MOVES {
+        moves.push(Move::NULL_MOVE);
+    }
+}
+
+#[test]
+#[cfg(not(debug_assertions))]
+fn overfilling_a_move_list_drops_the_extra_moves() {
+    let mut moves = MoveList::new();
+
+    for _ in 0..=MAX_MOVES {
+        moves.push(Move::NULL_MOVE);
+    }
+
+    assert_eq!(moves.length(), MAX_MOVES);
+    assert!(moves.is_truncated());
+}
+
+#[test]
+#[cfg(not(debug_assertions))]
+fn a_search_that_drops_moves_warns_once_each_time() {
+    let board = board(OVERFULL_FEN);
+    let mut moves = MoveList::new();
+    board.generate_all_moves(&mut moves).unwrap();
+    assert!(moves.is_truncated());
+
+    let mut search = Search::default();
+    search.board.parse_fen(OVERFULL_FEN).unwrap();
+
+    // the warning isn't used up by the first search
+    for _ in 0..2 {
+        let mut reporter = RecordingReporter::default();
+        search
+            .search_position(&SearchLimits::depth(3), &mut reporter)
+            .unwrap();
+
+        assert_eq!(
+            reporter.warnings,
+            ["more than 256 moves in a position, ignoring the rest"]
+        );
+    }
+
+    // and a position that fits doesn't get one
+    search.board.parse_fen(MOST_MOVES_FEN).unwrap();
+    let mut reporter = RecordingReporter::default();
+    search
+        .search_position(&SearchLimits::depth(3), &mut reporter)
+        .unwrap();
+
+    assert!(reporter.warnings.is_empty());
+}
@@ -0,0 +1,93 @@
+// moves are written and read in the long algebraic notation UCI uses, which is also what every
+// message shown to a user should contain
+
+use krusty::{
+    board::{Board, START_POSITION_FEN},
+    move_generator::{Move, MoveFlag, MoveKind},
+    square::Square,
+};
+
+#[test]
+fn coordinates_parse_without_a_board() {
+    let mv: Move = "e2e4".parse().unwrap();
+
+    assert_eq!(mv.from_square(), Square::E2);
+    assert_eq!(mv.to_square(), Square::E4);
+    assert_eq!(mv.kind(), MoveKind::Quiet);
+    assert_eq!(mv.flag(), MoveFlag::None);
+    assert_eq!(mv.to_uci(), "e2e4");
+}
+
+#[test]
+fn every_promotion_piece_parses() {
+    let promotions = [
+        ("a7a8n", MoveFlag::KnightPromotion),
+        ("b2b1b", MoveFlag::BishopPromotion),
+        ("g7h8r", MoveFlag::RookPromotion),
+        ("e7e8q", MoveFlag::QueenPromotion),
+    ];
+
+    for (move_str, flag) in promotions {
+        let mv: Move = move_str.parse().unwrap();
+
+        assert_eq!(mv.kind(), MoveKind::Promotion);
+        assert_eq!(mv.flag(), flag);
+        assert_eq!(mv.to_uci(), move_str);
+    }
+}
+
+#[test]
+fn null_move_round_trips() {
+    let mv: Move = "0000".parse().unwrap();
+
+    assert!(mv.is_null());
+    assert_eq!(mv.to_uci(), "0000");
+}
+
+#[test]
+fn malformed_moves_are_rejected() {
+    for move_str in [
+        "", "e2", "e2e", "e2e4qq", "i2e4", "e9e4", "e7e8k", "e7e8Q", "000",
+    ] {
+        assert!(
+            move_str.parse::<Move>().is_err(),
+            "`{}` should not parse",
+            move_str
+        );
+    }
+}
+
+#[test]
+fn debug_shows_the_move_and_its_bits() {
+    let mv: Move = "e7e8q".parse().unwrap();
+    let debug = format!("{:?}", mv);
+
+    assert!(debug.starts_with("e7e8q ("), "{}", debug);
+    assert!(debug.contains("flag: QueenPromotion"), "{}", debug);
+    assert!(
+        debug.contains(&format!("bits: {:#07x}", mv.bits())),
+        "{}",
+        debug
+    );
+}
+
+#[test]
+fn illegal_move_errors_use_uci_notation() {
+    let mut board = Board::default();
+    board.parse_fen(START_POSITION_FEN).unwrap();
+
+    let error = board.make_moves_from_str("e2e4 e7e5 e1e3").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Move `e1e3` is not legal in this position"
+    );
+
+    // castling through check is generated, and only found illegal once played
+    board.parse_fen("4k3/8/8/8/8/8/5r2/4K2R w K - 0 1").unwrap();
+
+    let error = board.make_moves_from_str("e1g1").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Move `e1g1` is not legal in this position"
+    );
+}
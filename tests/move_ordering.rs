@@ -0,0 +1,57 @@
+// taking back the piece that just captured goes before any other capture of a piece worth the
+// same, even when the recapture is made with something more valuable
+
+use krusty::{
+    board::Board,
+    move_generator::{Move, MoveList},
+    search::Search,
+};
+
+// white's knight takes on d5, and black can take it back with the queen or take the other knight
+// with a pawn
+const FEN: &str = "3qk3/8/8/3n2p1/5N2/2N5/8/4K3 w - - 0 1";
+
+fn first_move(search: &Search, previous_move: Move) -> String {
+    let mut moves = MoveList::new();
+    search.board.generate_all_moves(&mut moves).unwrap();
+
+    search.score_moves(&mut moves, Move::NULL_MOVE, previous_move);
+    moves.pick_ordered_move(0).to_uci()
+}
+
+// the position after white's knight takes on d5, and the capture itself
+fn search_after_exchange(fen: &str) -> (Search, Move) {
+    let mut board = Board::default();
+    board.parse_fen(fen).unwrap();
+
+    let metadata = board.get_move_metadata("c3d5").unwrap();
+    let capture = board.find_legal_move(metadata).unwrap().unwrap();
+    board.make_move(capture).unwrap();
+
+    let mut search = Search::default();
+    search.board = board;
+
+    (search, capture)
+}
+
+#[test]
+fn recapture_is_ordered_before_captures_of_equal_value() {
+    let (search, capture) = search_after_exchange(FEN);
+
+    assert_eq!(first_move(&search, capture), "d8d5");
+}
+
+#[test]
+fn without_a_previous_move_the_cheapest_attacker_goes_first() {
+    let (search, _) = search_after_exchange(FEN);
+
+    assert_eq!(first_move(&search, Move::NULL_MOVE), "g5f4");
+}
+
+#[test]
+fn a_more_valuable_victim_still_goes_first() {
+    // the rook on h4 is worth more than the knight that just captured
+    let (search, capture) = search_after_exchange("3qk3/8/8/3n2p1/7R/2N5/8/4K3 w - - 0 1");
+
+    assert_eq!(first_move(&search, capture), "g5h4");
+}
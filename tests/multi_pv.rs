@@ -0,0 +1,121 @@
+// with MultiPV, every iteration searches the root again with the moves already given a line left
+// out, so each line shows a different move. the move played always comes from the first line
+
+mod common;
+
+use krusty::{move_generator::Move, search::Search, search_limits::SearchLimits};
+
+use common::{assert_uci_output, start_uci_advertising, Iteration, RecordingReporter};
+
+const MIDDLEGAME_FEN: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+fn search(fen: &str, depth: u8, multi_pv: usize) -> (Move, Vec<Iteration>) {
+    let mut search = Search::default();
+    search.board.parse_fen(fen).unwrap();
+    search.set_seed(Some(1));
+    search.multi_pv = multi_pv;
+
+    let mut reporter = RecordingReporter::default();
+    let best_move = search
+        .search_position(&SearchLimits::depth(depth), &mut reporter)
+        .unwrap();
+
+    (best_move, reporter.iterations)
+}
+
+#[test]
+fn every_line_shows_a_different_move() {
+    let (best_move, lines) = search(MIDDLEGAME_FEN, 4, 3);
+
+    assert_eq!(lines.len(), 4 * 3);
+
+    for (depth, iteration) in lines.chunks(3).enumerate() {
+        let moves: Vec<Move> = iteration.iter().map(|line| line.pv[0]).collect();
+
+        for (index, line) in iteration.iter().enumerate() {
+            assert_eq!(line.depth as usize, depth + 1);
+            assert_eq!(line.multipv, Some(index + 1));
+            assert!(line
+                .info
+                .starts_with(&format!("info multipv {} depth", index + 1)));
+        }
+
+        assert_ne!(moves[0], moves[1]);
+        assert_ne!(moves[0], moves[2]);
+        assert_ne!(moves[1], moves[2]);
+    }
+
+    // the first line was searched with every move, so nothing left out did better
+    let last = &lines[lines.len() - 3..];
+    assert_eq!(best_move, last[0].pv[0]);
+    assert!(last[0].score >= last[1].score);
+    assert!(last[0].score >= last[2].score);
+}
+
+#[test]
+fn single_line_is_the_normal_search() {
+    let (best_move, lines) = search(MIDDLEGAME_FEN, 5, 1);
+    let (best_move_again, lines_again) = search(MIDDLEGAME_FEN, 5, 1);
+
+    assert_eq!(lines.len(), 5);
+    assert!(lines.iter().all(|line| line.multipv.is_none()));
+    assert!(lines.iter().all(|line| !line.info.contains("multipv")));
+
+    // the first line of a MultiPV search is the same search, up to the point where it carries on
+    // with the second line
+    let (multi_best_move, multi_lines) = search(MIDDLEGAME_FEN, 1, 2);
+
+    assert_eq!(multi_best_move, lines[0].pv[0]);
+    assert_eq!(multi_lines[0].nodes, lines[0].nodes);
+    assert_eq!(multi_lines[0].score, lines[0].score);
+
+    assert_eq!(best_move, best_move_again);
+    assert_eq!(
+        lines.last().unwrap().nodes,
+        lines_again.last().unwrap().nodes
+    );
+}
+
+#[test]
+fn lines_run_out_with_the_legal_moves() {
+    // the black king is in check from the rook, and can only step off the h-file
+    let (best_move, lines) = search("7k/8/8/8/8/8/8/K6R b - - 0 1", 3, 5);
+
+    for depth in 1..=3 {
+        let iteration: Vec<&Iteration> = lines.iter().filter(|line| line.depth == depth).collect();
+        assert_eq!(iteration.len(), 2, "depth {}", depth);
+    }
+
+    assert!(!best_move.is_null());
+}
+
+#[test]
+fn uci_bestmove_is_the_first_line() {
+    let mut engine = start_uci_advertising("option name MultiPV type spin default 1 min 1 max 256");
+
+    engine.send("setoption name MultiPV value 3");
+    engine.send("position startpos moves e2e4");
+    engine.send("go depth 4");
+
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    // the first line can fail out of its aspiration window, which is reported as a bound first
+    let first_lines: Vec<&String> = output
+        .iter()
+        .filter(|line| line.starts_with("info multipv 1 ") && !line.contains("bound "))
+        .collect();
+    assert_eq!(first_lines.len(), 4);
+
+    let third_lines = output
+        .iter()
+        .filter(|line| line.starts_with("info multipv 3 "))
+        .count();
+    assert_eq!(third_lines, 4);
+
+    let first_move = first_lines.last().unwrap().split(" pv ").nth(1).unwrap();
+    let best_move = output.last().unwrap().split_whitespace().nth(1).unwrap();
+    assert!(first_move.starts_with(best_move));
+
+    assert!(engine.quit().success());
+}
@@ -0,0 +1,180 @@
+// with `MustWin` on, a real draw (a repetition, the fifty-move rule, stalemate or a dead draw)
+// costs the engine `MustWinMargin` on top of any contempt, so it plays on for a win at a small
+// cost, but still takes the draw over anything that loses more than the margin
+
+use krusty::{
+    move_generator::Move, position::apply_moves, reporter::SilentReporter, search::Search,
+    search_limits::SearchLimits,
+};
+
+// level material, with pawns keeping the queens off each other's files
+const LEVEL_FEN: &str = "3qk3/3p4/8/8/8/8/3P4/3QK3 w - - 0 1";
+const LEVEL_BLACK_FEN: &str = "3qk3/3p4/8/8/8/8/3P4/3QK3 b - - 0 1";
+
+// the same without white's pawn, and black to move so it's white that can repeat
+const PAWN_DOWN_FEN: &str = "3qk3/3p4/8/8/8/8/8/3QK3 b - - 0 1";
+
+// the kings step aside and back twice, after which the side to move repeats a position for the
+// third time by stepping back again
+const WHITE_SHUFFLE: [&str; 7] = ["e1f1", "e8f8", "f1e1", "f8e8", "e1f1", "e8f8", "f1e1"];
+const BLACK_SHUFFLE: [&str; 7] = ["e8f8", "e1f1", "f8e8", "f1e1", "e8f8", "e1f1", "f8e8"];
+
+fn search_with(fen: &str, moves: &[&str], must_win: bool, margin: i32) -> Search {
+    let mut search = Search::default();
+    search.board.parse_fen(fen).unwrap();
+    apply_moves(&mut search.board, moves).unwrap();
+
+    search.set_seed(Some(1));
+    search.must_win = must_win;
+    search.must_win_margin = margin;
+    search
+}
+
+fn find_move(search: &mut Search, mv: &str) -> Move {
+    let metadata = search.board.get_move_metadata(mv).unwrap();
+    search.board.find_legal_move(metadata).unwrap().unwrap()
+}
+
+// the score of `mv` when it's the only move searched
+fn score_of(search: &mut Search, mv: &str, depth: u8) -> i32 {
+    let limits = SearchLimits {
+        searchmoves: vec![find_move(search, mv)],
+        ..SearchLimits::depth(depth)
+    };
+
+    search
+        .search_position(&limits, &mut SilentReporter)
+        .unwrap();
+    root_score(search, mv)
+}
+
+fn root_score(search: &Search, mv: &str) -> i32 {
+    search
+        .root_moves
+        .moves()
+        .iter()
+        .find(|root_move| root_move.mv.to_string() == mv)
+        .unwrap_or_else(|| panic!("{} wasn't searched at the root", mv))
+        .score
+}
+
+#[test]
+fn a_repetition_costs_the_margin_whichever_side_the_engine_plays() {
+    // black repeats after white's shuffle, and white after black's
+    for (fen, shuffle, repeating_move) in [
+        (LEVEL_FEN, WHITE_SHUFFLE, "f8e8"),
+        (LEVEL_BLACK_FEN, BLACK_SHUFFLE, "f1e1"),
+    ] {
+        let mut search = search_with(fen, &shuffle, true, 30);
+        assert_eq!(score_of(&mut search, repeating_move, 3), -30, "{}", fen);
+
+        // on top of contempt, which applies with or without it
+        search.contempt = 20;
+        assert_eq!(score_of(&mut search, repeating_move, 3), -50, "{}", fen);
+
+        search.must_win = false;
+        assert_eq!(score_of(&mut search, repeating_move, 3), -20, "{}", fen);
+    }
+}
+
+#[test]
+fn stalemate_and_the_fifty_move_rule_cost_the_margin_too() {
+    // Qb6 leaves black's king on a8 without a move
+    let mut search = search_with("k7/8/8/1Q6/8/8/8/7K w - - 0 1", &[], true, 40);
+    assert_eq!(score_of(&mut search, "b5b6", 3), -40);
+
+    // any move but a mate ends the game on the hundredth half move
+    let mut search = search_with("k7/8/8/1Q6/8/8/8/7K w - - 99 80", &[], true, 40);
+    assert_eq!(score_of(&mut search, "h1g1", 3), -40);
+
+    search.must_win = false;
+    assert_eq!(score_of(&mut search, "h1g1", 3), 0);
+}
+
+#[test]
+fn must_win_plays_on_at_a_small_cost_but_not_into_a_loss() {
+    // a pawn down, the draw is the better result without the option, or with a small margin
+    for (must_win, margin) in [(false, 300), (true, 0), (true, 50)] {
+        let mut search = search_with(PAWN_DOWN_FEN, &BLACK_SHUFFLE, must_win, margin);
+        let best_move = search
+            .search_position(&SearchLimits::depth(5), &mut SilentReporter)
+            .unwrap();
+
+        assert_eq!(best_move.to_string(), "f1e1", "margin {}", margin);
+    }
+
+    // but a pawn is worth less than a draw that costs three
+    let mut search = search_with(PAWN_DOWN_FEN, &BLACK_SHUFFLE, true, 300);
+    let best_move = search
+        .search_position(&SearchLimits::depth(5), &mut SilentReporter)
+        .unwrap();
+
+    assert_ne!(best_move.to_string(), "f1e1");
+    assert!(root_score(&search, &best_move.to_string()) > -300);
+}
+
+// drawish endings that either side can trade down or shuffle out of
+const EQUAL_ENDGAMES: [&str; 3] = [
+    "4k3/3q4/8/8/8/8/3Q4/4K3 w - - 0 1",
+    "3rk3/8/8/8/8/8/8/3RK3 w - - 0 1",
+    "4k3/4p3/8/8/8/8/4P3/4K3 w - - 0 1",
+];
+
+const MAX_GAME_PLIES: usize = 160;
+
+// plays the engine against itself until the game is drawn or decided, giving back how many plies
+// it took and how far from even the last position was
+fn self_play(fen: &str, must_win: bool) -> (usize, i32) {
+    let mut search = search_with(fen, &[], must_win, 50);
+    let mut plies = 0;
+
+    while plies < MAX_GAME_PLIES
+        && !search.board.is_draw()
+        && !search.board.has_insufficient_material()
+    {
+        let best_move = search
+            .search_position(&SearchLimits::depth(4), &mut SilentReporter)
+            .unwrap();
+
+        // checkmate or stalemate
+        if best_move.is_null() {
+            break;
+        }
+
+        search.board.make_move(best_move).unwrap();
+        plies += 1;
+    }
+
+    (plies, search.board.evaluate().unwrap().abs())
+}
+
+#[test]
+fn self_play_games_last_longer_and_end_less_even() {
+    let mut plies_without = 0;
+    let mut plies_with = 0;
+    let mut imbalance_without = 0;
+    let mut imbalance_with = 0;
+
+    for fen in EQUAL_ENDGAMES {
+        let (plies, imbalance) = self_play(fen, false);
+        plies_without += plies;
+        imbalance_without += imbalance;
+
+        let (plies, imbalance) = self_play(fen, true);
+        plies_with += plies;
+        imbalance_with += imbalance;
+    }
+
+    assert!(
+        plies_with > plies_without,
+        "{} vs {}",
+        plies_with,
+        plies_without
+    );
+    assert!(
+        imbalance_with > imbalance_without,
+        "{} vs {}",
+        imbalance_with,
+        imbalance_without
+    );
+}
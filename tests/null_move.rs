@@ -0,0 +1,65 @@
+// passing the turn is a quick way to show a position is good enough to stop searching, except
+// where having to move is the problem (zugzwang), which happens most in pawn endings
+
+mod common;
+
+use krusty::{
+    board::{Board, Side},
+    search::Search,
+    search_limits::SearchLimits,
+};
+
+use common::RecordingReporter;
+
+// whoever has to move first gives up their pawn (a trébuchet)
+const MUTUAL_ZUGZWANG_FEN: &str = "8/8/8/2Kp4/3Pk3/8/8/8 w - - 0 1";
+
+fn search(fen: &str, depth: u8) -> (Search, i32) {
+    let mut search = Search::default();
+    search.board.parse_fen(fen).unwrap();
+
+    let mut reporter = RecordingReporter::default();
+    search
+        .search_position(&SearchLimits::depth(depth), &mut reporter)
+        .unwrap();
+
+    (search, reporter.score().unwrap())
+}
+
+#[test]
+fn kings_and_pawns_are_not_material_to_pass_with() {
+    let mut board = Board::default();
+
+    board.parse_fen(MUTUAL_ZUGZWANG_FEN).unwrap();
+    assert!(!board.has_non_pawn_material(Side::White));
+    assert!(!board.has_non_pawn_material(Side::Black));
+
+    // only the side with the knight could pass
+    board
+        .parse_fen("8/8/8/2Kp4/3Pk3/8/8/6n1 w - - 0 1")
+        .unwrap();
+    assert!(!board.has_non_pawn_material(Side::White));
+    assert!(board.has_non_pawn_material(Side::Black));
+}
+
+#[test]
+fn both_sides_of_a_mutual_zugzwang_are_losing() {
+    for fen in [MUTUAL_ZUGZWANG_FEN, "8/8/8/2Kp4/3Pk3/8/8/8 b - - 0 1"] {
+        let (search, score) = search(fen, 8);
+
+        assert!(score < 0, "{} scored {}", fen, score);
+        assert_eq!(search.search_info.debug.null_move_searches, 0, "{}", fen);
+    }
+}
+
+#[test]
+fn null_moves_are_tried_with_pieces_on_the_board() {
+    let (search, _) = search(
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+        6,
+    );
+
+    let debug = search.search_info.debug;
+    assert!(debug.null_move_searches > 0);
+    assert!(debug.null_move_cutoffs <= debug.null_move_searches);
+}
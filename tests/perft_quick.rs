@@ -0,0 +1,68 @@
+// the quick suite is meant to be run before every commit, so it has to pass on its own
+
+use krusty::perft::{run_quick_perft_tests, QUICK_PERFT_MAX_NODES};
+
+const PERFT_SUITE: &str = include_str!("../perft.epd");
+
+#[test]
+fn quick_suite_passes() {
+    // more than one thread, whatever the machine, so the positions get shared out
+    let summary = run_quick_perft_tests(PERFT_SUITE, QUICK_PERFT_MAX_NODES, 3).unwrap();
+
+    for failure in &summary.failures {
+        println!(
+            "{} depth {}: expected {}, got {:?}",
+            failure.fen, failure.depth, failure.expected_nodes, failure.result
+        );
+    }
+
+    assert!(summary.failures.is_empty());
+
+    // positions only listed at a single deep depth are still run, as long as they aren't huge
+    let positions = PERFT_SUITE
+        .lines()
+        .filter(|line| line.contains(";D"))
+        .count();
+    assert_eq!(summary.passed + summary.skipped, positions);
+    assert!(summary.skipped < 10, "skipped {}", summary.skipped);
+}
+
+#[test]
+fn node_limit_leaves_out_deeper_tests() {
+    let suite = "4k3/8/8/8/8/8/8/4K2R w K - 0 1 ;D1 15 ;D2 66 ;D3 1197 ;D4 7059";
+
+    let summary = run_quick_perft_tests(suite, 1_000, 1).unwrap();
+
+    // only depth 2 fits, and it's the only one run
+    assert_eq!(summary.passed, 1);
+    assert_eq!(summary.nodes, 66);
+}
+
+#[test]
+fn single_deep_depths_are_run_unless_far_over_the_limit() {
+    let suite = "\
+        8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1 ;D6 1440467\n\
+        r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 ;D5 193690690";
+
+    let summary = run_quick_perft_tests(suite, QUICK_PERFT_MAX_NODES, 1).unwrap();
+
+    assert_eq!(summary.passed, 1);
+    assert_eq!(summary.skipped, 1);
+}
+
+#[test]
+fn wrong_counts_are_collected_instead_of_stopping_the_run() {
+    let suite = "\
+        4k3/8/8/8/8/8/8/4K2R w K - 0 1 ;D1 17\n\
+        4k3/8/8/8/8/8/8/R3K3 w Q - 0 1 ;D1 17\n\
+        rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20";
+
+    let summary = run_quick_perft_tests(suite, QUICK_PERFT_MAX_NODES, 2).unwrap();
+
+    assert_eq!(summary.passed, 1);
+    assert_eq!(summary.failures.len(), 2);
+    assert!(summary
+        .failures
+        .iter()
+        .all(|failure| failure.expected_nodes == 17 && failure.result.is_ok()));
+}
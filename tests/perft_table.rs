@@ -0,0 +1,59 @@
+// the perft hash table is only a shortcut, so the node counts have to come out the same whether
+// it's there or not, and however small it is
+
+use krusty::{board::Board, perft::perft, perft_table::PerftTable};
+
+// (fen, depth, nodes)
+const POSITIONS: [(&str, u8, u64); 3] = [
+    (
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        4,
+        197_281,
+    ),
+    (
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        3,
+        97_862,
+    ),
+    ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 5, 674_624),
+];
+
+fn perft_totals(table: &mut PerftTable) -> Vec<u64> {
+    let mut board = Board::default();
+
+    POSITIONS
+        .iter()
+        .map(|(fen, depth, _)| {
+            board.parse_fen(fen).unwrap();
+            perft(&mut board, *depth, table).unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn node_counts_do_not_depend_on_the_table() {
+    let expected: Vec<u64> = POSITIONS.iter().map(|(_, _, nodes)| *nodes).collect();
+
+    let mut disabled = PerftTable::new(0);
+    assert_eq!(disabled.capacity_entries(), 0);
+    assert_eq!(perft_totals(&mut disabled), expected);
+
+    // small enough that the positions have to compete for slots
+    let mut small = PerftTable::new(1);
+    assert_eq!(perft_totals(&mut small), expected);
+
+    let mut large = PerftTable::new(32);
+    assert!(large.capacity_entries() > small.capacity_entries());
+    assert_eq!(perft_totals(&mut large), expected);
+
+    // a second run is answered from what the first one stored
+    assert_eq!(perft_totals(&mut large), expected);
+}
+
+#[test]
+fn capacity_is_a_power_of_two() {
+    let table = PerftTable::new(3);
+
+    assert!(table.capacity_entries().is_power_of_two());
+    assert!(table.size_bytes() <= 3 * 1024 * 1024);
+}
@@ -0,0 +1,46 @@
+// every piece has its own slot in the tables indexed by `Piece::index`, and the zobrist keys
+// looked up through it haven't moved (saved hash files depend on that)
+
+use krusty::{
+    board::{Board, START_POSITION_FEN},
+    square::{Piece, PieceColor, PieceKind, PIECE_COUNT},
+};
+
+const KINDS: [PieceKind; 6] = [
+    PieceKind::Pawn,
+    PieceKind::Knight,
+    PieceKind::Bishop,
+    PieceKind::Rook,
+    PieceKind::Queen,
+    PieceKind::King,
+];
+
+#[test]
+fn every_piece_has_a_distinct_index() {
+    let mut seen = [false; PIECE_COUNT];
+
+    for color in [PieceColor::White, PieceColor::Black] {
+        for kind in KINDS {
+            let index = Piece::new(color, kind).index();
+
+            assert!(index < PIECE_COUNT);
+            assert!(
+                !seen[index],
+                "{:?} {:?} shares index {}",
+                color, kind, index
+            );
+            seen[index] = true;
+        }
+    }
+
+    assert_eq!(Piece::new(PieceColor::White, PieceKind::Pawn).index(), 0);
+    assert_eq!(Piece::new(PieceColor::Black, PieceKind::King).index(), 11);
+}
+
+#[test]
+fn start_position_hash_is_unchanged() {
+    let mut board = Board::default();
+    board.parse_fen(START_POSITION_FEN).unwrap();
+
+    assert_eq!(board.hash(), 0x6702_662e_d3ff_1f0d);
+}
@@ -0,0 +1,84 @@
+// the ponder move is the reply to the best move in the last line searched, or what the
+// transposition table has for the position after the best move when the line stops there. either
+// way it's only given if it can actually be played
+
+use krusty::{
+    move_generator::Move,
+    reporter::SilentReporter,
+    search::Search,
+    search_limits::SearchLimits,
+    transposition_table::{SearchEntryFlag, SearchTableEntry},
+};
+
+fn search(fen: &str, depth: u8) -> (Search, Move) {
+    let mut search = Search::default();
+    search.set_seed(Some(1));
+    search.board.parse_fen(fen).unwrap();
+
+    let best_move = search
+        .search_position(&SearchLimits::depth(depth), &mut SilentReporter)
+        .unwrap();
+
+    (search, best_move)
+}
+
+// stores `reply` as the best move of the position after `best_move`
+fn store_reply(search: &mut Search, best_move: Move, reply: &str) {
+    search.board.make_move(best_move).unwrap();
+
+    let reply = if reply == "0000" {
+        Move::NULL_MOVE
+    } else {
+        reply.parse().unwrap()
+    };
+    let hash = search.board.hash();
+    search.transposition_table.store(SearchTableEntry::new(
+        hash,
+        1,
+        0,
+        0,
+        SearchEntryFlag::Exact,
+        reply,
+    ));
+
+    search.board.unmake_move(best_move);
+}
+
+#[test]
+fn the_reply_in_the_line_is_checked_and_the_board_left_alone() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let (mut search, best_move) = search(fen, 4);
+
+    let ponder = search.ponder_move(best_move).unwrap();
+    assert_ne!(ponder, best_move);
+    assert_eq!(search.board.fen(), fen);
+}
+
+#[test]
+fn a_one_move_line_falls_back_on_the_transposition_table() {
+    // a depth 1 search has nothing after the best move in its line, and black's king can step to
+    // e7 whatever white plays
+    let fen = "4k3/8/8/8/8/8/7P/K7 w - - 0 1";
+    let (mut search, best_move) = search(fen, 1);
+    assert_eq!(search.ponder_move(best_move), None);
+
+    store_reply(&mut search, best_move, "e8e7");
+    assert_eq!(
+        search.ponder_move(best_move).map(|mv| mv.to_uci()),
+        Some(String::from("e8e7"))
+    );
+    assert_eq!(search.board.fen(), fen);
+}
+
+#[test]
+fn a_reply_that_cant_be_played_is_left_out() {
+    let fen = "4k3/8/8/8/8/8/7P/K7 w - - 0 1";
+    let (mut search, best_move) = search(fen, 1);
+
+    for reply in ["0000", "e8e6", "a7a6"] {
+        store_reply(&mut search, best_move, reply);
+        assert_eq!(search.ponder_move(best_move), None, "{}", reply);
+    }
+
+    assert_eq!(search.board.fen(), fen);
+}
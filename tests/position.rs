@@ -0,0 +1,274 @@
+// FENs and move lists from the UCI `position` command and the CLI's `fen` and `moves` go through
+// the same code, so a bad one is turned down the same way by both and leaves the board untouched
+
+mod common;
+
+use common::{start_uci, Engine};
+use krusty::{
+    board::{Board, START_POSITION_FEN},
+    move_generator::Move,
+    position::{apply_fen, apply_moves, assumed_promotions, ApplyError},
+};
+
+// the knight on e2 is pinned to its king by the rook on e7
+const PINNED_KNIGHT_FEN: &str = "4k3/4r3/8/8/8/8/4N2P/4K3 w - - 0 1";
+
+// each is played from the start position, and the moves before the bad one are fine
+const BAD_MOVE_LISTS: [&str; 3] = ["e2e4 e7e5 e1e5", "e2e4 e7e5 e2", "e2e4 f7f6 d1h5 0000"];
+
+fn board(fen: &str) -> Board {
+    let mut board = Board::default();
+    apply_fen(&mut board, fen).unwrap();
+    board
+}
+
+#[test]
+fn an_invalid_fen_leaves_the_board_as_it_was() {
+    let mut board = board(PINNED_KNIGHT_FEN);
+    let played = apply_moves(&mut board, &["h2h3"]).unwrap();
+    let before = board.clone();
+
+    for fen in ["", "8/8/8 w - - 0 1", "4k3/4r3/8/8/8/8/4N2P/4K3 x - - 0 1"] {
+        let error = apply_fen(&mut board, fen).unwrap_err();
+        assert!(matches!(error, ApplyError::InvalidFen(_)), "{:?}", error);
+    }
+
+    assert_eq!(board.fen(), before.fen());
+    assert_eq!(board.hash(), before.hash());
+
+    // the move played before is still there to be taken back
+    board.unmake_move(played[0]);
+    assert_eq!(board.fen(), PINNED_KNIGHT_FEN);
+}
+
+#[test]
+fn every_bad_move_says_which_one_it_was() {
+    for (moves, index) in [
+        (["h2h3", "e8d8", "e2c3"], 2),
+        (["h2h3", "e8d8", "e2z3"], 2),
+        (["e1f2", "e7f7", "0000"], 2),
+    ] {
+        let mut board = board(PINNED_KNIGHT_FEN);
+        let error = apply_moves(&mut board, &moves).unwrap_err();
+
+        assert_eq!(error.move_index(), Some(index), "{:?}", moves);
+
+        match error {
+            ApplyError::IllegalMove { move_str, .. } => assert_eq!(move_str, "e2c3"),
+            ApplyError::UnparsableMove { move_str, .. } => assert_eq!(move_str, "e2z3"),
+            ApplyError::NullMoveInCheck { .. } => (),
+            error => panic!("unexpected error: {:?}", error),
+        }
+
+        // the moves before it are taken back too
+        assert_eq!(board.fen(), PINNED_KNIGHT_FEN);
+    }
+}
+
+#[test]
+fn moves_can_only_be_played_once_there_is_a_position() {
+    let mut board = Board::default();
+
+    let error = apply_moves(&mut board, &["e2e4"]).unwrap_err();
+    assert!(matches!(error, ApplyError::Board(_)), "{:?}", error);
+    assert_eq!(error.move_index(), None);
+}
+
+#[test]
+fn the_moves_are_given_back_as_they_were_played() {
+    let fen = "8/4P3/8/8/8/8/k7/4K3 w - - 0 1";
+    let mut board = board(fen);
+
+    let moves = ["e7e8", "0000", "e8e4", "a2a3"];
+    let played = apply_moves(&mut board, &moves).unwrap();
+
+    let played_strs: Vec<_> = played.iter().map(Move::to_string).collect();
+    assert_eq!(played_strs, ["e7e8q", "0000", "e8e4", "a2a3"]);
+
+    // only the promotion that was sent without its piece was guessed at
+    let assumed: Vec<_> = assumed_promotions(&played, &moves)
+        .map(|(mv, move_str)| (mv.to_string(), move_str))
+        .collect();
+    assert_eq!(assumed, [("e7e8q".to_string(), "e7e8")]);
+
+    let played = apply_moves(&mut board, &["e4e8"]).unwrap();
+    assert_eq!(assumed_promotions(&played, &["e4e8"]).count(), 0);
+}
+
+// white plays two pawns forward with black passing in between, then both pass and black replies
+const NULL_MOVE_LIST: [&str; 6] = ["e2e4", "0000", "d2d4", "0000", "0000", "e7e5"];
+const AFTER_NULL_MOVE_LIST_FEN: &str =
+    "rnbqkbnr/pppp1ppp/8/4p3/3PP3/8/PPP2PPP/RNBQKBNR w KQkq - 0 4";
+
+#[test]
+fn null_moves_in_a_list_hash_the_same_as_playing_them_by_hand() {
+    let mut from_list = board(START_POSITION_FEN);
+    apply_moves(&mut from_list, &NULL_MOVE_LIST).unwrap();
+
+    let mut by_hand = board(START_POSITION_FEN);
+    for move_str in NULL_MOVE_LIST {
+        if move_str == "0000" {
+            by_hand.make_null_move();
+        } else {
+            let metadata = by_hand.get_move_metadata(move_str).unwrap();
+            let mv = by_hand.find_legal_move(metadata).unwrap().unwrap();
+            assert!(by_hand.make_move(mv).unwrap());
+        }
+    }
+
+    assert_eq!(from_list.hash(), by_hand.hash());
+    assert_eq!(from_list.hash(), from_list.hash_from_scratch());
+
+    // and the same as the position set up on its own
+    let expected = board(AFTER_NULL_MOVE_LIST_FEN);
+    assert_eq!(from_list.hash(), expected.hash());
+    assert_eq!(
+        from_list
+            .fen()
+            .split_whitespace()
+            .take(4)
+            .collect::<Vec<_>>(),
+        expected
+            .fen()
+            .split_whitespace()
+            .take(4)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn null_moves_are_taken_back_like_any_other() {
+    let mut board = board(START_POSITION_FEN);
+    let start_hash = board.hash();
+
+    let played = apply_moves(&mut board, &NULL_MOVE_LIST).unwrap();
+    assert_eq!(played.iter().filter(|mv| mv.is_null()).count(), 3);
+
+    for &mv in played.iter().rev() {
+        if mv.is_null() {
+            board.unmake_null_move();
+        } else {
+            board.unmake_move(mv);
+        }
+    }
+
+    assert_eq!(board.fen(), START_POSITION_FEN);
+    assert_eq!(board.hash(), start_hash);
+}
+
+#[test]
+fn uci_searches_for_the_side_handed_the_turn() {
+    let mut engine = start_uci();
+
+    // white passes straight back, so it's black to move with white's pawns on d4 and e4
+    engine.send("position startpos moves e2e4 0000 d2d4");
+    engine.send("go depth 3");
+
+    let bestmove = engine.read_until(|line| line.starts_with("bestmove"));
+    let mv = bestmove.last().unwrap().split_whitespace().nth(1).unwrap();
+
+    let mut expected = board(START_POSITION_FEN);
+    apply_moves(&mut expected, &["e2e4", "0000", "d2d4"]).unwrap();
+    assert!(apply_moves(&mut expected, &[mv]).is_ok(), "{}", mv);
+
+    assert!(engine.quit().success());
+}
+
+// the next thing the CLI prints, without the prompt in front of it
+fn cli_reply(engine: &mut Engine) -> String {
+    let line = engine.next_line();
+    line.rsplit("krusty> ").next().unwrap().to_string()
+}
+
+// the FEN the CLI's `print` shows
+fn printed_fen(engine: &mut Engine) -> String {
+    engine.send("print");
+
+    let output = engine.read_until(|line| line.starts_with("Checkers: "));
+    // the blank line after the board
+    assert_eq!(engine.next_line(), "");
+
+    let fen = output.iter().find_map(|line| line.strip_prefix("FEN: "));
+    fen.unwrap().to_string()
+}
+
+#[test]
+fn the_cli_keeps_its_position_after_bad_input() {
+    let mut engine = Engine::start();
+
+    // past the greeting
+    engine.send(&format!("fen {}", PINNED_KNIGHT_FEN));
+    assert_eq!(printed_fen(&mut engine), PINNED_KNIGHT_FEN);
+
+    // an invalid FEN used to leave the board cleared
+    engine.send("fen 8/8/8 w - - 0 1");
+    assert!(cli_reply(&mut engine).starts_with("invalid FEN: "));
+    assert_eq!(printed_fen(&mut engine), PINNED_KNIGHT_FEN);
+
+    engine.send("moves h2h3 e8d8 e2c3");
+    assert_eq!(
+        cli_reply(&mut engine),
+        "Move `e2c3` is not legal in this position"
+    );
+    assert_eq!(printed_fen(&mut engine), PINNED_KNIGHT_FEN);
+
+    engine.close_input();
+}
+
+#[test]
+fn the_cli_takes_position_current_moves_too() {
+    let mut engine = Engine::start();
+
+    engine.send(&format!("fen {}", PINNED_KNIGHT_FEN));
+    engine.send("position current moves h2h3 e8d8");
+    let played = printed_fen(&mut engine);
+    assert_eq!(played, "3k4/4r3/8/8/8/7P/4N3/4K3 w - - 1 1");
+
+    // all or nothing, the same as `moves`
+    engine.send("position current moves h3h4 d8c8 e2c3");
+    assert_eq!(
+        cli_reply(&mut engine),
+        "Move `e2c3` is not legal in this position"
+    );
+    assert_eq!(printed_fen(&mut engine), played);
+
+    engine.send("position currently moves e1d1");
+    assert_eq!(
+        cli_reply(&mut engine),
+        "Invalid position command, expected `position current moves ...`"
+    );
+    assert_eq!(printed_fen(&mut engine), played);
+
+    engine.close_input();
+}
+
+#[test]
+fn uci_and_the_cli_turn_down_the_same_moves() {
+    let mut cli = Engine::start();
+    cli.send("fen startpos");
+    assert_eq!(printed_fen(&mut cli), START_POSITION_FEN);
+
+    let mut uci = start_uci();
+
+    for moves in BAD_MOVE_LISTS {
+        cli.send(&format!("moves {}", moves));
+        let cli_error = cli_reply(&mut cli);
+
+        uci.send(&format!("position startpos moves {}", moves));
+        let uci_error = uci.next_line();
+
+        let move_number = moves.split_whitespace().count();
+        assert_eq!(
+            uci_error,
+            format!(
+                "info string move {} of the `position` command: {}, keeping the previous position",
+                move_number, cli_error
+            )
+        );
+
+        assert_eq!(printed_fen(&mut cli), START_POSITION_FEN);
+    }
+
+    assert!(uci.quit().success());
+    cli.close_input();
+}
@@ -0,0 +1,110 @@
+// with the `profile` feature, bench breaks its time down by phase, counting every thread that
+// searched. without it, there's nothing to report. run these with `cargo test --features profile`
+
+mod common;
+
+use krusty::{bench::run_bench, profile, search::Search};
+
+#[cfg(not(feature = "profile"))]
+#[test]
+fn there_is_no_profile_without_the_feature() {
+    assert!(profile::summary().is_none());
+
+    let mut search = Search::default();
+    assert!(run_bench(&mut search, 1).unwrap().profile.is_none());
+}
+
+#[cfg(feature = "profile")]
+mod with_profile {
+    use std::{
+        sync::{Mutex, MutexGuard},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use krusty::{
+        bench::BENCH_FENS, profile::Phase, reporter::SilentReporter, search_limits::SearchLimits,
+    };
+
+    use super::*;
+    use crate::common::Engine;
+
+    // the counters are shared by every thread in the process, so only one test can use them at a
+    // time
+    static COUNTERS: Mutex<()> = Mutex::new(());
+
+    fn lock_counters() -> MutexGuard<'static, ()> {
+        COUNTERS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn total(summary: &[(Phase, Duration)]) -> Duration {
+        summary.iter().map(|(_, time)| *time).sum()
+    }
+
+    #[test]
+    fn every_phase_gets_time_during_bench_on_another_thread() {
+        let _counters = lock_counters();
+
+        // the UCI bench runs on a worker thread, not the one that reads the summary
+        let result = thread::spawn(|| run_bench(&mut Search::default(), 4).unwrap())
+            .join()
+            .unwrap();
+
+        for (phase, time) in result.profile.unwrap() {
+            assert!(!time.is_zero(), "no time spent on {}", phase.name());
+        }
+
+        assert_eq!(profile::summary(), result.profile);
+    }
+
+    #[test]
+    fn helper_threads_count_towards_the_summary() {
+        let _counters = lock_counters();
+
+        let mut search = Search::default();
+        search.threads = 4;
+        search.board.parse_fen(BENCH_FENS[1]).unwrap();
+
+        profile::reset();
+        let started = Instant::now();
+        search
+            .search_position(&SearchLimits::depth(7), &mut SilentReporter)
+            .unwrap();
+        let elapsed = started.elapsed();
+
+        // four threads searching side by side spend far more time in the phases between them than
+        // the search took, which one thread on its own can't
+        let profiled = total(&profile::summary().unwrap());
+        assert!(profiled > elapsed * 2, "{:?} in {:?}", profiled, elapsed);
+    }
+
+    #[test]
+    fn the_printed_percentages_add_up_to_100() {
+        let mut engine = Engine::start();
+
+        engine.send("bench 3");
+        engine.read_until(|line| line.ends_with("Profile:"));
+
+        let percentages: Vec<f64> = (0..Phase::ALL.len())
+            .map(|_| engine.next_line())
+            .map(|line| {
+                let (_, percentage) = line.split_once(": ").unwrap();
+                percentage.split('%').next().unwrap().parse().unwrap()
+            })
+            .collect();
+
+        let sum: f64 = percentages.iter().sum();
+
+        // each one is rounded to a tenth
+        assert!(
+            (sum - 100.0).abs() <= 0.05 * Phase::ALL.len() as f64,
+            "{}",
+            sum
+        );
+        assert!(percentages.iter().all(|&percentage| percentage > 0.0));
+
+        assert!(engine.quit().success());
+    }
+}
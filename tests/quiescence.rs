@@ -0,0 +1,103 @@
+// the capture search past the horizon has to stop somewhere, even in positions where every piece
+// can take another, and it has to notice when the position it was handed is already a draw
+
+mod common;
+
+use krusty::{
+    move_generator::Move,
+    search::{Search, CHECKMATE_THRESHOLD},
+    search_limits::SearchLimits,
+};
+
+use common::RecordingReporter;
+
+// every queen attacks the queen facing it on the same file, and both back ranks are full, so
+// there's a capture available for the next 30 or so plies
+const CAPTURE_CHAIN_FEN: &str = "qqqqkqqq/qqqqqqqq/8/8/8/8/QQQQQQQQ/QQQQKQQQ w - - 0 1";
+
+// without the depth cap the capture search doesn't finish a single iteration in hundreds of
+// millions of nodes
+const NODE_BUDGET: u64 = 5_000_000;
+
+// black is a queen down, so every move loses unless it's a draw
+const QUEEN_DOWN_FEN: &str = "k7/8/8/8/8/8/4Q3/7K b - - 0 1";
+
+#[test]
+fn capture_chains_are_cut_off_with_a_sane_score() {
+    let mut search = Search::default();
+    search.board.parse_fen(CAPTURE_CHAIN_FEN).unwrap();
+
+    // the node limit is a backstop so a regression fails instead of hanging
+    let limits = SearchLimits {
+        depth: Some(1),
+        nodes: Some(NODE_BUDGET),
+        ..SearchLimits::default()
+    };
+
+    let mut reporter = RecordingReporter::default();
+    let best_move = search.search_position(&limits, &mut reporter).unwrap();
+
+    let info = search.search_info;
+    assert!(
+        info.nodes_searched < NODE_BUDGET,
+        "the search had to be stopped"
+    );
+    assert!(info.quiescence_nodes > 0);
+    assert!(info.quiescence_nodes <= info.nodes_searched);
+
+    let score = reporter.score().expect("the first iteration should finish");
+    assert!(score.abs() < CHECKMATE_THRESHOLD);
+    assert!(!best_move.is_null());
+}
+
+// a depth 1 search goes straight from each root move into the capture search
+fn search_depth_one(search: &mut Search) -> (Move, i32) {
+    search.set_seed(Some(1));
+
+    let mut reporter = RecordingReporter::default();
+    let best_move = search
+        .search_position(&SearchLimits::depth(1), &mut reporter)
+        .unwrap();
+
+    (best_move, reporter.score().unwrap())
+}
+
+#[test]
+fn repetition_at_the_horizon_is_a_draw() {
+    let mut search = Search::default();
+    search.board.parse_fen(QUEEN_DOWN_FEN).unwrap();
+
+    // kb8 takes black back to the position after the third move, which is the only way out of
+    // losing the queen's worth of material
+    search
+        .board
+        .make_moves_from_str("a8b8 h1h2 b8a8 h2h1 a8b8 h1h2")
+        .unwrap();
+
+    let (best_move, score) = search_depth_one(&mut search);
+
+    assert_eq!(best_move.to_string(), "b8a8");
+    assert_eq!(score, 0);
+}
+
+#[test]
+fn fifty_move_rule_at_the_horizon_is_a_draw() {
+    let mut search = Search::default();
+    search
+        .board
+        .parse_fen("k7/8/8/8/8/8/4Q3/7K b - - 99 80")
+        .unwrap();
+
+    // any quiet move is the hundredth reversible one
+    let (_, score) = search_depth_one(&mut search);
+    assert_eq!(score, 0);
+
+    // with room left on the clock, the material counts
+    search
+        .board
+        .parse_fen("k7/8/8/8/8/8/4Q3/7K b - - 90 80")
+        .unwrap();
+
+    let (_, score) = search_depth_one(&mut search);
+    assert!(score < -500, "score {}", score);
+}
@@ -0,0 +1,118 @@
+// analysis GUIs show why the moves that weren't chosen fail, using `info refutation` lines. they're
+// only printed when analysing, i.e. with MultiPV or debug mode on
+
+mod common;
+
+use common::{assert_uci_output, start_uci, Engine};
+use krusty::{
+    board::Board,
+    reporter::SilentReporter,
+    search::{Search, REFUTED_CANDIDATES},
+    search_limits::SearchLimits,
+};
+
+// taking the pawn on d5 loses the queen to exd5
+const QUEEN_TRAP_FEN: &str = "4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1";
+const LOSING_MOVE: &str = "d1d5";
+const PUNISHING_REPLY: &str = "e6d5";
+
+fn start_on_queen_trap() -> Engine {
+    let mut engine = start_uci();
+    engine.send(&format!("position fen {}", QUEEN_TRAP_FEN));
+
+    engine
+}
+
+// the refutation lines printed with the last iteration, which come after its first line
+fn last_refutations(engine: &mut Engine, depth: u8) -> Vec<String> {
+    engine.send(&format!("go depth {}", depth));
+
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    let last_iteration = output
+        .iter()
+        .position(|line| line.contains(&format!(" depth {} ", depth)))
+        .unwrap();
+
+    output[last_iteration..]
+        .iter()
+        .filter(|line| line.starts_with("info refutation "))
+        .cloned()
+        .collect()
+}
+
+fn assert_refuted(refutations: &[String]) {
+    let line = refutations
+        .iter()
+        .find(|line| line.starts_with(&format!("info refutation {} ", LOSING_MOVE)))
+        .unwrap_or_else(|| panic!("{} wasn't refuted: {:?}", LOSING_MOVE, refutations));
+
+    let moves: Vec<_> = line.split_whitespace().skip(2).collect();
+    assert_eq!(moves[..2], [LOSING_MOVE, PUNISHING_REPLY]);
+}
+
+#[test]
+fn the_search_keeps_the_reply_that_refuted_a_root_move() {
+    let mut search = Search::default();
+    search.board.parse_fen(QUEEN_TRAP_FEN).unwrap();
+
+    let best_move = search
+        .search_position(&SearchLimits::depth(6), &mut SilentReporter)
+        .unwrap();
+    assert_ne!(best_move.to_string(), LOSING_MOVE);
+
+    let root_moves = search.root_moves.moves();
+    let losing_move = root_moves
+        .iter()
+        .find(|root_move| root_move.mv.to_string() == LOSING_MOVE)
+        .unwrap();
+    assert_eq!(losing_move.refutation[0].to_string(), PUNISHING_REPLY);
+
+    // the best move wasn't refuted, and only a few of the others keep a line
+    let best = root_moves
+        .iter()
+        .find(|root_move| root_move.mv == best_move)
+        .unwrap();
+    assert!(best.refutation.is_empty());
+
+    let refuted = root_moves
+        .iter()
+        .filter(|root_move| !root_move.refutation.is_empty())
+        .count();
+    assert!(refuted <= REFUTED_CANDIDATES);
+
+    // and each line is playable from the root
+    let mut board = Board::default();
+    board.parse_fen(QUEEN_TRAP_FEN).unwrap();
+    for root_move in root_moves.iter().filter(|mv| !mv.refutation.is_empty()) {
+        let line: Vec<_> = [root_move.mv]
+            .iter()
+            .chain(&root_move.refutation)
+            .copied()
+            .collect();
+        assert!(board.line_to_san(&line).is_ok(), "{:?}", line);
+    }
+}
+
+#[test]
+fn refutations_are_shown_in_debug_mode() {
+    let mut engine = start_on_queen_trap();
+
+    assert!(last_refutations(&mut engine, 6).is_empty());
+
+    engine.send("debug on");
+    assert_refuted(&last_refutations(&mut engine, 6));
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn refutations_are_shown_with_multi_pv() {
+    let mut engine = start_on_queen_trap();
+
+    engine.send("setoption name MultiPV value 2");
+    assert_refuted(&last_refutations(&mut engine, 6));
+
+    assert!(engine.quit().success());
+}
@@ -0,0 +1,99 @@
+// a quiet move goes a little further down the list when the reply that refuted it last time is
+// still there to be played, but only while the refuting piece is still on its square
+
+use krusty::{
+    board::{Board, Side},
+    move_generator::{Move, MoveList},
+    search::{Search, REFUTED_MOVE_PENALTY},
+};
+
+// the rook on h1 can go up the file, and black's rook is ready to come down to a1
+const FEN: &str = "r3k3/8/8/8/8/8/8/4K2R w - - 0 1";
+
+const HISTORY_SCORE: u32 = 100;
+
+fn search() -> Search {
+    let mut board = Board::default();
+    board.parse_fen(FEN).unwrap();
+
+    let mut search = Search::default();
+    search.board = board;
+
+    search
+}
+
+fn find_move(board: &mut Board, uci: &str) -> Move {
+    let metadata = board.get_move_metadata(uci).unwrap();
+    board.find_legal_move(metadata).unwrap().unwrap()
+}
+
+fn remember(search: &mut Search, uci: &str) {
+    let mv = find_move(&mut search.board, uci);
+    search.history[Side::White.index()][mv.from_square().index()][mv.to_square().index()] =
+        HISTORY_SCORE;
+}
+
+fn refute(search: &mut Search, uci: &str, reply: &str) {
+    let mv = find_move(&mut search.board, uci);
+
+    let mut board = search.board.clone();
+    board.make_move(mv).unwrap();
+    let reply = find_move(&mut board, reply);
+
+    search.refutations[Side::Black.index()][mv.from_square().index()][mv.to_square().index()] =
+        reply;
+}
+
+fn scores(search: &Search) -> Vec<(String, u32)> {
+    let mut moves = MoveList::new();
+    search.board.generate_all_moves(&mut moves).unwrap();
+    search.score_moves(&mut moves, Move::NULL_MOVE, Move::NULL_MOVE);
+
+    (0..moves.length())
+        .map(|i| moves.get(i))
+        .map(|mv| (mv.to_uci(), mv.score()))
+        .collect()
+}
+
+fn score_of(scores: &[(String, u32)], uci: &str) -> u32 {
+    scores.iter().find(|(mv, _)| mv == uci).unwrap().1
+}
+
+#[test]
+fn a_move_whose_refutation_is_still_there_is_ordered_lower() {
+    let mut search = search();
+    remember(&mut search, "h1h4");
+    remember(&mut search, "h1h5");
+    refute(&mut search, "h1h5", "a8a1");
+
+    let scores = scores(&search);
+
+    assert_eq!(score_of(&scores, "h1h4"), HISTORY_SCORE);
+    assert_eq!(
+        score_of(&scores, "h1h5"),
+        HISTORY_SCORE - REFUTED_MOVE_PENALTY as u32
+    );
+}
+
+#[test]
+fn a_refutation_by_a_piece_thats_gone_is_ignored() {
+    let mut search = search();
+    remember(&mut search, "h1h5");
+    refute(&mut search, "h1h5", "a8a1");
+
+    // the same position, but without the rook that played the refutation
+    search
+        .board
+        .parse_fen("4k3/8/8/8/8/8/8/4K2R w - - 0 1")
+        .unwrap();
+
+    assert_eq!(score_of(&scores(&search), "h1h5"), HISTORY_SCORE);
+}
+
+#[test]
+fn the_penalty_never_takes_a_score_below_zero() {
+    let mut search = search();
+    refute(&mut search, "h1h5", "a8a1");
+
+    assert_eq!(score_of(&scores(&search), "h1h5"), 0);
+}
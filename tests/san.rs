@@ -0,0 +1,118 @@
+// moves shown to people are in standard algebraic notation. it depends on the position, so it's
+// worked out on the board
+
+mod common;
+
+use krusty::{
+    board::Board,
+    make_move::MoveMetadata,
+    move_generator::{Move, MoveList},
+    square::Square,
+};
+
+use common::board;
+
+fn legal_move(board: &mut Board, uci: &str) -> Move {
+    let mv: Move = uci.parse().unwrap();
+    board
+        .find_legal_move(MoveMetadata::from(mv))
+        .unwrap()
+        .unwrap_or_else(|| panic!("{} is not legal", uci))
+}
+
+fn san(fen: &str, uci: &str) -> String {
+    let mut board = board(fen);
+    let mv = legal_move(&mut board, uci);
+    let san = board.to_san(mv).unwrap();
+
+    assert_eq!(board.fen(), fen);
+    san
+}
+
+#[test]
+fn pieces_pawns_and_captures() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(san(start, "e2e4"), "e4");
+    assert_eq!(san(start, "g1f3"), "Nf3");
+
+    let fen = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(san(fen, "e4d5"), "exd5");
+    assert_eq!(san(fen, "f1b5"), "Bb5+");
+
+    let en_passant = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+    assert_eq!(san(en_passant, "e5d6"), "exd6");
+}
+
+#[test]
+fn promotions_castling_and_mate() {
+    assert_eq!(san("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), "b8=Q+");
+    assert_eq!(san("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7a8n"), "bxa8=N");
+
+    let castling = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    assert_eq!(san(castling, "e1g1"), "O-O");
+    assert_eq!(san(castling, "e1c1"), "O-O-O");
+
+    assert_eq!(san("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8#");
+}
+
+#[test]
+fn ambiguous_moves_name_their_starting_square() {
+    // knights on b1 and f1 can both reach d2, on different files
+    assert_eq!(san("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", "b1d2"), "Nbd2");
+
+    // rooks on a1 and a5 share the file
+    assert_eq!(san("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "a1a3"), "R1a3");
+
+    // queens on e4, h4 and h1 all reach e1, and h4 shares its file with one and its rank with
+    // the other
+    assert_eq!(san("1k6/8/8/8/4Q2Q/8/8/K6Q w - - 0 1", "h4e1"), "Qh4e1");
+
+    // a pinned knight isn't a rival
+    assert_eq!(san("4k3/4r3/8/8/8/8/4N3/2N1K3 w - - 0 1", "c1d3"), "Nd3");
+}
+
+#[test]
+fn lines_are_played_out_and_taken_back() {
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    let mut board = board(fen);
+
+    let line: Vec<Move> = ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5"]
+        .iter()
+        .map(|uci| uci.parse().unwrap())
+        .collect();
+
+    // parsed moves don't know what they capture or whether they're double pushes, so play them
+    // through the board to get the real ones
+    let mut moves = Vec::new();
+    for mv in &line {
+        let mv = board
+            .find_legal_move(MoveMetadata::from(*mv))
+            .unwrap()
+            .unwrap();
+        board.make_move(mv).unwrap();
+        moves.push(mv);
+    }
+    for mv in moves.iter().rev() {
+        board.unmake_move(*mv);
+    }
+
+    assert_eq!(board.line_to_san(&moves).unwrap(), "e4 e5 Nf3 Nc6 Bb5");
+    assert_eq!(board.fen(), fen);
+}
+
+#[test]
+fn an_illegal_move_is_an_error() {
+    let fen = "4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1";
+    let mut board = board(fen);
+
+    // the knight is pinned
+    let mut moves = MoveList::new();
+    board.generate_all_moves(&mut moves).unwrap();
+    let pinned = moves
+        .into_iter()
+        .find(|mv| mv.from_square() == Square::E2)
+        .unwrap();
+
+    assert!(board.to_san(pinned).is_err());
+    assert_eq!(board.fen(), fen);
+}
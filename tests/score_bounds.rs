@@ -0,0 +1,150 @@
+// a root search that fails high or low only has a bound on the score, which the `info` line says.
+// the move is only played once a search has the exact score
+
+use krusty::{
+    move_generator::Move,
+    reporter::{info_line, IterationReport, SearchReporter},
+    root_moves::ScoreBound,
+    search::{Search, INFINITY},
+    search_limits::SearchLimits,
+};
+
+fn report(score: i32, bound: ScoreBound, pv: &[Move]) -> IterationReport<'_> {
+    IterationReport {
+        depth: 7,
+        seldepth: 12,
+        multipv: None,
+        score,
+        bound,
+        nodes: 1000,
+        time_ms: 10,
+        hashfull: 0,
+        pv,
+        root_moves: &[],
+    }
+}
+
+#[test]
+fn bounds_follow_the_score_in_info_lines() {
+    let pv = ["e2e4".parse().unwrap()];
+
+    assert_eq!(
+        info_line(&report(35, ScoreBound::Lower, &pv)),
+        "info depth 7 seldepth 12 score cp 35 lowerbound nodes 1000 nps 100000 time 10 pv e2e4"
+    );
+    assert_eq!(
+        info_line(&report(-20, ScoreBound::Upper, &pv)),
+        "info depth 7 seldepth 12 score cp -20 upperbound nodes 1000 nps 100000 time 10 pv e2e4"
+    );
+    assert_eq!(
+        info_line(&report(INFINITY - 5, ScoreBound::Lower, &pv)),
+        "info depth 7 seldepth 12 score mate 3 lowerbound nodes 1000 nps 100000 time 10 pv e2e4"
+    );
+    assert_eq!(
+        info_line(&report(35, ScoreBound::Exact, &pv)),
+        "info depth 7 seldepth 12 score cp 35 nodes 1000 nps 100000 time 10 pv e2e4"
+    );
+}
+
+#[test]
+fn a_fail_hard_score_on_the_window_edge_is_a_bound() {
+    assert_eq!(ScoreBound::of(-50, -50, 50), ScoreBound::Upper);
+    assert_eq!(ScoreBound::of(-80, -50, 50), ScoreBound::Upper);
+    assert_eq!(ScoreBound::of(50, -50, 50), ScoreBound::Lower);
+    assert_eq!(ScoreBound::of(0, -50, 50), ScoreBound::Exact);
+}
+
+// the depth and bound of every report
+#[derive(Default)]
+struct BoundRecorder {
+    reports: Vec<(u8, ScoreBound)>,
+}
+
+impl SearchReporter for BoundRecorder {
+    fn iteration(&mut self, report: &IterationReport) {
+        self.reports.push((report.depth, report.bound));
+    }
+
+    fn best_move(&mut self, _best_move: Move, _ponder: Option<Move>) {}
+}
+
+// the first few depths are searched with the full window, and every depth after that ends with
+// an exact score however many times it had to be searched again
+#[test]
+fn every_depth_ends_with_an_exact_score() {
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        // the score swings from one depth to the next, so it falls out of the window
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        // a mate in one, and a side with no moves at all
+        "7k/5Q2/6K1/8/8/8/8/8 w - - 0 1",
+        "k7/8/1QK5/8/8/8/8/8 b - - 0 1",
+    ] {
+        let mut search = Search::default();
+        search.board.parse_fen(fen).unwrap();
+
+        let mut reporter = BoundRecorder::default();
+        search
+            .search_position(&SearchLimits::depth(6), &mut reporter)
+            .unwrap();
+
+        let exact: Vec<_> = reporter
+            .reports
+            .iter()
+            .filter(|(_, bound)| *bound == ScoreBound::Exact)
+            .map(|(depth, _)| *depth)
+            .collect();
+        assert_eq!(exact, [1, 2, 3, 4, 5, 6], "{}", fen);
+
+        assert_eq!(reporter.reports.last().unwrap().1, ScoreBound::Exact);
+        assert!(reporter
+            .reports
+            .iter()
+            .all(|(depth, bound)| *depth >= 4 || *bound == ScoreBound::Exact));
+
+        // every bound had to be searched again
+        let bounds = reporter.reports.len() - exact.len();
+        assert_eq!(
+            search.search_info.debug.aspiration_researches, bounds as u64,
+            "{}",
+            fen
+        );
+    }
+}
+
+#[test]
+fn a_swinging_score_is_searched_again_with_a_wider_window() {
+    let mut search = Search::default();
+
+    let mut reporter = BoundRecorder::default();
+    search
+        .search_position(&SearchLimits::depth(6), &mut reporter)
+        .unwrap();
+
+    assert!(search.search_info.debug.aspiration_researches > 0);
+    assert!(reporter
+        .reports
+        .iter()
+        .any(|(_, bound)| *bound != ScoreBound::Exact));
+}
+
+#[test]
+fn mate_scores_are_never_searched_with_a_window() {
+    // Ra8 is mate
+    let mut search = Search::default();
+    search
+        .board
+        .parse_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1")
+        .unwrap();
+
+    let mut reporter = BoundRecorder::default();
+    search
+        .search_position(&SearchLimits::depth(7), &mut reporter)
+        .unwrap();
+
+    assert_eq!(search.search_info.debug.aspiration_researches, 0);
+    assert!(reporter
+        .reports
+        .iter()
+        .all(|(_, bound)| *bound == ScoreBound::Exact));
+}
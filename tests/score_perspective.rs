@@ -0,0 +1,79 @@
+// the CLI can show scores from white's side like GUIs do, while the search itself (and UCI) stays
+// relative to the side to move
+
+use krusty::{
+    board::{Board, Side},
+    reporter::{evaluation_line, ScorePerspective},
+};
+
+// black to move, a rook up
+const BLACK_AHEAD_FEN: &str = "r3k3/8/8/8/8/8/8/4K3 b - - 0 1";
+
+fn black_to_move_evaluation() -> i32 {
+    let mut board = Board::default();
+    board.parse_fen(BLACK_AHEAD_FEN).unwrap();
+
+    assert_eq!(board.side_to_move(), Side::Black);
+    board.evaluate().unwrap()
+}
+
+#[test]
+fn side_to_move_mode_keeps_the_search_score() {
+    let score = black_to_move_evaluation();
+    assert!(score > 0, "black is ahead, got {}", score);
+
+    let perspective = ScorePerspective::SideToMove;
+    assert_eq!(perspective.convert(score, Side::Black), score);
+
+    assert_eq!(
+        evaluation_line(score, Side::Black, perspective),
+        format!(
+            "Evaluation (side to move): {} cp (+{:.2})",
+            score,
+            score as f64 / 100.0
+        )
+    );
+}
+
+#[test]
+fn white_mode_flips_the_score_when_black_is_to_move() {
+    let score = black_to_move_evaluation();
+
+    let perspective = ScorePerspective::White;
+    assert_eq!(perspective.convert(score, Side::Black), -score);
+    assert_eq!(perspective.convert(score, Side::White), score);
+
+    assert_eq!(
+        evaluation_line(score, Side::Black, perspective),
+        format!(
+            "Evaluation (white): {} cp (-{:.2})",
+            -score,
+            score as f64 / 100.0
+        )
+    );
+}
+
+#[test]
+fn pawns_are_shown_with_two_decimals() {
+    assert_eq!(
+        evaluation_line(35, Side::White, ScorePerspective::White),
+        "Evaluation (white): 35 cp (+0.35)"
+    );
+    assert_eq!(
+        evaluation_line(-1250, Side::White, ScorePerspective::SideToMove),
+        "Evaluation (side to move): -1250 cp (-12.50)"
+    );
+}
+
+#[test]
+fn modes_parse_from_their_cli_names() {
+    assert_eq!(
+        "stm".parse::<ScorePerspective>().unwrap(),
+        ScorePerspective::SideToMove
+    );
+    assert_eq!(
+        "white".parse::<ScorePerspective>().unwrap(),
+        ScorePerspective::White
+    );
+    assert!("black".parse::<ScorePerspective>().is_err());
+}
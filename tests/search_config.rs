@@ -0,0 +1,177 @@
+// a search can be set up in one go from a `SearchConfig`, which is also what UCI options are
+// applied onto
+
+mod common;
+
+use common::start_uci_advertising;
+use krusty::{
+    board::Board,
+    options::{OptionValue, UciOption},
+    reporter::SilentReporter,
+    search::Search,
+    search_config::SearchConfig,
+    search_limits::SearchLimits,
+    transposition_table::{SearchTableEntry, TranspositionTable},
+};
+
+const FEN: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+fn small_config() -> SearchConfig {
+    SearchConfig {
+        hash_mb: 1,
+        fen: FEN.into(),
+        ..SearchConfig::default()
+    }
+}
+
+#[test]
+fn a_search_is_built_as_configured() {
+    let search = small_config().build().unwrap();
+
+    let table = TranspositionTable::<SearchTableEntry>::new(1);
+    assert_eq!(search.transposition_table.configured_mb(), 1);
+    assert_eq!(
+        search.transposition_table.capacity_entries(),
+        table.capacity_entries()
+    );
+
+    let mut board = Board::default();
+    board.parse_fen(FEN).unwrap();
+    assert_eq!(search.board.hash(), board.hash());
+
+    assert_eq!(search.config(), small_config());
+}
+
+#[test]
+fn the_default_search_uses_the_default_config() {
+    assert_eq!(Search::default().config(), SearchConfig::default());
+}
+
+#[test]
+fn an_invalid_fen_is_an_error() {
+    let config = SearchConfig {
+        fen: String::from("8/8/8 w - - 0 1"),
+        ..small_config()
+    };
+
+    assert!(config.build().is_err());
+}
+
+#[test]
+fn options_are_applied_onto_the_config() {
+    let mut config = small_config();
+
+    for (name, value) in [
+        ("Hash", "2"),
+        ("Threads", "3"),
+        ("MultiPV", "4"),
+        ("Seed", "5"),
+        ("MustWin", "true"),
+        ("MustWinMargin", "60"),
+        ("Contempt", "-20"),
+        ("Move Overhead", "70"),
+    ] {
+        let value = UciOption::find(name).unwrap().parse_value(value).unwrap();
+        assert!(config.apply_option(name, &value), "{}", name);
+    }
+
+    assert_eq!(
+        config,
+        SearchConfig {
+            hash_mb: 2,
+            threads: 3,
+            multi_pv: 4,
+            seed: Some(5),
+            must_win: true,
+            must_win_margin: 60,
+            contempt: -20,
+            move_overhead_ms: 70,
+            fen: FEN.into(),
+        }
+    );
+
+    // options about the output rather than the search aren't part of it
+    assert!(!config.apply_option("MinimalOutput", &OptionValue::Check(true)));
+}
+
+#[test]
+fn reconfiguring_only_replaces_the_table_for_a_new_size() {
+    let mut search = small_config().build().unwrap();
+    search
+        .search_position(&SearchLimits::depth(5), &mut SilentReporter)
+        .unwrap();
+    assert!(search.transposition_table.hashfull() > 0);
+    let fen = search.board.fen();
+
+    let config = SearchConfig {
+        multi_pv: 2,
+        ..search.config()
+    };
+    search.configure(&config);
+    assert!(search.transposition_table.hashfull() > 0);
+
+    let config = SearchConfig {
+        hash_mb: 2,
+        ..search.config()
+    };
+    search.configure(&config);
+    assert_eq!(search.transposition_table.hashfull(), 0);
+    assert_eq!(search.transposition_table.configured_mb(), 2);
+
+    // and the position stays where it was
+    assert_eq!(search.board.fen(), fen);
+
+    // scores in the table include draws, so they're no good once draws are scored differently
+    search
+        .search_position(&SearchLimits::depth(5), &mut SilentReporter)
+        .unwrap();
+    assert!(search.transposition_table.hashfull() > 0);
+
+    let config = SearchConfig {
+        contempt: 10,
+        ..search.config()
+    };
+    search.configure(&config);
+    assert_eq!(search.transposition_table.hashfull(), 0);
+    assert_eq!(search.transposition_table.configured_mb(), 2);
+}
+
+#[test]
+fn resetting_keeps_the_config() {
+    let mut search = SearchConfig {
+        multi_pv: 3,
+        move_overhead_ms: 100,
+        ..small_config()
+    }
+    .build()
+    .unwrap();
+
+    search.reset();
+
+    assert_eq!(
+        search.config(),
+        SearchConfig {
+            multi_pv: 3,
+            move_overhead_ms: 100,
+            hash_mb: 1,
+            ..SearchConfig::default()
+        }
+    );
+}
+
+#[test]
+fn hash_can_be_set_over_uci() {
+    let mut engine = start_uci_advertising("option name Hash type spin default 64 min 1 max 16384");
+
+    engine.send("setoption name Hash value 0");
+    engine.send("setoption name Hash value 1");
+    engine.send("isready");
+
+    let output = engine.read_until(|line| line == "readyok");
+    assert_eq!(
+        output,
+        ["info string Hash must be between 1 and 16384", "readyok"]
+    );
+
+    assert!(engine.quit().success());
+}
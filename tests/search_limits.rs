@@ -0,0 +1,295 @@
+// how `go` arguments turn into search limits, and which limit wins when several are given
+
+use std::time::{Duration, Instant};
+
+use krusty::{
+    board::{Board, Side, START_POSITION_FEN},
+    move_generator::Move,
+    reporter::SilentReporter,
+    search::{Search, SearchDepth},
+    search_limits::{SearchLimits, TimeControls},
+    time_management::{ManualClock, SearchDuration, SearchTimer, DEFAULT_MOVE_OVERHEAD_MS},
+    uci::parse_go_args,
+};
+
+fn allowed_duration(limits: &SearchLimits) -> SearchDuration {
+    let mut timer = SearchTimer::with_clock(ManualClock::default());
+    timer.initialize_with_limits(limits);
+    timer.allowed_duration
+}
+
+// the start position, or the position after 1. e4 for black
+fn to_move(side: Side) -> Board {
+    let fen = match side {
+        Side::White => START_POSITION_FEN,
+        Side::Black => "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+    };
+
+    let mut board = Board::default();
+    board.parse_fen(fen).unwrap();
+    board
+}
+
+fn run_search(limits: &SearchLimits, clock_step: u64) -> (Search, Move) {
+    let mut search = Search::default();
+    search.set_seed(Some(1));
+    search.timer = SearchTimer::with_clock(ManualClock::with_step(clock_step));
+
+    let best_move = search.search_position(limits, &mut SilentReporter).unwrap();
+
+    (search, best_move)
+}
+
+#[test]
+fn only_the_clock_of_the_side_to_move_is_kept() {
+    let args = "wtime 60000 btime 30000 winc 1000 binc 500 movestogo 20";
+
+    let white = parse_go_args(args, &mut to_move(Side::White)).unwrap();
+    assert_eq!(
+        white.time_controls,
+        TimeControls {
+            time_remaining: Some(60000),
+            increment: 1000,
+            moves_to_go: Some(20),
+        }
+    );
+
+    let black = parse_go_args(args, &mut to_move(Side::Black)).unwrap();
+    assert_eq!(black.time_controls.time_remaining, Some(30000));
+    assert_eq!(black.time_controls.increment, 500);
+}
+
+#[test]
+fn missing_and_invalid_values_are_errors() {
+    assert!(parse_go_args("depth", &mut to_move(Side::White)).is_err());
+    assert!(parse_go_args("depth deep", &mut to_move(Side::White)).is_err());
+    assert!(parse_go_args("nodes", &mut to_move(Side::White)).is_err());
+    assert!(parse_go_args("movetime soon", &mut to_move(Side::White)).is_err());
+    assert!(parse_go_args("wtime", &mut to_move(Side::White)).is_err());
+
+    // the clock of the side not to move is never looked at
+    assert!(parse_go_args("btime", &mut to_move(Side::White)).is_ok());
+}
+
+#[test]
+fn no_limits_means_searching_as_deep_as_possible() {
+    let limits = parse_go_args("", &mut to_move(Side::White)).unwrap();
+
+    assert_eq!(limits.max_depth(), SearchDepth::MAX);
+    assert!(matches!(
+        allowed_duration(&limits),
+        SearchDuration::Infinite
+    ));
+}
+
+#[test]
+fn depth_and_movetime_both_apply() {
+    let limits = parse_go_args("depth 6 movetime 500", &mut to_move(Side::White)).unwrap();
+
+    assert_eq!(limits.max_depth(), 6);
+    assert!(matches!(
+        allowed_duration(&limits),
+        SearchDuration::Finite(duration) if duration == 500 - DEFAULT_MOVE_OVERHEAD_MS
+    ));
+
+    // the depth is reached long before the time runs out
+    let (search, _) = run_search(&SearchLimits::depth(2), 0);
+    assert!(!search.timer.is_stopped());
+
+    // and the time runs out long before the depth is reached
+    let args = format!("depth 60 movetime {}", 50 + DEFAULT_MOVE_OVERHEAD_MS);
+    let limits = parse_go_args(&args, &mut to_move(Side::White)).unwrap();
+    let (search, best_move) = run_search(&limits, 10);
+    assert!(search.timer.is_stopped());
+    assert!(!best_move.is_null());
+}
+
+#[test]
+fn movetime_takes_the_place_of_the_clock() {
+    let limits = parse_go_args(
+        "wtime 100000 winc 1000 movetime 250",
+        &mut to_move(Side::White),
+    )
+    .unwrap();
+
+    // no share of the clock is worked out, only the overhead comes off
+    assert!(matches!(
+        allowed_duration(&limits),
+        SearchDuration::Finite(duration) if duration == 250 - DEFAULT_MOVE_OVERHEAD_MS
+    ));
+
+    let limits = parse_go_args("movetime 20 movestogo 5", &mut to_move(Side::White)).unwrap();
+    assert!(matches!(
+        allowed_duration(&limits),
+        SearchDuration::Finite(0)
+    ));
+}
+
+#[test]
+fn movetime_search_returns_on_time() {
+    let mut search = Search::default();
+    let limits = parse_go_args("movetime 100", &mut to_move(Side::White)).unwrap();
+
+    let start = Instant::now();
+    let best_move = search
+        .search_position(&limits, &mut SilentReporter)
+        .unwrap();
+    let elapsed = start.elapsed();
+
+    assert!(!best_move.is_null());
+    assert!(search.search_info.nodes_searched > 0);
+
+    // the clock is only read every few thousand nodes, which is slow in a debug build
+    assert!(
+        elapsed < Duration::from_millis(100 + 150),
+        "took {:?}",
+        elapsed
+    );
+}
+
+#[test]
+fn infinite_ignores_the_clock_and_movetime_but_not_depth() {
+    let limits = parse_go_args(
+        "infinite wtime 1000 movetime 100 depth 4",
+        &mut to_move(Side::White),
+    )
+    .unwrap();
+
+    assert!(limits.infinite);
+    assert!(matches!(
+        allowed_duration(&limits),
+        SearchDuration::Infinite
+    ));
+    assert_eq!(limits.max_depth(), 4);
+}
+
+#[test]
+fn mate_limits_the_depth_to_the_length_of_the_mate() {
+    let mate_in_three = parse_go_args("mate 3", &mut to_move(Side::White)).unwrap();
+    assert_eq!(mate_in_three.max_depth(), 5);
+
+    // whichever is shallower wins
+    let both = parse_go_args("mate 3 depth 2", &mut to_move(Side::White)).unwrap();
+    assert_eq!(both.max_depth(), 2);
+
+    let both = parse_go_args("mate 3 depth 9", &mut to_move(Side::White)).unwrap();
+    assert_eq!(both.max_depth(), 5);
+}
+
+#[test]
+fn depth_is_capped_at_the_deepest_possible_search() {
+    let limits = parse_go_args("depth 200", &mut to_move(Side::White)).unwrap();
+    assert_eq!(limits.max_depth(), SearchDepth::MAX);
+
+    let limits = parse_go_args("depth 99999999999999999999", &mut to_move(Side::White)).unwrap();
+    assert_eq!(limits.max_depth(), SearchDepth::MAX);
+
+    let Err(error) = parse_go_args("depth -3", &mut to_move(Side::White)) else {
+        panic!("a negative depth was accepted");
+    };
+    assert_eq!(error.to_string(), "depth can't be negative, got -3");
+}
+
+#[test]
+fn there_is_always_at_least_one_iteration() {
+    for args in ["depth 0", "mate 0"] {
+        let limits = parse_go_args(args, &mut to_move(Side::White)).unwrap();
+        assert_eq!(limits.max_depth(), 1, "{}", args);
+
+        let (_, best_move) = run_search(&limits, 0);
+        assert!(!best_move.is_null(), "{}", args);
+    }
+}
+
+#[test]
+fn node_limit_stops_the_search() {
+    let limits = parse_go_args("nodes 20000", &mut to_move(Side::White)).unwrap();
+    let (search, best_move) = run_search(&limits, 0);
+
+    assert!(search.timer.is_stopped());
+    assert!(search.search_info.nodes_searched < 21000);
+    assert!(!best_move.is_null());
+}
+
+#[test]
+fn node_limited_searches_do_not_depend_on_the_clock() {
+    let limits = SearchLimits::nodes(30_000);
+
+    // one clock barely moves and the other races ahead, which makes no difference without a
+    // time limit
+    let (slow, slow_move) = run_search(&limits, 0);
+    let (fast, fast_move) = run_search(&limits, 1_000);
+
+    assert_eq!(slow_move, fast_move);
+    assert_eq!(
+        slow.search_info.nodes_searched,
+        fast.search_info.nodes_searched
+    );
+
+    // nodes still being unwound when the limit is hit count too, so it's only approximate
+    assert!(slow.search_info.nodes_searched < 31_000);
+}
+
+#[test]
+fn node_limit_only_applies_to_its_own_search() {
+    let (mut search, _) = run_search(&SearchLimits::nodes(100), 0);
+    assert!(search.timer.is_stopped());
+
+    // a new position, so nothing is answered from the table the first search filled
+    search
+        .board
+        .parse_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+        .unwrap();
+    search
+        .search_position(&SearchLimits::depth(3), &mut SilentReporter)
+        .unwrap();
+
+    assert!(!search.timer.is_stopped());
+    assert!(search.search_info.nodes_searched > 100);
+}
+
+#[test]
+fn searchmoves_restricts_the_root_moves() {
+    let mut search = Search::default();
+    let h3 = search
+        .board
+        .find_matching_move(search.board.get_move_metadata("h2h3").unwrap());
+
+    let limits = SearchLimits {
+        depth: Some(4),
+        searchmoves: vec![h3.unwrap()],
+        ..SearchLimits::default()
+    };
+
+    let best_move = search
+        .search_position(&limits, &mut SilentReporter)
+        .unwrap();
+
+    assert_eq!(best_move.to_string(), "h2h3");
+}
+
+#[test]
+fn searchmoves_are_parsed_up_to_the_next_keyword() {
+    let mut board = to_move(Side::White);
+    let limits = parse_go_args("searchmoves e2e4 g1f3 depth 5", &mut board).unwrap();
+
+    let moves: Vec<String> = limits.searchmoves.iter().map(Move::to_string).collect();
+    assert_eq!(moves, ["e2e4", "g1f3"]);
+    assert_eq!(limits.depth, Some(5));
+
+    // the moves are the ones generated in the position, not just the squares
+    let e4 = board.find_matching_move(board.get_move_metadata("e2e4").unwrap());
+    assert_eq!(limits.searchmoves[0], e4.unwrap());
+}
+
+#[test]
+fn searchmoves_that_are_not_moves_are_ignored() {
+    let mut board = to_move(Side::White);
+    let limits = parse_go_args("depth 3 searchmoves e2e5 nonsense 0000 d2d4", &mut board).unwrap();
+
+    let moves: Vec<String> = limits.searchmoves.iter().map(Move::to_string).collect();
+    assert_eq!(moves, ["d2d4"]);
+
+    let limits = parse_go_args("depth 3", &mut board).unwrap();
+    assert!(limits.searchmoves.is_empty());
+}
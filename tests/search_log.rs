@@ -0,0 +1,252 @@
+// with a log file set, every iteration and best move is appended to it as a line of tab-separated
+// fields, alongside the normal output. a file that can't be written is reported once and then left
+// alone, and nothing that was shown goes missing when the engine exits partway through a search
+
+mod common;
+
+use std::{env, fs, path::PathBuf};
+
+use common::{start_uci, Engine};
+
+const AFTER_E4_FEN: &str = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+
+// a file of its own for each test, since they run at the same time
+fn log_file_path(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("krusty-log-{}-{}.log", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn read_log(path: &PathBuf) -> Vec<Vec<String>> {
+    let lines = fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| line.split('\t').map(String::from).collect())
+        .collect();
+
+    fs::remove_file(path).unwrap();
+    lines
+}
+
+// a timestamp in milliseconds, then the root FEN, then what happened
+fn assert_line_start(fields: &[String], fen: &str) {
+    assert!(fields[0].parse::<u128>().unwrap() > 0, "{:?}", fields);
+    assert_eq!(fields[1], fen);
+}
+
+// the depth the iteration reached
+fn assert_iteration_line(fields: &[String], fen: &str) -> u8 {
+    assert_eq!(fields.len(), 7, "{:?}", fields);
+    assert_line_start(fields, fen);
+
+    assert!(fields[3].starts_with("score cp ") || fields[3].starts_with("score mate "));
+    assert!(
+        fields[4]
+            .strip_prefix("nodes ")
+            .unwrap()
+            .parse::<u64>()
+            .unwrap()
+            > 0
+    );
+    assert!(fields[5]
+        .strip_prefix("time ")
+        .unwrap()
+        .parse::<u128>()
+        .is_ok());
+    assert!(!fields[6].strip_prefix("pv ").unwrap().is_empty());
+
+    fields[2].strip_prefix("depth ").unwrap().parse().unwrap()
+}
+
+#[test]
+fn uci_logs_one_line_per_iteration_and_one_per_best_move() {
+    let path = log_file_path("uci");
+    let mut engine = start_uci();
+    engine.send(&format!("setoption name LogFile value {}", path.display()));
+
+    let mut best_moves = Vec::new();
+    let mut iterations = 0;
+
+    for _ in 0..2 {
+        engine.send("position startpos moves e2e4");
+        engine.send("go depth 4");
+
+        let output = engine.read_until(|line| line.starts_with("bestmove"));
+        iterations += output
+            .iter()
+            .filter(|line| line.contains(" score "))
+            .count();
+        best_moves.push(output.last().unwrap().clone());
+    }
+
+    assert!(engine.quit().success());
+
+    let lines = read_log(&path);
+    assert_eq!(lines.len(), iterations + 2);
+
+    // each search's iterations in order, then its best move as the GUI was sent it
+    let mut depth = 0;
+    let mut searches = best_moves.iter();
+
+    for fields in &lines {
+        if fields[2].starts_with("bestmove") {
+            assert_eq!(fields.len(), 3, "{:?}", fields);
+            assert_line_start(fields, AFTER_E4_FEN);
+            assert_eq!(&fields[2], searches.next().unwrap());
+
+            // every depth was logged, up to the last
+            assert_eq!(depth, 4);
+            depth = 0;
+            continue;
+        }
+
+        // a failed aspiration window is searched again at the same depth
+        let iteration_depth = assert_iteration_line(fields, AFTER_E4_FEN);
+        assert!(
+            iteration_depth == depth || iteration_depth == depth + 1,
+            "{:?}",
+            fields
+        );
+        depth = iteration_depth;
+    }
+
+    assert!(searches.next().is_none());
+}
+
+#[test]
+fn the_cli_logs_until_told_to_stop() {
+    let path = log_file_path("cli");
+    let mut engine = Engine::start();
+
+    engine.send(&format!("log {}", path.display()));
+    engine.send("search 3");
+    engine.read_until(|line| line.starts_with("- PV:"));
+
+    engine.send("log");
+    engine.send("search 2");
+    engine.read_until(|line| line.starts_with("- PV:"));
+
+    assert!(engine.quit().success());
+
+    // nothing from the second search
+    let lines = read_log(&path);
+    let start_fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    let (best_move, iterations) = lines.split_last().unwrap();
+    assert!(best_move[2].starts_with("bestmove "), "{:?}", best_move);
+    assert_line_start(best_move, start_fen);
+
+    let depths: Vec<_> = iterations
+        .iter()
+        .map(|fields| assert_iteration_line(fields, start_fen))
+        .collect();
+    assert_eq!(depths.first(), Some(&1));
+    assert_eq!(depths.last(), Some(&3));
+}
+
+#[test]
+fn a_log_file_that_cannot_be_written_is_reported_once() {
+    let path = env::temp_dir()
+        .join(format!("krusty-missing-{}", std::process::id()))
+        .join("x.log");
+    let mut engine = start_uci();
+    engine.send(&format!("setoption name LogFile value {}", path.display()));
+
+    let mut output = Vec::new();
+    for _ in 0..2 {
+        engine.send("position startpos");
+        engine.send("go depth 2");
+        output.extend(engine.read_until(|line| line.starts_with("bestmove")));
+    }
+
+    assert!(engine.quit().success());
+
+    // and the searches carry on as normal
+    let failures: Vec<_> = output
+        .iter()
+        .filter(|line| line.starts_with("info string failed to write log file"))
+        .collect();
+    assert_eq!(failures.len(), 1, "{:?}", output);
+    assert_eq!(
+        output
+            .iter()
+            .filter(|line| line.starts_with("bestmove"))
+            .count(),
+        2
+    );
+    assert!(!path.exists());
+}
+
+// stops a search partway through, by `quit` or by closing the engine's input, and returns all it
+// showed before exiting
+fn output_before_exit(mut engine: Engine, search: &str, quit: bool) -> Vec<String> {
+    engine.send(search);
+    let mut output = engine.read_until(|line| line.contains("depth 4 "));
+
+    let (rest, status) = if quit {
+        engine.send("quit");
+        engine.wait_for_exit()
+    } else {
+        engine.close_input()
+    };
+
+    assert!(status.success());
+
+    output.extend(rest);
+    output
+}
+
+// the CLI's prompt can come before the first one
+fn iterations_shown(output: &[String]) -> usize {
+    output
+        .iter()
+        .filter(|line| line.contains("info depth") && line.contains(" score "))
+        .count()
+}
+
+#[test]
+fn uci_flushes_the_log_on_the_way_out() {
+    for quit in [true, false] {
+        let path = log_file_path(if quit { "uci-quit" } else { "uci-eof" });
+        let mut engine = start_uci();
+        engine.send(&format!("setoption name LogFile value {}", path.display()));
+        engine.send("position startpos moves e2e4");
+
+        let output = output_before_exit(engine, "go infinite", quit);
+        let best_moves = output
+            .iter()
+            .filter(|line| line.starts_with("bestmove"))
+            .count();
+
+        let lines = read_log(&path);
+        assert_eq!(
+            lines.len(),
+            iterations_shown(&output) + best_moves,
+            "quit: {}",
+            quit
+        );
+
+        // `quit` wants no best move, but a GUI that's gone away still gets one
+        let (last, iterations) = lines.split_last().unwrap();
+        assert_eq!(last[2].starts_with("bestmove "), !quit, "{:?}", last);
+
+        for fields in iterations {
+            assert_iteration_line(fields, AFTER_E4_FEN);
+        }
+    }
+}
+
+#[test]
+fn the_cli_flushes_the_log_when_its_input_is_closed() {
+    let path = log_file_path("cli-eof");
+    let mut engine = Engine::start();
+
+    engine.send(&format!("log {}", path.display()));
+
+    // the CLI finishes its search before it notices the input is gone
+    let output = output_before_exit(engine, "search 6", false);
+    let lines = read_log(&path);
+
+    assert_eq!(lines.len(), iterations_shown(&output) + 1);
+    assert!(lines.last().unwrap()[2].starts_with("bestmove "));
+}
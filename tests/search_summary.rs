@@ -0,0 +1,104 @@
+// a CLI search ends with a summary of the whole search, in a form meant for people rather than
+// GUIs. UCI output is left as it was
+
+mod common;
+
+use common::Engine;
+use krusty::{
+    reporter::{format_duration, pawns_string},
+    search::INFINITY,
+};
+
+#[test]
+fn durations_use_the_largest_unit_that_fits() {
+    assert_eq!(format_duration(0), "0ms");
+    assert_eq!(format_duration(999), "999ms");
+    assert_eq!(format_duration(1000), "1.0s");
+    assert_eq!(format_duration(1299), "1.2s");
+    assert_eq!(format_duration(59_999), "59.9s");
+    assert_eq!(format_duration(60_000), "1m00s");
+    assert_eq!(format_duration(125_000), "2m05s");
+    assert_eq!(format_duration(3_600_000), "60m00s");
+}
+
+#[test]
+fn scores_are_shown_in_pawns() {
+    assert_eq!(pawns_string(35), "+0.35");
+    assert_eq!(pawns_string(-120), "-1.20");
+    assert_eq!(pawns_string(0), "+0.00");
+
+    // mating on the fifth ply is mate in 3, and being mated on the fourth is mate in -2
+    assert_eq!(pawns_string(INFINITY - 5), "#3");
+    assert_eq!(pawns_string(-INFINITY + 4), "#-2");
+}
+
+#[test]
+fn a_search_ends_with_a_summary() {
+    let mut engine = Engine::start();
+
+    engine.send("search 8");
+    engine.read_until(|line| line.ends_with("Summary:"));
+    let summary = engine.read_until(|line| line.starts_with("- PV: "));
+
+    let fields: Vec<(&str, &str)> = summary
+        .iter()
+        .map(|line| {
+            line.strip_prefix("- ")
+                .and_then(|line| line.split_once(": "))
+                .unwrap_or_else(|| panic!("not a summary field: {:?}", line))
+        })
+        .collect();
+
+    let names: Vec<&str> = fields.iter().map(|(name, _)| *name).collect();
+    assert_eq!(
+        names,
+        [
+            "Time",
+            "Nodes",
+            "NPS",
+            "Depth",
+            "TT hit rate",
+            "Evaluation (side to move)",
+            "PV"
+        ]
+    );
+
+    let value = |name: &str| fields.iter().find(|field| field.0 == name).unwrap().1;
+
+    assert!(value("Time").ends_with('s'));
+    assert!(value("Nodes").parse::<u64>().unwrap() > 0);
+    assert!(value("NPS").parse::<u64>().unwrap() > 0);
+
+    let (depth, seldepth) = value("Depth").split_once(" (seldepth ").unwrap();
+    assert_eq!(depth, "8");
+    assert!(seldepth.trim_end_matches(')').parse::<u8>().unwrap() >= 8);
+
+    let hit_rate: f64 = value("TT hit rate").trim_end_matches('%').parse().unwrap();
+    assert!(hit_rate > 0.0 && hit_rate <= 100.0);
+
+    let evaluation = value("Evaluation (side to move)");
+    assert!(evaluation.starts_with(['+', '-']) && evaluation.parse::<f64>().is_ok());
+
+    // in SAN, and nothing is ambiguous from the starting position, so the first move only names
+    // the square it goes to rather than both squares as in UCI
+    let pv: Vec<&str> = value("PV").split(' ').collect();
+    let digits = pv[0].chars().filter(char::is_ascii_digit).count();
+    assert_eq!(digits, 1, "{}", value("PV"));
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn json_searches_have_no_summary() {
+    let mut engine = Engine::start();
+
+    engine.send("search json 3");
+    engine.read_until(|line| line.contains(r#""type":"result""#));
+    engine.send("evalmode");
+
+    // the next thing printed is the reply to `evalmode`
+    let line = engine.next_line();
+    assert!(line.ends_with("Evaluation mode: side to move"), "{}", line);
+
+    assert!(engine.quit().success());
+}
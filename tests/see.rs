@@ -0,0 +1,93 @@
+// the threshold version of the static exchange stops as soon as it knows the answer, which has to
+// be the same one the full exchange gives, whatever the move and threshold
+
+use krusty::{
+    bench::BENCH_FENS,
+    board::Board,
+    move_generator::{Move, MoveList},
+    square::{Piece, PieceColor, PieceKind},
+};
+
+const PERFT_SUITE: &str = include_str!("../perft.epd");
+
+// the rook can take a pawn nothing defends
+const PAWN_UP_FEN: &str = "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1";
+
+// every 5cp from a queen and a bit down to a queen and a bit up
+const THRESHOLD_STEP: usize = 5;
+const MAX_THRESHOLD: i32 = 1500;
+
+fn corpus() -> Vec<Board> {
+    let perft_fens = PERFT_SUITE
+        .lines()
+        .filter_map(|line| line.split(';').next())
+        .map(str::trim)
+        .filter(|fen| !fen.is_empty());
+
+    BENCH_FENS
+        .iter()
+        .copied()
+        .chain(perft_fens)
+        .chain([PAWN_UP_FEN])
+        .map(|fen| {
+            let mut board = Board::default();
+            board.parse_fen(fen).unwrap();
+            board
+        })
+        .collect()
+}
+
+fn moves(board: &Board) -> Vec<Move> {
+    let mut move_list = MoveList::new();
+    board.generate_all_moves(&mut move_list).unwrap();
+
+    (0..move_list.length()).map(|i| move_list.get(i)).collect()
+}
+
+#[test]
+fn see_ge_agrees_with_see_on_every_move_and_threshold() {
+    let mut checked = 0;
+
+    for board in corpus() {
+        for mv in moves(&board) {
+            let see = board.see(mv);
+
+            // the answer flips right at the exchange's value, so those get checked whatever the step
+            let thresholds = (-MAX_THRESHOLD..=MAX_THRESHOLD)
+                .step_by(THRESHOLD_STEP)
+                .chain([see - 1, see, see + 1]);
+
+            for threshold in thresholds {
+                assert_eq!(
+                    board.see_ge(mv, threshold),
+                    see >= threshold,
+                    "{} {}: see {}, threshold {}",
+                    board.fen(),
+                    mv,
+                    see,
+                    threshold
+                );
+                checked += 1;
+            }
+        }
+    }
+
+    // a sanity check that the corpus didn't come out empty
+    assert!(checked > 1_000_000, "only {} checks", checked);
+}
+
+#[test]
+fn taking_an_undefended_pawn_wins_exactly_a_pawn() {
+    let mut board = Board::default();
+    board.parse_fen(PAWN_UP_FEN).unwrap();
+
+    let mv = moves(&board)
+        .into_iter()
+        .find(|mv| mv.to_string() == "e1e5")
+        .unwrap();
+
+    let pawn = Piece::new(PieceColor::Black, PieceKind::Pawn).material_value();
+    assert_eq!(board.see(mv), pawn);
+    assert!(board.see_ge(mv, pawn));
+    assert!(!board.see_ge(mv, pawn + 1));
+}
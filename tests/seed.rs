@@ -0,0 +1,115 @@
+// every random decision the search makes draws from its own generator. with a fixed seed the
+// numbers, and so the searches, are the same on every run, and a zero seed doesn't get stuck
+
+mod common;
+
+use common::start_uci;
+use krusty::{prng::Prng, search::Search};
+
+fn numbers(prng: &mut Prng) -> Vec<u64> {
+    (0..16).map(|_| prng.random_u64()).collect()
+}
+
+fn seeded(seed: Option<u64>) -> Search {
+    let mut search = Search::default();
+    search.set_seed(seed);
+    search
+}
+
+#[test]
+fn a_fixed_seed_gives_the_same_numbers_every_time() {
+    assert_eq!(numbers(&mut Prng::new(7)), numbers(&mut Prng::new(7)));
+    assert_ne!(numbers(&mut Prng::new(7)), numbers(&mut Prng::new(8)));
+
+    assert_eq!(
+        numbers(&mut seeded(Some(7)).rng),
+        numbers(&mut seeded(Some(7)).rng)
+    );
+    assert_ne!(
+        numbers(&mut seeded(None).rng),
+        numbers(&mut seeded(None).rng)
+    );
+}
+
+#[test]
+fn a_zero_seed_does_not_get_stuck_at_zero() {
+    let zeros = numbers(&mut Prng::new(0));
+
+    assert!(zeros.iter().all(|&number| number != 0), "{:?}", zeros);
+    assert_eq!(zeros, numbers(&mut Prng::new(0)));
+}
+
+#[test]
+fn the_seed_outlives_a_reset() {
+    let mut search = seeded(Some(42));
+    let first_run = numbers(&mut search.rng);
+
+    // a new game starts the numbers over
+    search.reset();
+    assert_eq!(numbers(&mut search.rng), first_run);
+}
+
+#[test]
+fn only_a_new_seed_starts_the_numbers_over() {
+    let mut search = seeded(Some(42));
+    let first_run = numbers(&mut search.rng);
+
+    // changing any other setting carries on from where the numbers were
+    let mut config = search.config();
+    config.multi_pv = 2;
+    search.configure(&config);
+    assert_ne!(numbers(&mut search.rng), first_run);
+
+    config.seed = Some(43);
+    search.configure(&config);
+    assert_eq!(numbers(&mut search.rng), numbers(&mut Prng::new(43)));
+
+    // and going back to entropy doesn't keep the old one
+    config.seed = None;
+    search.configure(&config);
+    assert_eq!(search.config().seed, None);
+    assert_ne!(numbers(&mut search.rng), numbers(&mut Prng::new(43)));
+}
+
+// everything a seeded engine says about a few searches, without the times, which vary
+fn seeded_output() -> Vec<String> {
+    let mut engine = start_uci();
+    engine.send("setoption name Seed value 12345");
+
+    let mut output = Vec::new();
+    for moves in ["", " moves e2e4", " moves e2e4 c7c5 g1f3"] {
+        engine.send(&format!("position startpos{}", moves));
+        engine.send("go nodes 20000");
+
+        output.extend(engine.read_until(|line| line.starts_with("bestmove")));
+    }
+
+    assert!(engine.quit().success());
+
+    output
+        .iter()
+        .filter(|line| !line.starts_with("info string"))
+        .map(|line| {
+            let mut words = line.split(' ');
+            let mut kept = Vec::new();
+
+            while let Some(word) = words.next() {
+                if word == "nps" || word == "time" {
+                    words.next();
+                    continue;
+                }
+                kept.push(word);
+            }
+
+            kept.join(" ")
+        })
+        .collect()
+}
+
+#[test]
+fn a_seeded_engine_plays_the_same_every_run() {
+    let first_run = seeded_output();
+
+    assert!(first_run.iter().any(|line| line.starts_with("bestmove")));
+    assert_eq!(seeded_output(), first_run);
+}
@@ -0,0 +1,58 @@
+// `Side` indexes everything stored per side, and `PieceColor` is a `Side` plus the color of an
+// empty square. the conversions between them have to agree both ways
+
+use krusty::{
+    board::{Board, Side, START_POSITION_FEN},
+    square::PieceColor,
+};
+
+#[test]
+fn each_side_has_its_own_index() {
+    assert_eq!(Side::White.index(), 0);
+    assert_eq!(Side::Black.index(), 1);
+
+    for side in [Side::White, Side::Black] {
+        assert_eq!(usize::from(side), side.index());
+        assert_ne!((!side).index(), side.index());
+        assert_eq!(!!side, side);
+    }
+}
+
+#[test]
+fn sides_and_colors_convert_both_ways() {
+    for side in [Side::White, Side::Black] {
+        let color = PieceColor::from(side);
+
+        assert_eq!(color.side(), Some(side));
+        assert_eq!(color.known_side(), side);
+        assert_eq!(Side::try_from(color).unwrap(), side);
+    }
+
+    assert_eq!(PieceColor::from(Side::White), PieceColor::White);
+    assert_eq!(PieceColor::from(Side::Black), PieceColor::Black);
+}
+
+#[test]
+fn an_empty_square_has_no_side() {
+    assert_eq!(PieceColor::None.side(), None);
+    assert!(Side::try_from(PieceColor::None).is_err());
+}
+
+#[test]
+#[should_panic(expected = "found a piece with no color")]
+fn an_empty_square_has_no_known_side_either() {
+    PieceColor::None.known_side();
+}
+
+#[test]
+fn moving_from_an_empty_square_leaves_the_board_alone() {
+    let mut board = Board::default();
+    board.parse_fen(START_POSITION_FEN).unwrap();
+
+    let hash = board.hash();
+    let mv = "e3e4".parse().unwrap();
+
+    assert!(board.make_move(mv).is_err());
+    assert_eq!(board.hash(), hash);
+    assert_eq!(board.fen(), START_POSITION_FEN);
+}
@@ -0,0 +1,68 @@
+// the light and dark square masks against the (rank + file) parity of every square. a1 is dark
+
+use krusty::{
+    bitboard::{Bitboard, DARK_SQUARES, EMPTY_BB, LIGHT_SQUARES},
+    board::Board,
+    square::{Square, SquareColor},
+};
+
+#[test]
+fn masks_split_the_board_in_half() {
+    assert_eq!(LIGHT_SQUARES.count_ones(), 32);
+    assert_eq!(DARK_SQUARES.count_ones(), 32);
+    assert_eq!(LIGHT_SQUARES & DARK_SQUARES, EMPTY_BB);
+    assert_eq!(LIGHT_SQUARES | DARK_SQUARES, Bitboard(u64::MAX));
+    assert_eq!(!LIGHT_SQUARES, DARK_SQUARES);
+}
+
+#[test]
+fn masks_agree_with_parity() {
+    for index in 0..64usize {
+        let square = Square::from(index);
+        let is_light = (index / 8 + index % 8) % 2 == 1;
+
+        assert_eq!(square.is_light(), is_light, "{:?}", square);
+        assert_eq!(LIGHT_SQUARES.is_occupied(square), is_light, "{:?}", square);
+        assert_eq!(DARK_SQUARES.is_occupied(square), !is_light, "{:?}", square);
+
+        let color = if is_light {
+            SquareColor::Light
+        } else {
+            SquareColor::Dark
+        };
+        assert_eq!(square.color(), color, "{:?}", square);
+        assert_eq!(square.bitboard().on_color(color), square.bitboard());
+    }
+
+    assert!(!Square::A1.is_light());
+    assert!(Square::H1.is_light());
+    assert!(Square::A1.is_same_color(Square::H8));
+    assert!(!Square::A1.is_same_color(Square::A8));
+}
+
+#[test]
+fn on_color_splits_any_bitboard() {
+    let bitboard = Bitboard(0x0123_4567_89ab_cdef);
+    let light = bitboard.on_color(SquareColor::Light);
+    let dark = bitboard.on_color(SquareColor::Dark);
+
+    assert_eq!(light | dark, bitboard);
+    assert_eq!(light & dark, EMPTY_BB);
+    assert_eq!(light, bitboard & LIGHT_SQUARES);
+}
+
+#[test]
+fn bishops_on_one_color_cant_mate() {
+    let mut board = Board::default();
+
+    // both bishops on light squares, then on different colors
+    board
+        .parse_fen("8/8/4k3/3b4/8/8/4K3/5B2 w - - 0 1")
+        .unwrap();
+    assert!(board.has_insufficient_material());
+
+    board
+        .parse_fen("8/8/4k3/3b4/8/8/4K3/2B5 w - - 0 1")
+        .unwrap();
+    assert!(!board.has_insufficient_material());
+}
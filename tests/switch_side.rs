@@ -0,0 +1,70 @@
+// `switch` hands the turn over without a move, which is a null move kept in the history. the hash
+// has to come back to where it started, and a flip that would leave a king en prise is refused
+
+mod common;
+
+use krusty::board::Side;
+
+use common::board;
+
+#[test]
+fn switching_twice_restores_the_hash() {
+    let mut board = board("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let hash = board.hash();
+
+    board.switch_side_to_move().unwrap();
+    assert_eq!(board.side_to_move(), Side::Black);
+    assert_ne!(board.hash(), hash);
+    assert_eq!(board.hash(), board.hash_from_scratch());
+
+    board.switch_side_to_move().unwrap();
+    assert_eq!(board.side_to_move(), Side::White);
+    assert_eq!(board.hash(), hash);
+}
+
+#[test]
+fn switching_clears_en_passant_from_the_position_and_the_hash() {
+    let mut board = board("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3");
+
+    board.switch_side_to_move().unwrap();
+
+    let flipped = self::board("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3");
+    assert_eq!(board.hash(), flipped.hash());
+    assert_eq!(board.hash(), board.hash_from_scratch());
+}
+
+#[test]
+fn switching_can_be_unmade_like_a_null_move() {
+    let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 1";
+    let mut board = board(fen);
+    let hash = board.hash();
+
+    board.switch_side_to_move().unwrap();
+    board.unmake_null_move();
+
+    assert_eq!(board.fen(), fen);
+    assert_eq!(board.hash(), hash);
+}
+
+#[test]
+fn switching_is_refused_when_the_side_to_move_is_in_check() {
+    // white is in check, so black to move could take the king
+    let fen = "4k3/8/8/8/8/8/4r3/4K3 w - - 0 1";
+    let mut board = board(fen);
+    let hash = board.hash();
+
+    assert!(board.switch_side_to_move().is_err());
+    assert_eq!(board.fen(), fen);
+    assert_eq!(board.hash(), hash);
+}
+
+#[test]
+fn moves_can_be_played_after_switching() {
+    let mut board = board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+    board.switch_side_to_move().unwrap();
+    board.make_moves_from_str("e7e5").unwrap();
+
+    assert_eq!(board.side_to_move(), Side::White);
+    assert_eq!(board.hash(), board.hash_from_scratch());
+}
@@ -0,0 +1,42 @@
+// the sizes `memory` reports are worked out from entry counts, so they have to stay in line with
+// what each table was configured with
+
+use krusty::transposition_table::{SearchTableEntry, TranspositionTable};
+
+type SearchTable = TranspositionTable<SearchTableEntry>;
+
+const MEGABYTE: usize = 1024 * 1024;
+
+#[test]
+fn the_search_table_is_as_big_as_its_entries() {
+    let entry_size = SearchTable::ENTRY_SIZE_BYTES;
+
+    for size_in_mb in [1, 3, 16, 64] {
+        let table = SearchTable::new(size_in_mb);
+        let entries_bytes = table.capacity_entries() * entry_size;
+
+        assert!(
+            table.size_bytes().abs_diff(entries_bytes) < entry_size,
+            "{} MB: {} bytes for {} entries",
+            size_in_mb,
+            table.size_bytes(),
+            table.capacity_entries()
+        );
+
+        // and nothing bigger than asked for, with less than an entry left unused
+        assert_eq!(table.configured_mb(), size_in_mb);
+        assert!(table.size_bytes() <= size_in_mb * MEGABYTE);
+        assert!(size_in_mb * MEGABYTE - table.size_bytes() < entry_size);
+
+        // every handle is a view of the same entries
+        assert_eq!(table.share().size_bytes(), table.size_bytes());
+    }
+}
+
+#[test]
+fn even_a_table_of_nothing_has_one_entry() {
+    let table = SearchTable::new(0);
+
+    assert_eq!(table.capacity_entries(), 1);
+    assert_eq!(table.size_bytes(), SearchTable::ENTRY_SIZE_BYTES);
+}
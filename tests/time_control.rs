@@ -0,0 +1,629 @@
+// simulated time controls. the search only reads the clock every so many nodes, so a clock that
+// moves a fixed step on every reading stands in for batches of nodes taking that long to search.
+// the number of nodes between readings adapts to how long they take
+
+use std::sync::atomic::Ordering;
+
+use krusty::{
+    debug_sink::DebugEvent,
+    move_generator::Move,
+    reporter::{info_line, IterationReport, SearchReporter},
+    root_moves::ScoreBound,
+    search::Search,
+    search_limits::{SearchLimits, TimeControls},
+    time_management::{
+        ManualClock, SearchDuration, SearchTimer, DEFAULT_MOVE_OVERHEAD_MS, MAX_CHECK_INTERVAL,
+        MAX_MOVE_SHARE, MIN_CHECK_INTERVAL, PANIC_TIME_FACTOR,
+    },
+};
+
+const MIDDLEGAME_FEN: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+// a clock that leaves a second to search for the last move before the time control, once the
+// overhead is taken off
+const ONE_SECOND_LEFT: u128 =
+    ((1000 + DEFAULT_MOVE_OVERHEAD_MS) as f64 / MAX_MOVE_SHARE).ceil() as u128;
+
+struct CompletedIteration {
+    end_ms: u128,
+    // as the report gave it
+    time_ms: u128,
+    info: String,
+    nodes: u64,
+    depth: u8,
+    best_move: Move,
+}
+
+// remembers the simulated time and search results at the end of each completed iteration, and
+// when the time was extended
+struct ClockedReporter {
+    clock: ManualClock,
+    iterations: Vec<CompletedIteration>,
+    time_extended_at: Option<u128>,
+}
+
+impl SearchReporter for ClockedReporter {
+    fn iteration(&mut self, report: &IterationReport) {
+        self.iterations.push(CompletedIteration {
+            end_ms: self.clock.current_ms(),
+            time_ms: report.time_ms,
+            info: info_line(report),
+            nodes: report.nodes,
+            depth: report.depth,
+            best_move: report.pv[0],
+        });
+    }
+
+    fn best_move(&mut self, _best_move: Move, _ponder: Option<Move>) {}
+
+    fn debug(&mut self, event: &DebugEvent) {
+        if let DebugEvent::TimeExtended { elapsed_ms, .. } = event {
+            self.time_extended_at = Some(*elapsed_ms);
+        }
+    }
+}
+
+fn search_with_clock(
+    fen: &str,
+    step: u64,
+    time_remaining: u128,
+    increment: u128,
+    moves_to_go: Option<u64>,
+) -> (Search, ClockedReporter, Move) {
+    let limits = SearchLimits {
+        time_controls: TimeControls {
+            time_remaining: Some(time_remaining),
+            increment,
+            moves_to_go,
+        },
+        ..SearchLimits::default()
+    };
+
+    search_with_limits(fen, step, &limits)
+}
+
+fn search_with_limits(
+    fen: &str,
+    step: u64,
+    limits: &SearchLimits,
+) -> (Search, ClockedReporter, Move) {
+    let clock = ManualClock::with_step(step);
+
+    let mut search = Search::default();
+    search.board.parse_fen(fen).unwrap();
+    search.set_seed(Some(1));
+    search.timer = SearchTimer::with_clock(clock.clone());
+
+    let mut reporter = ClockedReporter {
+        clock,
+        iterations: Vec::new(),
+        time_extended_at: None,
+    };
+
+    let best_move = search.search_position(limits, &mut reporter).unwrap();
+
+    (search, reporter, best_move)
+}
+
+fn allowed_ms(timer: &SearchTimer) -> u128 {
+    match timer.allowed_duration {
+        SearchDuration::Finite(duration) => duration,
+        SearchDuration::Infinite => panic!("expected a finite search duration"),
+    }
+}
+
+fn assert_real_move(best_move: Move) {
+    assert_ne!(best_move.to_string(), "0000", "the search returned no move");
+}
+
+#[test]
+fn fixed_budget_stops_within_one_check_interval() {
+    let step = 10;
+    let (search, reporter, best_move) =
+        search_with_clock(MIDDLEGAME_FEN, step, ONE_SECOND_LEFT, 0, Some(1));
+
+    let budget = allowed_ms(&search.timer);
+    assert_eq!(budget, 1000);
+
+    // every reading made during the search advanced the clock by one step, so the last one
+    // happened a step before the current time
+    let last_reading = reporter.clock.current_ms() - step as u128;
+    assert!(
+        last_reading <= budget,
+        "kept searching after {} ms of a {} ms budget",
+        last_reading,
+        budget
+    );
+
+    // the clock isn't checked until the first iteration is over. after that, a reading every
+    // 10 ms is far too slow, so the interval never grows past its smallest
+    let first_iteration_nodes = reporter.iterations[0].nodes;
+    let checks_in_budget = (budget / step as u128) as u64;
+    assert!(
+        search.search_info.nodes_searched
+            <= first_iteration_nodes + (checks_in_budget + 1) * MIN_CHECK_INTERVAL
+    );
+    assert_eq!(search.timer.check_interval(), MIN_CHECK_INTERVAL);
+
+    assert_real_move(best_move);
+}
+
+// checks the timer the way the search does, at a steady `nodes_per_ms`, until a `budget_ms` move
+// time runs out. (the time it stopped at, the check interval it settled on)
+fn run_timer(nodes_per_ms: u64, budget_ms: u128) -> (u128, u64) {
+    let clock = ManualClock::default();
+    let mut timer = SearchTimer::with_clock(clock.clone());
+    timer.move_overhead_ms = 0;
+
+    timer.initialize_with_limits(&SearchLimits {
+        movetime: Some(budget_ms),
+        ..SearchLimits::default()
+    });
+    timer.start();
+
+    let mut nodes = 0;
+
+    while !timer.is_stopped() {
+        nodes += 1;
+
+        if nodes % nodes_per_ms == 0 {
+            clock.advance(1);
+        }
+
+        if timer.should_check(nodes) {
+            timer.check();
+        }
+    }
+
+    (clock.current_ms(), timer.check_interval())
+}
+
+#[test]
+fn check_interval_adapts_to_the_speed() {
+    // a few milliseconds' worth of nodes, between the bounds
+    let (_, interval) = run_timer(1000, 200);
+    assert!((2000..=5000).contains(&interval), "{}", interval);
+
+    // slow enough that even the smallest interval takes a few milliseconds
+    let (_, interval) = run_timer(10, 200);
+    assert_eq!(interval, MIN_CHECK_INTERVAL);
+
+    // and fast enough that the largest still checks far more often than needed
+    let (_, interval) = run_timer(100_000, 200);
+    assert_eq!(interval, MAX_CHECK_INTERVAL);
+}
+
+#[test]
+fn hard_limit_is_barely_overshot_at_any_speed() {
+    for nodes_per_ms in [5, 10, 100, 1000, 10_000, 100_000] {
+        for budget in [1, 5, 20, 100] {
+            let (stopped_at, _) = run_timer(nodes_per_ms, budget);
+
+            assert!(
+                stopped_at >= budget && stopped_at <= budget + 7,
+                "stopped at {} ms of a {} ms budget with {} nodes per ms",
+                stopped_at,
+                budget,
+                nodes_per_ms
+            );
+        }
+    }
+}
+
+#[test]
+fn increment_only_control_never_plans_beyond_the_clock() {
+    let mut timer = SearchTimer::with_clock(ManualClock::default());
+
+    // the increment only arrives after the move, so it can't be spent now
+    timer.initialize(Some(400), 2000, None);
+    assert!(allowed_ms(&timer) <= 400);
+
+    // nothing left but the increment: move immediately
+    timer.initialize(Some(0), 2000, None);
+    assert_eq!(allowed_ms(&timer), 0);
+
+    let (search, _, best_move) = search_with_clock(MIDDLEGAME_FEN, 1, 30, 1000, None);
+    assert!(search.timer.elapsed_ms() <= 30);
+    assert_real_move(best_move);
+}
+
+#[test]
+fn moves_to_go_one_scramble_still_moves() {
+    let mut timer = SearchTimer::with_clock(ManualClock::default());
+
+    // with one move left, most of the clock can be used, minus the overhead
+    timer.initialize(Some(1000), 0, Some(1));
+    assert_eq!(allowed_ms(&timer), 700 - DEFAULT_MOVE_OVERHEAD_MS);
+
+    // less time than the overhead must not wrap around into a huge budget
+    timer.initialize(Some(20), 0, Some(1));
+    assert_eq!(allowed_ms(&timer), 0);
+
+    // and a GUI sending zero is treated as one
+    timer.initialize(Some(1000), 0, Some(0));
+    assert_eq!(allowed_ms(&timer), 700 - DEFAULT_MOVE_OVERHEAD_MS);
+
+    let (_, reporter, best_move) = search_with_clock(MIDDLEGAME_FEN, 5, 20, 0, Some(1));
+    assert_eq!(reporter.iterations.len(), 1);
+    assert_real_move(best_move);
+}
+
+#[test]
+fn move_overhead_is_kept_back_near_zero_time() {
+    let mut timer = SearchTimer::with_clock(ManualClock::default());
+
+    for overhead in [0, DEFAULT_MOVE_OVERHEAD_MS, 1000] {
+        timer.move_overhead_ms = overhead;
+
+        for time in 0..=overhead + 100 {
+            for (increment, moves_to_go) in [(0, Some(1)), (0, None), (5000, None), (5000, Some(1))]
+            {
+                timer.initialize(Some(time), increment, moves_to_go);
+                assert!(
+                    allowed_ms(&timer) <= time.saturating_sub(overhead),
+                    "{} ms allowed from {} ms with {} ms overhead",
+                    allowed_ms(&timer),
+                    time,
+                    overhead
+                );
+            }
+
+            timer.initialize_with_limits(&SearchLimits {
+                movetime: Some(time),
+                ..SearchLimits::default()
+            });
+            assert_eq!(allowed_ms(&timer), time.saturating_sub(overhead));
+        }
+    }
+
+    // with no overhead, everything but what's held back for later moves can be spent
+    timer.move_overhead_ms = 0;
+    timer.initialize(Some(10), 0, Some(1));
+    assert_eq!(allowed_ms(&timer), 7);
+}
+
+#[test]
+fn no_move_takes_most_of_the_clock() {
+    let mut timer = SearchTimer::with_clock(ManualClock::default());
+
+    for time in [0, 50, 999, 1000, 12_345, 60_000, 600_000] {
+        for increment in [0, 100, 1000, 5000] {
+            for moves_to_go in [None, Some(1), Some(2), Some(3), Some(5), Some(10), Some(40)] {
+                timer.initialize(Some(time), increment, moves_to_go);
+                let allowed = allowed_ms(&timer);
+
+                let setup = format!(
+                    "{} ms allowed from {} ms + {} ms with {:?} to go",
+                    allowed, time, increment, moves_to_go
+                );
+
+                assert!(allowed as f64 <= time as f64 * MAX_MOVE_SHARE, "{}", setup);
+
+                // never more than three times the even share, however big the increment
+                let moves = moves_to_go.unwrap_or(30).max(1) as u128;
+                assert!(allowed <= (time * 3).div_ceil(moves), "{}", setup);
+            }
+        }
+    }
+}
+
+#[test]
+fn allocations_for_the_last_moves_before_the_time_control() {
+    let mut timer = SearchTimer::with_clock(ManualClock::default());
+    let overhead = DEFAULT_MOVE_OVERHEAD_MS;
+
+    // (time, increment, moves to go, allowed)
+    for (time, increment, moves_to_go, allowed) in [
+        // the even share used to be the whole clock, now some of it is kept
+        (10_000, 0, 1, 7000),
+        // half the clock is the even share, and the increment tops it up to the cap
+        (10_000, 0, 2, 5000),
+        (10_000, 1000, 2, 5800),
+        (10_000, 5000, 2, 7000),
+        (9000, 0, 3, 3000),
+        (9000, 10_000, 3, 6300),
+        // far from the time control, the increment counts for most of itself
+        (60_000, 1000, 40, 2300),
+        (60_000, 0, 40, 1500),
+        // three times the even share is the cap here
+        (60_000, 50_000, 10, 18_000),
+    ] {
+        timer.initialize(Some(time), increment, Some(moves_to_go));
+        assert_eq!(
+            allowed_ms(&timer),
+            allowed - overhead,
+            "{} ms + {} ms with {} to go",
+            time,
+            increment,
+            moves_to_go
+        );
+    }
+}
+
+// plays a whole game of 40 moves in 60 seconds, repeating, with every move taking exactly its
+// budget plus the overhead, so the clock runs down as fast as the allocation lets it
+#[test]
+fn repeating_time_controls_never_lose_on_time() {
+    let mut timer = SearchTimer::with_clock(ManualClock::default());
+    let overhead = DEFAULT_MOVE_OVERHEAD_MS;
+
+    let mut clock = 60_000;
+
+    for _ in 0..3 {
+        for moves_to_go in (1..=40).rev() {
+            timer.initialize(Some(clock), 0, Some(moves_to_go));
+
+            let used = allowed_ms(&timer) + overhead;
+            assert!(used <= clock, "flagged with {} to go", moves_to_go);
+
+            clock -= used;
+
+            // something is always left over for the move after
+            assert!(clock > 0, "nothing left with {} to go", moves_to_go);
+        }
+
+        clock += 60_000;
+    }
+}
+
+#[test]
+fn no_iteration_starts_without_time_to_finish() {
+    for step in [5, 10, 20] {
+        let (search, reporter, _) =
+            search_with_clock(MIDDLEGAME_FEN, step, ONE_SECOND_LEFT, 0, Some(1));
+
+        let budget = allowed_ms(&search.timer);
+        let mut previous_end = 0;
+
+        for (index, iteration) in reporter.iterations.iter().enumerate() {
+            let end = iteration.end_ms;
+            let duration = end - previous_end;
+            previous_end = end;
+
+            // any nodes past this iteration's total belong to a later iteration, which must only
+            // have been started if there was at least this iteration's duration left
+            let next_started = match reporter.iterations.get(index + 1) {
+                Some(_) => true,
+                None => search.search_info.nodes_searched > iteration.nodes,
+            };
+
+            if next_started {
+                assert!(
+                    budget.saturating_sub(end) >= duration,
+                    "started an iteration at {} ms with {} ms left, but the last one took {} ms",
+                    end,
+                    budget.saturating_sub(end),
+                    duration
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn aborted_iteration_keeps_the_last_complete_best_move() {
+    // different steps abort the last iteration at different points
+    for step in [3, 7, 11, 13, 17] {
+        let (search, reporter, best_move) =
+            search_with_clock(MIDDLEGAME_FEN, step, ONE_SECOND_LEFT, 0, Some(1));
+
+        let last = reporter.iterations.last().unwrap();
+
+        if best_move == last.best_move {
+            continue;
+        }
+
+        // the only reason to play something else is a deeper, unfinished iteration finding a move
+        // that beat it
+        let root_move = search
+            .root_moves
+            .moves()
+            .iter()
+            .find(|root_move| root_move.mv == best_move)
+            .expect("the best move should have been searched at the root");
+
+        assert_eq!(root_move.depth, last.depth + 1);
+        assert_ne!(root_move.bound, ScoreBound::Upper);
+    }
+}
+
+#[test]
+fn info_lines_give_the_time_and_speed_so_far() {
+    let step = 5;
+    let (_, reporter, _) = search_with_clock(MIDDLEGAME_FEN, step, 60_000, 0, Some(1));
+
+    assert!(reporter.iterations.len() > 1);
+
+    for (previous, iteration) in reporter.iterations.iter().zip(&reporter.iterations[1..]) {
+        assert!(iteration.time_ms >= previous.time_ms);
+    }
+
+    for iteration in &reporter.iterations {
+        // the clock moves on once more as the time is read for the report
+        assert_eq!(iteration.time_ms + step as u128, iteration.end_ms);
+
+        let nps = iteration.nodes as u128 * 1000 / iteration.time_ms.max(1);
+        let expected = format!(
+            "nodes {} nps {} time {} pv ",
+            iteration.nodes, nps, iteration.time_ms
+        );
+        assert!(iteration.info.contains(&expected), "{}", iteration.info);
+        assert!(iteration.info.contains(" seldepth "));
+    }
+}
+
+#[test]
+fn ponder_clock_only_starts_on_ponderhit() {
+    let clock = ManualClock::default();
+    let mut timer = SearchTimer::with_clock(clock.clone());
+
+    let limits = SearchLimits {
+        ponder: true,
+        time_controls: TimeControls {
+            time_remaining: Some(3000 + DEFAULT_MOVE_OVERHEAD_MS),
+            increment: 0,
+            moves_to_go: Some(1),
+        },
+        ..SearchLimits::default()
+    };
+
+    let pondering = timer.pondering();
+    pondering.store(true, Ordering::Relaxed);
+
+    timer.initialize_with_limits(&limits);
+    timer.start();
+    let allowed = allowed_ms(&timer);
+
+    // however long the opponent thinks, none of it comes off the engine's time
+    clock.advance(60_000);
+    timer.check();
+    assert!(!timer.is_stopped());
+    assert_eq!(timer.elapsed_ms(), 0);
+
+    // the time reported to the GUI still counts from `go`
+    assert_eq!(timer.search_time_ms(), 60_000);
+    assert!(timer.can_start_iteration(0, 60_000));
+
+    pondering.store(false, Ordering::Relaxed);
+    timer.check();
+    assert!(!timer.is_stopped());
+
+    clock.advance(allowed as u64 - 1);
+    timer.check();
+    assert!(!timer.is_stopped());
+
+    clock.advance(1);
+    timer.check();
+    assert!(timer.is_stopped());
+}
+
+#[test]
+fn ponder_move_is_the_reply_in_the_principal_variation() {
+    let (mut search, reporter, best_move) = search_with_clock(MIDDLEGAME_FEN, 1, 3000, 0, None);
+
+    let ponder = search.ponder_move(best_move);
+
+    if best_move == reporter.iterations.last().unwrap().best_move {
+        assert!(ponder.is_some());
+        assert_ne!(ponder, Some(best_move));
+    } else {
+        assert_eq!(ponder, None);
+    }
+
+    // a move the search didn't settle on has no expected reply
+    let other = search
+        .root_moves
+        .moves()
+        .iter()
+        .find(|root_move| root_move.mv != best_move)
+        .unwrap();
+    assert_eq!(search.ponder_move(other.mv), None);
+}
+
+// the queen's best square up to depth 5 turns out to lose more at depth 6, where another one takes
+// over
+const FAIL_LOW_FEN: &str = "rnbqk2r/p1p1n2p/3p1pp1/4p1Q1/3PP3/bp3P1N/RPP1N1PP/2BK1B1R w kq - 0 1";
+const FAIL_LOW_DEPTH: u8 = 6;
+const FAIL_LOW_STEP: u64 = 10;
+
+// with the default 30 moves to go and no increment, the time a move is normally allocated
+fn clock_for_allocation(allowed_ms: u128) -> u128 {
+    (allowed_ms + DEFAULT_MOVE_OVERHEAD_MS) * 30
+}
+
+#[test]
+fn a_late_fail_low_gets_more_time_to_find_a_better_move() {
+    // where the fail-low happens, with so much time that nothing stops the search early
+    let (_, reference, _) = search_with_clock(
+        FAIL_LOW_FEN,
+        FAIL_LOW_STEP,
+        clock_for_allocation(1_000_000),
+        0,
+        None,
+    );
+    let fail_low_at = reference
+        .time_extended_at
+        .expect("the best move should fail low");
+
+    // the last report of each depth is the one with its final move
+    let before = reference
+        .iterations
+        .iter()
+        .rfind(|it| it.depth == FAIL_LOW_DEPTH - 1)
+        .unwrap();
+    let after = reference
+        .iterations
+        .iter()
+        .rfind(|it| it.depth == FAIL_LOW_DEPTH)
+        .unwrap();
+    assert!(before.end_ms < fail_low_at && fail_low_at < after.end_ms);
+    assert_ne!(before.best_move, after.best_move);
+
+    // the fail-low comes 80% of the way through the usual allocation
+    let allowed = fail_low_at * 5 / 4;
+
+    // a fixed move time can't be extended, so the search stops and plays the move that failed low
+    let (search, reporter, best_move) = search_with_limits(
+        FAIL_LOW_FEN,
+        FAIL_LOW_STEP,
+        &SearchLimits {
+            movetime: Some(allowed + DEFAULT_MOVE_OVERHEAD_MS),
+            ..SearchLimits::default()
+        },
+    );
+    assert_eq!(allowed_ms(&search.timer), allowed);
+    assert_eq!(reporter.time_extended_at, None);
+    let last_exact = reporter
+        .iterations
+        .iter()
+        .rfind(|it| !it.info.contains("bound"))
+        .unwrap();
+    assert_eq!(last_exact.depth, FAIL_LOW_DEPTH - 1);
+    assert_eq!(best_move, before.best_move);
+
+    // against the clock, the search keeps going past its allocation and switches moves
+    let (search, reporter, best_move) = search_with_clock(
+        FAIL_LOW_FEN,
+        FAIL_LOW_STEP,
+        clock_for_allocation(allowed),
+        0,
+        None,
+    );
+    assert_eq!(reporter.time_extended_at, Some(fail_low_at));
+
+    let extended = allowed_ms(&search.timer);
+    assert!(extended > allowed);
+    assert!(reporter.clock.current_ms() > allowed);
+    // the reading that found the time was up is within a step of it, and there's one more after
+    assert!(reporter.clock.current_ms() <= extended + 2 * FAIL_LOW_STEP as u128);
+    assert_ne!(best_move, before.best_move);
+
+    // the extra time is enough to finish the iteration that failed low, which settles on the same
+    // move as the search that had all the time it wanted
+    let finished = reporter
+        .iterations
+        .iter()
+        .rfind(|it| it.depth == FAIL_LOW_DEPTH && !it.info.contains("bound"))
+        .expect("the iteration that failed low should finish");
+    assert_eq!(finished.best_move, after.best_move);
+}
+
+#[test]
+fn extra_time_is_limited_to_the_share_of_the_clock() {
+    let mut timer = SearchTimer::with_clock(ManualClock::default());
+
+    timer.initialize(Some(30_000), 0, None);
+    assert_eq!(allowed_ms(&timer), 970);
+    assert!(timer.extend_to_maximum());
+    assert_eq!(
+        allowed_ms(&timer),
+        (1000.0 * PANIC_TIME_FACTOR) as u128 - DEFAULT_MOVE_OVERHEAD_MS
+    );
+    assert!(!timer.extend_to_maximum());
+
+    // the last move before the time control already gets as much as any move can
+    timer.initialize(Some(1000), 0, Some(1));
+    assert_eq!(allowed_ms(&timer), 670);
+    assert!(!timer.extend_to_maximum());
+    assert_eq!(allowed_ms(&timer), 670);
+}
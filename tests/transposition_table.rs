@@ -0,0 +1,337 @@
+// the transposition table only hands back entries stored for the hash asked about. whether a slot
+// is in use is tracked apart from the hash, so a position hashing to 0 is stored like any other.
+// handles made with `share` all see the same entries
+
+mod common;
+
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use common::{start_uci_advertising, Engine};
+use krusty::{
+    engine_details::ENGINE_VERSION,
+    move_generator::Move,
+    reporter::SilentReporter,
+    search_config::SearchConfig,
+    search_limits::SearchLimits,
+    transposition_table::{SearchEntryFlag, SearchTableEntry, TranspositionTable},
+};
+
+fn entry(hash: u64, score: i32) -> SearchTableEntry {
+    SearchTableEntry::new(hash, 3, score, 0, SearchEntryFlag::Exact, Move::NULL_MOVE)
+}
+
+fn stored_score(table: &TranspositionTable<SearchTableEntry>, hash: u64) -> Option<i32> {
+    table.probe(hash).map(|entry| entry.score)
+}
+
+// a file of its own for each test, since they run at the same time
+fn hash_file_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("krusty-tt-{}-{}.hash", name, std::process::id()))
+}
+
+// the error from loading `path` into a table of `size_in_mb`, after the file's been deleted
+fn load_error(path: &Path, size_in_mb: usize) -> String {
+    let mut table = TranspositionTable::<SearchTableEntry>::new(size_in_mb);
+    let result = table.load(path);
+    fs::remove_file(path).unwrap();
+
+    format!("{:#}", result.unwrap_err())
+}
+
+#[test]
+fn a_tiny_table_still_has_one_entry() {
+    let table = TranspositionTable::<SearchTableEntry>::new(0);
+    assert_eq!(table.capacity_entries(), 1);
+    assert_eq!(table.hashfull(), 0);
+
+    // every hash shares the one slot, so the last one stored wins
+    table.store(entry(7, 10));
+    table.store(entry(12345, 20));
+
+    assert_eq!(stored_score(&table, 12345), Some(20));
+    assert_eq!(stored_score(&table, 7), None);
+    assert_eq!(table.hashfull(), 1000);
+}
+
+#[test]
+fn probes_for_another_hash_find_nothing() {
+    let table = TranspositionTable::<SearchTableEntry>::new(1);
+    let size = table.capacity_entries() as u64;
+
+    assert_eq!(stored_score(&table, 42), None);
+
+    table.store(entry(42, 10));
+    assert_eq!(stored_score(&table, 42), Some(10));
+
+    // the same slot, but a different position
+    assert_eq!(stored_score(&table, 42 + size), None);
+}
+
+#[test]
+fn hash_zero_is_stored_like_any_other() {
+    let table = TranspositionTable::<SearchTableEntry>::new(1);
+
+    // an empty slot isn't mistaken for an entry with a hash of 0
+    assert!(table.probe(0).is_none());
+
+    table.store(entry(0, 25));
+    assert_eq!(stored_score(&table, 0), Some(25));
+
+    // and it survives a round trip through a hash file, empty slots and all
+    let path = hash_file_path("zero");
+    table.save(&path).unwrap();
+
+    let mut loaded = TranspositionTable::<SearchTableEntry>::new(1);
+    let result = loaded.load(&path);
+    fs::remove_file(&path).unwrap();
+    result.unwrap();
+
+    assert_eq!(loaded.capacity_entries(), table.capacity_entries());
+    assert_eq!(stored_score(&loaded, 0), Some(25));
+    assert!(loaded.probe(1).is_none());
+}
+
+#[test]
+fn shared_handles_see_the_same_entries() {
+    let table = TranspositionTable::<SearchTableEntry>::new(1);
+    let shared = table.share();
+
+    shared.store(entry(99, -30));
+    assert_eq!(stored_score(&table, 99), Some(-30));
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| shared.store(entry(100, 40)));
+    });
+    assert_eq!(stored_score(&table, 100), Some(40));
+}
+
+#[test]
+fn clearing_empties_the_table_in_place() {
+    // small enough for a short search to show up in `hashfull`
+    let mut search = SearchConfig {
+        hash_mb: 1,
+        ..SearchConfig::default()
+    }
+    .build()
+    .unwrap();
+    search
+        .search_position(&SearchLimits::depth(5), &mut SilentReporter)
+        .unwrap();
+
+    let table = &search.transposition_table;
+    let shared = table.share();
+    let hash = search.board.hash();
+    let capacity = table.capacity_entries();
+    assert!(table.probe(hash).is_some());
+    assert!(table.hashfull() > 0);
+
+    table.clear();
+
+    assert!(table.probe(hash).is_none());
+    assert!(shared.probe(hash).is_none());
+    assert_eq!(table.hashfull(), 0);
+    assert_eq!(table.capacity_entries(), capacity);
+
+    // and it fills up again as before
+    table.store(entry(hash, 30));
+    assert_eq!(stored_score(&shared, hash), Some(30));
+}
+
+// nodes searched in the last iteration of `go depth 5` from the starting position
+fn nodes_to_depth_5(engine: &mut Engine) -> u64 {
+    engine.send("go depth 5");
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+
+    // the last line at depth 5, after any re-searches with a wider window
+    let info = output
+        .iter()
+        .rfind(|line| line.starts_with("info depth 5 "))
+        .unwrap();
+    let tokens: Vec<_> = info.split_whitespace().collect();
+    let nodes = tokens.iter().position(|&token| token == "nodes").unwrap();
+
+    tokens[nodes + 1].parse().unwrap()
+}
+
+#[test]
+fn the_table_can_be_cleared_over_uci() {
+    let mut engine = start_uci_advertising("option name Clear Hash type button");
+
+    engine.send("position startpos");
+    nodes_to_depth_5(&mut engine);
+
+    // the same search again is answered almost entirely from the table
+    let with_table = nodes_to_depth_5(&mut engine);
+
+    engine.send("setoption name Clear Hash");
+    engine.send("isready");
+    assert_eq!(engine.read_until(|line| line == "readyok"), ["readyok"]);
+
+    // and has to be searched properly once it's been cleared
+    let cleared = nodes_to_depth_5(&mut engine);
+    assert!(cleared > with_table * 10, "{} vs {}", cleared, with_table);
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn entries_round_trip_through_a_slot() {
+    let table = TranspositionTable::<SearchTableEntry>::new(1);
+    let mv = Move::from_bits(0x1abcd);
+
+    for (score, flag) in [
+        (-99_990, SearchEntryFlag::Alpha),
+        (0, SearchEntryFlag::Exact),
+        (99_990, SearchEntryFlag::Beta),
+    ] {
+        table.store(SearchTableEntry::new(u64::MAX, 64, score, 0, flag, mv));
+
+        let stored = table.probe(u64::MAX).unwrap();
+        assert_eq!(stored.hash, u64::MAX);
+        assert_eq!(stored.depth, 64);
+        assert_eq!(stored.score, score);
+        assert_eq!(stored.flag, flag);
+        assert_eq!(stored.best_move, mv);
+    }
+}
+
+#[test]
+fn a_saved_table_gives_a_fresh_search_a_head_start() {
+    let limits = SearchLimits::depth(8);
+
+    let mut search = SearchConfig::default().build().unwrap();
+    let best_move = search
+        .search_position(&limits, &mut SilentReporter)
+        .unwrap();
+    let nodes = search.search_info.nodes_searched;
+
+    let path = hash_file_path("search");
+    search.transposition_table.save(&path).unwrap();
+
+    let mut fresh = SearchConfig::default().build().unwrap();
+    let result = fresh.transposition_table.load(&path);
+    fs::remove_file(&path).unwrap();
+    result.unwrap();
+
+    let reloaded_best_move = fresh.search_position(&limits, &mut SilentReporter).unwrap();
+
+    assert_eq!(reloaded_best_move, best_move);
+    assert!(fresh.search_info.debug.tt_hits > 0);
+    assert!(
+        fresh.search_info.nodes_searched * 10 < nodes,
+        "{} vs {}",
+        fresh.search_info.nodes_searched,
+        nodes
+    );
+}
+
+#[test]
+fn a_truncated_hash_file_is_refused() {
+    let table = TranspositionTable::<SearchTableEntry>::new(1);
+    table.store(entry(42, 10));
+
+    let path = hash_file_path("truncated");
+    table.save(&path).unwrap();
+
+    let file = OpenOptions::new().write(true).open(&path).unwrap();
+    let length = file.metadata().unwrap().len();
+    file.set_len(length - 1).unwrap();
+
+    let error = load_error(&path, 1);
+    assert!(error.contains("but has room for"), "{}", error);
+}
+
+#[test]
+fn an_entry_count_the_file_cant_hold_is_refused_before_allocating() {
+    let table = TranspositionTable::<SearchTableEntry>::new(1);
+
+    let path = hash_file_path("count");
+    table.save(&path).unwrap();
+
+    // the count comes after the magic bytes, the version and the slot size
+    let count_offset = 8 + 1 + ENGINE_VERSION.len() as u64 + 4;
+    let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+    file.seek(SeekFrom::Start(count_offset)).unwrap();
+    file.write_all(&u64::MAX.to_le_bytes()).unwrap();
+    drop(file);
+
+    let error = load_error(&path, 1);
+    assert!(error.contains("says it has"), "{}", error);
+}
+
+#[test]
+fn a_hash_file_only_holds_the_entries_in_use() {
+    let path = hash_file_path("occupied");
+    let mut lengths = Vec::new();
+
+    // the same entry in tables of different sizes saves to the same file
+    for size_in_mb in [1, 4] {
+        let table = TranspositionTable::<SearchTableEntry>::new(size_in_mb);
+        table.store(entry(12345, 20));
+        table.save(&path).unwrap();
+
+        lengths.push(fs::metadata(&path).unwrap().len());
+    }
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(lengths[0], lengths[1]);
+
+    // and an empty table saves and loads as an empty table
+    let empty = TranspositionTable::<SearchTableEntry>::new(1);
+    empty.save(&path).unwrap();
+
+    let mut loaded = TranspositionTable::<SearchTableEntry>::new(1);
+    loaded.store(entry(7, 10));
+    let result = loaded.load(&path);
+    fs::remove_file(&path).unwrap();
+    result.unwrap();
+
+    assert_eq!(loaded.hashfull(), 0);
+    assert_eq!(stored_score(&loaded, 7), None);
+}
+
+#[test]
+fn a_hash_file_from_a_bigger_table_keeps_what_fits() {
+    // every slot of the bigger table in use, which is more entries than the smaller one has slots
+    let big = TranspositionTable::<SearchTableEntry>::new(2);
+    for hash in 0..big.capacity_entries() as u64 {
+        big.store(entry(hash, hash as i32 % 100));
+    }
+
+    let path = hash_file_path("bigger");
+    big.save(&path).unwrap();
+
+    // the table keeps the size `Hash` gives it, with each entry stored again where it hashes to
+    let mut loaded = TranspositionTable::<SearchTableEntry>::new(1);
+    let capacity = loaded.capacity_entries();
+    let result = loaded.load(&path);
+    fs::remove_file(&path).unwrap();
+    result.unwrap();
+
+    assert!(capacity < big.capacity_entries());
+    assert_eq!(loaded.capacity_entries(), capacity);
+    assert_eq!(loaded.hashfull(), 1000);
+
+    let kept = (0..big.capacity_entries() as u64)
+        .filter(|&hash| stored_score(&loaded, hash) == Some(hash as i32 % 100))
+        .count();
+    assert_eq!(kept, capacity);
+
+    // a smaller file is spread over the table, which keeps its configured size
+    let small = TranspositionTable::<SearchTableEntry>::new(1);
+    small.store(entry(12345, 20));
+    small.save(&path).unwrap();
+
+    let mut loaded = TranspositionTable::<SearchTableEntry>::new(2);
+    let result = loaded.load(&path);
+    fs::remove_file(&path).unwrap();
+    result.unwrap();
+
+    assert_eq!(loaded.capacity_entries(), big.capacity_entries());
+    assert_eq!(stored_score(&loaded, 12345), Some(20));
+}
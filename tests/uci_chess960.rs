@@ -0,0 +1,38 @@
+// `UCI_Chess960` switches castling to king-takes-rook notation, both ways over the protocol
+
+mod common;
+
+use common::{assert_uci_output, start_uci_advertising, Engine};
+
+fn bestmove(engine: &mut Engine, go: &str) -> String {
+    engine.send(go);
+
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    output
+        .last()
+        .unwrap()
+        .split_whitespace()
+        .nth(1)
+        .unwrap()
+        .into()
+}
+
+#[test]
+fn castling_notation_follows_the_option() {
+    let mut engine = start_uci_advertising("option name UCI_Chess960 type check default false");
+
+    engine.send("position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    assert_eq!(bestmove(&mut engine, "go depth 1 searchmoves e1g1"), "e1g1");
+
+    engine.send("setoption name UCI_Chess960 value true");
+    engine.send("position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    assert_eq!(bestmove(&mut engine, "go depth 1 searchmoves e1a1"), "e1a1");
+
+    // rights in Shredder-FEN, and castling in the moves of `position`
+    engine.send("position fen r3k2r/8/8/8/8/8/8/1R2K2R w HBkq - 0 1 moves e1h1");
+    assert_eq!(bestmove(&mut engine, "go depth 1 searchmoves e8a8"), "e8a8");
+
+    assert!(engine.quit().success());
+}
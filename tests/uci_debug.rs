@@ -0,0 +1,165 @@
+// `debug on` adds `info string` lines about what the search is doing, always between iterations.
+// with it off, nothing changes about the output
+
+mod common;
+
+use common::{assert_uci_output, start_uci, RecordingReporter};
+use krusty::{
+    debug_sink::{DebugEvent, DebugStats},
+    search::Search,
+    search_limits::SearchLimits,
+    time_management::SearchDuration,
+};
+
+fn search(fen: &str, limits: &SearchLimits) -> Vec<Result<u8, DebugEvent>> {
+    let mut search = Search::default();
+    search.board.parse_fen(fen).unwrap();
+
+    let mut reporter = RecordingReporter::default();
+    search.search_position(limits, &mut reporter).unwrap();
+
+    reporter.events
+}
+
+#[test]
+fn every_iteration_is_followed_by_its_stats() {
+    let output = search(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &SearchLimits::depth(5),
+    );
+
+    assert_eq!(
+        output[0],
+        Err(DebugEvent::TimeAllocated {
+            allowed: SearchDuration::Infinite,
+            move_overhead_ms: 30
+        })
+    );
+
+    let rest = &output[1..];
+    assert_eq!(rest.len(), 10);
+
+    let mut total = DebugStats::default();
+
+    for (depth, pair) in (1..=5).zip(rest.chunks(2)) {
+        assert_eq!(pair[0], Ok(depth));
+
+        let Err(DebugEvent::IterationStats {
+            depth: stats_depth,
+            stats,
+        }) = pair[1]
+        else {
+            panic!("expected the stats for depth {}, got {:?}", depth, pair[1]);
+        };
+
+        assert_eq!(stats_depth, depth);
+        assert!(stats.tt_hits <= stats.tt_probes);
+        assert!(stats.null_move_cutoffs <= stats.null_move_searches);
+
+        total.tt_probes += stats.tt_probes;
+        total.null_move_searches += stats.null_move_searches;
+    }
+
+    assert!(total.tt_probes > 0);
+    assert!(total.null_move_searches > 0);
+}
+
+#[test]
+fn a_timed_search_reports_its_allocation() {
+    let mut limits = SearchLimits::default();
+    limits.time_controls.time_remaining = Some(3000);
+
+    let output = search(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        &limits,
+    );
+
+    assert!(matches!(
+        output[0],
+        Err(DebugEvent::TimeAllocated {
+            allowed: SearchDuration::Finite(70),
+            move_overhead_ms: 30
+        })
+    ));
+
+    // the time runs out either mid-iteration or before starting one
+    let last = output.last().unwrap();
+    assert!(
+        matches!(
+            last,
+            Err(DebugEvent::IterationSkipped { .. } | DebugEvent::IterationStats { .. })
+        ),
+        "{:?}",
+        last
+    );
+}
+
+#[test]
+fn debug_lines_are_only_printed_when_turned_on() {
+    let mut engine = start_uci();
+
+    engine.send("position startpos");
+    engine.send("go depth 3");
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+    assert!(!output.iter().any(|line| line.starts_with("info string")));
+
+    engine.send("debug on");
+    engine.send("go depth 3");
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    let debug_lines: Vec<_> = output
+        .iter()
+        .filter(|line| line.starts_with("info string"))
+        .collect();
+    assert_eq!(debug_lines[0], "info string time allocated without limit");
+    assert_eq!(debug_lines.len(), 4);
+    assert!(debug_lines[3].starts_with("info string depth 3 tt hits "));
+
+    // each iteration's stats come straight after its `info` line, and the refutations debug mode
+    // shows for it
+    let depth_3 = output
+        .iter()
+        .position(|line| line.starts_with("info depth 3 "))
+        .unwrap();
+    let after_depth_3 = output[depth_3 + 1..]
+        .iter()
+        .find(|line| !line.starts_with("info refutation "))
+        .unwrap();
+    assert_eq!(after_depth_3, debug_lines[3]);
+
+    engine.send("debug off");
+    engine.send("debug sideways");
+    engine.send("go depth 3");
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    assert_eq!(
+        output[0],
+        "info string invalid `debug` command, expected `on` or `off`"
+    );
+    assert!(!output[1..]
+        .iter()
+        .any(|line| line.starts_with("info string")));
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn debug_can_be_turned_on_during_a_search() {
+    let mut engine = start_uci();
+
+    engine.send("position startpos");
+    engine.send("go infinite");
+    engine.send("debug on");
+    engine.send("isready");
+    engine.read_until(|line| line == "readyok");
+    engine.send("stop");
+
+    // the search carried on after `debug on`, rather than ending there
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    assert!(output
+        .iter()
+        .any(|line| line.starts_with("info string depth")));
+
+    assert!(engine.quit().success());
+}
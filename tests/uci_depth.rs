@@ -0,0 +1,61 @@
+// every depth a GUI can ask for ends with a legal move, even ones the engine can't search to
+// exactly
+
+mod common;
+
+use common::{assert_uci_output, start_uci};
+use krusty::board::{Board, START_POSITION_FEN};
+
+// (depth, the `info string` explaining what's searched instead)
+const DEPTHS: [(&str, Option<&str>); 3] = [
+    ("0", Some("info string depth 0 searches to depth 1")),
+    ("1", None),
+    (
+        "200",
+        Some("info string depth 200 is beyond the deepest search, searching to depth 64"),
+    ),
+];
+
+fn is_legal(move_str: &str) -> bool {
+    let mut board = Board::default();
+    board.parse_fen(START_POSITION_FEN).unwrap();
+
+    let metadata = board.get_move_metadata(move_str).unwrap();
+    board.find_legal_move(metadata).unwrap().is_some()
+}
+
+#[test]
+fn every_depth_gives_a_legal_bestmove() {
+    let mut engine = start_uci();
+
+    for (depth, info) in DEPTHS {
+        engine.send("position startpos");
+        engine.send(&format!("go depth {}", depth));
+
+        // depth 200 won't finish, so it needs telling to stop
+        if depth == "200" {
+            engine.send("stop");
+        }
+
+        let output = engine.read_until(|line| line.starts_with("bestmove"));
+        output.iter().for_each(|line| assert_uci_output(line));
+
+        let info_strings: Vec<&str> = output
+            .iter()
+            .map(String::as_str)
+            .filter(|line| line.starts_with("info string"))
+            .collect();
+        assert_eq!(info_strings, info.as_slice(), "depth {}", depth);
+
+        let bestmove = output.last().unwrap().split_whitespace().nth(1).unwrap();
+        assert!(is_legal(bestmove), "depth {}: {}", depth, bestmove);
+
+        // depth 0 is searched exactly like depth 1
+        if depth == "0" {
+            assert!(output.iter().any(|line| line.starts_with("info depth 1 ")));
+            assert!(!output.iter().any(|line| line.starts_with("info depth 2 ")));
+        }
+    }
+
+    assert!(engine.quit().success());
+}
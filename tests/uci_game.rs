@@ -0,0 +1,103 @@
+// plays a whole game against the engine binary over UCI, the same way a GUI would, with a random
+// opponent on the other side. every engine move is checked against the test's own copy of the
+// game
+
+mod common;
+
+use common::{assert_uci_output, Engine};
+use krusty::{
+    board::{Board, START_POSITION_FEN},
+    move_generator::{Move, MoveList},
+    prng::Prng,
+};
+
+const MAX_PLIES: usize = 60;
+const MOVE_TIME_MS: u64 = 50;
+
+fn legal_moves(board: &mut Board) -> Vec<Move> {
+    let mut move_list = MoveList::new();
+    board.generate_all_moves(&mut move_list).unwrap();
+
+    let mut legal = Vec::new();
+
+    for index in 0..move_list.length() {
+        let mv = move_list.get(index);
+
+        if board.make_move(mv).unwrap() {
+            legal.push(mv);
+        }
+
+        board.unmake_move(mv);
+    }
+
+    legal
+}
+
+fn is_game_over(board: &mut Board) -> bool {
+    board.is_draw() || board.has_insufficient_material() || legal_moves(board).is_empty()
+}
+
+#[test]
+fn engine_plays_a_legal_game_against_a_random_opponent() {
+    let mut engine = Engine::start();
+    let mut board = Board::default();
+    board.parse_fen(START_POSITION_FEN).unwrap();
+
+    let mut prng = Prng::new(2248);
+    let mut history: Vec<String> = Vec::new();
+
+    // the CLI greeting comes first, and its prompt ends up in front of the first UCI line
+    engine.send("uci");
+    engine.read_until(|line| line.ends_with("uciok"));
+
+    engine.send("isready");
+    assert_eq!(engine.next_line(), "readyok");
+
+    while history.len() < MAX_PLIES && !is_game_over(&mut board) {
+        let is_engine_turn = history.len().is_multiple_of(2);
+
+        let mv = if is_engine_turn {
+            if history.is_empty() {
+                engine.send("position startpos");
+            } else {
+                engine.send(&format!("position startpos moves {}", history.join(" ")));
+            }
+
+            engine.send(&format!("go movetime {}", MOVE_TIME_MS));
+
+            let output = engine.read_until(|line| line.starts_with("bestmove "));
+            output.iter().for_each(|line| assert_uci_output(line));
+
+            // anything printed after `bestmove` and before `readyok` would be a second reply to
+            // the same `go`
+            engine.send("isready");
+            let trailing = engine.read_until(|line| line == "readyok");
+            assert_eq!(trailing, ["readyok"], "extra output after bestmove");
+
+            let reply = output.last().unwrap();
+            let move_str = reply
+                .split_whitespace()
+                .nth(1)
+                .expect("bestmove without a move");
+
+            let legal = legal_moves(&mut board);
+            *legal
+                .iter()
+                .find(|mv| mv.to_string() == move_str)
+                .unwrap_or_else(|| {
+                    panic!("illegal move {} in {}", move_str, board.fen());
+                })
+        } else {
+            let legal = legal_moves(&mut board);
+            legal[prng.random_u64() as usize % legal.len()]
+        };
+
+        assert!(board.make_move(mv).unwrap());
+        history.push(mv.to_string());
+    }
+
+    assert!(history.len() >= 2, "the game ended before the engine moved");
+
+    let status = engine.quit();
+    assert!(status.success(), "the engine exited with {}", status);
+}
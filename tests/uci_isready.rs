@@ -0,0 +1,79 @@
+// `isready` is how a GUI waits for the engine to catch up, so it's only answered once everything
+// sent before it has been applied, however long that takes. resizing the hash happens on a worker
+// thread, so the input loop keeps reading in the meantime
+
+mod common;
+
+use common::{assert_uci_output, start_uci};
+
+#[test]
+fn readyok_waits_for_a_large_hash_and_comes_before_the_search() {
+    let mut engine = start_uci();
+
+    // all sent at once, before any of it has been handled
+    engine.send("setoption name Hash value 1024");
+    engine.send("isready");
+    engine.send("go depth 3");
+    engine.send("isready");
+
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    assert_eq!(output[0], "readyok");
+    assert!(output[1..output.len() - 1]
+        .iter()
+        .all(|line| line.starts_with("info ") || line == "readyok"));
+
+    // the second `isready` is answered during the search or once it's over, but only once
+    let readyoks = output.iter().filter(|line| *line == "readyok").count();
+    let readyoks = if readyoks == 2 {
+        readyoks
+    } else {
+        readyoks + engine.read_until(|line| line == "readyok").len()
+    };
+    assert_eq!(readyoks, 2);
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn commands_sent_during_a_resize_are_applied_after_it() {
+    let mut engine = start_uci();
+
+    engine.send("setoption name Hash value 1024");
+    engine.send("debug on");
+    engine.send("position fen 7k/8/8/8/8/8/8/K5R1 b - - 0 1");
+    engine.send("isready");
+    engine.send("isready");
+
+    assert_eq!(
+        engine.read_until(|line| line == "readyok"),
+        ["readyok"],
+        "nothing should be printed before the hash is ready"
+    );
+    assert_eq!(engine.next_line(), "readyok");
+
+    // the king is the only black piece, so the position did change
+    engine.send("go depth 1");
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    assert!(output.last().unwrap().starts_with("bestmove h8"));
+
+    // and debug mode was turned on while the table was being allocated
+    assert!(output.iter().any(|line| line.starts_with("info string ")));
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn a_hash_that_fails_to_load_is_still_followed_by_readyok() {
+    let mut engine = start_uci();
+
+    engine.send("setoption name LoadHash value /nonexistent/krusty.hash");
+    engine.send("isready");
+
+    let output = engine.read_until(|line| line == "readyok");
+    assert_eq!(output.len(), 2);
+    assert!(output[0].starts_with("info string failed to load hash"));
+
+    assert!(engine.quit().success());
+}
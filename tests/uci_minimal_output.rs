@@ -0,0 +1,31 @@
+// with `MinimalOutput` on, a bot bridge only gets the last iteration's `info` line and the
+// `bestmove`, however deep the search goes
+
+mod common;
+
+use common::{assert_uci_output, start_uci};
+
+#[test]
+fn only_two_lines_are_printed_per_go() {
+    let mut engine = start_uci();
+
+    engine.send("setoption name MinimalOutput value true");
+    engine.send("position startpos moves e2e4 e7e5");
+
+    for depth in [7, 3] {
+        engine.send(&format!("go depth {}", depth));
+
+        let output = engine.read_until(|line| line.starts_with("bestmove"));
+        output.iter().for_each(|line| assert_uci_output(line));
+
+        assert_eq!(output.len(), 2, "{:?}", output);
+        assert!(
+            output[0].starts_with(&format!("info depth {} ", depth)),
+            "{}",
+            output[0]
+        );
+        assert!(output[0].contains(" pv "), "{}", output[0]);
+    }
+
+    assert!(engine.quit().success());
+}
@@ -0,0 +1,32 @@
+// `Move Overhead` is the time kept back from every move for getting it to the GUI
+
+mod common;
+
+use common::{assert_uci_output, start_uci_advertising};
+
+#[test]
+fn move_overhead_is_a_spin_option() {
+    let mut engine =
+        start_uci_advertising("option name Move Overhead type spin default 30 min 0 max 1000");
+
+    engine.send("setoption name Move Overhead value 1001");
+    engine.send("setoption name move overhead value 1000");
+    engine.send("isready");
+    let output = engine.read_until(|line| line == "readyok");
+    assert_eq!(
+        output,
+        [
+            "info string Move Overhead must be between 0 and 1000",
+            "readyok"
+        ]
+    );
+
+    // all of a short move time goes to the overhead, but there's still a move to play
+    engine.send("position startpos");
+    engine.send("go movetime 500");
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+    assert_ne!(output.last().unwrap(), "bestmove 0000");
+
+    assert!(engine.quit().success());
+}
@@ -0,0 +1,163 @@
+// only the options the engine advertises can be set. anything else, and any variant it can't
+// play, is refused with an info string and changes nothing. option names and combo values are
+// matched without regard to case, since several GUIs change it
+
+mod common;
+
+use common::{assert_uci_output, start_uci, Engine};
+use krusty::options::{OptionValue, UciOption, SUPPORTED_VARIANTS, UCI_OPTIONS};
+
+// everything the engine says in reply to `command`, up to the `readyok` after it
+fn reply_to(engine: &mut Engine, command: &str) -> Vec<String> {
+    engine.send(command);
+    engine.send("isready");
+
+    let mut output = engine.read_until(|line| line == "readyok");
+    output.pop();
+    output
+}
+
+// how many lines the last completed iteration of a short search showed
+fn lines_per_iteration(engine: &mut Engine) -> usize {
+    engine.send("position startpos");
+    engine.send("go depth 3");
+
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output
+        .iter()
+        .filter(|line| line.contains(" depth 3 ") && !line.contains("bound "))
+        .count()
+}
+
+#[test]
+fn only_the_registered_options_are_advertised() {
+    let mut engine = Engine::start();
+
+    engine.send("uci");
+    let options: Vec<_> = engine
+        .read_until(|line| line.ends_with("uciok"))
+        .into_iter()
+        .filter(|line| line.starts_with("option "))
+        .collect();
+
+    let declarations: Vec<_> = UCI_OPTIONS.iter().map(UciOption::declaration).collect();
+    assert_eq!(options, declarations);
+
+    assert!(options.contains(&"option name UCI_Variant type combo default chess var chess".into()));
+    assert_eq!(SUPPORTED_VARIANTS, ["chess"]);
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn an_unsupported_variant_is_refused() {
+    let mut engine = start_uci();
+
+    for variant in ["crazyhouse", "atomic", "3check"] {
+        assert_eq!(
+            reply_to(
+                &mut engine,
+                &format!("setoption name UCI_Variant value {}", variant)
+            ),
+            [format!(
+                "info string unsupported option UCI_Variant value {}",
+                variant
+            )]
+        );
+    }
+
+    // and standard chess is still played
+    assert!(reply_to(&mut engine, "setoption name UCI_Variant value chess").is_empty());
+
+    engine.send("position startpos");
+    engine.send("go depth 2");
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn an_unknown_option_is_refused() {
+    let mut engine = start_uci();
+
+    assert_eq!(
+        reply_to(&mut engine, "setoption name Skill Level value 3"),
+        ["info string unsupported option Skill Level"]
+    );
+    assert_eq!(
+        reply_to(&mut engine, "setoption name Ownbook"),
+        ["info string unsupported option Ownbook"]
+    );
+    assert_eq!(
+        reply_to(&mut engine, "setoption MultiPV value 2"),
+        ["info string invalid `setoption` command"]
+    );
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn a_refused_value_keeps_the_previous_one() {
+    let mut engine = start_uci();
+
+    assert!(reply_to(&mut engine, "setoption name MultiPV value 2").is_empty());
+
+    assert_eq!(
+        reply_to(&mut engine, "setoption name MultiPV value 1000"),
+        ["info string MultiPV must be between 1 and 256"]
+    );
+    assert_eq!(
+        reply_to(&mut engine, "setoption name MultiPV value two"),
+        ["info string MultiPV must be an integer: invalid digit found in string"]
+    );
+
+    assert_eq!(lines_per_iteration(&mut engine), 2);
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn option_names_are_matched_in_any_case() {
+    let mut engine = start_uci();
+
+    assert!(reply_to(&mut engine, "setoption name multipv value 3").is_empty());
+    assert_eq!(lines_per_iteration(&mut engine), 3);
+
+    assert!(reply_to(&mut engine, "setoption name MULTIPV value 1").is_empty());
+    assert_eq!(lines_per_iteration(&mut engine), 1);
+
+    for command in [
+        "setoption name uci_variant value CHESS",
+        "setoption name clear hash",
+        "setoption name move overhead value 50",
+        "setoption name ponder value TRUE",
+    ] {
+        assert!(reply_to(&mut engine, command).is_empty(), "{}", command);
+    }
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn the_registry_finds_options_in_any_case() {
+    for option in &UCI_OPTIONS {
+        for name in [
+            option.name.to_string(),
+            option.name.to_lowercase(),
+            option.name.to_uppercase(),
+        ] {
+            assert_eq!(UciOption::find(&name).unwrap().name, option.name);
+        }
+    }
+
+    assert!(UciOption::find("UCI_Variants").is_none());
+    assert!(UciOption::find("").is_none());
+
+    let variant = UciOption::find("uci_variant").unwrap();
+    assert!(matches!(
+        variant.parse_value("Chess").unwrap(),
+        OptionValue::Text(value) if value == "chess"
+    ));
+    assert!(variant.parse_value("crazyhouse").is_err());
+}
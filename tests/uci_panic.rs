@@ -0,0 +1,52 @@
+// a bug in the search shouldn't forfeit the game. the panic is reported, a legal move is played
+// anyway, and the engine carries on answering the GUI. debug builds can be told to panic partway
+// through a search, so this only runs in those
+#![cfg(debug_assertions)]
+
+mod common;
+
+use std::{env, fs, process::Stdio};
+
+use common::Engine;
+use krusty::search::PANIC_AFTER_NODES_VAR;
+
+#[test]
+fn a_panic_in_the_search_still_ends_with_a_bestmove() {
+    // the crash log is written to the working directory
+    let dir = env::temp_dir().join(format!("krusty-panic-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let mut engine = Engine::start_with(|command| {
+        command
+            .current_dir(&dir)
+            .env(PANIC_AFTER_NODES_VAR, "500")
+            .stderr(Stdio::null())
+    });
+
+    engine.send("uci");
+    engine.read_until(|line| line.ends_with("uciok"));
+
+    engine.send("position startpos moves e2e4");
+    engine.send("go depth 6");
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+
+    assert!(output
+        .iter()
+        .any(|line| line.starts_with("info string PANIC: injected panic after ")));
+
+    // the first legal move, rather than nothing at all
+    let bestmove = output.last().unwrap();
+    assert_ne!(bestmove, "bestmove 0000");
+
+    engine.send("isready");
+    assert_eq!(engine.read_until(|line| line == "readyok"), ["readyok"]);
+
+    assert!(engine.quit().success());
+
+    let crash_log = fs::read_to_string(dir.join("krusty_crash.log")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(crash_log.starts_with("panic: injected panic after "));
+    assert!(crash_log.lines().any(|line| line.starts_with("fen ")));
+    assert!(crash_log.contains("position startpos moves e2e4"));
+}
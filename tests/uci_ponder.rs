@@ -0,0 +1,122 @@
+// pondering searches the position after the move the engine expects its opponent to play, on the
+// opponent's time. `ponderhit` turns it into a normal timed search, and `stop` ends it
+
+mod common;
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::{assert_uci_output, start_uci_advertising, Engine};
+
+// a clock this size leaves a few hundred milliseconds for the move
+const PONDER_GO: &str = "go ponder wtime 10000 btime 10000";
+
+// well beyond the time the engine is given, so a search that ignored `ponder` would be over
+const PONDER_WAIT: Duration = Duration::from_millis(1500);
+
+const BESTMOVE_DEADLINE: Duration = Duration::from_secs(2);
+
+fn start_pondering() -> Engine {
+    let mut engine = start_uci_advertising("option name Ponder type check default false");
+    engine.send("setoption name Ponder value true");
+
+    engine
+}
+
+fn assert_still_pondering(engine: &mut Engine) {
+    thread::sleep(PONDER_WAIT);
+
+    engine.send("isready");
+    let output = engine.read_until(|line| line == "readyok");
+    assert!(
+        output.iter().all(|line| !line.starts_with("bestmove")),
+        "{:?}",
+        output
+    );
+}
+
+fn read_bestmove(engine: &mut Engine) -> String {
+    let started = Instant::now();
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+
+    assert!(
+        started.elapsed() < BESTMOVE_DEADLINE,
+        "took {:?} to send bestmove",
+        started.elapsed()
+    );
+
+    output.iter().for_each(|line| assert_uci_output(line));
+    output.last().unwrap().clone()
+}
+
+#[test]
+fn ponderhit_starts_the_clock() {
+    let mut engine = start_pondering();
+
+    engine.send("position startpos moves e2e4 e7e5");
+    engine.send(PONDER_GO);
+    assert_still_pondering(&mut engine);
+
+    engine.send("ponderhit");
+    let bestmove = read_bestmove(&mut engine);
+    assert_ne!(bestmove, "bestmove 0000");
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn stop_ends_pondering_with_a_move() {
+    let mut engine = start_pondering();
+
+    engine.send("position startpos moves d2d4 d7d5");
+    engine.send(PONDER_GO);
+    assert_still_pondering(&mut engine);
+
+    engine.send("stop");
+    let bestmove = read_bestmove(&mut engine);
+    assert_ne!(bestmove, "bestmove 0000");
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn finished_ponder_search_waits_for_ponderhit() {
+    let mut engine = start_pondering();
+
+    engine.send("position startpos");
+    engine.send("go ponder depth 2");
+    assert_still_pondering(&mut engine);
+
+    engine.send("ponderhit");
+    read_bestmove(&mut engine);
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn bestmove_includes_the_expected_reply() {
+    let mut engine = start_pondering();
+
+    engine.send("position startpos moves e2e4");
+    engine.send("go depth 4");
+
+    let bestmove = read_bestmove(&mut engine);
+    let fields: Vec<&str> = bestmove.split_whitespace().collect();
+
+    assert_eq!(fields.len(), 4, "{}", bestmove);
+    assert_eq!(fields[2], "ponder");
+
+    // black's move, then white's reply. only the e-pawn has moved off white's back two ranks
+    assert!(fields[1].ends_with('6') || fields[1].ends_with('5'));
+
+    let reply_from = &fields[3][..2];
+    assert!(
+        reply_from.ends_with('1') || reply_from.ends_with('2') || reply_from == "e4",
+        "{} is not a white move",
+        fields[3]
+    );
+
+    assert!(engine.quit().success());
+}
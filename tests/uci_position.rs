@@ -0,0 +1,215 @@
+// a `position` command is all or nothing. one that can't be set up completely leaves the engine in
+// the position it had before, rather than somewhere between the two
+
+mod common;
+
+use common::{assert_uci_output, start_uci, Engine};
+
+// black is in check from the rook, and the king can only step off the h-file
+const CHECKED_KING_FEN: &str = "7k/8/8/8/8/8/8/K6R b - - 0 1";
+const KING_MOVES: [&str; 2] = ["h8g8", "h8g7"];
+
+fn start_on_checked_king() -> Engine {
+    let mut engine = start_uci();
+    engine.send(&format!("position fen {}", CHECKED_KING_FEN));
+
+    engine
+}
+
+// the lines printed in reply to a command that prints nothing when it works
+fn replies_to(engine: &mut Engine, command: &str) -> Vec<String> {
+    engine.send(command);
+    engine.send("isready");
+
+    let mut output = engine.read_until(|line| line == "readyok");
+    output.pop();
+    output
+}
+
+fn bestmove(engine: &mut Engine) -> String {
+    engine.send("go depth 3");
+
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    let bestmove = output.last().unwrap().split_whitespace().nth(1).unwrap();
+    bestmove.to_string()
+}
+
+#[test]
+fn illegal_move_keeps_the_previous_position() {
+    let mut engine = start_on_checked_king();
+
+    // the first two moves are fine, and would have been left on the board
+    let output = replies_to(&mut engine, "position startpos moves e2e4 e7e5 e1e5 g1f3");
+    assert_eq!(
+        output,
+        ["info string move 3 of the `position` command: Move `e1e5` is not legal in this position, keeping the previous position"]
+    );
+
+    let bestmove = bestmove(&mut engine);
+    assert!(KING_MOVES.contains(&bestmove.as_str()), "{}", bestmove);
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn invalid_fen_keeps_the_previous_position() {
+    let mut engine = start_on_checked_king();
+
+    let output = replies_to(&mut engine, "position fen 8/8/8 w - - 0 1");
+    assert_eq!(output.len(), 1);
+    assert!(output[0].starts_with("info string invalid FEN: "));
+    assert!(output[0].ends_with(", keeping the previous position"));
+
+    let output = replies_to(&mut engine, "position somewhere");
+    assert_eq!(
+        output,
+        ["info string invalid `position` command, keeping the previous position"]
+    );
+
+    let bestmove = bestmove(&mut engine);
+    assert!(KING_MOVES.contains(&bestmove.as_str()), "{}", bestmove);
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn a_fen_with_the_side_not_to_move_in_check_is_rejected() {
+    let mut engine = start_on_checked_king();
+
+    // the same position, but white to move with black's king still attacked by the rook
+    let output = replies_to(&mut engine, "position fen 7k/8/8/8/8/8/8/K6R w - - 0 1");
+    assert_eq!(
+        output,
+        ["info string invalid FEN: FEN is illegal: the side not to move is in check, keeping the previous position"]
+    );
+
+    let bestmove = bestmove(&mut engine);
+    assert!(KING_MOVES.contains(&bestmove.as_str()), "{}", bestmove);
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn go_after_a_rejected_first_fen_does_not_crash() {
+    let mut engine = start_uci();
+
+    // white's king is in check from the queen, but it's black to move
+    let output = replies_to(&mut engine, "position fen 4k3/8/8/8/8/8/8/q3K3 b - - 0 1");
+    assert_eq!(output.len(), 1);
+    assert!(output[0].starts_with("info string invalid FEN: FEN is illegal: "));
+
+    engine.send("go depth 3");
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn rejected_current_moves_are_all_taken_back() {
+    let mut engine = start_on_checked_king();
+
+    let output = replies_to(&mut engine, "position current moves h8g8 a1b1 g8g9");
+    assert_eq!(output.len(), 1);
+    assert!(output[0].starts_with("info string move 3 of the `position` command: "));
+
+    // still black to move, with the king in check, so the same moves can be played again
+    assert!(replies_to(&mut engine, "position current moves h8g8").is_empty());
+
+    let bestmove = bestmove(&mut engine);
+    assert!(
+        bestmove.starts_with("a1") || bestmove.starts_with("h1"),
+        "{}",
+        bestmove
+    );
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn current_moves_are_kept_for_the_next_position_command() {
+    let mut engine = start_on_checked_king();
+
+    assert!(replies_to(&mut engine, "position current moves h8g8").is_empty());
+
+    // the GUI sends the whole game, and only the rook move is new. the engine has to know the
+    // king move is already on the board, or it would play it a second time
+    let command = format!("position fen {} moves h8g8 h1h8", CHECKED_KING_FEN);
+    assert!(replies_to(&mut engine, &command).is_empty());
+    assert_eq!(bestmove(&mut engine), "g8h8");
+
+    // a new game starts from scratch
+    assert!(replies_to(&mut engine, "position startpos moves e2e4").is_empty());
+    let bestmove = bestmove(&mut engine);
+    assert!(matches!(&bestmove[1..2], "7" | "8"), "{}", bestmove);
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn current_and_moves_have_to_be_whole_words() {
+    let mut engine = start_on_checked_king();
+
+    assert_eq!(
+        replies_to(&mut engine, "position currentish moves h8g8"),
+        ["info string invalid `position` command, keeping the previous position"]
+    );
+    assert_eq!(
+        replies_to(&mut engine, "position current movesh8g8"),
+        ["info string invalid `position current` command, keeping the previous position"]
+    );
+
+    let bestmove = bestmove(&mut engine);
+    assert!(KING_MOVES.contains(&bestmove.as_str()), "{}", bestmove);
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn a_fen_that_isnt_there_keeps_the_previous_position() {
+    let mut engine = start_on_checked_king();
+
+    for command in ["position fen", "position fen moves h8g8"] {
+        let output = replies_to(&mut engine, command);
+        assert_eq!(
+            output,
+            ["info string invalid `position` command, keeping the previous position"]
+        );
+    }
+
+    let bestmove = bestmove(&mut engine);
+    assert!(KING_MOVES.contains(&bestmove.as_str()), "{}", bestmove);
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn every_complaint_is_an_info_string() {
+    let mut engine = start_on_checked_king();
+
+    for command in [
+        "position startpos moves e2e5",
+        "position fen 8/8/8/8/8/8/8/8 w - - 0 1",
+        "position current e2e4",
+        "go depth -1",
+        "go nodes lots",
+        "go movetime",
+    ] {
+        let output = replies_to(&mut engine, command);
+        assert_eq!(output.len(), 1, "{}", command);
+        assert_uci_output(&output[0]);
+        assert!(output[0].starts_with("info string "), "{}", output[0]);
+    }
+
+    assert_eq!(
+        replies_to(&mut engine, "go depth -1"),
+        ["info string depth can't be negative, got -1, ignoring the `go` command"]
+    );
+
+    let bestmove = bestmove(&mut engine);
+    assert!(KING_MOVES.contains(&bestmove.as_str()), "{}", bestmove);
+
+    assert!(engine.quit().success());
+}
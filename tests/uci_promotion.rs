@@ -0,0 +1,53 @@
+// `AssumeQueenPromotion` decides what happens to a promotion a GUI sent without its piece
+
+mod common;
+
+use common::{assert_uci_output, start_uci_advertising, Engine};
+
+const PROMOTION_FEN: &str = "8/4P3/8/8/8/8/k7/4K3 w - - 0 1";
+
+const ASSUME_QUEEN_PROMOTION_OPTION: &str =
+    "option name AssumeQueenPromotion type check default true";
+
+// everything printed for a `position` command, up to the `readyok` after it
+fn position(engine: &mut Engine, args: &str) -> Vec<String> {
+    engine.send(&format!("position {}", args));
+    engine.send("isready");
+
+    let mut output = engine.read_until(|line| line == "readyok");
+    output.pop();
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    output
+}
+
+#[test]
+fn a_promotion_without_a_piece_is_to_a_queen_by_default() {
+    let mut engine = start_uci_advertising(ASSUME_QUEEN_PROMOTION_OPTION);
+
+    let output = position(&mut engine, &format!("fen {} moves e7e8", PROMOTION_FEN));
+    assert_eq!(output, ["info string assuming e7e8q for e7e8"]);
+
+    // an underpromotion that's written out is played as it is
+    let output = position(&mut engine, &format!("fen {} moves e7e8n", PROMOTION_FEN));
+    assert!(output.is_empty());
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn a_promotion_without_a_piece_is_rejected_with_the_option_off() {
+    let mut engine = start_uci_advertising(ASSUME_QUEEN_PROMOTION_OPTION);
+
+    engine.send("setoption name AssumeQueenPromotion value false");
+
+    let output = position(&mut engine, &format!("fen {} moves e7e8", PROMOTION_FEN));
+    assert_eq!(output.len(), 1);
+    assert!(
+        output[0].contains("is a promotion without a promotion piece"),
+        "{}",
+        output[0]
+    );
+
+    assert!(engine.quit().success());
+}
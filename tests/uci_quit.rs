@@ -0,0 +1,59 @@
+// `quit` ends the whole engine, even in the middle of a search or after coming to UCI mode from
+// the CLI, and without a `bestmove` nobody is waiting for
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{assert_uci_output, start_uci};
+
+const QUIT_DEADLINE: Duration = Duration::from_secs(2);
+
+#[test]
+fn quit_ends_an_infinite_search_without_a_bestmove() {
+    let mut engine = start_uci();
+
+    engine.send("go infinite");
+    engine.read_until(|line| line.starts_with("info depth"));
+
+    engine.send("quit");
+    let quit_at = Instant::now();
+
+    let (output, status) = engine.wait_for_exit();
+    assert!(status.success());
+    assert!(
+        quit_at.elapsed() < QUIT_DEADLINE,
+        "took {:?} to quit",
+        quit_at.elapsed()
+    );
+
+    output.iter().for_each(|line| assert_uci_output(line));
+    assert!(output.iter().all(|line| !line.starts_with("bestmove")));
+}
+
+#[test]
+fn quit_ends_a_timed_search_without_a_bestmove() {
+    let mut engine = start_uci();
+
+    engine.send("go movetime 60000");
+    engine.read_until(|line| line.starts_with("info depth"));
+    engine.send("quit");
+
+    let (output, status) = engine.wait_for_exit();
+    assert!(status.success());
+    assert!(output.iter().all(|line| !line.starts_with("bestmove")));
+}
+
+#[test]
+fn quit_in_uci_mode_does_not_go_back_to_the_cli() {
+    let mut engine = start_uci();
+    engine.send("quit");
+
+    let (output, status) = engine.wait_for_exit();
+    assert!(status.success());
+    assert!(
+        output.iter().all(|line| !line.contains("krusty>")),
+        "back at the prompt: {:?}",
+        output
+    );
+}
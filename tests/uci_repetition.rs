@@ -0,0 +1,141 @@
+// a GUI sends the whole game with every `position` command. the positions the game has been
+// through have to survive that, or the engine can't see a threefold repetition coming
+
+mod common;
+
+use common::{assert_uci_output, start_uci, Engine};
+
+// white is a queen up, so only a repetition saves black
+const FEN: &str = "4k3/8/8/8/8/8/8/3QK3 w - - 0 1";
+
+// both kings step aside and back twice, and black's king going back to e8 would repeat the
+// starting position for the third time
+const SHUFFLE: [&str; 7] = ["e1f1", "e8f8", "f1e1", "f8e8", "e1f1", "e8f8", "f1e1"];
+const REPEATING_MOVE: &str = "f8e8";
+
+fn position(moves: &[&str]) -> String {
+    format!("position fen {} moves {}", FEN, moves.join(" "))
+}
+
+// the score of the last iteration, searching nothing but `mv`
+fn score_of(engine: &mut Engine, mv: &str) -> String {
+    engine.send(&format!("go depth 2 searchmoves {}", mv));
+
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    let info = output
+        .iter()
+        .rev()
+        .find(|line| line.starts_with("info depth"))
+        .unwrap();
+
+    // `cp 0`, `mate -3` and so on
+    let tokens: Vec<_> = info.split_whitespace().collect();
+    let score = tokens.iter().position(|&token| token == "score").unwrap();
+    tokens[score + 1..score + 3].join(" ")
+}
+
+#[test]
+fn a_game_sent_one_move_at_a_time_keeps_its_history() {
+    let mut engine = start_uci();
+
+    engine.send(&format!("position fen {}", FEN));
+    for played in 1..=SHUFFLE.len() {
+        engine.send(&position(&SHUFFLE[..played]));
+    }
+
+    assert_eq!(score_of(&mut engine, REPEATING_MOVE), "cp 0");
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn a_game_sent_all_at_once_rebuilds_its_history() {
+    let mut engine = start_uci();
+
+    // starting somewhere else, so nothing of the game can be reused
+    engine.send("position startpos moves e2e4");
+    engine.send(&position(&SHUFFLE));
+
+    assert_eq!(score_of(&mut engine, REPEATING_MOVE), "cp 0");
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn the_same_position_without_its_history_is_lost() {
+    let mut engine = start_uci();
+
+    engine.send("position fen 5k2/8/8/8/8/8/8/3QK3 b - - 0 1");
+
+    assert_ne!(score_of(&mut engine, REPEATING_MOVE), "cp 0");
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn a_rejected_extension_keeps_the_history() {
+    let mut engine = start_uci();
+
+    engine.send(&position(&SHUFFLE[..6]));
+    engine.send(&position(&SHUFFLE));
+
+    let mut moves = SHUFFLE.to_vec();
+    // the king move is played before the illegal one is found, and has to be taken back
+    moves.extend(["f8g8", "e1e3"]);
+    engine.send(&position(&moves));
+    engine.send("isready");
+
+    let output = engine.read_until(|line| line == "readyok");
+    assert!(output[0].ends_with(", keeping the previous position"));
+
+    assert_eq!(score_of(&mut engine, REPEATING_MOVE), "cp 0");
+
+    assert!(engine.quit().success());
+}
+
+fn best_move(engine: &mut Engine) -> String {
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output
+        .last()
+        .unwrap()
+        .split_whitespace()
+        .nth(1)
+        .unwrap()
+        .into()
+}
+
+// the same shuffle with black a queen up, so it's black that has to avoid the repetition
+const WINNING_FEN: &str = "3qk3/8/8/8/8/8/8/4K3 w - - 0 1";
+
+#[test]
+fn the_winning_side_does_not_repeat_at_the_root() {
+    let mut engine = start_uci();
+
+    // searched first, so the table remembers the starting position as won for black, which the
+    // position repeated for the third time is not
+    engine.send(&format!("position fen {}", WINNING_FEN));
+    engine.send("go depth 6");
+    engine.read_until(|line| line.starts_with("bestmove"));
+
+    engine.send(&format!(
+        "position fen {} moves {}",
+        WINNING_FEN,
+        SHUFFLE.join(" ")
+    ));
+
+    // the other king moves win just as well
+    assert_eq!(score_of(&mut engine, REPEATING_MOVE), "cp 0");
+    assert!(score_of(&mut engine, "f8g8").starts_with("cp "));
+    assert_ne!(score_of(&mut engine, "f8g8"), "cp 0");
+
+    engine.send("go depth 4");
+    assert_ne!(best_move(&mut engine), REPEATING_MOVE);
+
+    // but with nothing else to play, the repetition is still played
+    engine.send(&format!("go depth 4 searchmoves {}", REPEATING_MOVE));
+    assert_eq!(best_move(&mut engine), REPEATING_MOVE);
+
+    assert!(engine.quit().success());
+}
@@ -0,0 +1,69 @@
+// with `RootMoveInfo` on, every iteration lists each legal root move once, with the score it got
+// in that iteration or the last one that reached it
+
+mod common;
+
+use std::collections::HashSet;
+
+use common::{assert_uci_output, start_uci};
+use krusty::{board::Board, move_generator::MoveList};
+
+// plenty of captures, checks and both castles for white
+const TACTICAL_FEN: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const DEPTH: u8 = 6;
+
+fn legal_moves(fen: &str) -> HashSet<String> {
+    let mut board = Board::default();
+    board.parse_fen(fen).unwrap();
+
+    let mut moves = MoveList::new();
+    board.generate_all_moves(&mut moves).unwrap();
+
+    (0..moves.length())
+        .map(|i| moves.get(i))
+        .filter(|&mv| {
+            let mut board = board.clone();
+            board.make_move(mv).unwrap()
+        })
+        .map(|mv| mv.to_string())
+        .collect()
+}
+
+#[test]
+fn every_legal_root_move_is_listed_once_with_a_score() {
+    let mut engine = start_uci();
+    engine.send("setoption name RootMoveInfo value true");
+    engine.send(&format!("position fen {}", TACTICAL_FEN));
+    engine.send(&format!("go depth {}", DEPTH));
+
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    let last_iteration = output
+        .iter()
+        .rposition(|line| line.starts_with(&format!("info depth {} ", DEPTH)))
+        .unwrap();
+
+    let root_moves: Vec<_> = output[last_iteration..]
+        .iter()
+        .filter_map(|line| line.strip_prefix("info string rootmove "))
+        .collect();
+
+    let mut listed = HashSet::new();
+
+    for line in &root_moves {
+        let words: Vec<_> = line.split_whitespace().collect();
+        assert!(
+            listed.insert(words[0].to_string()),
+            "{} is listed twice",
+            words[0]
+        );
+
+        let score = words.iter().position(|&word| word == "score").unwrap();
+        assert!(matches!(words[score + 1], "cp" | "mate"), "{}", line);
+        assert!(words[score + 2].parse::<i32>().is_ok(), "{}", line);
+    }
+
+    assert_eq!(listed, legal_moves(TACTICAL_FEN));
+    assert!(engine.quit().success());
+}
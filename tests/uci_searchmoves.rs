@@ -0,0 +1,80 @@
+// `go searchmoves` limits the search to the listed root moves, for that one `go` only
+
+mod common;
+
+use common::{assert_uci_output, start_uci, Engine};
+
+fn go(engine: &mut Engine, args: &str) -> Vec<String> {
+    engine.send(&format!("go {}", args));
+
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    output.iter().for_each(|line| assert_uci_output(line));
+
+    output
+}
+
+fn bestmove(output: &[String]) -> &str {
+    output.last().unwrap().split_whitespace().nth(1).unwrap()
+}
+
+#[test]
+fn only_the_listed_moves_are_searched() {
+    let mut engine = start_uci();
+
+    engine.send("position startpos");
+    let output = go(&mut engine, "depth 4 searchmoves h2h3 a2a3");
+    assert!(
+        ["h2h3", "a2a3"].contains(&bestmove(&output)),
+        "{:?}",
+        output
+    );
+
+    // the next `go` without `searchmoves` is free to pick anything again
+    let output = go(&mut engine, "depth 4");
+    assert!(!["h2h3", "a2a3"].contains(&bestmove(&output)));
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn moves_not_in_the_position_are_warned_about() {
+    let mut engine = start_uci();
+
+    engine.send("position startpos moves e2e4");
+    let output = go(&mut engine, "searchmoves e2e4 g8f6 depth 3");
+
+    assert!(output
+        .iter()
+        .any(|line| line == "info string ignoring searchmoves e2e4"));
+    assert_eq!(bestmove(&output), "g8f6");
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn illegal_moves_are_warned_about_and_an_empty_list_searches_everything() {
+    let mut engine = start_uci();
+
+    // the knight on e2 is pinned to its king by the rook
+    engine.send("position fen 4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1");
+    let output = go(&mut engine, "depth 3 searchmoves e2c3");
+
+    assert!(output
+        .iter()
+        .any(|line| line == "info string ignoring searchmoves e2c3"));
+    assert!(output
+        .iter()
+        .any(|line| line == "info string no legal searchmoves, searching every move"));
+
+    let best_move = bestmove(&output);
+    assert!(!["0000", "e2c3"].contains(&best_move), "{:?}", output);
+
+    // a legal move alongside it is still searched on its own
+    let output = go(&mut engine, "depth 3 searchmoves e2c3 e1d1");
+    assert_eq!(bestmove(&output), "e1d1");
+    assert!(!output
+        .iter()
+        .any(|line| line.starts_with("info string no legal searchmoves")));
+
+    assert!(engine.quit().success());
+}
@@ -0,0 +1,144 @@
+// the search runs alongside the input loop, so a GUI can stop it, or check the engine is alive,
+// while it's still going
+
+mod common;
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::{assert_uci_output, start_uci, Engine};
+
+// much slower than the engine should ever be, but fast enough to tell a stopped search from one
+// that ran to its end
+const STOP_DEADLINE: Duration = Duration::from_secs(1);
+
+#[test]
+fn stop_ends_an_infinite_search() {
+    let mut engine = start_uci();
+
+    engine.send("position startpos moves e2e4");
+    engine.send("go infinite");
+
+    // answered straight away, not once the search is over
+    engine.send("isready");
+    let output = engine.read_until(|line| line == "readyok");
+    assert!(output.iter().all(|line| !line.starts_with("bestmove")));
+
+    thread::sleep(Duration::from_millis(300));
+
+    engine.send("stop");
+    let stopped_at = Instant::now();
+
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    assert!(
+        stopped_at.elapsed() < STOP_DEADLINE,
+        "took {:?} to stop",
+        stopped_at.elapsed()
+    );
+
+    output.iter().for_each(|line| assert_uci_output(line));
+    assert_ne!(output.last().unwrap(), "bestmove 0000");
+
+    // the engine is ready for the next command, and there's only one `bestmove`
+    engine.send("isready");
+    assert_eq!(engine.next_line(), "readyok");
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn stop_without_a_search_is_ignored() {
+    let mut engine = start_uci();
+
+    engine.send("stop");
+    engine.send("isready");
+    assert_eq!(engine.next_line(), "readyok");
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn commands_sent_during_a_search_are_handled_after_it() {
+    let mut engine = start_uci();
+
+    engine.send("go movetime 200");
+    engine.send("position startpos moves d2d4");
+    engine.send("go depth 1");
+
+    let first = engine.read_until(|line| line.starts_with("bestmove"));
+    let second = engine.read_until(|line| line.starts_with("bestmove"));
+
+    assert!(first.iter().any(|line| line.starts_with("info depth")));
+
+    // the second search is from black's side, after 1. d4
+    let reply = second.last().unwrap().split_whitespace().nth(1).unwrap();
+    assert!(
+        reply.ends_with('6') || reply.ends_with('5'),
+        "{} is not a black move",
+        reply
+    );
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn quit_during_a_search_exits() {
+    let mut engine = start_uci();
+
+    engine.send("go infinite");
+    thread::sleep(Duration::from_millis(100));
+
+    assert!(engine.quit().success());
+}
+
+// `bestmove` ends an infinite search as far as the GUI is concerned, so it has to wait for `stop`
+// even when there's nothing left to search
+fn assert_waits_for_stop(engine: &mut Engine) {
+    thread::sleep(Duration::from_millis(300));
+
+    engine.send("isready");
+    let output = engine.read_until(|line| line == "readyok");
+    assert!(
+        output.iter().all(|line| !line.starts_with("bestmove")),
+        "{:?}",
+        output
+    );
+
+    engine.send("stop");
+    let output = engine.read_until(|line| line.starts_with("bestmove"));
+    assert_ne!(output.last().unwrap(), "bestmove 0000");
+}
+
+#[test]
+fn infinite_search_that_runs_out_of_depth_waits_for_stop() {
+    let mut engine = start_uci();
+
+    engine.send("position startpos");
+    engine.send("go infinite depth 2");
+    assert_waits_for_stop(&mut engine);
+
+    // nothing to search at all
+    engine.send("position fen 8/8/4k3/8/8/3K4/8/8 w - - 0 1");
+    engine.send("go infinite");
+    assert_waits_for_stop(&mut engine);
+
+    assert!(engine.quit().success());
+}
+
+#[test]
+fn other_commands_end_an_infinite_search() {
+    let mut engine = start_uci();
+
+    engine.send("go infinite depth 1");
+    thread::sleep(Duration::from_millis(100));
+
+    engine.send("position startpos moves e2e4");
+    engine.send("isready");
+
+    let output = engine.read_until(|line| line == "readyok");
+    assert!(output.iter().any(|line| line.starts_with("bestmove")));
+
+    assert!(engine.quit().success());
+}
@@ -0,0 +1,47 @@
+// a board that never had a FEN loaded has no kings, so everything that would look for one turns it
+// down with an error instead of panicking
+
+use krusty::{
+    board::Board,
+    move_generator::{Move, MoveList},
+    perft::perft,
+    perft_table::PerftTable,
+    reporter::SilentReporter,
+    search::{Search, INFINITY},
+    search_limits::SearchLimits,
+};
+
+fn assert_no_position<T: std::fmt::Debug>(result: anyhow::Result<T>) {
+    assert_eq!(result.unwrap_err().to_string(), "no position loaded");
+}
+
+#[test]
+fn every_entry_point_refuses_an_empty_board() {
+    let mut board = Board::default();
+    let mut move_list = MoveList::new();
+    let mv: Move = "e2e4".parse().unwrap();
+
+    assert_no_position(board.generate_all_moves(&mut move_list));
+    assert_no_position(board.generate_all_captures(&mut move_list));
+    assert_no_position(board.make_move(mv));
+    assert_no_position(board.make_moves_from_str("e2e4"));
+    assert_no_position(board.evaluate());
+    assert_no_position(board.evaluate_lazy(-INFINITY, INFINITY));
+    assert_no_position(perft(&mut board, 2, &mut PerftTable::new(0)));
+
+    let mut search = Search::default();
+    search.board = Board::default();
+    assert_no_position(search.search_position(&SearchLimits::depth(2), &mut SilentReporter));
+}
+
+#[test]
+fn a_failed_fen_leaves_the_board_without_a_position() {
+    let mut board = Board::default();
+    assert!(board.parse_fen("8/8/8 w - - 0 1").is_err());
+
+    assert!(!board.is_initialized());
+    assert_no_position(board.evaluate());
+
+    board.parse_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    assert!(board.evaluate().is_ok());
+}